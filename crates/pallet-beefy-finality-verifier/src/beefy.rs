@@ -0,0 +1,241 @@
+// Copyright (C) 2022 Subspace Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::chain::Chain;
+use beefy_primitives::crypto::{AuthorityId, Signature};
+use beefy_primitives::{
+    known_payload_ids, ConsensusLog, SignedCommitment, ValidatorSet, ValidatorSetId,
+    BEEFY_ENGINE_ID,
+};
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_core::ecdsa;
+use sp_mmr_primitives::{DataOrHash, OpaqueLeaf, Proof};
+use sp_runtime::traits::{Convert, Hash as HashT, Header as HeaderT};
+use sp_std::vec::Vec;
+
+/// Proof that a block is finalized by BEEFY.
+///
+/// BEEFY validators only sign the root of the MMR of block hashes, so the block is proven to be
+/// finalized by the MMR leaf of its child, which commits to the block number and hash.
+#[derive(Debug, Encode, Decode, Clone, PartialEq, Eq)]
+pub struct BeefyFinalityProof<Number, MmrHash> {
+    /// Commitment to the MMR root signed by the BEEFY validators.
+    pub signed_commitment: SignedCommitment<Number, Signature>,
+    /// Scale encoded MMR leaf of the child of the block.
+    pub mmr_leaf: Vec<u8>,
+    /// Proof that `mmr_leaf` is included in the signed MMR.
+    pub mmr_proof: Proof<MmrHash>,
+}
+
+/// A BEEFY validator set and ID.
+#[derive(Debug, Default, Encode, Decode, PartialEq, Clone, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct AuthoritySet {
+    /// List of BEEFY validators of the current set.
+    pub authorities: Vec<AuthorityId>,
+    /// Monotonic identifier of the current BEEFY validator set.
+    pub set_id: ValidatorSetId,
+    /// Commitment to the next validator set, taken from an MMR leaf signed by the current set.
+    pub next_authority_set: Option<NextAuthoritySet>,
+}
+
+impl From<ValidatorSet<AuthorityId>> for AuthoritySet {
+    fn from(validator_set: ValidatorSet<AuthorityId>) -> Self {
+        AuthoritySet {
+            authorities: validator_set.validators().to_vec(),
+            set_id: validator_set.id(),
+            next_authority_set: None,
+        }
+    }
+}
+
+/// Commitment to a BEEFY validator set included in the MMR leaves by `pallet-beefy-mmr`.
+///
+/// Mirrors `beefy_primitives::mmr::BeefyNextAuthoritySet`.
+#[derive(Debug, Default, Encode, Decode, PartialEq, Eq, Clone, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct NextAuthoritySet {
+    /// Identifier of the validator set.
+    pub set_id: ValidatorSetId,
+    /// Number of validators in the set.
+    pub len: u32,
+    /// Merkle root of the Ethereum addresses of the validators.
+    pub root: [u8; 32],
+}
+
+impl NextAuthoritySet {
+    /// Returns `true` if the commitment is made to the given validator set.
+    pub(crate) fn commits_to(&self, validator_set: &ValidatorSet<AuthorityId>) -> bool {
+        let addresses = validator_set
+            .validators()
+            .iter()
+            .cloned()
+            .map(pallet_beefy_mmr::BeefyEcdsaToEthereum::convert);
+
+        self.set_id == validator_set.id()
+            && self.len as usize == validator_set.validators().len()
+            && self.root
+                == beefy_merkle_tree::merkle_root::<beefy_merkle_tree::Keccak256, _, _>(addresses)
+    }
+}
+
+/// The prefix shared by every version of the BEEFY MMR leaf.
+#[derive(Decode)]
+struct MmrLeafPrefix<Number, Hash> {
+    _version: u8,
+    parent_number_and_hash: (Number, Hash),
+    beefy_next_authority_set: NextAuthoritySet,
+}
+
+/// BEEFY finality proof verification error.
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// The commitment is signed by another validator set.
+    InvalidValidatorSetId,
+    /// The commitment is not for a descendant of the block.
+    InvalidCommitmentTarget,
+    /// The number of signatures doesn't match the number of validators.
+    InvalidSignaturesCount,
+    /// The validator has provided an invalid signature.
+    InvalidAuthoritySignature,
+    /// The number of valid signatures is not enough to consider the commitment final.
+    TooLowSignaturesCount,
+    /// The commitment payload doesn't contain an MMR root.
+    MissingMmrRoot,
+    /// The MMR leaf can't be decoded.
+    InvalidMmrLeaf,
+    /// The MMR leaf doesn't commit to the block.
+    MmrLeafTargetMismatch,
+    /// The MMR leaf is not included in the signed MMR.
+    InvalidMmrProof,
+}
+
+/// Verify that the finality proof, signed by the given validator set, finalizes given block.
+///
+/// Returns the commitment to the next validator set found in the verified MMR leaf.
+pub(crate) fn verify_finality_proof<C: Chain>(
+    finalized_target: (C::BlockNumber, C::Hash),
+    authority_set: &AuthoritySet,
+    finality_proof: BeefyFinalityProof<C::BlockNumber, <C::MmrHasher as HashT>::Output>,
+) -> Result<NextAuthoritySet, Error> {
+    let BeefyFinalityProof {
+        signed_commitment,
+        mmr_leaf,
+        mmr_proof,
+    } = finality_proof;
+
+    // the leaf of the child is only included in the MMR signed at its descendants
+    let commitment = &signed_commitment.commitment;
+    if commitment.block_number <= finalized_target.0 {
+        return Err(Error::InvalidCommitmentTarget);
+    }
+
+    verify_signed_commitment(&signed_commitment, authority_set)?;
+
+    let mmr_root = commitment
+        .payload
+        .get_decoded::<<C::MmrHasher as HashT>::Output>(&known_payload_ids::MMR_ROOT_ID)
+        .ok_or(Error::MissingMmrRoot)?;
+
+    verify_mmr_leaf::<C>(mmr_root, finalized_target, mmr_leaf, mmr_proof)
+}
+
+/// Verify that the commitment is signed by the supermajority of the given validator set.
+pub(crate) fn verify_signed_commitment<Number: Encode>(
+    signed_commitment: &SignedCommitment<Number, Signature>,
+    authority_set: &AuthoritySet,
+) -> Result<(), Error> {
+    let commitment = &signed_commitment.commitment;
+    if commitment.validator_set_id != authority_set.set_id {
+        return Err(Error::InvalidValidatorSetId);
+    }
+
+    let authorities = &authority_set.authorities;
+    if authorities.is_empty() || signed_commitment.signatures.len() != authorities.len() {
+        return Err(Error::InvalidSignaturesCount);
+    }
+
+    let message = sp_io::hashing::keccak_256(&commitment.encode());
+    let mut valid_signatures = 0usize;
+    for (authority, maybe_signature) in authorities.iter().zip(&signed_commitment.signatures) {
+        let signature = match maybe_signature {
+            Some(signature) => signature,
+            None => continue,
+        };
+
+        let public: &ecdsa::Public = authority.as_ref();
+        let signature: &ecdsa::Signature = signature.as_ref();
+        if !sp_io::crypto::ecdsa_verify_prehashed(signature, &message, public) {
+            return Err(Error::InvalidAuthoritySignature);
+        }
+
+        valid_signatures += 1;
+    }
+
+    // same threshold as the BEEFY gadget uses to conclude a round
+    let threshold = authorities.len() - (authorities.len() - 1) / 3;
+    if valid_signatures >= threshold {
+        Ok(())
+    } else {
+        Err(Error::TooLowSignaturesCount)
+    }
+}
+
+/// Verify that the MMR leaf commits to the given block and is included in the MMR with given root.
+///
+/// Returns the commitment to the next validator set found in the leaf.
+pub(crate) fn verify_mmr_leaf<C: Chain>(
+    mmr_root: <C::MmrHasher as HashT>::Output,
+    finalized_target: (C::BlockNumber, C::Hash),
+    mmr_leaf: Vec<u8>,
+    mmr_proof: Proof<<C::MmrHasher as HashT>::Output>,
+) -> Result<NextAuthoritySet, Error> {
+    let leaf_prefix = MmrLeafPrefix::<C::BlockNumber, C::Hash>::decode(&mut mmr_leaf.as_slice())
+        .map_err(|_| Error::InvalidMmrLeaf)?;
+    if leaf_prefix.parent_number_and_hash != finalized_target {
+        return Err(Error::MmrLeafTargetMismatch);
+    }
+
+    pallet_mmr::verify_leaf_proof::<C::MmrHasher, OpaqueLeaf>(
+        mmr_root,
+        DataOrHash::Data(OpaqueLeaf::from_encoded_leaf(mmr_leaf)),
+        mmr_proof,
+    )
+    .map_err(|_| Error::InvalidMmrProof)?;
+
+    Ok(leaf_prefix.beefy_next_authority_set)
+}
+
+/// Checks the given header for a consensus digest signaling a BEEFY validator set change and
+/// extracts it.
+pub(crate) fn find_authorities_change<H: HeaderT>(header: &H) -> Option<ValidatorSet<AuthorityId>> {
+    use sp_runtime::generic::OpaqueDigestItemId;
+
+    let id = OpaqueDigestItemId::Consensus(&BEEFY_ENGINE_ID);
+
+    let filter_log = |log: ConsensusLog<AuthorityId>| match log {
+        ConsensusLog::AuthoritiesChange(validator_set) => Some(validator_set),
+        _ => None,
+    };
+
+    // find the first consensus digest with the right ID which converts to
+    // the right kind of consensus log.
+    header
+        .digest()
+        .convert_first(|l| l.try_to(id).and_then(filter_log))
+}
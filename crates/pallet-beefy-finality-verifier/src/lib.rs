@@ -0,0 +1,341 @@
+// Copyright (C) 2022 Subspace Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Substrate BEEFY finality verifier
+//!
+//! This pallet is an on-chain BEEFY finality verifier for Substrate based chains. It is an
+//! alternative to `pallet-grandpa-finality-verifier` for chains where GRANDPA justifications are
+//! too heavy to submit.
+//!
+//! BEEFY validators sign commitments to the root of the MMR built by `pallet-mmr` over the block
+//! hashes. A block is accepted when it comes with a commitment signed by the current validator
+//! set we know of, along with a proof that the MMR leaf committing to the block is included in
+//! the signed MMR. The header is inspected for an `AuthoritiesChange` digest item, which is then
+//! used to update to next validator set. Since the block signaling the change is already finalized
+//! by the next validator set, the new set is only accepted if it matches the next validator set
+//! committed to in an MMR leaf previously signed by the current one.
+//!
+//! Since this pallet only tracks finalized headers it does not deal with forks. Forks can only
+//! occur if the BEEFY validator set on the bridged chain is either colluding or there is a severe
+//! bug causing resulting in an equivocation. Such events are outside the scope of this pallet.
+//! Shall the fork occur on the bridged chain governance intervention will be required to
+//! re-initialize the bridge and track the right fork.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+mod beefy;
+
+pub mod chain;
+#[cfg(test)]
+mod tests;
+
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_std::fmt::Debug;
+use sp_std::vec::Vec;
+
+pub use beefy::BeefyFinalityProof;
+// Re-export in crate namespace for `construct_runtime!`
+pub use pallet::*;
+
+/// Data required to initialize a Chain
+#[derive(Default, Debug, Encode, Decode, Clone, PartialEq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct InitializationData {
+    /// Scale encoded best finalized header we know.
+    ///
+    /// The header must signal the BEEFY validator set that finalizes its descendants.
+    pub best_known_finalized_header: Vec<u8>,
+}
+
+// Scale encoded block number, hash, and header of the target chain
+type EncodedBlockNumber = Vec<u8>;
+type EncodedBlockHash = Vec<u8>;
+type EncodedHeader = Vec<u8>;
+
+#[frame_support::pallet]
+pub mod pallet {
+    use crate::beefy::{find_authorities_change, verify_finality_proof, AuthoritySet};
+    use crate::chain::Chain;
+    use crate::{EncodedBlockHash, EncodedBlockNumber, EncodedHeader, InitializationData};
+    use beefy_primitives::crypto::AuthorityId;
+    use beefy_primitives::{ValidatorSet, BEEFY_ENGINE_ID};
+    use frame_support::pallet_prelude::*;
+    use sp_runtime::traits::{CheckedAdd, CheckedSub, Hash, Header, One};
+    use sp_runtime::ArithmeticError;
+    use sp_std::fmt::Debug;
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {
+        // Chain ID uniquely identifies a substrate based chain
+        type ChainId: Parameter + Member + Debug + Default + Copy;
+    }
+
+    #[pallet::pallet]
+    #[pallet::without_storage_info]
+    pub struct Pallet<T>(PhantomData<T>);
+
+    /// The point after which the block validation begins
+    #[pallet::storage]
+    pub(super) type ValidationCheckPoint<T: Config> =
+        StorageMap<_, Identity, T::ChainId, (EncodedBlockNumber, EncodedHeader), ValueQuery>;
+
+    /// Oldest known parent
+    #[pallet::storage]
+    pub(super) type OldestKnownParent<T: Config> =
+        StorageMap<_, Identity, T::ChainId, (EncodedBlockNumber, EncodedBlockHash), ValueQuery>;
+
+    /// Known tip of the chain
+    #[pallet::storage]
+    pub(super) type ChainTip<T: Config> =
+        StorageMap<_, Identity, T::ChainId, (EncodedBlockNumber, EncodedBlockHash), ValueQuery>;
+
+    /// The current BEEFY validator set for a given Chain
+    #[pallet::storage]
+    pub(super) type CurrentAuthoritySet<T: Config> =
+        StorageMap<_, Identity, T::ChainId, AuthoritySet, ValueQuery>;
+
+    #[pallet::error]
+    pub enum Error<T> {
+        /// The block and its contents are not valid
+        InvalidBlock,
+        /// The validator set from the underlying header chain is invalid.
+        InvalidAuthoritySet,
+        /// Finality proof is missing.
+        MissingFinalityProof,
+        /// The given finality proof is invalid for the given header.
+        InvalidFinalityProof,
+        /// Failed to decode initialization data
+        FailedDecodingInitData,
+        /// Failed to Decode header
+        FailedDecodingHeader,
+        /// Failed to Decode block number
+        FailedDecodingBlockNumber,
+        /// Failed to Decode block hash
+        FailedDecodingBlockHash,
+        /// Failed to Decode block
+        FailedDecodingBlock,
+        /// Failed to decode finality proof
+        FailedDecodingFinalityProof,
+        /// The header is already finalized
+        InvalidHeader,
+        /// The initialization header doesn't signal the BEEFY validator set.
+        MissingAuthoritiesChange,
+    }
+
+    /// Initializes the chain by extracting the validator set and best known parent of the chain.
+    /// After the initialization the import of blocks can happen in forward and reverse direction based on the parent stored
+    /// If Genesis is the validation point, then parent is set to Genesis.
+    /// Else parent is set to the parent of the best finalized header
+    pub(crate) fn initialize_chain<T: Config, C: Chain>(
+        chain_id: T::ChainId,
+        init_params: InitializationData,
+    ) -> DispatchResult {
+        let InitializationData {
+            best_known_finalized_header: encoded_header,
+        } = init_params;
+        let header = C::decode_header::<T>(encoded_header.as_slice())?;
+        let validator_set =
+            find_authorities_change(&header).ok_or(Error::<T>::MissingAuthoritiesChange)?;
+
+        // Set the validation point
+        let encoded_number = header.number().encode();
+        ValidationCheckPoint::<T>::insert(chain_id, (encoded_number.clone(), encoded_header));
+
+        // Set authority set
+        CurrentAuthoritySet::<T>::insert(chain_id, AuthoritySet::from(validator_set));
+
+        // set the oldest known parent
+        let (parent_number, parent_hash) = header
+            .number()
+            .checked_sub(&One::one())
+            .map(|number| (number.encode(), header.parent_hash().encode()))
+            .unwrap_or((encoded_number, header.hash().encode()));
+
+        OldestKnownParent::<T>::insert(chain_id, (parent_number.clone(), parent_hash.clone()));
+        // we also set the chain tip to parent so that we sequentially import blocks from parent + 1
+        ChainTip::<T>::insert(chain_id, (parent_number, parent_hash));
+        Ok(())
+    }
+
+    pub fn validate_finalized_block<T: Config, C: Chain>(
+        chain_id: T::ChainId,
+        object: &[u8],
+    ) -> Result<(C::Hash, C::BlockNumber), DispatchError> {
+        // basic block validation
+        let block = C::decode_block::<T>(object)?;
+        let number = *block.block.header.number();
+        let hash = block.block.header.hash();
+
+        let extrinsics_root = C::Hasher::ordered_trie_root(
+            block.block.extrinsics.iter().map(Encode::encode).collect(),
+            sp_runtime::StateVersion::V0,
+        );
+        ensure!(
+            extrinsics_root == *block.block.header.extrinsics_root(),
+            Error::<T>::InvalidBlock
+        );
+
+        let (oldest_known_parent_height, oldest_known_parent_hash) =
+            C::decode_block_number_and_hash::<T>(OldestKnownParent::<T>::get(chain_id))?;
+
+        // if the target is the known oldest parent, we import the block and progress backward
+        if oldest_known_parent_height == number {
+            ensure!(oldest_known_parent_hash == hash, Error::<T>::InvalidBlock);
+
+            OldestKnownParent::<T>::insert(
+                chain_id,
+                (
+                    number.checked_sub(&One::one()).unwrap_or(number).encode(),
+                    block.block.header.parent_hash().encode(),
+                ),
+            );
+
+            return Ok((hash, number));
+        }
+
+        // get last imported block height and hash
+        let (parent_number, parent_hash) =
+            C::decode_block_number_and_hash::<T>(ChainTip::<T>::get(chain_id))?;
+
+        // block height must be always increasing
+        ensure!(
+            number
+                == parent_number
+                    .checked_add(&One::one())
+                    .ok_or(ArithmeticError::Overflow)?,
+            Error::<T>::InvalidBlock
+        );
+        ensure!(
+            *block.block.header.parent_hash() == parent_hash,
+            Error::<T>::InvalidBlock
+        );
+
+        // double check the validation header before importing the block
+        let (encoded_number, encoded_validation_header) = ValidationCheckPoint::<T>::get(chain_id);
+        let validation_number = C::decode_block_number::<T>(encoded_number.as_slice())?;
+        if number == validation_number {
+            ensure!(
+                encoded_validation_header == block.block.header.encode(),
+                Error::<T>::InvalidHeader
+            );
+        }
+
+        // if the target header is a descendent of validation block, validate the finality proof
+        if number > validation_number {
+            let finality_proof = block
+                .justifications
+                .ok_or(Error::<T>::MissingFinalityProof)?
+                .into_justification(BEEFY_ENGINE_ID)
+                .ok_or(Error::<T>::MissingFinalityProof)?;
+            let finality_proof = C::decode_finality_proof::<T>(finality_proof.as_slice())?;
+
+            // fetch current authority set
+            let mut authority_set = <CurrentAuthoritySet<T>>::get(chain_id);
+
+            // the block signaling a new validator set is the first one finalized by that set
+            if let Some(validator_set) = find_authorities_change(&block.block.header) {
+                try_enact_authority_change::<T>(&mut authority_set, validator_set)?;
+            }
+            ensure!(
+                !authority_set.authorities.is_empty(),
+                Error::<T>::InvalidAuthoritySet
+            );
+
+            // verify finality proof
+            let next_authority_set =
+                verify_finality_proof::<C>((number, hash), &authority_set, finality_proof)
+                    .map_err(|e| {
+                        log::error!(
+                            target: "runtime::beefy-finality-verifier",
+                            "Received invalid finality proof for {:?}: {:?}",
+                            hash,
+                            e,
+                        );
+                        Error::<T>::InvalidFinalityProof
+                    })?;
+
+            // remember the hand-off to the next validator set signed by the current one
+            if Some(next_authority_set.set_id) == authority_set.set_id.checked_add(1) {
+                authority_set.next_authority_set = Some(next_authority_set);
+            }
+
+            CurrentAuthoritySet::<T>::insert(chain_id, authority_set);
+        }
+
+        // update the latest descendant
+        ChainTip::<T>::insert(chain_id, (number.encode(), hash.encode()));
+        Ok((hash, number))
+    }
+
+    /// Enacts the validator set signaled by a header, provided it is the next validator set the
+    /// current one committed to.
+    pub(crate) fn try_enact_authority_change<T: Config>(
+        authority_set: &mut AuthoritySet,
+        validator_set: ValidatorSet<AuthorityId>,
+    ) -> DispatchResult {
+        let is_committed = authority_set
+            .next_authority_set
+            .as_ref()
+            .map(|next_authority_set| next_authority_set.commits_to(&validator_set))
+            .unwrap_or(false);
+        ensure!(is_committed, Error::<T>::InvalidAuthoritySet);
+
+        let current_set_id = authority_set.set_id;
+        *authority_set = AuthoritySet::from(validator_set);
+
+        log::info!(
+            target: "runtime::beefy-finality-verifier",
+            "Transitioned from validator set {} to {}! New validators are: {:?}",
+            current_set_id,
+            authority_set.set_id,
+            authority_set.authorities,
+        );
+
+        Ok(())
+    }
+
+    /// Bootstrap the chain to start importing valid finalized blocks
+    ///
+    /// The initial configuration provided does not need to be the genesis header of the bridged
+    /// chain, it can be any arbitrary header signaling a BEEFY validator set.
+    ///
+    /// This function is only allowed to be called from a trusted origin and writes to storage
+    /// with practically no checks in terms of the validity of the data. It is important that
+    /// you ensure that valid data is being passed in.
+    pub fn initialize<T: Config, C: Chain>(
+        chain_id: T::ChainId,
+        init_data: &[u8],
+    ) -> DispatchResult {
+        let data = InitializationData::decode(&mut &*init_data).map_err(|error| {
+            log::error!("Cannot decode init data, error: {:?}", error);
+            Error::<T>::FailedDecodingInitData
+        })?;
+
+        initialize_chain::<T, C>(chain_id, data)?;
+        Ok(())
+    }
+
+    /// purges the on chain state of a given chain
+    pub fn purge<T: Config>(chain_id: T::ChainId) -> DispatchResult {
+        ValidationCheckPoint::<T>::remove(chain_id);
+        CurrentAuthoritySet::<T>::remove(chain_id);
+        ChainTip::<T>::remove(chain_id);
+        OldestKnownParent::<T>::remove(chain_id);
+        Ok(())
+    }
+}
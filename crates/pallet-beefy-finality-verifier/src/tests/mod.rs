@@ -0,0 +1,415 @@
+mod mock;
+
+use crate::beefy::{AuthoritySet, NextAuthoritySet};
+use crate::chain::{Chain, OpaqueExtrinsic};
+use crate::{
+    initialize, validate_finalized_block, BeefyFinalityProof, ChainTip, CurrentAuthoritySet,
+    Error as ErrorP, InitializationData, OldestKnownParent, ValidationCheckPoint,
+};
+use beefy_primitives::crypto::{AuthorityId, Signature};
+use beefy_primitives::{
+    known_payload_ids, Commitment, ConsensusLog, Payload, SignedCommitment, ValidatorSet,
+    ValidatorSetId, BEEFY_ENGINE_ID,
+};
+use codec::Encode;
+use frame_support::dispatch::DispatchResult;
+use frame_support::{assert_err, assert_ok};
+use mock::{run_test, ChainId, TestRuntime};
+use sp_core::{ecdsa, Hasher as HasherT, Pair};
+use sp_mmr_primitives::Proof;
+use sp_runtime::generic::SignedBlock;
+use sp_runtime::traits::{BlakeTwo256, Convert, Hash, Header, Keccak256};
+use sp_runtime::{generic, Digest, DigestItem, DispatchError};
+
+type TestHeader = generic::Header<u32, BlakeTwo256>;
+
+struct TestFeedChain;
+
+impl Chain for TestFeedChain {
+    type BlockNumber = u32;
+    type Hash = <BlakeTwo256 as HasherT>::Out;
+    type Header = generic::Header<u32, BlakeTwo256>;
+    type Hasher = BlakeTwo256;
+    type MmrHasher = Keccak256;
+}
+
+const TEST_VALIDATOR_SET_ID: ValidatorSetId = 1;
+
+fn create_validators(seed: u8, count: u8) -> Vec<ecdsa::Pair> {
+    (0..count)
+        .map(|index| ecdsa::Pair::from_seed(&[seed.wrapping_add(index); 32]))
+        .collect()
+}
+
+fn validator_set(validators: &[ecdsa::Pair], set_id: ValidatorSetId) -> ValidatorSet<AuthorityId> {
+    ValidatorSet::new(
+        validators
+            .iter()
+            .map(|pair| AuthorityId::from(pair.public()))
+            .collect::<Vec<_>>(),
+        set_id,
+    )
+    .expect("validator set is not empty; qed")
+}
+
+fn next_authority_set(validators: &[ecdsa::Pair], set_id: ValidatorSetId) -> NextAuthoritySet {
+    let addresses = validators
+        .iter()
+        .map(|pair| pallet_beefy_mmr::BeefyEcdsaToEthereum::convert(pair.public().into()));
+
+    NextAuthoritySet {
+        set_id,
+        len: validators.len() as u32,
+        root: beefy_merkle_tree::merkle_root::<beefy_merkle_tree::Keccak256, _, _>(addresses),
+    }
+}
+
+fn authorities_change_log(validator_set: ValidatorSet<AuthorityId>) -> Digest {
+    Digest {
+        logs: vec![DigestItem::Consensus(
+            BEEFY_ENGINE_ID,
+            ConsensusLog::AuthoritiesChange(validator_set).encode(),
+        )],
+    }
+}
+
+fn valid_extrinsics() -> Vec<OpaqueExtrinsic> {
+    vec![(0..255).collect()]
+}
+
+fn test_header(number: u32, parent: &TestHeader) -> TestHeader {
+    TestHeader::new(
+        number,
+        BlakeTwo256::ordered_trie_root(
+            valid_extrinsics().iter().map(Encode::encode).collect(),
+            sp_runtime::StateVersion::V0,
+        ),
+        Default::default(),
+        parent.hash(),
+        Default::default(),
+    )
+}
+
+/// Creates a finality proof for the header out of a single leaf MMR, which root is the hash of the
+/// leaf itself.
+fn make_finality_proof(
+    header: &TestHeader,
+    signers: &[Option<&ecdsa::Pair>],
+    set_id: ValidatorSetId,
+    next_authority_set: NextAuthoritySet,
+) -> BeefyFinalityProof<u32, <Keccak256 as Hash>::Output> {
+    let mmr_leaf = (0u8, (*header.number(), header.hash()), next_authority_set).encode();
+    let mmr_root = Keccak256::hash(&mmr_leaf);
+
+    let commitment = Commitment {
+        payload: Payload::new(known_payload_ids::MMR_ROOT_ID, mmr_root.encode()),
+        block_number: header.number() + 1,
+        validator_set_id: set_id,
+    };
+    let message = sp_io::hashing::keccak_256(&commitment.encode());
+    let signatures = signers
+        .iter()
+        .map(|maybe_signer| {
+            maybe_signer.map(|signer| Signature::from(signer.sign_prehashed(&message)))
+        })
+        .collect();
+
+    BeefyFinalityProof {
+        signed_commitment: SignedCommitment {
+            commitment,
+            signatures,
+        },
+        mmr_leaf,
+        mmr_proof: Proof {
+            leaf_index: 0,
+            leaf_count: 1,
+            items: vec![],
+        },
+    }
+}
+
+fn make_default_finality_proof(
+    header: &TestHeader,
+    validators: &[ecdsa::Pair],
+) -> BeefyFinalityProof<u32, <Keccak256 as Hash>::Output> {
+    make_finality_proof(
+        header,
+        &validators.iter().map(Some).collect::<Vec<_>>(),
+        TEST_VALIDATOR_SET_ID,
+        Default::default(),
+    )
+}
+
+fn submit_finality_proof(
+    chain_id: ChainId,
+    header: TestHeader,
+    maybe_finality_proof: Option<BeefyFinalityProof<u32, <Keccak256 as Hash>::Output>>,
+) -> DispatchResult {
+    let justifications = maybe_finality_proof
+        .map(|finality_proof| (BEEFY_ENGINE_ID, finality_proof.encode()).into());
+    let block = SignedBlock {
+        block: generic::Block::<TestHeader, OpaqueExtrinsic> {
+            header,
+            extrinsics: valid_extrinsics(),
+        },
+        justifications,
+    };
+
+    validate_finalized_block::<TestRuntime, TestFeedChain>(chain_id, block.encode().as_slice())?;
+    Ok(())
+}
+
+fn init_with_origin(
+    chain_id: ChainId,
+    validators: &[ecdsa::Pair],
+) -> Result<TestHeader, DispatchError> {
+    let mut genesis = TestHeader::new(
+        0,
+        BlakeTwo256::ordered_trie_root(
+            valid_extrinsics().iter().map(Encode::encode).collect(),
+            sp_runtime::StateVersion::V0,
+        ),
+        Default::default(),
+        Default::default(),
+        Default::default(),
+    );
+    genesis.digest = authorities_change_log(validator_set(validators, TEST_VALIDATOR_SET_ID));
+    let init_data = InitializationData {
+        best_known_finalized_header: genesis.encode(),
+    };
+
+    initialize::<TestRuntime, TestFeedChain>(chain_id, init_data.encode().as_slice())?;
+    // import block
+    submit_finality_proof(chain_id, genesis.clone(), None)?;
+    Ok(genesis)
+}
+
+#[test]
+fn test_init_storage_entries_are_correctly_initialized() {
+    run_test(|| {
+        let chain_id: ChainId = 1;
+        let validators = create_validators(1, 4);
+        let genesis = init_with_origin(chain_id, &validators).unwrap();
+
+        assert_eq!(
+            CurrentAuthoritySet::<TestRuntime>::get(chain_id),
+            AuthoritySet::from(validator_set(&validators, TEST_VALIDATOR_SET_ID))
+        );
+        assert_eq!(
+            <ValidationCheckPoint<TestRuntime>>::get(chain_id),
+            (0u32.encode(), genesis.encode())
+        );
+        assert_eq!(
+            <ChainTip<TestRuntime>>::get(chain_id),
+            (0u32.encode(), genesis.hash().encode())
+        );
+        assert_eq!(
+            <OldestKnownParent<TestRuntime>>::get(chain_id),
+            (0u32.encode(), [0u8; 32].encode())
+        );
+    })
+}
+
+#[test]
+fn initialization_requires_validator_set() {
+    run_test(|| {
+        let init_data = InitializationData {
+            best_known_finalized_header: TestHeader::new(
+                0,
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+            )
+            .encode(),
+        };
+
+        assert_err!(
+            initialize::<TestRuntime, TestFeedChain>(1, init_data.encode().as_slice()),
+            ErrorP::<TestRuntime>::MissingAuthoritiesChange
+        );
+    })
+}
+
+#[test]
+fn successfully_imports_header_in_forward_direction() {
+    run_test(|| {
+        let chain_id: ChainId = 1;
+        let validators = create_validators(1, 4);
+        let mut parent_header = init_with_origin(chain_id, &validators).unwrap();
+
+        for tip in 1..10 {
+            let header = test_header(tip, &parent_header);
+            assert_ok!(submit_finality_proof(
+                chain_id,
+                header.clone(),
+                Some(make_default_finality_proof(&header, &validators))
+            ));
+            assert_eq!(
+                <ChainTip<TestRuntime>>::get(chain_id),
+                (tip.encode(), header.hash().encode())
+            );
+            parent_header = header;
+        }
+    })
+}
+
+#[test]
+fn does_not_import_header_without_finality_proof() {
+    run_test(|| {
+        let chain_id: ChainId = 1;
+        let validators = create_validators(1, 4);
+        let genesis = init_with_origin(chain_id, &validators).unwrap();
+
+        assert_err!(
+            submit_finality_proof(chain_id, test_header(1, &genesis), None),
+            ErrorP::<TestRuntime>::MissingFinalityProof
+        );
+    })
+}
+
+#[test]
+fn does_not_import_header_with_invalid_finality_proof() {
+    run_test(|| {
+        let chain_id: ChainId = 1;
+        let validators = create_validators(1, 4);
+        let genesis = init_with_origin(chain_id, &validators).unwrap();
+        let header = test_header(1, &genesis);
+        let signers = validators.iter().map(Some).collect::<Vec<_>>();
+
+        // signed by another validator set
+        let finality_proof = make_finality_proof(
+            &header,
+            &signers,
+            TEST_VALIDATOR_SET_ID + 1,
+            Default::default(),
+        );
+        assert_err!(
+            submit_finality_proof(chain_id, header.clone(), Some(finality_proof)),
+            ErrorP::<TestRuntime>::InvalidFinalityProof
+        );
+
+        // not enough signatures, 3 out of 4 are required
+        let finality_proof = make_finality_proof(
+            &header,
+            &[Some(&validators[0]), Some(&validators[1]), None, None],
+            TEST_VALIDATOR_SET_ID,
+            Default::default(),
+        );
+        assert_err!(
+            submit_finality_proof(chain_id, header.clone(), Some(finality_proof)),
+            ErrorP::<TestRuntime>::InvalidFinalityProof
+        );
+
+        // signed by unknown validators
+        let finality_proof = make_default_finality_proof(&header, &create_validators(100, 4));
+        assert_err!(
+            submit_finality_proof(chain_id, header.clone(), Some(finality_proof)),
+            ErrorP::<TestRuntime>::InvalidFinalityProof
+        );
+
+        // MMR leaf commits to another block
+        let finality_proof = make_default_finality_proof(&test_header(1, &header), &validators);
+        assert_err!(
+            submit_finality_proof(chain_id, header.clone(), Some(finality_proof)),
+            ErrorP::<TestRuntime>::InvalidFinalityProof
+        );
+
+        // MMR leaf is not included in the signed MMR
+        let mut finality_proof = make_default_finality_proof(&header, &validators);
+        finality_proof.mmr_proof.leaf_count = 2;
+        assert_err!(
+            submit_finality_proof(chain_id, header.clone(), Some(finality_proof)),
+            ErrorP::<TestRuntime>::InvalidFinalityProof
+        );
+
+        // tolerates a third of validators missing
+        let finality_proof = make_finality_proof(
+            &header,
+            &[
+                Some(&validators[0]),
+                Some(&validators[1]),
+                None,
+                Some(&validators[3]),
+            ],
+            TEST_VALIDATOR_SET_ID,
+            Default::default(),
+        );
+        assert_ok!(submit_finality_proof(
+            chain_id,
+            header,
+            Some(finality_proof)
+        ));
+    })
+}
+
+#[test]
+fn importing_header_enacts_committed_validator_set() {
+    run_test(|| {
+        let chain_id: ChainId = 1;
+        let validators = create_validators(1, 4);
+        let next_validators = create_validators(50, 3);
+        let genesis = init_with_origin(chain_id, &validators).unwrap();
+
+        // the current validator set commits to the next one in the MMR leaf
+        let header = test_header(1, &genesis);
+        let finality_proof = make_finality_proof(
+            &header,
+            &validators.iter().map(Some).collect::<Vec<_>>(),
+            TEST_VALIDATOR_SET_ID,
+            next_authority_set(&next_validators, TEST_VALIDATOR_SET_ID + 1),
+        );
+        assert_ok!(submit_finality_proof(
+            chain_id,
+            header.clone(),
+            Some(finality_proof)
+        ));
+
+        // the header signaling the next validator set is finalized by that set
+        let mut next_header = test_header(2, &header);
+        next_header.digest =
+            authorities_change_log(validator_set(&next_validators, TEST_VALIDATOR_SET_ID + 1));
+        let finality_proof = make_finality_proof(
+            &next_header,
+            &next_validators.iter().map(Some).collect::<Vec<_>>(),
+            TEST_VALIDATOR_SET_ID + 1,
+            Default::default(),
+        );
+        assert_ok!(submit_finality_proof(
+            chain_id,
+            next_header,
+            Some(finality_proof)
+        ));
+
+        assert_eq!(
+            CurrentAuthoritySet::<TestRuntime>::get(chain_id),
+            AuthoritySet::from(validator_set(&next_validators, TEST_VALIDATOR_SET_ID + 1))
+        );
+    })
+}
+
+#[test]
+fn importing_header_rejects_uncommitted_validator_set() {
+    run_test(|| {
+        let chain_id: ChainId = 1;
+        let validators = create_validators(1, 4);
+        let next_validators = create_validators(50, 3);
+        let genesis = init_with_origin(chain_id, &validators).unwrap();
+
+        // the current validator set never committed to the next one
+        let mut header = test_header(1, &genesis);
+        header.digest =
+            authorities_change_log(validator_set(&next_validators, TEST_VALIDATOR_SET_ID + 1));
+        let finality_proof = make_finality_proof(
+            &header,
+            &next_validators.iter().map(Some).collect::<Vec<_>>(),
+            TEST_VALIDATOR_SET_ID + 1,
+            Default::default(),
+        );
+
+        assert_err!(
+            submit_finality_proof(chain_id, header, Some(finality_proof)),
+            ErrorP::<TestRuntime>::InvalidAuthoritySet
+        );
+    })
+}
@@ -14,6 +14,21 @@
 // limitations under the License.
 
 //! Pallet Executor
+//!
+//! Besides tracking the execution receipts, this pallet hosts the bisection disputes over them.
+//! A challenger who disagrees with the execution trace of a receipt opens a [`Dispute`] with its
+//! own final state root and a bond, then reveals its intermediate roots one at a time. Each
+//! revealed root is compared against the trace committed by the executor in the receipt, halving
+//! the disputed range until the first diverging trace index is found, i.e., the execution phase a
+//! fraud proof has to cover.
+//!
+//! Up to [`Config::MaxDisputesPerReceipt`] challengers can dispute the same receipt
+//! independently. A fraud proof is only accepted for the execution phase a settled dispute
+//! diverges at, starting from the executor's root before it and ending at the challenger's root,
+//! and it must pass the verification. Once the receipt is reverted by a fraud proof, the bond of
+//! the challenger whose dispute it resolves is returned, while the bonds of the other disputes
+//! over the reverted receipts, and of the disputes that expire or outlive the receipt, are
+//! slashed.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
@@ -21,19 +36,25 @@
 mod tests;
 
 use frame_support::ensure;
-use frame_support::traits::Get;
+use frame_support::traits::{Currency, Get, ReservableCurrency};
+use frame_support::weights::Weight;
 use frame_system::offchain::SubmitTransaction;
 pub use pallet::*;
 use sp_executor::{
-    BundleEquivocationProof, FraudProof, InvalidTransactionProof, SignedExecutionReceipt,
-    SignedOpaqueBundle,
+    BundleEquivocationProof, ExecutionPhase, ExecutionReceipt, FraudProof, InvalidTransactionProof,
+    SignedExecutionReceipt, SignedOpaqueBundle,
 };
 use sp_runtime::traits::{BlockNumberProvider, One};
 use sp_runtime::RuntimeAppPublic;
 
+type BalanceOf<T> =
+    <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
+
 #[frame_support::pallet]
 mod pallet {
+    use super::BalanceOf;
     use frame_support::pallet_prelude::*;
+    use frame_support::traits::ReservableCurrency;
     use frame_support::PalletError;
     use frame_system::pallet_prelude::*;
     use sp_core::H256;
@@ -43,8 +64,10 @@ mod pallet {
     };
     use sp_runtime::traits::{
         CheckEqual, CheckedSub, MaybeDisplay, MaybeMallocSizeOf, One, SimpleBitOps,
+        UniqueSaturatedInto,
     };
     use sp_std::fmt::Debug;
+    use subspace_runtime_primitives::OnExecutionReceipt;
 
     #[pallet::config]
     pub trait Config: frame_system::Config {
@@ -77,6 +100,26 @@ mod pallet {
         /// best execution chain number, this receipt will be rejected as being too far in the
         /// future.
         type MaximumReceiptDrift: Get<Self::BlockNumber>;
+
+        /// Number of blocks the challenger has to take the next step of a dispute, i.e., to
+        /// reveal the next intermediate root or to get the receipt reverted by a fraud proof once
+        /// the dispute is settled, before the dispute expires.
+        #[pallet::constant]
+        type DisputeTimeout: Get<Self::BlockNumber>;
+
+        /// Currency the dispute bonds are reserved in.
+        type Currency: ReservableCurrency<Self::AccountId>;
+
+        /// Amount reserved from the challenger for opening a dispute.
+        #[pallet::constant]
+        type DisputeBond: Get<BalanceOf<Self>>;
+
+        /// Maximum number of pending disputes over an execution receipt.
+        #[pallet::constant]
+        type MaxDisputesPerReceipt: Get<u32>;
+
+        /// Handler of the execution receipts that were pruned or reverted.
+        type OnExecutionReceipt: OnExecutionReceipt<Self::BlockNumber, Self::AccountId>;
     }
 
     #[pallet::pallet]
@@ -126,6 +169,11 @@ mod pallet {
         ExecutionReceiptPruned,
         /// Trying to prove an receipt from the future.
         ExecutionReceiptInFuture,
+        /// The fraud proof doesn't cover the diverging execution phase of any settled dispute over
+        /// the execution receipt.
+        NoSettledDispute,
+        /// The fraud proof failed the verification.
+        BadProof,
     }
 
     impl<T> From<FraudProofError> for Error<T> {
@@ -134,6 +182,32 @@ mod pallet {
         }
     }
 
+    #[derive(TypeInfo, Encode, Decode, PalletError, Debug)]
+    pub enum DisputeError {
+        /// The disputed execution receipt is not in the state.
+        UnknownExecutionReceipt,
+        /// The disputed execution receipt has an empty trace.
+        EmptyTrace,
+        /// The final state root of the challenger matches the one of the execution receipt.
+        NoDivergence,
+        /// The challenger has already opened a dispute over the execution receipt.
+        AlreadyOpened,
+        /// The challenger has no dispute over the execution receipt.
+        NotFound,
+        /// The first diverging trace index has already been found.
+        AlreadySettled,
+        /// The challenger can't afford the dispute bond.
+        InsufficientBond,
+        /// The execution receipt is already disputed by the maximum number of challengers.
+        TooManyDisputes,
+    }
+
+    impl<T> From<DisputeError> for Error<T> {
+        fn from(e: DisputeError) -> Self {
+            Self::Dispute(e)
+        }
+    }
+
     #[pallet::error]
     pub enum Error<T> {
         /// Invalid bundle.
@@ -142,6 +216,8 @@ mod pallet {
         ExecutionReceipt(ExecutionReceiptError),
         /// Invalid fraud proof.
         FraudProof(FraudProofError),
+        /// Invalid dispute step.
+        Dispute(DisputeError),
     }
 
     #[pallet::event]
@@ -160,12 +236,44 @@ mod pallet {
         BundleEquivocationProofProcessed,
         /// An invalid transaction proof was processed.
         InvalidTransactionProofProcessed,
+        /// A dispute over an execution receipt was opened.
+        DisputeOpened {
+            primary_number: T::BlockNumber,
+            challenger: T::AccountId,
+        },
+        /// The disputed range of a trace was narrowed down.
+        DisputeBisected {
+            primary_number: T::BlockNumber,
+            challenger: T::AccountId,
+            agreed_len: u32,
+            disputed_index: u32,
+        },
+        /// The first diverging trace index of a dispute was found, a fraud proof for the execution
+        /// phase at this index is expected.
+        DisputeSettled {
+            primary_number: T::BlockNumber,
+            challenger: T::AccountId,
+            trace_index: u32,
+        },
+        /// The disputed execution receipt was reverted by a fraud proof for the dispute, the bond
+        /// was returned.
+        DisputeResolved {
+            primary_number: T::BlockNumber,
+            challenger: T::AccountId,
+        },
+        /// A dispute expired as the challenger didn't take the next step in time, or the disputed
+        /// execution receipt was pruned or reverted by a fraud proof for another dispute, the bond
+        /// was slashed.
+        DisputeExpired {
+            primary_number: T::BlockNumber,
+            challenger: T::AccountId,
+        },
     }
 
     #[pallet::call]
     impl<T: Config> Pallet<T> {
         // TODO: proper weight
-        #[pallet::weight((10_000, Pays::No))]
+        #[pallet::weight((10_000 + Pallet::<T>::remove_receipt_weight(), Pays::No))]
         pub fn submit_execution_receipt(
             origin: OriginFor<T>,
             signed_execution_receipt: SignedExecutionReceipt<
@@ -183,7 +291,9 @@ mod pallet {
             );
 
             let SignedExecutionReceipt {
-                execution_receipt, ..
+                execution_receipt,
+                signer,
+                ..
             } = signed_execution_receipt;
 
             let primary_hash = execution_receipt.primary_hash;
@@ -203,10 +313,18 @@ mod pallet {
             // Apply the execution receipt.
             <Receipts<T>>::insert(primary_number, execution_receipt);
             <ExecutionChainBestNumber<T>>::put(primary_number);
+            if let Some((executor, _)) = Self::executor().filter(|(_, key)| *key == signer) {
+                ReceiptAuthors::<T>::insert(primary_number, executor);
+            }
 
             // Remove the oldest once the receipts cache is full.
             if let Some(to_prune) = primary_number.checked_sub(&T::ReceiptsPruningDepth::get()) {
                 Receipts::<T>::remove(to_prune);
+                // The pruned receipt can't be challenged anymore.
+                if let Some(executor) = ReceiptAuthors::<T>::take(to_prune) {
+                    T::OnExecutionReceipt::on_receipt_confirmed(to_prune, executor);
+                }
+                Self::close_disputes(to_prune);
                 BlockHash::<T>::remove(to_prune);
                 OldestReceiptNumber::<T>::put(to_prune + One::one());
             }
@@ -241,8 +359,16 @@ mod pallet {
         }
 
         // TODO: proper weight
-        #[pallet::weight((10_000, Pays::No))]
-        pub fn submit_fraud_proof(origin: OriginFor<T>, fraud_proof: FraudProof) -> DispatchResult {
+        #[pallet::weight((
+            10_000
+                + Pallet::<T>::remove_receipt_weight()
+                    .saturating_mul(T::ReceiptsPruningDepth::get().unique_saturated_into()),
+            Pays::No
+        ))]
+        pub fn submit_fraud_proof(
+            origin: OriginFor<T>,
+            fraud_proof: FraudProof,
+        ) -> DispatchResultWithPostInfo {
             ensure_none(origin)?;
 
             log::debug!(
@@ -251,13 +377,34 @@ mod pallet {
                 fraud_proof
             );
 
-            // Revert the execution chain.
+            let challenger = Self::validate_fraud_proof(&fraud_proof).map_err(Error::<T>::from)?;
+
+            // Return the bond of the challenger whose dispute is resolved by the fraud proof.
             let new_best: T::BlockNumber = fraud_proof.parent_number.into();
+            let primary_number = new_best + One::one();
+            if let Some(dispute) = Disputes::<T>::take(primary_number, &challenger) {
+                DisputeDeadlines::<T>::remove(
+                    dispute.deadline,
+                    (primary_number, challenger.clone()),
+                );
+                T::Currency::unreserve(&challenger, dispute.bond);
+                Self::deposit_event(Event::DisputeResolved {
+                    primary_number,
+                    challenger,
+                });
+            }
+
+            // Revert the execution chain.
+            let mut reverted = 0u64;
             <ExecutionChainBestNumber<T>>::mutate(|current_best| {
-                let mut to_remove = new_best + One::one();
+                let mut to_remove = primary_number;
                 while to_remove <= *current_best {
                     Receipts::<T>::remove(to_remove);
+                    ReceiptAuthors::<T>::remove(to_remove);
+                    T::OnExecutionReceipt::on_receipt_reverted(to_remove);
+                    Self::close_disputes(to_remove);
                     to_remove += One::one();
+                    reverted += 1;
                 }
                 *current_best = new_best;
             });
@@ -266,7 +413,7 @@ mod pallet {
 
             Self::deposit_event(Event::FraudProofProcessed);
 
-            Ok(())
+            Ok(Some(10_000 + Self::remove_receipt_weight().saturating_mul(reverted)).into())
         }
 
         // TODO: proper weight
@@ -310,6 +457,128 @@ mod pallet {
 
             Ok(())
         }
+
+        /// Opens a dispute over the execution receipt at `primary_number`, claiming its final
+        /// state root is `final_root` instead of the one in the receipt.
+        ///
+        /// [`Config::DisputeBond`] is reserved from the challenger until the dispute is closed.
+        // TODO: proper weight
+        #[pallet::weight(
+            10_000 + T::DbWeight::get().reads(T::MaxDisputesPerReceipt::get().into())
+        )]
+        pub fn open_dispute(
+            origin: OriginFor<T>,
+            primary_number: T::BlockNumber,
+            final_root: T::SecondaryHash,
+        ) -> DispatchResult {
+            let challenger = ensure_signed(origin)?;
+
+            let receipt = Self::validate_dispute_opening(primary_number, &challenger, &final_root)
+                .map_err(Error::<T>::from)?;
+
+            let bond = T::DisputeBond::get();
+            T::Currency::reserve(&challenger, bond)
+                .map_err(|_| Error::<T>::Dispute(DisputeError::InsufficientBond))?;
+
+            let dispute = Dispute {
+                agreed_len: 0,
+                disputed_index: receipt.trace.len() as u32 - 1,
+                disputed_root: final_root,
+                deadline: Self::dispute_deadline(),
+                bond,
+            };
+            DisputeDeadlines::<T>::insert(
+                dispute.deadline,
+                (primary_number, challenger.clone()),
+                (),
+            );
+            Disputes::<T>::insert(primary_number, &challenger, dispute);
+
+            Self::deposit_event(Event::DisputeOpened {
+                primary_number,
+                challenger,
+            });
+
+            Ok(())
+        }
+
+        /// Takes the next step of the challenger's dispute over the execution receipt at
+        /// `primary_number` by revealing its root at [`Dispute::next_index`].
+        // TODO: proper weight
+        #[pallet::weight(10_000)]
+        pub fn bisect_dispute(
+            origin: OriginFor<T>,
+            primary_number: T::BlockNumber,
+            root: T::SecondaryHash,
+        ) -> DispatchResult {
+            let challenger = ensure_signed(origin)?;
+
+            let (mut dispute, receipt) =
+                Self::validate_dispute_bisection(primary_number, &challenger)
+                    .map_err(Error::<T>::from)?;
+
+            let index = dispute.next_index();
+            if receipt.trace[index as usize] == root {
+                dispute.agreed_len = index + 1;
+            } else {
+                dispute.disputed_index = index;
+                dispute.disputed_root = root;
+            }
+
+            let deadline = Self::dispute_deadline();
+            DisputeDeadlines::<T>::remove(dispute.deadline, (primary_number, challenger.clone()));
+            DisputeDeadlines::<T>::insert(deadline, (primary_number, challenger.clone()), ());
+            dispute.deadline = deadline;
+
+            if dispute.is_settled() {
+                Self::deposit_event(Event::DisputeSettled {
+                    primary_number,
+                    challenger: challenger.clone(),
+                    trace_index: dispute.disputed_index,
+                });
+            } else {
+                Self::deposit_event(Event::DisputeBisected {
+                    primary_number,
+                    challenger: challenger.clone(),
+                    agreed_len: dispute.agreed_len,
+                    disputed_index: dispute.disputed_index,
+                });
+            }
+
+            Disputes::<T>::insert(primary_number, &challenger, dispute);
+
+            Ok(())
+        }
+    }
+
+    /// State of a bisection dispute over the trace of an execution receipt.
+    ///
+    /// The trace of the receipt is the executor's side of the dispute, the challenger narrows
+    /// down `[agreed_len, disputed_index]` by revealing its own intermediate roots.
+    #[derive(TypeInfo, Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+    pub struct Dispute<BlockNumber, SecondaryHash, Balance> {
+        /// Number of the leading trace roots agreed by the challenger.
+        pub agreed_len: u32,
+        /// The lowest trace index known to diverge.
+        pub disputed_index: u32,
+        /// The challenger's root at `disputed_index`.
+        pub disputed_root: SecondaryHash,
+        /// The dispute expires if the challenger takes no step until this block.
+        pub deadline: BlockNumber,
+        /// Amount reserved from the challenger.
+        pub bond: Balance,
+    }
+
+    impl<BlockNumber, SecondaryHash, Balance> Dispute<BlockNumber, SecondaryHash, Balance> {
+        /// Returns `true` if the first diverging trace index has been found, i.e., `disputed_index`.
+        pub fn is_settled(&self) -> bool {
+            self.agreed_len == self.disputed_index
+        }
+
+        /// Returns the trace index the challenger has to reveal its root at in the next step.
+        pub fn next_index(&self) -> u32 {
+            self.agreed_len + (self.disputed_index - self.agreed_len) / 2
+        }
     }
 
     /// A tuple of (stable_executor_id, executor_signing_key).
@@ -322,6 +591,7 @@ mod pallet {
     /// The capacity of receipts stored in the state is [`Config::ReceiptsPruningDepth`], the older
     /// ones will be pruned once the size of receipts exceeds this number.
     #[pallet::storage]
+    #[pallet::getter(fn receipts)]
     pub(super) type Receipts<T: Config> = StorageMap<
         _,
         Twox64Concat,
@@ -330,6 +600,13 @@ mod pallet {
         OptionQuery,
     >;
 
+    /// Mapping from the primary block number to the executor that authored the execution receipt
+    /// of the block, pruned along with [`Receipts`].
+    #[pallet::storage]
+    #[pallet::getter(fn receipt_authors)]
+    pub(super) type ReceiptAuthors<T: Config> =
+        StorageMap<_, Twox64Concat, T::BlockNumber, T::AccountId, OptionQuery>;
+
     /// Map of block number to block hash.
     ///
     /// NOTE: The oldest block hash will be pruned once the oldest receipt is pruned. However, if the
@@ -349,6 +626,33 @@ mod pallet {
     #[pallet::storage]
     pub(super) type OldestReceiptNumber<T: Config> = StorageValue<_, T::BlockNumber, ValueQuery>;
 
+    /// Pending disputes over the execution receipts, keyed by the primary block number and the
+    /// challenger, each challenger can have one dispute per receipt.
+    #[pallet::storage]
+    #[pallet::getter(fn disputes)]
+    pub(super) type Disputes<T: Config> = StorageDoubleMap<
+        _,
+        Twox64Concat,
+        T::BlockNumber,
+        Blake2_128Concat,
+        T::AccountId,
+        Dispute<T::BlockNumber, T::SecondaryHash, BalanceOf<T>>,
+        OptionQuery,
+    >;
+
+    /// Index of the pending disputes by their deadline, which allows to find the expired ones
+    /// without iterating over all the disputes.
+    #[pallet::storage]
+    pub(super) type DisputeDeadlines<T: Config> = StorageDoubleMap<
+        _,
+        Twox64Concat,
+        T::BlockNumber,
+        Blake2_128Concat,
+        (T::BlockNumber, T::AccountId),
+        (),
+        OptionQuery,
+    >;
+
     #[pallet::hooks]
     impl<T: Config> Hooks<T::BlockNumber> for Pallet<T> {
         fn on_initialize(block_number: T::BlockNumber) -> Weight {
@@ -356,7 +660,20 @@ mod pallet {
                 block_number - One::one(),
                 frame_system::Pallet::<T>::parent_hash(),
             );
-            T::DbWeight::get().writes(1)
+
+            // Each step moves the dispute to a later deadline, the ones left at the deadline of the
+            // previous block have expired.
+            let mut expired = 0u64;
+            for ((primary_number, challenger), ()) in
+                DisputeDeadlines::<T>::drain_prefix(block_number - One::one())
+            {
+                if let Some(dispute) = Disputes::<T>::take(primary_number, &challenger) {
+                    Self::slash_dispute(primary_number, challenger, dispute);
+                }
+                expired += 1;
+            }
+
+            T::DbWeight::get().reads_writes(1 + 2 * expired, 1 + 3 * expired)
         }
     }
 
@@ -631,7 +948,9 @@ impl<T: Config> Pallet<T> {
         Ok(())
     }
 
-    fn validate_fraud_proof(fraud_proof: &FraudProof) -> Result<(), FraudProofError> {
+    /// Validates the fraud proof against the settled disputes over the execution receipt it
+    /// proves and returns the challenger of the dispute it resolves.
+    fn validate_fraud_proof(fraud_proof: &FraudProof) -> Result<T::AccountId, FraudProofError> {
         let to_prove: T::BlockNumber = (fraud_proof.parent_number + 1u32).into();
         ensure!(
             to_prove >= OldestReceiptNumber::<T>::get(),
@@ -641,15 +960,137 @@ impl<T: Config> Pallet<T> {
             to_prove <= ExecutionChainBestNumber::<T>::get(),
             FraudProofError::ExecutionReceiptInFuture
         );
+        let receipt =
+            Receipts::<T>::get(to_prove).ok_or(FraudProofError::ExecutionReceiptPruned)?;
 
-        // TODO: prevent the spamming of fraud proof transaction.
-        // TODO: verify the fraud proof on the client side.
-        // if !sp_executor::fraud_proof_ext::fraud_proof::verify(fraud_proof) {
-        // log::error!(target: "runtime::subspace::executor", "Invalid fraud proof: {:?}", fraud_proof);
-        // return InvalidTransaction::Custom(INVALID_FRAUD_PROOF).into();
-        // }
+        let challenger = Disputes::<T>::iter_prefix(to_prove)
+            .find(|(_, dispute)| {
+                dispute.is_settled()
+                    && Self::fraud_proof_covers_dispute(fraud_proof, &receipt, dispute)
+            })
+            .map(|(challenger, _)| challenger)
+            .ok_or(FraudProofError::NoSettledDispute)?;
 
-        Ok(())
+        ensure!(
+            sp_executor::fraud_proof_ext::fraud_proof::verify(fraud_proof),
+            FraudProofError::BadProof
+        );
+
+        Ok(challenger)
+    }
+
+    /// Returns `true` if the fraud proof covers the execution phase at the disputed index of the
+    /// settled dispute, i.e., it starts from the executor's root before the phase and ends at the
+    /// challenger's root.
+    fn fraud_proof_covers_dispute(
+        fraud_proof: &FraudProof,
+        receipt: &ExecutionReceipt<T::BlockNumber, T::Hash, T::SecondaryHash>,
+        dispute: &Dispute<T::BlockNumber, T::SecondaryHash, BalanceOf<T>>,
+    ) -> bool {
+        let index = dispute.disputed_index as usize;
+        let is_final_index = index + 1 == receipt.trace.len();
+        let phase_matches = match fraud_proof.execution_phase {
+            ExecutionPhase::InitializeBlock { .. } => index == 0,
+            ExecutionPhase::ApplyExtrinsic { .. } => index > 0 && !is_final_index,
+            ExecutionPhase::FinalizeBlock => index > 0 && is_final_index,
+        };
+
+        // The first phase starts from the final root of the parent receipt, which can't be
+        // checked once the parent receipt is pruned.
+        let pre_state_root = match index.checked_sub(1) {
+            Some(pre_index) => receipt.trace.get(pre_index).copied(),
+            None => Receipts::<T>::get(receipt.primary_number - One::one())
+                .and_then(|parent_receipt| parent_receipt.trace.last().copied()),
+        };
+
+        phase_matches
+            && pre_state_root
+                .map(|root| root.as_ref() == fraud_proof.pre_state_root.as_bytes())
+                .unwrap_or(false)
+            && dispute.disputed_root.as_ref() == fraud_proof.post_state_root.as_bytes()
+    }
+
+    fn validate_dispute_opening(
+        primary_number: T::BlockNumber,
+        challenger: &T::AccountId,
+        final_root: &T::SecondaryHash,
+    ) -> Result<ExecutionReceipt<T::BlockNumber, T::Hash, T::SecondaryHash>, DisputeError> {
+        let receipt =
+            Receipts::<T>::get(primary_number).ok_or(DisputeError::UnknownExecutionReceipt)?;
+
+        ensure!(
+            !Disputes::<T>::contains_key(primary_number, challenger),
+            DisputeError::AlreadyOpened
+        );
+        ensure!(
+            (Disputes::<T>::iter_key_prefix(primary_number).count() as u32)
+                < T::MaxDisputesPerReceipt::get(),
+            DisputeError::TooManyDisputes
+        );
+
+        match receipt.trace.last() {
+            Some(receipt_final_root) => {
+                ensure!(receipt_final_root != final_root, DisputeError::NoDivergence)
+            }
+            None => return Err(DisputeError::EmptyTrace),
+        }
+
+        Ok(receipt)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn validate_dispute_bisection(
+        primary_number: T::BlockNumber,
+        challenger: &T::AccountId,
+    ) -> Result<
+        (
+            Dispute<T::BlockNumber, T::SecondaryHash, BalanceOf<T>>,
+            ExecutionReceipt<T::BlockNumber, T::Hash, T::SecondaryHash>,
+        ),
+        DisputeError,
+    > {
+        let dispute =
+            Disputes::<T>::get(primary_number, challenger).ok_or(DisputeError::NotFound)?;
+        ensure!(!dispute.is_settled(), DisputeError::AlreadySettled);
+
+        let receipt =
+            Receipts::<T>::get(primary_number).ok_or(DisputeError::UnknownExecutionReceipt)?;
+
+        Ok((dispute, receipt))
+    }
+
+    fn dispute_deadline() -> T::BlockNumber {
+        frame_system::Pallet::<T>::current_block_number() + T::DisputeTimeout::get()
+    }
+
+    /// Closes all the pending disputes over the execution receipt at `primary_number`, which is
+    /// pruned or reverted, slashing their bonds.
+    ///
+    /// There are at most [`Config::MaxDisputesPerReceipt`] of them.
+    fn close_disputes(primary_number: T::BlockNumber) {
+        for (challenger, dispute) in Disputes::<T>::drain_prefix(primary_number) {
+            DisputeDeadlines::<T>::remove(dispute.deadline, (primary_number, challenger.clone()));
+            Self::slash_dispute(primary_number, challenger, dispute);
+        }
+    }
+
+    /// Weight of removing an execution receipt along with closing the disputes over it.
+    fn remove_receipt_weight() -> Weight {
+        let max_disputes = Weight::from(T::MaxDisputesPerReceipt::get());
+        T::DbWeight::get().reads_writes(2 + 2 * max_disputes, 4 + 3 * max_disputes)
+    }
+
+    fn slash_dispute(
+        primary_number: T::BlockNumber,
+        challenger: T::AccountId,
+        dispute: Dispute<T::BlockNumber, T::SecondaryHash, BalanceOf<T>>,
+    ) {
+        // The slashed bond is burnt.
+        let _ = T::Currency::slash_reserved(&challenger, dispute.bond);
+        Self::deposit_event(Event::DisputeExpired {
+            primary_number,
+            challenger,
+        });
     }
 
     // TODO: Checks if the bundle equivocation proof is valid.
@@ -1,17 +1,21 @@
 use crate::{
-    self as pallet_executor, BlockHash, Error, ExecutionChainBestNumber, ExecutionReceiptError,
-    OldestReceiptNumber, Receipts,
+    self as pallet_executor, BlockHash, Dispute, DisputeDeadlines, DisputeError, Disputes, Error,
+    ExecutionChainBestNumber, ExecutionReceiptError, FraudProofError, OldestReceiptNumber,
+    ReceiptAuthors, Receipts,
 };
-use frame_support::traits::{ConstU16, ConstU32, ConstU64, GenesisBuild, Hooks};
+use frame_support::traits::{ConstU128, ConstU16, ConstU32, ConstU64, GenesisBuild, Hooks};
 use frame_support::{assert_noop, assert_ok, parameter_types};
 use sp_core::crypto::Pair;
 use sp_core::{H256, U256};
+use sp_executor::fraud_proof_ext::{Externalities, FraudProofExt};
 use sp_executor::{
     ExecutionPhase, ExecutionReceipt, ExecutorPair, FraudProof, SignedExecutionReceipt,
 };
 use sp_runtime::testing::Header;
 use sp_runtime::traits::{BlakeTwo256, IdentityLookup};
-use sp_trie::StorageProof;
+use sp_trie::CompactProof;
+use std::cell::RefCell;
+use subspace_runtime_primitives::OnExecutionReceipt;
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
 type Block = frame_system::mocking::MockBlock<Test>;
@@ -23,12 +27,14 @@ frame_support::construct_runtime!(
         UncheckedExtrinsic = UncheckedExtrinsic,
     {
         System: frame_system,
+        Balances: pallet_balances,
         Executor: pallet_executor,
     }
 );
 
 type BlockNumber = u64;
 type Hash = H256;
+type Balance = u128;
 
 impl frame_system::Config for Test {
     type BaseCallFilter = frame_support::traits::Everything;
@@ -48,7 +54,7 @@ impl frame_system::Config for Test {
     type BlockHashCount = ConstU64<2>;
     type Version = ();
     type PalletInfo = PalletInfo;
-    type AccountData = ();
+    type AccountData = pallet_balances::AccountData<Balance>;
     type OnNewAccount = ();
     type OnKilledAccount = ();
     type SystemWeightInfo = ();
@@ -57,9 +63,22 @@ impl frame_system::Config for Test {
     type MaxConsumers = ConstU32<16>;
 }
 
+impl pallet_balances::Config for Test {
+    type MaxLocks = ();
+    type MaxReserves = ();
+    type ReserveIdentifier = [u8; 8];
+    type Balance = Balance;
+    type DustRemoval = ();
+    type Event = Event;
+    type ExistentialDeposit = ConstU128<1>;
+    type AccountStore = System;
+    type WeightInfo = ();
+}
+
 parameter_types! {
     pub const ReceiptsPruningDepth: BlockNumber = 256;
     pub const MaximumReceiptDrift: BlockNumber = 128;
+    pub const DisputeTimeout: BlockNumber = 10;
 }
 
 impl pallet_executor::Config for Test {
@@ -67,13 +86,71 @@ impl pallet_executor::Config for Test {
     type SecondaryHash = H256;
     type ReceiptsPruningDepth = ReceiptsPruningDepth;
     type MaximumReceiptDrift = MaximumReceiptDrift;
+    type DisputeTimeout = DisputeTimeout;
+    type Currency = Balances;
+    type DisputeBond = ConstU128<DISPUTE_BOND>;
+    type MaxDisputesPerReceipt = ConstU32<2>;
+    type OnExecutionReceipt = MockOnExecutionReceipt;
+}
+
+thread_local! {
+    static CONFIRMED_RECEIPTS: RefCell<Vec<(BlockNumber, u64)>> = RefCell::new(Vec::new());
+    static REVERTED_RECEIPTS: RefCell<Vec<BlockNumber>> = RefCell::new(Vec::new());
+}
+
+/// Records the confirmed and reverted execution receipts.
+pub struct MockOnExecutionReceipt;
+
+impl OnExecutionReceipt<BlockNumber, u64> for MockOnExecutionReceipt {
+    fn on_receipt_confirmed(primary_number: BlockNumber, executor: u64) {
+        CONFIRMED_RECEIPTS.with(|f| f.borrow_mut().push((primary_number, executor)));
+    }
+
+    fn on_receipt_reverted(primary_number: BlockNumber) {
+        REVERTED_RECEIPTS.with(|f| f.borrow_mut().push(primary_number));
+    }
+}
+
+fn confirmed_receipts() -> Vec<(BlockNumber, u64)> {
+    CONFIRMED_RECEIPTS.with(|f| f.borrow().clone())
+}
+
+fn reverted_receipts() -> Vec<BlockNumber> {
+    REVERTED_RECEIPTS.with(|f| f.borrow().clone())
 }
 
+/// Accepts the fraud proofs with a non-empty storage proof.
+struct MockFraudProofVerifier;
+
+impl Externalities for MockFraudProofVerifier {
+    fn verify_fraud_proof(&self, proof: &FraudProof) -> bool {
+        !proof.proof.encoded_nodes.is_empty()
+    }
+}
+
+const DISPUTE_BOND: Balance = 10;
+/// Accounts able to afford the dispute bond.
+const CHALLENGERS: [u64; 2] = [1, 2];
+/// Another account able to afford the dispute bond.
+const CAROL: u64 = 4;
+const INITIAL_BALANCE: Balance = 100;
+
 fn new_test_ext() -> sp_io::TestExternalities {
     let mut t = frame_system::GenesisConfig::default()
         .build_storage::<Test>()
         .unwrap();
 
+    pallet_balances::GenesisConfig::<Test> {
+        balances: vec![
+            (CHALLENGERS[0], INITIAL_BALANCE),
+            (CHALLENGERS[1], INITIAL_BALANCE),
+            (3, DISPUTE_BOND - 1),
+            (CAROL, INITIAL_BALANCE),
+        ],
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+
     pallet_executor::GenesisConfig::<Test> {
         executor: Some((
             100,
@@ -83,12 +160,22 @@ fn new_test_ext() -> sp_io::TestExternalities {
     .assimilate_storage(&mut t)
     .unwrap();
 
-    t.into()
+    let mut ext = sp_io::TestExternalities::new(t);
+    ext.register_extension(FraudProofExt::new(MockFraudProofVerifier));
+    ext
 }
 
 fn create_dummy_receipt(
     primary_number: BlockNumber,
     primary_hash: Hash,
+) -> SignedExecutionReceipt<BlockNumber, Hash, H256> {
+    create_dummy_receipt_with_trace(primary_number, primary_hash, Vec::new())
+}
+
+fn create_dummy_receipt_with_trace(
+    primary_number: BlockNumber,
+    primary_hash: Hash,
+    trace: Vec<H256>,
 ) -> SignedExecutionReceipt<BlockNumber, Hash, H256> {
     let pair = ExecutorPair::from_seed(&U256::from(0u32).into());
     let signer = pair.public();
@@ -97,8 +184,9 @@ fn create_dummy_receipt(
         primary_number,
         primary_hash,
         secondary_hash: H256::random(),
-        trace: Vec::new(),
+        trace,
         trace_root: Default::default(),
+        invalid_extrinsics_root: Default::default(),
     };
 
     let signature = pair.sign(execution_receipt.hash().as_ref());
@@ -213,20 +301,136 @@ fn submit_execution_receipt_with_huge_gap_should_work() {
     });
 }
 
+fn create_executor_receipt(
+    primary_number: BlockNumber,
+    primary_hash: Hash,
+    trace: Vec<H256>,
+) -> SignedExecutionReceipt<BlockNumber, Hash, H256> {
+    let executor_pair = ExecutorPair::from_seed(&U256::from(100u32).into());
+    let mut receipt = create_dummy_receipt_with_trace(primary_number, primary_hash, trace);
+    receipt.signature = executor_pair.sign(receipt.execution_receipt.hash().as_ref());
+    receipt.signer = executor_pair.public();
+    receipt
+}
+
 #[test]
-fn submit_fraud_proof_should_work() {
-    let dummy_receipts = (1u64..=256u64)
-        .map(|n| create_dummy_receipt(n, Hash::random()))
+fn receipt_author_should_be_confirmed_once_receipt_is_pruned() {
+    let receipts = (1u64..=256u64 + 2)
+        .map(|n| {
+            if n == 1 {
+                create_executor_receipt(n, Hash::random(), Vec::new())
+            } else {
+                create_dummy_receipt(n, Hash::random())
+            }
+        })
         .collect::<Vec<_>>();
 
-    let dummy_proof = FraudProof {
-        parent_number: 99,
+    new_test_ext().execute_with(|| {
+        for receipt in &receipts[..256] {
+            assert_ok!(Executor::submit_execution_receipt(
+                Origin::none(),
+                receipt.clone()
+            ));
+        }
+        assert_eq!(ReceiptAuthors::<Test>::get(1), Some(100));
+        // Signer of the receipt is not the executor
+        assert_eq!(ReceiptAuthors::<Test>::get(2), None);
+        // Receipts can still be challenged
+        assert!(confirmed_receipts().is_empty());
+
+        assert_ok!(Executor::submit_execution_receipt(
+            Origin::none(),
+            receipts[256].clone()
+        ));
+        assert_eq!(confirmed_receipts(), vec![(1, 100)]);
+        assert!(ReceiptAuthors::<Test>::get(1).is_none());
+
+        // Receipts without a known author are not confirmed to anyone
+        assert_ok!(Executor::submit_execution_receipt(
+            Origin::none(),
+            receipts[257].clone()
+        ));
+        assert_eq!(confirmed_receipts(), vec![(1, 100)]);
+        assert!(reverted_receipts().is_empty());
+    });
+}
+
+/// Disputes the final root of the receipt at `primary_number` with `final_root` and agrees with
+/// all the other roots in `trace`, returning the fraud proof for the settled dispute.
+fn settle_final_root_dispute(
+    challenger: u64,
+    primary_number: BlockNumber,
+    trace: &[H256],
+    final_root: H256,
+) -> FraudProof {
+    assert_ok!(Executor::open_dispute(
+        Origin::signed(challenger),
+        primary_number,
+        final_root
+    ));
+    while !Disputes::<Test>::get(primary_number, challenger)
+        .unwrap()
+        .is_settled()
+    {
+        let index = Disputes::<Test>::get(primary_number, challenger)
+            .unwrap()
+            .next_index();
+        assert_ok!(Executor::bisect_dispute(
+            Origin::signed(challenger),
+            primary_number,
+            trace[index as usize]
+        ));
+    }
+
+    FraudProof {
+        parent_number: primary_number as u32 - 1,
         parent_hash: H256::random(),
-        pre_state_root: H256::random(),
-        post_state_root: H256::random(),
-        proof: StorageProof::empty(),
+        pre_state_root: trace[trace.len() - 2],
+        post_state_root: final_root,
+        proof: CompactProof {
+            encoded_nodes: vec![vec![0]],
+        },
         execution_phase: ExecutionPhase::FinalizeBlock,
-    };
+    }
+}
+
+#[test]
+fn reverted_receipt_should_not_be_confirmed() {
+    let trace = (0u8..3).map(H256::repeat_byte).collect::<Vec<_>>();
+
+    new_test_ext().execute_with(|| {
+        assert_ok!(Executor::submit_execution_receipt(
+            Origin::none(),
+            create_executor_receipt(1, Hash::random(), trace.clone())
+        ));
+        assert_ok!(Executor::submit_execution_receipt(
+            Origin::none(),
+            create_dummy_receipt(2, Hash::random())
+        ));
+
+        let fraud_proof = settle_final_root_dispute(CHALLENGERS[0], 1, &trace, H256::random());
+        assert_ok!(Executor::submit_fraud_proof(Origin::none(), fraud_proof));
+        assert_eq!(reverted_receipts(), vec![1, 2]);
+        assert!(ReceiptAuthors::<Test>::get(1).is_none());
+
+        // The replacing receipts are not authored by the executor
+        for n in 1u64..=256 + 1 {
+            assert_ok!(Executor::submit_execution_receipt(
+                Origin::none(),
+                create_dummy_receipt(n, Hash::random())
+            ));
+        }
+        assert!(confirmed_receipts().is_empty());
+    });
+}
+
+#[test]
+fn submit_fraud_proof_should_work() {
+    let trace = (0u8..4).map(H256::repeat_byte).collect::<Vec<_>>();
+    let dummy_receipts = (1u64..=256u64)
+        .map(|n| create_dummy_receipt_with_trace(n, Hash::random(), trace.clone()))
+        .collect::<Vec<_>>();
+    let alice = CHALLENGERS[0];
 
     new_test_ext().execute_with(|| {
         (0u64..256u64).for_each(|index| {
@@ -237,12 +441,396 @@ fn submit_fraud_proof_should_work() {
             assert!(Receipts::<Test>::get(index + 1).is_some());
         });
 
-        assert_ok!(Executor::submit_fraud_proof(Origin::none(), dummy_proof));
+        let fraud_proof = settle_final_root_dispute(alice, 100, &trace, H256::random());
+        assert_ok!(Executor::submit_fraud_proof(Origin::none(), fraud_proof));
         assert_eq!(<ExecutionChainBestNumber<Test>>::get(), 99);
         assert!(Receipts::<Test>::get(99).is_some());
         // Receipts for block [100, 256] should be removed as being invalid.
         (100..=256).for_each(|block_number| {
             assert!(Receipts::<Test>::get(block_number).is_none());
         });
+        assert_eq!(Balances::reserved_balance(alice), 0);
+        assert_eq!(Balances::free_balance(alice), INITIAL_BALANCE);
+    });
+}
+
+#[test]
+fn fraud_proof_should_resolve_settled_dispute() {
+    let trace = (0u8..4).map(H256::repeat_byte).collect::<Vec<_>>();
+    let final_root = H256::random();
+    let alice = CHALLENGERS[0];
+
+    let fraud_proof = |execution_phase, pre_state_root, post_state_root| FraudProof {
+        parent_number: 1,
+        parent_hash: H256::random(),
+        pre_state_root,
+        post_state_root,
+        proof: CompactProof {
+            encoded_nodes: vec![vec![0]],
+        },
+        execution_phase,
+    };
+    let no_settled_dispute = Error::<Test>::FraudProof(FraudProofError::NoSettledDispute);
+
+    new_test_ext().execute_with(|| {
+        assert_ok!(Executor::submit_execution_receipt(
+            Origin::none(),
+            create_dummy_receipt_with_trace(1, Hash::random(), trace.clone()),
+        ));
+        assert_ok!(Executor::submit_execution_receipt(
+            Origin::none(),
+            create_dummy_receipt_with_trace(2, Hash::random(), trace.clone()),
+        ));
+
+        // Undisputed receipt.
+        assert_noop!(
+            Executor::submit_fraud_proof(
+                Origin::none(),
+                fraud_proof(ExecutionPhase::FinalizeBlock, trace[2], final_root)
+            ),
+            no_settled_dispute
+        );
+
+        // The dispute is not settled yet.
+        assert_ok!(Executor::open_dispute(Origin::signed(alice), 2, final_root));
+        assert_noop!(
+            Executor::submit_fraud_proof(
+                Origin::none(),
+                fraud_proof(ExecutionPhase::FinalizeBlock, trace[2], final_root)
+            ),
+            no_settled_dispute
+        );
+
+        // Settles at the final root.
+        assert_ok!(Executor::bisect_dispute(Origin::signed(alice), 2, trace[1]));
+        assert_ok!(Executor::bisect_dispute(Origin::signed(alice), 2, trace[2]));
+        assert!(Disputes::<Test>::get(2, alice).unwrap().is_settled());
+
+        // Mismatching execution phase.
+        assert_noop!(
+            Executor::submit_fraud_proof(
+                Origin::none(),
+                fraud_proof(
+                    ExecutionPhase::ApplyExtrinsic {
+                        call_data: Vec::new()
+                    },
+                    trace[2],
+                    final_root
+                )
+            ),
+            no_settled_dispute
+        );
+        // Mismatching pre state root.
+        assert_noop!(
+            Executor::submit_fraud_proof(
+                Origin::none(),
+                fraud_proof(ExecutionPhase::FinalizeBlock, trace[1], final_root)
+            ),
+            no_settled_dispute
+        );
+        // Mismatching post state root.
+        assert_noop!(
+            Executor::submit_fraud_proof(
+                Origin::none(),
+                fraud_proof(ExecutionPhase::FinalizeBlock, trace[2], trace[3])
+            ),
+            no_settled_dispute
+        );
+        // Invalid proof.
+        let mut invalid_proof = fraud_proof(ExecutionPhase::FinalizeBlock, trace[2], final_root);
+        invalid_proof.proof.encoded_nodes.clear();
+        assert_noop!(
+            Executor::submit_fraud_proof(Origin::none(), invalid_proof),
+            Error::<Test>::FraudProof(FraudProofError::BadProof)
+        );
+
+        assert_ok!(Executor::submit_fraud_proof(
+            Origin::none(),
+            fraud_proof(ExecutionPhase::FinalizeBlock, trace[2], final_root)
+        ));
+        assert_eq!(<ExecutionChainBestNumber<Test>>::get(), 1);
+        assert!(Disputes::<Test>::get(2, alice).is_none());
+        assert_eq!(Balances::free_balance(alice), INITIAL_BALANCE);
+    });
+}
+
+#[test]
+fn fraud_proof_for_initialize_block_should_start_from_parent_final_root() {
+    let parent_trace = (0u8..3).map(H256::repeat_byte).collect::<Vec<_>>();
+    let trace = (10u8..13).map(H256::repeat_byte).collect::<Vec<_>>();
+    let disputed_root = H256::random();
+    let alice = CHALLENGERS[0];
+
+    let initialize_block_proof = |pre_state_root| FraudProof {
+        parent_number: 1,
+        parent_hash: H256::random(),
+        pre_state_root,
+        post_state_root: disputed_root,
+        proof: CompactProof {
+            encoded_nodes: vec![vec![0]],
+        },
+        execution_phase: ExecutionPhase::InitializeBlock {
+            call_data: Vec::new(),
+        },
+    };
+
+    new_test_ext().execute_with(|| {
+        assert_ok!(Executor::submit_execution_receipt(
+            Origin::none(),
+            create_dummy_receipt_with_trace(1, Hash::random(), parent_trace.clone()),
+        ));
+        assert_ok!(Executor::submit_execution_receipt(
+            Origin::none(),
+            create_dummy_receipt_with_trace(2, Hash::random(), trace.clone()),
+        ));
+
+        // Diverges at #1 and #0.
+        assert_ok!(Executor::open_dispute(
+            Origin::signed(alice),
+            2,
+            H256::random()
+        ));
+        assert_ok!(Executor::bisect_dispute(
+            Origin::signed(alice),
+            2,
+            H256::random()
+        ));
+        assert_ok!(Executor::bisect_dispute(
+            Origin::signed(alice),
+            2,
+            disputed_root
+        ));
+        let dispute = Disputes::<Test>::get(2, alice).unwrap();
+        assert!(dispute.is_settled());
+        assert_eq!(dispute.disputed_index, 0);
+
+        assert_noop!(
+            Executor::submit_fraud_proof(Origin::none(), initialize_block_proof(trace[0])),
+            Error::<Test>::FraudProof(FraudProofError::NoSettledDispute)
+        );
+        assert_ok!(Executor::submit_fraud_proof(
+            Origin::none(),
+            initialize_block_proof(parent_trace[2])
+        ));
+        assert_eq!(<ExecutionChainBestNumber<Test>>::get(), 1);
+    });
+}
+
+#[test]
+fn bisection_dispute_should_settle_on_first_diverging_root() {
+    // Initialize block, 4 extrinsics and finalize block.
+    let executor_trace = (0u8..6).map(H256::repeat_byte).collect::<Vec<_>>();
+    // The challenger agrees with the first 2 roots only.
+    let challenger_trace = (0u8..6)
+        .map(|i| {
+            if i < 2 {
+                H256::repeat_byte(i)
+            } else {
+                H256::repeat_byte(100 + i)
+            }
+        })
+        .collect::<Vec<_>>();
+    let [alice, bob] = CHALLENGERS;
+
+    let fraud_proof = |execution_phase, pre_state_root, post_state_root| FraudProof {
+        parent_number: 1,
+        parent_hash: H256::random(),
+        pre_state_root,
+        post_state_root,
+        proof: CompactProof {
+            encoded_nodes: Vec::new(),
+        },
+        execution_phase,
+    };
+
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+
+        assert_ok!(Executor::submit_execution_receipt(
+            Origin::none(),
+            create_dummy_receipt(1, Hash::random()),
+        ));
+        assert_ok!(Executor::submit_execution_receipt(
+            Origin::none(),
+            create_dummy_receipt_with_trace(2, Hash::random(), executor_trace.clone()),
+        ));
+
+        assert_noop!(
+            Executor::open_dispute(Origin::none(), 2, challenger_trace[5]),
+            sp_runtime::traits::BadOrigin
+        );
+        assert_noop!(
+            Executor::open_dispute(Origin::signed(alice), 1, H256::random()),
+            Error::<Test>::Dispute(DisputeError::EmptyTrace)
+        );
+        assert_noop!(
+            Executor::open_dispute(Origin::signed(alice), 2, executor_trace[5]),
+            Error::<Test>::Dispute(DisputeError::NoDivergence)
+        );
+        assert_noop!(
+            Executor::open_dispute(Origin::signed(3), 2, challenger_trace[5]),
+            Error::<Test>::Dispute(DisputeError::InsufficientBond)
+        );
+        assert_noop!(
+            Executor::bisect_dispute(Origin::signed(alice), 2, challenger_trace[2]),
+            Error::<Test>::Dispute(DisputeError::NotFound)
+        );
+
+        assert_ok!(Executor::open_dispute(
+            Origin::signed(alice),
+            2,
+            challenger_trace[5]
+        ));
+        assert_eq!(
+            Disputes::<Test>::get(2, alice),
+            Some(Dispute {
+                agreed_len: 0,
+                disputed_index: 5,
+                disputed_root: challenger_trace[5],
+                deadline: 11,
+                bond: DISPUTE_BOND,
+            })
+        );
+        assert_eq!(Balances::reserved_balance(alice), DISPUTE_BOND);
+        assert_noop!(
+            Executor::open_dispute(Origin::signed(alice), 2, challenger_trace[5]),
+            Error::<Test>::Dispute(DisputeError::AlreadyOpened)
+        );
+
+        // Another challenger disputes the same receipt independently.
+        assert_ok!(Executor::open_dispute(
+            Origin::signed(bob),
+            2,
+            H256::random()
+        ));
+        assert_noop!(
+            Executor::open_dispute(Origin::signed(CAROL), 2, H256::random()),
+            Error::<Test>::Dispute(DisputeError::TooManyDisputes)
+        );
+        assert_noop!(
+            Executor::bisect_dispute(Origin::signed(3), 2, challenger_trace[2]),
+            Error::<Test>::Dispute(DisputeError::NotFound)
+        );
+
+        // Diverges at #2.
+        let index = Disputes::<Test>::get(2, alice).unwrap().next_index();
+        assert_eq!(index, 2);
+        assert_ok!(Executor::bisect_dispute(
+            Origin::signed(alice),
+            2,
+            challenger_trace[index as usize]
+        ));
+
+        // Agrees at #1.
+        let index = Disputes::<Test>::get(2, alice).unwrap().next_index();
+        assert_eq!(index, 1);
+        assert_ok!(Executor::bisect_dispute(
+            Origin::signed(alice),
+            2,
+            challenger_trace[index as usize]
+        ));
+
+        let dispute = Disputes::<Test>::get(2, alice).unwrap();
+        assert!(dispute.is_settled());
+        assert_eq!(dispute.disputed_index, 2);
+        assert_noop!(
+            Executor::bisect_dispute(Origin::signed(alice), 2, challenger_trace[2]),
+            Error::<Test>::Dispute(DisputeError::AlreadySettled)
+        );
+
+        // The fraud proof has to cover the diverging extrinsic.
+        assert_noop!(
+            Executor::submit_fraud_proof(
+                Origin::none(),
+                fraud_proof(
+                    ExecutionPhase::FinalizeBlock,
+                    executor_trace[4],
+                    challenger_trace[5]
+                ),
+            ),
+            Error::<Test>::FraudProof(FraudProofError::NoSettledDispute)
+        );
+
+        // Bob's dispute is not settled, which does not block the fraud proof.
+        assert!(!Disputes::<Test>::get(2, bob).unwrap().is_settled());
+        assert_ok!(Executor::submit_fraud_proof(
+            Origin::none(),
+            fraud_proof(
+                ExecutionPhase::ApplyExtrinsic {
+                    call_data: Vec::new()
+                },
+                executor_trace[1],
+                challenger_trace[2]
+            ),
+        ));
+        assert_eq!(<ExecutionChainBestNumber<Test>>::get(), 1);
+        assert!(Receipts::<Test>::get(2).is_none());
+
+        // Only the bond of the resolved dispute is returned, the other disputes over the reverted
+        // receipt are closed with the bonds slashed.
+        assert_eq!(Disputes::<Test>::iter_prefix(2).count(), 0);
+        assert_eq!(DisputeDeadlines::<Test>::iter().count(), 0);
+        for challenger in CHALLENGERS {
+            assert_eq!(Balances::reserved_balance(challenger), 0);
+        }
+        assert_eq!(Balances::free_balance(alice), INITIAL_BALANCE);
+        assert_eq!(Balances::free_balance(bob), INITIAL_BALANCE - DISPUTE_BOND);
+    });
+}
+
+#[test]
+fn dispute_should_expire_without_progress() {
+    let executor_trace = (0u8..4).map(H256::repeat_byte).collect::<Vec<_>>();
+    let [alice, bob] = CHALLENGERS;
+
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+
+        assert_ok!(Executor::submit_execution_receipt(
+            Origin::none(),
+            create_dummy_receipt_with_trace(1, Hash::random(), executor_trace.clone()),
+        ));
+        assert_ok!(Executor::open_dispute(
+            Origin::signed(alice),
+            1,
+            H256::random()
+        ));
+        assert_ok!(Executor::open_dispute(
+            Origin::signed(bob),
+            1,
+            H256::random()
+        ));
+        assert!(DisputeDeadlines::<Test>::contains_key(11, (1, alice)));
+
+        // Each step extends the deadline.
+        System::set_block_number(5);
+        assert_ok!(Executor::bisect_dispute(
+            Origin::signed(alice),
+            1,
+            executor_trace[1]
+        ));
+        assert_eq!(Disputes::<Test>::get(1, alice).unwrap().deadline, 15);
+        assert!(!DisputeDeadlines::<Test>::contains_key(11, (1, alice)));
+        assert!(DisputeDeadlines::<Test>::contains_key(15, (1, alice)));
+
+        // Bob's dispute expires at its original deadline.
+        Executor::on_initialize(11);
+        assert!(Disputes::<Test>::get(1, bob).is_some());
+        Executor::on_initialize(12);
+        assert!(Disputes::<Test>::get(1, bob).is_none());
+        assert_eq!(Balances::reserved_balance(bob), 0);
+        assert_eq!(Balances::free_balance(bob), INITIAL_BALANCE - DISPUTE_BOND);
+
+        Executor::on_initialize(15);
+        assert!(Disputes::<Test>::get(1, alice).is_some());
+
+        Executor::on_initialize(16);
+        assert!(Disputes::<Test>::get(1, alice).is_none());
+        assert_eq!(
+            Balances::free_balance(alice),
+            INITIAL_BALANCE - DISPUTE_BOND
+        );
+        assert_eq!(DisputeDeadlines::<Test>::iter().count(), 0);
+        assert!(Receipts::<Test>::get(1).is_some());
     });
 }
@@ -35,10 +35,13 @@ mod tests;
 mod pallet {
     use crate::feed_processor::{FeedMetadata, FeedProcessor as FeedProcessorT};
     use frame_support::pallet_prelude::*;
+    use frame_support::weights::PostDispatchInfo;
     use frame_system::pallet_prelude::*;
-    use sp_runtime::traits::{CheckedAdd, Hash, One, StaticLookup};
+    use sp_runtime::traits::{CheckedAdd, Hash, One, Saturating, StaticLookup};
     use sp_runtime::ArithmeticError;
     use sp_std::prelude::*;
+    use subspace_core_primitives::objects::ObjectManifest;
+    use subspace_core_primitives::{crypto, Sha256Hash};
 
     #[pallet::config]
     pub trait Config: frame_system::Config {
@@ -54,6 +57,14 @@ mod pallet {
         #[pallet::constant]
         type MaxFeeds: Get<u32>;
 
+        /// Maximum number of chunks a manifest can refer to.
+        #[pallet::constant]
+        type MaxManifestChunks: Get<u32>;
+
+        /// Number of blocks after which a chunk that is not part of a manifest expires.
+        #[pallet::constant]
+        type PendingChunkExpiry: Get<Self::BlockNumber>;
+
         fn feed_processor(
             feed_processor_kind: Self::FeedProcessorKind,
         ) -> Box<dyn FeedProcessorT<Self::FeedId>>;
@@ -69,6 +80,11 @@ mod pallet {
     pub(super) type Object = Vec<u8>;
     /// User provided initial data for validation
     pub(super) type InitData = Vec<u8>;
+    /// Block size and totals of a delegated writer
+    type WriterUsage<T> = (
+        (<T as frame_system::Config>::BlockNumber, u64),
+        TotalObjectsAndSize,
+    );
 
     /// Total amount of data and number of objects stored in a feed
     #[derive(Debug, Decode, Encode, TypeInfo, Default, PartialEq, Eq)]
@@ -79,6 +95,15 @@ mod pallet {
         pub count: u64,
     }
 
+    /// Limits on the data a delegated writer can put into a feed
+    #[derive(Debug, Decode, Encode, TypeInfo, Default, Clone, Copy, PartialEq, Eq)]
+    pub struct WriterQuota {
+        /// Maximum size of objects in bytes the writer can put within a single block
+        pub max_block_size: Option<u64>,
+        /// Maximum size of objects in bytes the writer can put in total
+        pub max_total_size: Option<u64>,
+    }
+
     #[derive(Debug, Decode, Encode, TypeInfo, Default)]
     pub struct FeedConfig<FeedProcessorId, AccountId> {
         pub active: bool,
@@ -111,6 +136,71 @@ mod pallet {
     pub(super) type Totals<T: Config> =
         StorageMap<_, Identity, T::FeedId, TotalObjectsAndSize, ValueQuery>;
 
+    /// Delegated writers of a feed along with their quota
+    #[pallet::storage]
+    #[pallet::getter(fn writers)]
+    pub(super) type Writers<T: Config> =
+        StorageDoubleMap<_, Identity, T::FeedId, Identity, T::AccountId, WriterQuota, OptionQuery>;
+
+    /// Total amount of data and number of objects put into a feed by a delegated writer
+    #[pallet::storage]
+    #[pallet::getter(fn writer_totals)]
+    pub(super) type WriterTotals<T: Config> = StorageDoubleMap<
+        _,
+        Identity,
+        T::FeedId,
+        Identity,
+        T::AccountId,
+        TotalObjectsAndSize,
+        ValueQuery,
+    >;
+
+    /// Amount of data put into a feed by a delegated writer within the last block it put into
+    #[pallet::storage]
+    pub(super) type WriterBlockSize<T: Config> = StorageDoubleMap<
+        _,
+        Identity,
+        T::FeedId,
+        Identity,
+        T::AccountId,
+        (T::BlockNumber, u64),
+        ValueQuery,
+    >;
+
+    /// Sizes and expiry blocks of the chunks put into a feed that are not part of a complete
+    /// object yet
+    #[pallet::storage]
+    #[pallet::getter(fn pending_chunks)]
+    pub(super) type PendingChunks<T: Config> = StorageDoubleMap<
+        _,
+        Identity,
+        T::FeedId,
+        Identity,
+        Sha256Hash,
+        (u64, T::BlockNumber),
+        OptionQuery,
+    >;
+
+    /// Pending chunks by the block number they expire at
+    #[pallet::storage]
+    pub(super) type ChunkExpiries<T: Config> = StorageDoubleMap<
+        _,
+        Twox64Concat,
+        T::BlockNumber,
+        Identity,
+        (T::FeedId, Sha256Hash),
+        (),
+        OptionQuery,
+    >;
+
+    /// Block number from which the chunk expiries are not swept yet
+    #[pallet::storage]
+    pub(super) type NextExpiryToSweep<T: Config> = StorageValue<_, T::BlockNumber, OptionQuery>;
+
+    /// Deleted feeds whose writers and pending chunks are not cleared yet
+    #[pallet::storage]
+    pub(super) type FeedsToClear<T: Config> = StorageMap<_, Identity, T::FeedId, (), OptionQuery>;
+
     #[pallet::storage]
     #[pallet::getter(fn next_feed_id)]
     pub(super) type NextFeedId<T: Config> = StorageValue<_, T::FeedId, ValueQuery>;
@@ -129,6 +219,20 @@ mod pallet {
             metadata: FeedMetadata,
             object_size: u64,
         },
+        /// New chunk of an object was added.
+        ChunkSubmitted {
+            feed_id: T::FeedId,
+            who: T::AccountId,
+            chunk_hash: Sha256Hash,
+            chunk_size: u64,
+        },
+        /// Object made up of the chunks was added.
+        ChunkedObjectSubmitted {
+            feed_id: T::FeedId,
+            who: T::AccountId,
+            content_root: Sha256Hash,
+            object_size: u64,
+        },
         /// New feed was created.
         FeedCreated {
             feed_id: T::FeedId,
@@ -147,6 +251,12 @@ mod pallet {
             who: T::AccountId,
         },
 
+        /// Closed feed was reopened.
+        FeedReopened {
+            feed_id: T::FeedId,
+            who: T::AccountId,
+        },
+
         /// Feed was deleted.
         FeedDeleted {
             feed_id: T::FeedId,
@@ -159,6 +269,21 @@ mod pallet {
             old_owner: T::AccountId,
             new_owner: T::AccountId,
         },
+
+        /// Writer was allowed to put into the feed or its quota was updated.
+        WriterAdded {
+            feed_id: T::FeedId,
+            who: T::AccountId,
+            writer: T::AccountId,
+            quota: WriterQuota,
+        },
+
+        /// Writer is no longer allowed to put into the feed.
+        WriterRemoved {
+            feed_id: T::FeedId,
+            who: T::AccountId,
+            writer: T::AccountId,
+        },
     }
 
     /// `pallet-feeds` errors
@@ -175,6 +300,30 @@ mod pallet {
 
         /// Maximum feeds created by the caller
         MaxFeedsReached,
+
+        /// Neither a feed owner nor a delegated writer
+        NotFeedWriter,
+
+        /// Account is not a delegated writer of the feed
+        UnknownWriter,
+
+        /// Writer exceeded the size of objects it can put within a block
+        WriterBlockQuotaExceeded,
+
+        /// Writer exceeded the total size of objects it can put
+        WriterTotalQuotaExceeded,
+
+        /// Object is not a valid manifest or doesn't match the size of its chunks
+        InvalidManifest,
+
+        /// Manifest refers to a chunk that was not put into the feed
+        UnknownChunk,
+
+        /// Manifest refers to more chunks than allowed
+        TooManyChunks,
+
+        /// Feed is not closed
+        FeedNotClosed,
     }
 
     macro_rules! ensure_owner {
@@ -186,12 +335,32 @@ mod pallet {
         }};
     }
 
+    macro_rules! ensure_writer {
+        ( $origin:expr, $feed_id:expr ) => {{
+            let sender = ensure_signed($origin)?;
+            let feed_config = FeedConfigs::<T>::get($feed_id).ok_or(Error::<T>::UnknownFeedId)?;
+            ensure!(
+                feed_config.owner == sender || Writers::<T>::contains_key($feed_id, &sender),
+                Error::<T>::NotFeedWriter
+            );
+            (sender, feed_config)
+        }};
+    }
+
     #[pallet::hooks]
     impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
         fn on_initialize(_now: BlockNumberFor<T>) -> Weight {
             SuccessfulPuts::<T>::kill();
             T::DbWeight::get().writes(1)
         }
+
+        fn on_idle(now: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+            let consumed_weight = Self::clear_deleted_feeds(remaining_weight);
+            consumed_weight.saturating_add(Self::remove_expired_chunks(
+                now,
+                remaining_weight.saturating_sub(consumed_weight),
+            ))
+        }
     }
 
     #[pallet::call]
@@ -273,11 +442,14 @@ mod pallet {
         /// Put a new object into a feed
         #[pallet::weight((10_000, Pays::No))]
         pub fn put(origin: OriginFor<T>, feed_id: T::FeedId, object: Object) -> DispatchResult {
-            let (owner, feed_config) = ensure_owner!(origin, feed_id);
+            let (writer, feed_config) = ensure_writer!(origin, feed_id);
             // ensure feed is active
             ensure!(feed_config.active, Error::<T>::FeedClosed);
 
             let object_size = object.len() as u64;
+            let writer_usage =
+                Self::writer_usage_after_put(feed_id, &writer, &feed_config.owner, object_size)?;
+
             let feed_processor = T::feed_processor(feed_config.feed_processor_id);
 
             let metadata = feed_processor
@@ -285,14 +457,11 @@ mod pallet {
                 .unwrap_or_default();
             Metadata::<T>::insert(feed_id, metadata.clone());
 
-            Totals::<T>::mutate(feed_id, |feed_totals| {
-                feed_totals.size += object_size;
-                feed_totals.count += 1;
-            });
+            Self::note_put(feed_id, &writer, writer_usage, object_size);
 
             Self::deposit_event(Event::ObjectSubmitted {
                 feed_id,
-                who: owner,
+                who: writer,
                 metadata,
                 object_size,
             });
@@ -305,6 +474,120 @@ mod pallet {
             Ok(())
         }
 
+        // TODO: add proper weights
+        /// Put a chunk of an object that is too large to fit into a single block.
+        ///
+        /// Chunks are not passed to the feed processor, they make up an object once its manifest
+        /// is put with `put_manifest`.
+        #[pallet::weight((10_000, Pays::No))]
+        pub fn put_chunk(
+            origin: OriginFor<T>,
+            feed_id: T::FeedId,
+            chunk: Object,
+        ) -> DispatchResult {
+            let (writer, feed_config) = ensure_writer!(origin, feed_id);
+            // ensure feed is active
+            ensure!(feed_config.active, Error::<T>::FeedClosed);
+
+            let chunk_size = chunk.len() as u64;
+            let writer_usage =
+                Self::writer_usage_after_put(feed_id, &writer, &feed_config.owner, chunk_size)?;
+
+            let chunk_hash = crypto::sha256_hash(&chunk);
+            // putting the same chunk again extends its expiry, the earlier expiry is ignored
+            let expires_at = frame_system::Pallet::<T>::block_number()
+                .saturating_add(T::PendingChunkExpiry::get());
+            PendingChunks::<T>::insert(feed_id, chunk_hash, (chunk_size, expires_at));
+            ChunkExpiries::<T>::insert(expires_at, (feed_id, chunk_hash), ());
+            Self::note_put(feed_id, &writer, writer_usage, chunk_size);
+
+            Self::deposit_event(Event::ChunkSubmitted {
+                feed_id,
+                who: writer,
+                chunk_hash,
+                chunk_size,
+            });
+
+            let uniq =
+                T::Hashing::hash(Call::<T>::put_chunk { feed_id, chunk }.encode().as_slice());
+            SuccessfulPuts::<T>::append(uniq);
+            Ok(())
+        }
+
+        // TODO: add proper weights
+        /// Put the encoded [`ObjectManifest`] of an object whose chunks were put into the feed.
+        ///
+        /// The hash of the manifest is the content root of the whole object.
+        #[pallet::weight((
+            Pallet::<T>::put_manifest_weight(T::MaxManifestChunks::get()),
+            Pays::No
+        ))]
+        pub fn put_manifest(
+            origin: OriginFor<T>,
+            feed_id: T::FeedId,
+            manifest: Object,
+        ) -> DispatchResultWithPostInfo {
+            let (writer, feed_config) = ensure_writer!(origin, feed_id);
+            // ensure feed is active
+            ensure!(feed_config.active, Error::<T>::FeedClosed);
+
+            let object_manifest =
+                ObjectManifest::from_object(&manifest).ok_or(Error::<T>::InvalidManifest)?;
+            ensure!(
+                !object_manifest.chunks.is_empty(),
+                Error::<T>::InvalidManifest
+            );
+
+            let chunks = object_manifest.chunks.len() as u32;
+            ensure!(
+                chunks <= T::MaxManifestChunks::get(),
+                Error::<T>::TooManyChunks
+            );
+
+            // all the chunks must be put already, not expired and make up the size of the object
+            let now = frame_system::Pallet::<T>::block_number();
+            let mut object_size = 0u64;
+            for chunk_hash in &object_manifest.chunks {
+                let (chunk_size, _) = PendingChunks::<T>::get(feed_id, chunk_hash)
+                    .filter(|(_, expires_at)| *expires_at > now)
+                    .ok_or(Error::<T>::UnknownChunk)?;
+                object_size = object_size
+                    .checked_add(chunk_size)
+                    .ok_or(ArithmeticError::Overflow)?;
+            }
+            ensure!(
+                object_size == object_manifest.size,
+                Error::<T>::InvalidManifest
+            );
+
+            let manifest_size = manifest.len() as u64;
+            let writer_usage =
+                Self::writer_usage_after_put(feed_id, &writer, &feed_config.owner, manifest_size)?;
+
+            for chunk_hash in &object_manifest.chunks {
+                PendingChunks::<T>::remove(feed_id, chunk_hash);
+            }
+            Self::note_put(feed_id, &writer, writer_usage, manifest_size);
+
+            Self::deposit_event(Event::ChunkedObjectSubmitted {
+                feed_id,
+                who: writer,
+                content_root: crypto::sha256_hash(&manifest),
+                object_size,
+            });
+
+            let uniq = T::Hashing::hash(
+                Call::<T>::put_manifest { feed_id, manifest }
+                    .encode()
+                    .as_slice(),
+            );
+            SuccessfulPuts::<T>::append(uniq);
+            Ok(PostDispatchInfo {
+                actual_weight: Some(Self::put_manifest_weight(chunks)),
+                pays_fee: Pays::No,
+            })
+        }
+
         /// Closes the feed and stops accepting new feed.
         #[pallet::weight((T::DbWeight::get().reads_writes(1, 1), Pays::No))]
         pub fn close(origin: OriginFor<T>, feed_id: T::FeedId) -> DispatchResult {
@@ -318,6 +601,52 @@ mod pallet {
             Ok(())
         }
 
+        /// Reopens the closed feed to accept new objects again.
+        #[pallet::weight((T::DbWeight::get().reads_writes(1, 1), Pays::No))]
+        pub fn reopen(origin: OriginFor<T>, feed_id: T::FeedId) -> DispatchResult {
+            let (owner, mut feed_config) = ensure_owner!(origin, feed_id);
+            ensure!(!feed_config.active, Error::<T>::FeedNotClosed);
+            feed_config.active = true;
+            FeedConfigs::<T>::insert(feed_id, feed_config);
+            Self::deposit_event(Event::FeedReopened {
+                feed_id,
+                who: owner,
+            });
+            Ok(())
+        }
+
+        /// Deletes the feed along with the data of its feed processor and frees the slot of the
+        /// owner.
+        ///
+        /// The writers and pending chunks of the feed are not bounded, they are cleared
+        /// afterwards with the weight left in the following blocks.
+        #[pallet::weight((T::DbWeight::get().reads_writes(2, 6), Pays::No))]
+        pub fn delete(origin: OriginFor<T>, feed_id: T::FeedId) -> DispatchResult {
+            let (owner, feed_config) = ensure_owner!(origin, feed_id);
+            let feed_processor = T::feed_processor(feed_config.feed_processor_id);
+            feed_processor.delete(feed_id)?;
+
+            FeedConfigs::<T>::remove(feed_id);
+            Metadata::<T>::remove(feed_id);
+            Totals::<T>::remove(feed_id);
+            FeedsToClear::<T>::insert(feed_id, ());
+
+            // if the owner doesn't own any feed, then reclaim empty storage
+            let mut owned_feeds = Feeds::<T>::get(owner.clone()).unwrap_or_default();
+            owned_feeds.retain(|x| *x != feed_id);
+            if owned_feeds.is_empty() {
+                Feeds::<T>::remove(owner.clone());
+            } else {
+                Feeds::<T>::insert(owner.clone(), owned_feeds);
+            }
+
+            Self::deposit_event(Event::FeedDeleted {
+                feed_id,
+                who: owner,
+            });
+            Ok(())
+        }
+
         /// Transfers feed from current owner to new owner
         #[pallet::weight((T::DbWeight::get().reads_writes(3, 3), Pays::No))]
         pub fn transfer(
@@ -355,6 +684,215 @@ mod pallet {
             });
             Ok(())
         }
+
+        /// Allows the writer to put into the feed within the given quota. Updates the quota if
+        /// the writer is already allowed.
+        #[pallet::weight((T::DbWeight::get().reads_writes(1, 1), Pays::No))]
+        pub fn add_writer(
+            origin: OriginFor<T>,
+            feed_id: T::FeedId,
+            writer: <T::Lookup as StaticLookup>::Source,
+            quota: WriterQuota,
+        ) -> DispatchResult {
+            let (owner, _feed_config) = ensure_owner!(origin, feed_id);
+            let writer = T::Lookup::lookup(writer)?;
+
+            Writers::<T>::insert(feed_id, &writer, quota);
+            Self::deposit_event(Event::WriterAdded {
+                feed_id,
+                who: owner,
+                writer,
+                quota,
+            });
+            Ok(())
+        }
+
+        /// Disallows the writer to put into the feed and removes its usage.
+        #[pallet::weight((T::DbWeight::get().reads_writes(2, 3), Pays::No))]
+        pub fn remove_writer(
+            origin: OriginFor<T>,
+            feed_id: T::FeedId,
+            writer: <T::Lookup as StaticLookup>::Source,
+        ) -> DispatchResult {
+            let (owner, _feed_config) = ensure_owner!(origin, feed_id);
+            let writer = T::Lookup::lookup(writer)?;
+            ensure!(
+                Writers::<T>::contains_key(feed_id, &writer),
+                Error::<T>::UnknownWriter
+            );
+
+            Writers::<T>::remove(feed_id, &writer);
+            WriterTotals::<T>::remove(feed_id, &writer);
+            WriterBlockSize::<T>::remove(feed_id, &writer);
+            Self::deposit_event(Event::WriterRemoved {
+                feed_id,
+                who: owner,
+                writer,
+            });
+            Ok(())
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        /// Returns the block size and totals of the delegated writer after putting an object of
+        /// given size, ensuring they are within the writer's quota. Returns `None` for the owner,
+        /// who is not bound by any quota.
+        fn writer_usage_after_put(
+            feed_id: T::FeedId,
+            writer: &T::AccountId,
+            owner: &T::AccountId,
+            object_size: u64,
+        ) -> Result<Option<WriterUsage<T>>, DispatchError> {
+            if writer == owner {
+                return Ok(None);
+            }
+
+            let quota = Writers::<T>::get(feed_id, writer).ok_or(Error::<T>::NotFeedWriter)?;
+
+            // block size is only accounted within the current block
+            let current_block = frame_system::Pallet::<T>::block_number();
+            let (last_block, last_block_size) = WriterBlockSize::<T>::get(feed_id, writer);
+            let block_size = if last_block == current_block {
+                last_block_size
+            } else {
+                0
+            }
+            .checked_add(object_size)
+            .ok_or(ArithmeticError::Overflow)?;
+            if let Some(max_block_size) = quota.max_block_size {
+                ensure!(
+                    block_size <= max_block_size,
+                    Error::<T>::WriterBlockQuotaExceeded
+                );
+            }
+
+            let mut totals = WriterTotals::<T>::get(feed_id, writer);
+            totals.size = totals
+                .size
+                .checked_add(object_size)
+                .ok_or(ArithmeticError::Overflow)?;
+            totals.count += 1;
+            if let Some(max_total_size) = quota.max_total_size {
+                ensure!(
+                    totals.size <= max_total_size,
+                    Error::<T>::WriterTotalQuotaExceeded
+                );
+            }
+
+            Ok(Some(((current_block, block_size), totals)))
+        }
+
+        /// Clears the writers and pending chunks of the deleted feeds within the given weight and
+        /// returns the weight consumed.
+        pub(super) fn clear_deleted_feeds(remaining_weight: Weight) -> Weight {
+            let db_weight = T::DbWeight::get();
+            // looking up the next feed to clear and marking it as cleared
+            let feed_weight = db_weight.reads_writes(1, 1);
+            let item_weight = db_weight.reads_writes(1, 1);
+
+            let mut consumed_weight = 0;
+            while consumed_weight.saturating_add(feed_weight + item_weight) <= remaining_weight {
+                let feed_id = match FeedsToClear::<T>::iter_keys().next() {
+                    Some(feed_id) => feed_id,
+                    None => break,
+                };
+                consumed_weight += feed_weight;
+
+                let limit = (remaining_weight - consumed_weight)
+                    .checked_div(item_weight)
+                    .and_then(|limit| usize::try_from(limit).ok())
+                    .unwrap_or(usize::MAX);
+                let mut cleared = PendingChunks::<T>::drain_prefix(feed_id)
+                    .take(limit)
+                    .count();
+                cleared += Writers::<T>::drain_prefix(feed_id)
+                    .take(limit - cleared)
+                    .count();
+                cleared += WriterTotals::<T>::drain_prefix(feed_id)
+                    .take(limit - cleared)
+                    .count();
+                cleared += WriterBlockSize::<T>::drain_prefix(feed_id)
+                    .take(limit - cleared)
+                    .count();
+                consumed_weight += item_weight.saturating_mul(cleared as u64);
+
+                if cleared == limit {
+                    // there might be more items left
+                    break;
+                }
+                FeedsToClear::<T>::remove(feed_id);
+            }
+
+            consumed_weight
+        }
+
+        /// Weight of `put_manifest` with the given number of chunks.
+        pub(super) fn put_manifest_weight(chunks: u32) -> Weight {
+            let chunks = Weight::from(chunks);
+            T::DbWeight::get()
+                .reads_writes(chunks, chunks)
+                .saturating_add(10_000)
+        }
+
+        /// Removes the pending chunks expired by the given block within the given weight and
+        /// returns the weight consumed.
+        pub(super) fn remove_expired_chunks(
+            now: T::BlockNumber,
+            remaining_weight: Weight,
+        ) -> Weight {
+            let db_weight = T::DbWeight::get();
+            // reading and updating the block number to sweep next
+            let mut consumed_weight = db_weight.reads_writes(1, 1);
+            // looking up the next expired chunk and removing it with its pending entry
+            let chunk_weight = db_weight.reads_writes(2, 2);
+            if consumed_weight > remaining_weight {
+                return 0;
+            }
+
+            // nothing expires before the first sweep as chunks always expire in the future
+            let mut expiry = NextExpiryToSweep::<T>::get().unwrap_or(now);
+            while expiry <= now && consumed_weight.saturating_add(chunk_weight) <= remaining_weight
+            {
+                match ChunkExpiries::<T>::iter_key_prefix(expiry).next() {
+                    Some((feed_id, chunk_hash)) => {
+                        ChunkExpiries::<T>::remove(expiry, (feed_id, chunk_hash));
+                        // the chunk might be part of a manifest or put again in the meantime
+                        if matches!(
+                            PendingChunks::<T>::get(feed_id, chunk_hash),
+                            Some((_, expires_at)) if expires_at == expiry
+                        ) {
+                            PendingChunks::<T>::remove(feed_id, chunk_hash);
+                        }
+                        consumed_weight += chunk_weight;
+                    }
+                    None => {
+                        expiry += One::one();
+                        consumed_weight += db_weight.reads(1);
+                    }
+                }
+            }
+            NextExpiryToSweep::<T>::put(expiry);
+
+            consumed_weight
+        }
+
+        /// Accounts the put object in the totals of the feed and the usage of the writer.
+        fn note_put(
+            feed_id: T::FeedId,
+            writer: &T::AccountId,
+            writer_usage: Option<WriterUsage<T>>,
+            object_size: u64,
+        ) {
+            Totals::<T>::mutate(feed_id, |feed_totals| {
+                feed_totals.size += object_size;
+                feed_totals.count += 1;
+            });
+
+            if let Some((block_size, writer_totals)) = writer_usage {
+                WriterBlockSize::<T>::insert(feed_id, writer, block_size);
+                WriterTotals::<T>::insert(feed_id, writer, writer_totals);
+            }
+        }
     }
 }
 
@@ -374,8 +912,12 @@ impl<T: Config> Pallet<T> {
 }
 
 impl<T: Config> Call<T> {
-    /// Extract the call objects if an extrinsic corresponds to `put` call
+    /// Extract the call objects if an extrinsic corresponds to `put`, `put_chunk` or
+    /// `put_manifest` call
     pub fn extract_call_objects(&self) -> Vec<CallObject> {
+        // +1 for the Call enum variant
+        // Since first arg is feed_id, we bump the offset by its encoded size
+        let base_offset = 1 + mem::size_of::<T::FeedId>() as u32;
         match self {
             Self::put { feed_id, object } => {
                 let feed_processor_id = match FeedConfigs::<T>::get(feed_id) {
@@ -385,9 +927,6 @@ impl<T: Config> Call<T> {
                 };
                 let feed_processor = T::feed_processor(feed_processor_id);
                 let objects_mappings = feed_processor.object_mappings(*feed_id, object);
-                // +1 for the Call::put enum variant
-                // Since first arg is feed_id, we bump the offset by its encoded size
-                let base_offset = 1 + mem::size_of::<T::FeedId>() as u32;
                 objects_mappings
                     .into_iter()
                     .filter_map(|object_mapping| {
@@ -401,6 +940,16 @@ impl<T: Config> Call<T> {
                     })
                     .collect()
             }
+            // Chunks and manifests are always addressed by their content
+            Self::put_chunk { chunk: object, .. }
+            | Self::put_manifest {
+                manifest: object, ..
+            } => {
+                vec![CallObject {
+                    key: crypto::sha256_hash(object),
+                    offset: base_offset,
+                }]
+            }
             _ => Default::default(),
         }
     }
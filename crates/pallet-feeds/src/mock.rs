@@ -3,6 +3,7 @@ use crate::{self as pallet_feeds};
 use codec::{Compact, CompactLen, Decode, Encode};
 use frame_support::parameter_types;
 use frame_support::traits::{ConstU16, ConstU32, ConstU64};
+use frame_support::weights::constants::RocksDbWeight;
 use scale_info::TypeInfo;
 use sp_core::H256;
 use sp_runtime::testing::Header;
@@ -27,7 +28,7 @@ impl frame_system::Config for Test {
     type BaseCallFilter = frame_support::traits::Everything;
     type BlockWeights = ();
     type BlockLength = ();
-    type DbWeight = ();
+    type DbWeight = RocksDbWeight;
     type Origin = Origin;
     type Call = Call;
     type Index = u64;
@@ -73,6 +74,8 @@ impl pallet_feeds::Config for Test {
     type FeedId = FeedId;
     type FeedProcessorKind = MockFeedProcessorKind;
     type MaxFeeds = MaxFeeds;
+    type MaxManifestChunks = ConstU32<4>;
+    type PendingChunkExpiry = ConstU64<10>;
 
     fn feed_processor(
         feed_processor_kind: Self::FeedProcessorKind,
@@ -1,12 +1,19 @@
 use crate::mock::{
     new_test_ext, ContentEnum, Event, Feeds, MockFeedProcessorKind, Origin, System, Test,
 };
-use crate::{Call as FeedsCall, Error, Object, SuccessfulPuts, TotalObjectsAndSize};
+use crate::{
+    Call as FeedsCall, ChunkExpiries, Error, FeedsToClear, Object, SuccessfulPuts,
+    TotalObjectsAndSize, WriterQuota,
+};
 use codec::{Decode, Encode};
+use frame_support::traits::Hooks;
+use frame_support::weights::constants::RocksDbWeight;
+use frame_support::weights::{GetDispatchInfo, Weight};
 use frame_support::{assert_noop, assert_ok};
 use sp_core::Hasher;
 use sp_runtime::traits::BlakeTwo256;
 use subspace_core_primitives::crypto;
+use subspace_core_primitives::objects::ObjectManifest;
 
 const FEED_ID: u64 = 0;
 const OWNER: u64 = 100;
@@ -77,10 +84,115 @@ fn can_do_put() {
             object_size,
         }));
 
-        // only owner can put
+        // only owner and writers can put
         assert_noop!(
             Feeds::put(Origin::signed(NOT_OWNER), FEED_ID, object),
-            Error::<Test>::NotFeedOwner
+            Error::<Test>::NotFeedWriter
+        );
+    });
+}
+
+#[test]
+fn can_put_chunked_object() {
+    new_test_ext().execute_with(|| {
+        let object: Object = (0..=255).collect();
+        let chunks = object.chunks(100).collect::<Vec<_>>();
+        let manifest = ObjectManifest::from_chunks(chunks.iter().copied());
+        assert_ok!(Feeds::create(
+            Origin::signed(OWNER),
+            Default::default(),
+            None
+        ));
+
+        // manifest can't refer to chunks that were not put
+        assert_noop!(
+            Feeds::put_manifest(Origin::signed(OWNER), FEED_ID, manifest.to_object()),
+            Error::<Test>::UnknownChunk
+        );
+
+        for chunk in &chunks {
+            assert_ok!(Feeds::put_chunk(
+                Origin::signed(OWNER),
+                FEED_ID,
+                chunk.to_vec()
+            ));
+            System::assert_last_event(Event::Feeds(crate::Event::<Test>::ChunkSubmitted {
+                feed_id: FEED_ID,
+                who: OWNER,
+                chunk_hash: crypto::sha256_hash(chunk),
+                chunk_size: chunk.len() as u64,
+            }));
+        }
+
+        // only manifests can be put
+        assert_noop!(
+            Feeds::put_manifest(Origin::signed(OWNER), FEED_ID, object.clone()),
+            Error::<Test>::InvalidManifest
+        );
+        // manifest size must match the size of the chunks
+        let invalid_manifest = ObjectManifest {
+            size: manifest.size + 1,
+            chunks: manifest.chunks.clone(),
+        };
+        assert_noop!(
+            Feeds::put_manifest(Origin::signed(OWNER), FEED_ID, invalid_manifest.to_object()),
+            Error::<Test>::InvalidManifest
+        );
+
+        assert_ok!(Feeds::put_manifest(
+            Origin::signed(OWNER),
+            FEED_ID,
+            manifest.to_object()
+        ));
+        System::assert_last_event(Event::Feeds(crate::Event::<Test>::ChunkedObjectSubmitted {
+            feed_id: FEED_ID,
+            who: OWNER,
+            content_root: manifest.content_root(),
+            object_size: object.len() as u64,
+        }));
+
+        // chunks are consumed by the manifest
+        for chunk in &chunks {
+            assert_eq!(
+                Feeds::pending_chunks(FEED_ID, crypto::sha256_hash(chunk)),
+                None
+            );
+        }
+        assert_eq!(
+            Feeds::totals(FEED_ID),
+            TotalObjectsAndSize {
+                count: chunks.len() as u64 + 1,
+                size: (object.len() + manifest.to_object().len()) as u64,
+            }
+        );
+        assert_eq!(SuccessfulPuts::<Test>::get().len(), chunks.len() + 1);
+
+        // chunks and manifest are mapped by their content
+        for call in [
+            FeedsCall::<Test>::put_chunk {
+                feed_id: FEED_ID,
+                chunk: chunks[0].to_vec(),
+            },
+            FeedsCall::<Test>::put_manifest {
+                feed_id: FEED_ID,
+                manifest: manifest.to_object(),
+            },
+        ] {
+            let mappings = call.extract_call_objects();
+            assert_eq!(mappings.len(), 1);
+            let encoded_call = call.encode();
+            let mapped_object =
+                Vec::<u8>::decode(&mut &encoded_call[mappings[0].offset as usize..]).unwrap();
+            assert_eq!(mappings[0].key, crypto::sha256_hash(&mapped_object));
+        }
+        assert_eq!(
+            FeedsCall::<Test>::put_manifest {
+                feed_id: FEED_ID,
+                manifest: manifest.to_object(),
+            }
+            .extract_call_objects()[0]
+                .key,
+            manifest.content_root()
         );
     });
 }
@@ -132,6 +244,262 @@ fn can_close_open_feed() {
     });
 }
 
+#[test]
+fn can_reopen_closed_feed() {
+    new_test_ext().execute_with(|| {
+        let object: Object = vec![1, 2, 3, 4, 5];
+        assert_ok!(Feeds::create(
+            Origin::signed(OWNER),
+            Default::default(),
+            None
+        ));
+        assert_ok!(Feeds::close(Origin::signed(OWNER), FEED_ID));
+
+        // only owner can reopen
+        assert_noop!(
+            Feeds::reopen(Origin::signed(NOT_OWNER), FEED_ID),
+            Error::<Test>::NotFeedOwner
+        );
+
+        assert_ok!(Feeds::reopen(Origin::signed(OWNER), FEED_ID));
+        System::assert_last_event(Event::Feeds(crate::Event::<Test>::FeedReopened {
+            feed_id: FEED_ID,
+            who: OWNER,
+        }));
+
+        assert_ok!(Feeds::put(Origin::signed(OWNER), FEED_ID, object));
+    });
+}
+
+#[test]
+fn can_delete_feed() {
+    new_test_ext().execute_with(|| {
+        let object: Object = vec![1, 2, 3, 4, 5];
+        let writer = 102u64;
+        assert_ok!(Feeds::create(
+            Origin::signed(OWNER),
+            Default::default(),
+            None
+        ));
+        assert_ok!(Feeds::add_writer(
+            Origin::signed(OWNER),
+            FEED_ID,
+            writer,
+            WriterQuota::default()
+        ));
+        assert_ok!(Feeds::put(Origin::signed(writer), FEED_ID, object));
+
+        // only owner can delete
+        assert_noop!(
+            Feeds::delete(Origin::signed(NOT_OWNER), FEED_ID),
+            Error::<Test>::NotFeedOwner
+        );
+
+        assert_ok!(Feeds::delete(Origin::signed(OWNER), FEED_ID));
+        System::assert_last_event(Event::Feeds(crate::Event::<Test>::FeedDeleted {
+            feed_id: FEED_ID,
+            who: OWNER,
+        }));
+
+        assert!(Feeds::feed_configs(FEED_ID).is_none());
+        assert_eq!(Feeds::metadata(FEED_ID), None);
+        assert_eq!(Feeds::totals(FEED_ID), TotalObjectsAndSize::default());
+        assert_eq!(Feeds::feeds(OWNER), None);
+
+        // writers are cleared with the weight left in the block
+        assert!(Feeds::writers(FEED_ID, writer).is_some());
+        Feeds::on_idle(1, Weight::MAX);
+        assert!(FeedsToClear::<Test>::get(FEED_ID).is_none());
+        assert_eq!(Feeds::writers(FEED_ID, writer), None);
+        assert_eq!(
+            Feeds::writer_totals(FEED_ID, writer),
+            TotalObjectsAndSize::default()
+        );
+
+        assert_noop!(
+            Feeds::delete(Origin::signed(OWNER), FEED_ID),
+            Error::<Test>::UnknownFeedId
+        );
+
+        // slot is freed, so the owner can create a new feed despite the limit of one
+        assert_ok!(Feeds::create(
+            Origin::signed(OWNER),
+            Default::default(),
+            None
+        ));
+        assert_eq!(Feeds::feeds(OWNER).unwrap().to_vec(), vec![FEED_ID + 1]);
+    });
+}
+
+#[test]
+fn deleted_feed_is_cleared_within_remaining_weight() {
+    new_test_ext().execute_with(|| {
+        let writers = [102u64, 103, 104];
+        assert_ok!(Feeds::create(
+            Origin::signed(OWNER),
+            Default::default(),
+            None
+        ));
+        for writer in writers {
+            assert_ok!(Feeds::add_writer(
+                Origin::signed(OWNER),
+                FEED_ID,
+                writer,
+                WriterQuota::default()
+            ));
+        }
+        assert_ok!(Feeds::delete(Origin::signed(OWNER), FEED_ID));
+
+        // enough weight to look up the feed and clear two writers
+        let item_weight = RocksDbWeight::get().reads_writes(1, 1);
+        let weight = item_weight * 3;
+        assert_eq!(Feeds::clear_deleted_feeds(weight), weight);
+        let remaining = writers
+            .iter()
+            .filter(|writer| Feeds::writers(FEED_ID, **writer).is_some())
+            .count();
+        assert_eq!(remaining, 1);
+        assert!(FeedsToClear::<Test>::get(FEED_ID).is_some());
+
+        // not enough weight to clear anything
+        assert_eq!(Feeds::clear_deleted_feeds(item_weight), 0);
+        assert!(FeedsToClear::<Test>::get(FEED_ID).is_some());
+
+        Feeds::on_idle(3, Weight::MAX);
+        for writer in writers {
+            assert_eq!(Feeds::writers(FEED_ID, writer), None);
+        }
+        assert!(FeedsToClear::<Test>::get(FEED_ID).is_none());
+        assert_eq!(Feeds::clear_deleted_feeds(Weight::MAX), 0);
+    });
+}
+
+#[test]
+fn manifest_chunks_are_limited() {
+    new_test_ext().execute_with(|| {
+        let object: Object = (0..=255).collect();
+        let manifest = ObjectManifest::from_chunks(object.chunks(50));
+        assert_eq!(manifest.chunks.len(), 6);
+        assert_ok!(Feeds::create(
+            Origin::signed(OWNER),
+            Default::default(),
+            None
+        ));
+
+        for chunk in object.chunks(50) {
+            assert_ok!(Feeds::put_chunk(
+                Origin::signed(OWNER),
+                FEED_ID,
+                chunk.to_vec()
+            ));
+        }
+
+        assert_noop!(
+            Feeds::put_manifest(Origin::signed(OWNER), FEED_ID, manifest.to_object()),
+            Error::<Test>::TooManyChunks
+        );
+    });
+}
+
+#[test]
+fn put_manifest_is_weighed_by_chunks() {
+    new_test_ext().execute_with(|| {
+        let object: Object = (0..=255).collect();
+        let manifest = ObjectManifest::from_chunks(object.chunks(100));
+        assert_ok!(Feeds::create(
+            Origin::signed(OWNER),
+            Default::default(),
+            None
+        ));
+        for chunk in object.chunks(100) {
+            assert_ok!(Feeds::put_chunk(
+                Origin::signed(OWNER),
+                FEED_ID,
+                chunk.to_vec()
+            ));
+        }
+
+        let call = FeedsCall::<Test>::put_manifest {
+            feed_id: FEED_ID,
+            manifest: manifest.to_object(),
+        };
+        assert_eq!(
+            call.get_dispatch_info().weight,
+            Feeds::put_manifest_weight(4)
+        );
+        let post_info =
+            Feeds::put_manifest(Origin::signed(OWNER), FEED_ID, manifest.to_object()).unwrap();
+        assert_eq!(post_info.actual_weight, Some(Feeds::put_manifest_weight(3)));
+    });
+}
+
+#[test]
+fn pending_chunks_expire() {
+    new_test_ext().execute_with(|| {
+        let object: Object = (0..=255).collect();
+        let manifest = ObjectManifest::from_chunks(object.chunks(100));
+        assert_ok!(Feeds::create(
+            Origin::signed(OWNER),
+            Default::default(),
+            None
+        ));
+        for chunk in object.chunks(100) {
+            assert_ok!(Feeds::put_chunk(
+                Origin::signed(OWNER),
+                FEED_ID,
+                chunk.to_vec()
+            ));
+        }
+        Feeds::on_idle(1, Weight::MAX);
+        assert!(Feeds::pending_chunks(FEED_ID, manifest.chunks[0]).is_some());
+
+        // putting a chunk again extends its expiry
+        System::set_block_number(5);
+        assert_ok!(Feeds::put_chunk(
+            Origin::signed(OWNER),
+            FEED_ID,
+            object[..100].to_vec()
+        ));
+        assert_eq!(
+            Feeds::pending_chunks(FEED_ID, manifest.chunks[0]),
+            Some((100, 15))
+        );
+
+        // expired chunks can't be referred to even before they are removed
+        System::set_block_number(11);
+        assert_noop!(
+            Feeds::put_manifest(Origin::signed(OWNER), FEED_ID, manifest.to_object()),
+            Error::<Test>::UnknownChunk
+        );
+
+        Feeds::on_idle(11, Weight::MAX);
+        assert!(Feeds::pending_chunks(FEED_ID, manifest.chunks[0]).is_some());
+        assert_eq!(Feeds::pending_chunks(FEED_ID, manifest.chunks[1]), None);
+        assert_eq!(Feeds::pending_chunks(FEED_ID, manifest.chunks[2]), None);
+        assert_eq!(ChunkExpiries::<Test>::iter_prefix(11).count(), 0);
+
+        System::set_block_number(15);
+        Feeds::on_idle(15, Weight::MAX);
+        assert_eq!(Feeds::pending_chunks(FEED_ID, manifest.chunks[0]), None);
+        assert_eq!(ChunkExpiries::<Test>::iter().count(), 0);
+    });
+}
+
+#[test]
+fn cannot_reopen_open_feed() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Feeds::create(
+            Origin::signed(OWNER),
+            Default::default(),
+            None
+        ));
+        assert_noop!(
+            Feeds::reopen(Origin::signed(OWNER), FEED_ID),
+            Error::<Test>::FeedNotClosed
+        );
+    });
+}
+
 #[test]
 fn cannot_close_invalid_feed() {
     new_test_ext().execute_with(|| {
@@ -202,6 +570,150 @@ fn transfer_feed_ownership() {
     });
 }
 
+#[test]
+fn writers_can_put() {
+    new_test_ext().execute_with(|| {
+        let object: Object = vec![1, 2, 3, 4, 5];
+        let object_size = object.len() as u64;
+        let writer = 102u64;
+        assert_ok!(Feeds::create(
+            Origin::signed(OWNER),
+            Default::default(),
+            None
+        ));
+
+        // only owner can add writers
+        assert_noop!(
+            Feeds::add_writer(
+                Origin::signed(NOT_OWNER),
+                FEED_ID,
+                writer,
+                WriterQuota::default()
+            ),
+            Error::<Test>::NotFeedOwner
+        );
+        assert_ok!(Feeds::add_writer(
+            Origin::signed(OWNER),
+            FEED_ID,
+            writer,
+            WriterQuota::default()
+        ));
+        System::assert_last_event(Event::Feeds(crate::Event::<Test>::WriterAdded {
+            feed_id: FEED_ID,
+            who: OWNER,
+            writer,
+            quota: WriterQuota::default(),
+        }));
+
+        assert_ok!(Feeds::put(Origin::signed(writer), FEED_ID, object.clone()));
+        System::assert_last_event(Event::Feeds(crate::Event::<Test>::ObjectSubmitted {
+            feed_id: FEED_ID,
+            who: writer,
+            metadata: vec![],
+            object_size,
+        }));
+        assert_ok!(Feeds::put(Origin::signed(OWNER), FEED_ID, object.clone()));
+
+        // feed totals include the objects of both, writer totals only its own
+        assert_eq!(
+            Feeds::totals(FEED_ID),
+            TotalObjectsAndSize {
+                count: 2,
+                size: object_size * 2,
+            }
+        );
+        assert_eq!(
+            Feeds::writer_totals(FEED_ID, writer),
+            TotalObjectsAndSize {
+                count: 1,
+                size: object_size,
+            }
+        );
+
+        // only owner can remove writers
+        assert_noop!(
+            Feeds::remove_writer(Origin::signed(writer), FEED_ID, writer),
+            Error::<Test>::NotFeedOwner
+        );
+        assert_ok!(Feeds::remove_writer(Origin::signed(OWNER), FEED_ID, writer));
+        System::assert_last_event(Event::Feeds(crate::Event::<Test>::WriterRemoved {
+            feed_id: FEED_ID,
+            who: OWNER,
+            writer,
+        }));
+        assert_eq!(Feeds::writers(FEED_ID, writer), None);
+        assert_eq!(
+            Feeds::writer_totals(FEED_ID, writer),
+            TotalObjectsAndSize::default()
+        );
+
+        assert_noop!(
+            Feeds::put(Origin::signed(writer), FEED_ID, object),
+            Error::<Test>::NotFeedWriter
+        );
+        assert_noop!(
+            Feeds::remove_writer(Origin::signed(OWNER), FEED_ID, writer),
+            Error::<Test>::UnknownWriter
+        );
+    });
+}
+
+#[test]
+fn writers_are_bound_by_quota() {
+    new_test_ext().execute_with(|| {
+        let object: Object = vec![1, 2, 3, 4, 5];
+        let writer = 102u64;
+        assert_ok!(Feeds::create(
+            Origin::signed(OWNER),
+            Default::default(),
+            None
+        ));
+        assert_ok!(Feeds::add_writer(
+            Origin::signed(OWNER),
+            FEED_ID,
+            writer,
+            WriterQuota {
+                max_block_size: Some(10),
+                max_total_size: Some(15),
+            }
+        ));
+
+        assert_ok!(Feeds::put(Origin::signed(writer), FEED_ID, object.clone()));
+        assert_ok!(Feeds::put(Origin::signed(writer), FEED_ID, object.clone()));
+        assert_noop!(
+            Feeds::put(Origin::signed(writer), FEED_ID, object.clone()),
+            Error::<Test>::WriterBlockQuotaExceeded
+        );
+
+        // owner is not bound by the quota
+        assert_ok!(Feeds::put(Origin::signed(OWNER), FEED_ID, object.clone()));
+
+        // block quota is reset in the next block
+        System::set_block_number(2);
+        assert_ok!(Feeds::put(Origin::signed(writer), FEED_ID, object.clone()));
+        assert_noop!(
+            Feeds::put(Origin::signed(writer), FEED_ID, object.clone()),
+            Error::<Test>::WriterTotalQuotaExceeded
+        );
+        assert_eq!(
+            Feeds::writer_totals(FEED_ID, writer),
+            TotalObjectsAndSize { count: 3, size: 15 }
+        );
+
+        // raising the quota allows the writer to put again
+        assert_ok!(Feeds::add_writer(
+            Origin::signed(OWNER),
+            FEED_ID,
+            writer,
+            WriterQuota {
+                max_block_size: None,
+                max_total_size: Some(20),
+            }
+        ));
+        assert_ok!(Feeds::put(Origin::signed(writer), FEED_ID, object));
+    });
+}
+
 #[test]
 fn cannot_create_after_max_feeds() {
     new_test_ext().execute_with(|| {
@@ -52,6 +52,23 @@ pub struct AuthoritySet {
     pub authorities: AuthorityList,
     /// Monotonic identifier of the current GRANDPA authority set.
     pub set_id: SetId,
+    /// Authority set change signaled by an imported header that is not enacted yet.
+    pub pending_change: Option<PendingChange>,
+}
+
+/// A signaled GRANDPA authority set change waiting for its delay to elapse.
+#[derive(Debug, Encode, Decode, PartialEq, Clone, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct PendingChange {
+    /// List of GRANDPA authorities the change hands off to.
+    pub next_authorities: AuthorityList,
+    /// Scale encoded number of the block at which the change is enacted.
+    pub enact_at: Vec<u8>,
+    /// Whether the change is forced.
+    ///
+    /// Forced changes are enacted once the block they are effective at is imported, while standard
+    /// changes have to wait for that block to be finalized by the current authority set.
+    pub forced: bool,
 }
 
 /// Votes ancestries with useful methods.
@@ -18,8 +18,15 @@
 //! This pallet is an on-chain GRANDPA finality verifier for Substrate based chains.
 //!
 //! The pallet is responsible for tracking GRANDPA validator set hand-offs. We only accept headers
-//! with justifications signed by the current validator set we know of. The header is inspected for
-//! a `ScheduledChanges` digest item, which is then used to update to next validator set.
+//! with justifications signed by the current validator set we know of. Once its justification is
+//! verified, the header is inspected for `ScheduledChange` and `ForcedChange` digest items, which
+//! are tracked as a pending change of the current validator set until their delay elapses, so a
+//! change is never taken from a header that is not finalized. Standard changes are enacted once the
+//! block they are effective at is finalized, forced changes as soon as that block is imported, so
+//! its justification is expected to be signed by the next validator set already.
+//!
+//! The state roots of the recently finalized headers are kept, so parachain blocks can be validated
+//! with a storage proof of the parachain head against the finalized state of their relay chain.
 //!
 //! Since this pallet only tracks finalized headers it does not deal with forks. Forks can only
 //! occur if the GRANDPA validator set on the bridged chain is either colluding or there is a severe
@@ -32,6 +39,8 @@
 mod grandpa;
 
 pub mod chain;
+pub mod migrations;
+pub mod parachain;
 #[cfg(test)]
 mod tests;
 
@@ -60,18 +69,27 @@ pub struct InitializationData {
 type EncodedBlockNumber = Vec<u8>;
 type EncodedBlockHash = Vec<u8>;
 type EncodedHeader = Vec<u8>;
+type EncodedStateRoot = Vec<u8>;
 
 #[frame_support::pallet]
 pub mod pallet {
     use crate::chain::Chain;
     use crate::grandpa::{
         find_forced_change, find_scheduled_change, verify_justification, AuthoritySet,
+        PendingChange,
+    };
+    use crate::parachain::{
+        read_parachain_head, ParaId, ParachainHeadProof, ParachainInitializationData,
+        PARACHAIN_HEAD_PROOF_ENGINE_ID,
+    };
+    use crate::{
+        EncodedBlockHash, EncodedBlockNumber, EncodedHeader, EncodedStateRoot, InitializationData,
     };
-    use crate::{EncodedBlockHash, EncodedBlockNumber, EncodedHeader, InitializationData};
     use finality_grandpa::voter_set::VoterSet;
     use frame_support::pallet_prelude::*;
+    use frame_system::pallet_prelude::BlockNumberFor;
     use sp_finality_grandpa::GRANDPA_ENGINE_ID;
-    use sp_runtime::traits::{CheckedAdd, CheckedSub, Hash, Header, One, Zero};
+    use sp_runtime::traits::{CheckedAdd, CheckedSub, Hash, Header, One};
     use sp_runtime::ArithmeticError;
     use sp_std::fmt::Debug;
 
@@ -79,12 +97,27 @@ pub mod pallet {
     pub trait Config: frame_system::Config {
         // Chain ID uniquely identifies a substrate based chain
         type ChainId: Parameter + Member + Debug + Default + Copy;
+
+        /// Number of the most recent finalized headers of each chain to keep the state root of.
+        #[pallet::constant]
+        type HeadersToKeep: Get<u32>;
     }
 
+    /// The current storage version.
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
     #[pallet::pallet]
+    #[pallet::storage_version(STORAGE_VERSION)]
     #[pallet::without_storage_info]
     pub struct Pallet<T>(PhantomData<T>);
 
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_runtime_upgrade() -> Weight {
+            crate::migrations::migrate_to_v1::<T>()
+        }
+    }
+
     /// The point after which the block validation begins
     #[pallet::storage]
     pub(super) type ValidationCheckPoint<T: Config> =
@@ -101,10 +134,41 @@ pub mod pallet {
         StorageMap<_, Identity, T::ChainId, (EncodedBlockNumber, EncodedBlockHash), ValueQuery>;
 
     /// The current GRANDPA Authority set for a given Chain
+    ///
+    /// The pending change of the authority set is tracked since storage version 1.
     #[pallet::storage]
     pub(super) type CurrentAuthoritySet<T: Config> =
         StorageMap<_, Identity, T::ChainId, AuthoritySet, ValueQuery>;
 
+    /// Hashes of the recent finalized headers by their number for a given Chain
+    #[pallet::storage]
+    pub(super) type FinalizedHashes<T: Config> = StorageDoubleMap<
+        _,
+        Identity,
+        T::ChainId,
+        Identity,
+        EncodedBlockNumber,
+        EncodedBlockHash,
+        OptionQuery,
+    >;
+
+    /// State roots of the recent finalized headers by their hash for a given Chain
+    #[pallet::storage]
+    pub(super) type FinalizedStateRoots<T: Config> = StorageDoubleMap<
+        _,
+        Identity,
+        T::ChainId,
+        Identity,
+        EncodedBlockHash,
+        EncodedStateRoot,
+        OptionQuery,
+    >;
+
+    /// Relay chain and para ID of a given parachain
+    #[pallet::storage]
+    pub(super) type Parachains<T: Config> =
+        StorageMap<_, Identity, T::ChainId, (T::ChainId, ParaId), OptionQuery>;
+
     #[pallet::error]
     pub enum Error<T> {
         /// The block and its contents are not valid
@@ -131,8 +195,25 @@ pub mod pallet {
         InvalidHeader,
         /// The scheduled authority set change found in the header is unsupported by the pallet.
         ///
-        /// This is the case for non-standard (e.g forced) authority set changes.
+        /// This is the case for an initialization header without a scheduled change.
         UnsupportedScheduledChange,
+        /// The header signals an authority set change while another one is still pending.
+        AuthorityChangeAlreadyPending,
+        /// The relay chain of the parachain is not initialized.
+        UnknownRelayChain,
+        /// The chain is not initialized as a parachain.
+        ///
+        /// This is also the case for the parachains imported before their blocks were validated
+        /// against the relay chain, see [`initialize_parachain`].
+        UnknownParachain,
+        /// The relay chain block is not finalized or its state root is not kept anymore.
+        UnknownRelayChainBlock,
+        /// Parachain head proof is missing.
+        MissingParachainHeadProof,
+        /// Failed to decode parachain head proof
+        FailedDecodingParachainHeadProof,
+        /// The block is not the head of the parachain at the given relay chain block.
+        InvalidParachainHeadProof,
     }
 
     /// Initializes the chain by extracting the Authority set and best known parent of the chain.
@@ -159,6 +240,7 @@ pub mod pallet {
         let authority_set = AuthoritySet {
             authorities: change.next_authorities,
             set_id,
+            pending_change: None,
         };
         CurrentAuthoritySet::<T>::insert(chain_id, authority_set);
 
@@ -240,17 +322,21 @@ pub mod pallet {
 
         // if the target header is a descendent of validation block, validate the justification
         if number > validation_number {
+            // fetch current authority set
+            let mut authority_set = <CurrentAuthoritySet<T>>::get(chain_id);
+
+            // forced changes do not wait for finality, the block they are effective at is
+            // already finalized by the next authority set
+            try_enact_pending_change::<T, C>(&mut authority_set, number, true)?;
+
             let justification = block
                 .justifications
-                .ok_or(Error::<T>::MissingJustification)?
-                .into_justification(GRANDPA_ENGINE_ID)
+                .and_then(|justifications| justifications.into_justification(GRANDPA_ENGINE_ID))
                 .ok_or(Error::<T>::MissingJustification)?;
             let justification = C::decode_grandpa_justifications::<T>(justification.as_slice())?;
 
-            // fetch current authority set
-            let authority_set = <CurrentAuthoritySet<T>>::get(chain_id);
-            let voter_set =
-                VoterSet::new(authority_set.authorities).ok_or(Error::<T>::InvalidAuthoritySet)?;
+            let voter_set = VoterSet::new(authority_set.authorities.clone())
+                .ok_or(Error::<T>::InvalidAuthoritySet)?;
             let set_id = authority_set.set_id;
 
             // verify justification
@@ -265,56 +351,135 @@ pub mod pallet {
                 Error::<T>::InvalidJustification
             })?;
 
-            // Update any next authority set if any
-            try_enact_authority_change::<T, C>(chain_id, &block.block.header, set_id)?;
+            // track any change signaled by the header now that it is finalized by the current set
+            schedule_authority_change::<T, C>(&mut authority_set, &block.block.header)?;
+
+            // standard changes are enacted once the block they are effective at is finalized,
+            // changes without a delay are effective at the block signaling them
+            try_enact_pending_change::<T, C>(&mut authority_set, number, false)?;
+            try_enact_pending_change::<T, C>(&mut authority_set, number, true)?;
+
+            CurrentAuthoritySet::<T>::insert(chain_id, authority_set);
         }
 
         // update the latest descendant
         ChainTip::<T>::insert(chain_id, (number.encode(), hash.encode()));
+        record_finalized_header::<T, C>(chain_id, &block.block.header);
         Ok((hash, number))
     }
 
-    /// Check the given header for a GRANDPA scheduled authority set change. If a change
-    /// is found it will be enacted immediately.
-    ///
-    /// This function does not support forced changes, or scheduled changes with delays
-    /// since these types of changes are indicative of abnormal behavior from GRANDPA.
-    pub(crate) fn try_enact_authority_change<T: Config, C: Chain>(
+    /// Keeps the state root of the finalized header and prunes the one that is
+    /// `T::HeadersToKeep` blocks older.
+    pub(crate) fn record_finalized_header<T: Config, C: Chain>(
+        chain_id: T::ChainId,
+        header: &C::Header,
+    ) {
+        let number = *header.number();
+        let hash = header.hash().encode();
+        FinalizedHashes::<T>::insert(chain_id, number.encode(), hash.clone());
+        FinalizedStateRoots::<T>::insert(chain_id, hash, header.state_root().encode());
+
+        if let Some(pruned_number) = number.checked_sub(&T::HeadersToKeep::get().into()) {
+            if let Some(pruned_hash) = FinalizedHashes::<T>::take(chain_id, pruned_number.encode())
+            {
+                FinalizedStateRoots::<T>::remove(chain_id, pruned_hash);
+            }
+        }
+    }
+
+    /// Returns the state root of the given finalized header, if it is still kept.
+    pub fn finalized_state_root<T: Config, C: Chain>(
         chain_id: T::ChainId,
+        hash: C::Hash,
+    ) -> Result<Option<C::Hash>, DispatchError> {
+        FinalizedStateRoots::<T>::get(chain_id, hash.encode())
+            .map(|state_root| C::decode_block_hash::<T>(state_root.as_slice()))
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    /// Check the given header for a GRANDPA scheduled or forced authority set change. If a change
+    /// is found it is tracked as the pending change of the authority set.
+    ///
+    /// The header must already be finalized by the authority set.
+    ///
+    /// GRANDPA does not signal a new change until the pending one is enacted, so a header doing
+    /// so is rejected.
+    pub(crate) fn schedule_authority_change<T: Config, C: Chain>(
+        authority_set: &mut AuthoritySet,
         header: &C::Header,
-        current_set_id: sp_finality_grandpa::SetId,
     ) -> DispatchResult {
-        // We don't support forced changes - at that point governance intervention is required.
+        let (change, forced) = match find_forced_change(header) {
+            Some((median_last_finalized, change)) => {
+                log::info!(
+                    target: "runtime::grandpa-finality-verifier",
+                    "Forced authority set change signaled at {:?}, median last finalized is {:?}",
+                    header.number(),
+                    median_last_finalized,
+                );
+                (change, true)
+            }
+            None => match find_scheduled_change(header) {
+                Some(change) => (change, false),
+                None => return Ok(()),
+            },
+        };
+
         ensure!(
-            find_forced_change(header).is_none(),
-            Error::<T>::UnsupportedScheduledChange
+            authority_set.pending_change.is_none(),
+            Error::<T>::AuthorityChangeAlreadyPending
         );
 
-        if let Some(change) = find_scheduled_change(header) {
-            // GRANDPA only includes a `delay` for forced changes, so this isn't valid.
-            ensure!(
-                change.delay == Zero::zero(),
-                Error::<T>::UnsupportedScheduledChange
-            );
+        let enact_at = header
+            .number()
+            .checked_add(&change.delay)
+            .ok_or(ArithmeticError::Overflow)?;
 
-            let next_authorities = AuthoritySet {
-                authorities: change.next_authorities,
-                set_id: current_set_id + 1,
-            };
-
-            // Since our header schedules a change and we know the delay is 0, it must also enact
-            // the change.
-            CurrentAuthoritySet::<T>::insert(chain_id, &next_authorities);
-
-            log::info!(
-                target: "runtime::grandpa-finality-verifier",
-                "Transitioned from authority set {} to {}! New authorities are: {:?}",
-                current_set_id,
-                current_set_id + 1,
-                next_authorities,
-            );
+        authority_set.pending_change = Some(PendingChange {
+            next_authorities: change.next_authorities,
+            enact_at: enact_at.encode(),
+            forced,
+        });
+
+        Ok(())
+    }
+
+    /// Enacts the pending change of the authority set if it is of the requested kind and
+    /// effective at the given block number.
+    pub(crate) fn try_enact_pending_change<T: Config, C: Chain>(
+        authority_set: &mut AuthoritySet,
+        number: C::BlockNumber,
+        forced: bool,
+    ) -> DispatchResult {
+        let pending_change = match authority_set.pending_change.take() {
+            Some(pending_change) if pending_change.forced == forced => pending_change,
+            pending_change => {
+                authority_set.pending_change = pending_change;
+                return Ok(());
+            }
         };
 
+        let enact_at = C::decode_block_number::<T>(pending_change.enact_at.as_slice())?;
+        if number < enact_at {
+            authority_set.pending_change = Some(pending_change);
+            return Ok(());
+        }
+
+        let current_set_id = authority_set.set_id;
+        authority_set.authorities = pending_change.next_authorities;
+        authority_set.set_id = current_set_id
+            .checked_add(1)
+            .ok_or(ArithmeticError::Overflow)?;
+
+        log::info!(
+            target: "runtime::grandpa-finality-verifier",
+            "Transitioned from authority set {} to {} at {:?}! New authorities are: {:?}",
+            current_set_id,
+            authority_set.set_id,
+            number,
+            authority_set.authorities,
+        );
+
         Ok(())
     }
 
@@ -346,6 +511,112 @@ pub mod pallet {
         CurrentAuthoritySet::<T>::remove(chain_id);
         ChainTip::<T>::remove(chain_id);
         OldestKnownParent::<T>::remove(chain_id);
+        FinalizedHashes::<T>::drain_prefix(chain_id).for_each(drop);
+        FinalizedStateRoots::<T>::drain_prefix(chain_id).for_each(drop);
+        Ok(())
+    }
+
+    /// Initializes a parachain validated by the given relay chain.
+    ///
+    /// The parachains imported before their blocks were validated against the relay chain don't
+    /// have a relay chain and para ID recorded, which can't be derived from their existing state,
+    /// so they reject every block with [`Error::UnknownParachain`] until they are initialized
+    /// again with this function. The import then starts over from any block after the
+    /// initialization.
+    pub fn initialize_parachain<T: Config>(
+        chain_id: T::ChainId,
+        init_data: &[u8],
+    ) -> DispatchResult {
+        let ParachainInitializationData {
+            relay_chain_id,
+            para_id,
+        } = ParachainInitializationData::<T::ChainId>::decode(&mut &*init_data).map_err(
+            |error| {
+                log::error!("Cannot decode parachain init data, error: {:?}", error);
+                Error::<T>::FailedDecodingInitData
+            },
+        )?;
+
+        ensure!(
+            ValidationCheckPoint::<T>::contains_key(relay_chain_id),
+            Error::<T>::UnknownRelayChain
+        );
+
+        Parachains::<T>::insert(chain_id, (relay_chain_id, para_id));
+        ChainTip::<T>::remove(chain_id);
+        Ok(())
+    }
+
+    /// Validates that the parachain block is the head of the parachain at a finalized block of
+    /// its relay chain `R` and extends the last imported parachain block.
+    pub fn validate_parachain_block<T: Config, C: Chain, R: Chain>(
+        chain_id: T::ChainId,
+        object: &[u8],
+    ) -> Result<(C::Hash, C::BlockNumber), DispatchError> {
+        let block = C::decode_block::<T>(object)?;
+        let number = *block.block.header.number();
+        let hash = block.block.header.hash();
+
+        let extrinsics_root = C::Hasher::ordered_trie_root(
+            block.block.extrinsics.iter().map(Encode::encode).collect(),
+            sp_runtime::StateVersion::V0,
+        );
+        ensure!(
+            extrinsics_root == *block.block.header.extrinsics_root(),
+            Error::<T>::InvalidBlock
+        );
+
+        let (relay_chain_id, para_id) =
+            Parachains::<T>::get(chain_id).ok_or(Error::<T>::UnknownParachain)?;
+
+        let proof = block
+            .justifications
+            .ok_or(Error::<T>::MissingParachainHeadProof)?
+            .into_justification(PARACHAIN_HEAD_PROOF_ENGINE_ID)
+            .ok_or(Error::<T>::MissingParachainHeadProof)?;
+        let ParachainHeadProof {
+            relay_block_hash,
+            storage_proof,
+        } = ParachainHeadProof::<R::Hash>::decode(&mut proof.as_slice()).map_err(|error| {
+            log::error!("Cannot decode parachain head proof, error: {:?}", error);
+            Error::<T>::FailedDecodingParachainHeadProof
+        })?;
+
+        let state_root = finalized_state_root::<T, R>(relay_chain_id, relay_block_hash)?
+            .ok_or(Error::<T>::UnknownRelayChainBlock)?;
+
+        let head = read_parachain_head::<R::Hasher>(state_root, para_id, storage_proof)
+            .ok_or(Error::<T>::InvalidParachainHeadProof)?;
+        ensure!(
+            head == block.block.header.encode(),
+            Error::<T>::InvalidParachainHeadProof
+        );
+
+        // the parachain blocks are imported sequentially, starting from any block after the
+        // initialization
+        if let Ok(parachain_tip) = ChainTip::<T>::try_get(chain_id) {
+            let (parent_number, parent_hash) = C::decode_block_number_and_hash::<T>(parachain_tip)?;
+            ensure!(
+                number
+                    == parent_number
+                        .checked_add(&One::one())
+                        .ok_or(ArithmeticError::Overflow)?,
+                Error::<T>::InvalidBlock
+            );
+            ensure!(
+                *block.block.header.parent_hash() == parent_hash,
+                Error::<T>::InvalidBlock
+            );
+        }
+        ChainTip::<T>::insert(chain_id, (number.encode(), hash.encode()));
+
+        Ok((hash, number))
+    }
+
+    /// purges the on chain state of a given parachain
+    pub fn purge_parachain<T: Config>(chain_id: T::ChainId) -> DispatchResult {
+        Parachains::<T>::remove(chain_id);
+        ChainTip::<T>::remove(chain_id);
         Ok(())
     }
 }
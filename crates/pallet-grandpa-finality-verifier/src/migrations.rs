@@ -0,0 +1,56 @@
+// Copyright (C) 2022 Subspace Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage migrations of the GRANDPA finality verifier.
+
+use crate::grandpa::AuthoritySet;
+use crate::pallet::{Config, CurrentAuthoritySet, Pallet};
+use codec::Decode;
+use frame_support::traits::{Get, GetStorageVersion, StorageVersion};
+use frame_support::weights::Weight;
+use sp_finality_grandpa::{AuthorityList, SetId};
+
+/// Authority set as stored before the pending authority set changes were tracked.
+#[derive(Decode)]
+struct AuthoritySetV0 {
+    authorities: AuthorityList,
+    set_id: SetId,
+}
+
+/// Migrates the authority sets of all chains to the format tracking the pending change.
+pub fn migrate_to_v1<T: Config>() -> Weight {
+    if Pallet::<T>::on_chain_storage_version() >= 1 {
+        return T::DbWeight::get().reads(1);
+    }
+
+    let mut translated = 0u64;
+    CurrentAuthoritySet::<T>::translate::<AuthoritySetV0, _>(|_chain_id, authority_set| {
+        translated += 1;
+        Some(AuthoritySet {
+            authorities: authority_set.authorities,
+            set_id: authority_set.set_id,
+            pending_change: None,
+        })
+    });
+    StorageVersion::new(1).put::<Pallet<T>>();
+
+    log::info!(
+        target: "runtime::grandpa-finality-verifier",
+        "Migrated {} authority sets to storage version 1",
+        translated,
+    );
+
+    T::DbWeight::get().reads_writes(translated + 1, translated + 1)
+}
@@ -0,0 +1,80 @@
+// Copyright (C) 2022 Subspace Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Verification of parachain blocks against the finalized state of their relay chain.
+//!
+//! The relay chain stores the head of every parachain under `Paras::Heads`, so a parachain block
+//! is as final as the relay chain block whose state contains its header as the head of the
+//! parachain.
+
+use codec::{Decode, Encode};
+use frame_support::{StorageHasher, Twox64Concat};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::traits::Hash as HashT;
+use sp_runtime::ConsensusEngineId;
+use sp_std::vec::Vec;
+use sp_trie::{read_trie_value, LayoutV1, StorageProof};
+
+/// Identifier of a parachain on its relay chain.
+pub type ParaId = u32;
+
+/// Engine id the parachain head proof is attached to the parachain block justifications with.
+pub const PARACHAIN_HEAD_PROOF_ENGINE_ID: ConsensusEngineId = *b"PHPF";
+
+/// Data required to initialize a parachain.
+#[derive(Default, Debug, Encode, Decode, Clone, PartialEq, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct ParachainInitializationData<ChainId> {
+    /// Chain ID of the relay chain the parachain is validated by.
+    pub relay_chain_id: ChainId,
+    /// ID of the parachain on the relay chain.
+    pub para_id: ParaId,
+}
+
+/// Proof that a parachain block is the head of the parachain at some finalized relay chain block.
+#[derive(Debug, Encode, Decode, Clone, PartialEq, Eq)]
+pub struct ParachainHeadProof<RelayHash> {
+    /// Hash of the finalized relay chain block.
+    pub relay_block_hash: RelayHash,
+    /// Proof of the `Paras::Heads` entry of the parachain in the state of the relay chain block.
+    pub storage_proof: StorageProof,
+}
+
+/// Returns the storage key of the `Paras::Heads` entry of given parachain on the relay chain.
+pub(crate) fn parachain_head_storage_key(para_id: ParaId) -> Vec<u8> {
+    let mut key = frame_support::storage::storage_prefix(b"Paras", b"Heads").to_vec();
+    key.extend(Twox64Concat::hash(&para_id.encode()));
+    key
+}
+
+/// Reads the encoded head of the given parachain from the relay chain state proof.
+pub(crate) fn read_parachain_head<Hasher: HashT>(
+    state_root: Hasher::Output,
+    para_id: ParaId,
+    storage_proof: StorageProof,
+) -> Option<Vec<u8>> {
+    let db = storage_proof.into_memory_db::<Hasher>();
+    let value = read_trie_value::<LayoutV1<Hasher>, _>(
+        &db,
+        &state_root,
+        &parachain_head_storage_key(para_id),
+    )
+    .ok()??;
+
+    // `HeadData` is a wrapper around the encoded header.
+    Vec::<u8>::decode(&mut value.as_slice()).ok()
+}
@@ -27,6 +27,7 @@ parameter_types! {
     pub const MaximumBlockWeight: Weight = 1024;
     pub const MaximumBlockLength: u32 = 2 * 1024;
     pub const AvailableBlockRatio: Perbill = Perbill::one();
+    pub const HeadersToKeep: u32 = 5;
 }
 
 impl frame_system::Config for TestRuntime {
@@ -58,6 +59,7 @@ impl frame_system::Config for TestRuntime {
 
 impl grandpa::Config for TestRuntime {
     type ChainId = ChainId;
+    type HeadersToKeep = HeadersToKeep;
 }
 
 pub fn run_test<T>(test: impl FnOnce() -> T) -> T {
@@ -3,13 +3,22 @@ mod keyring;
 mod mock;
 
 use crate::chain::{Chain, OpaqueExtrinsic};
-use crate::grandpa::{verify_justification, AuthoritySet, Error, GrandpaJustification};
+use crate::grandpa::{
+    verify_justification, AuthoritySet, Error, GrandpaJustification, PendingChange,
+};
+use crate::migrations::migrate_to_v1;
+use crate::parachain::{
+    parachain_head_storage_key, ParaId, ParachainHeadProof, ParachainInitializationData,
+    PARACHAIN_HEAD_PROOF_ENGINE_ID,
+};
 use crate::{
-    initialize, validate_finalized_block, ChainTip, CurrentAuthoritySet, Error as ErrorP,
-    InitializationData, OldestKnownParent, ValidationCheckPoint,
+    finalized_state_root, initialize, initialize_parachain, purge, purge_parachain,
+    validate_finalized_block, validate_parachain_block, ChainTip, CurrentAuthoritySet,
+    Error as ErrorP, InitializationData, OldestKnownParent, ValidationCheckPoint,
 };
 use codec::Encode;
 use frame_support::dispatch::DispatchResult;
+use frame_support::traits::StorageVersion;
 use frame_support::{assert_err, assert_ok};
 use justification::*;
 use keyring::*;
@@ -17,8 +26,10 @@ use mock::{run_test, ChainId, TestRuntime};
 use sp_core::Hasher as HasherT;
 use sp_finality_grandpa::{ConsensusLog, ScheduledChange, GRANDPA_ENGINE_ID};
 use sp_runtime::generic::SignedBlock;
+use sp_runtime::testing::H256;
 use sp_runtime::traits::{BlakeTwo256, Hash, Header};
 use sp_runtime::{generic, Digest, DigestItem, DispatchError};
+use sp_trie::{MemoryDB, StorageProof, TrieDBMutV1, TrieMut};
 
 type TestHeader = generic::Header<u32, BlakeTwo256>;
 
@@ -570,38 +581,136 @@ fn importing_header_enacts_new_authority_set() {
             <CurrentAuthoritySet<TestRuntime>>::get(chain_id),
             AuthoritySet {
                 authorities: next_authorities,
-                set_id: next_set_id
+                set_id: next_set_id,
+                pending_change: None,
             },
         );
     })
 }
 
+/// Imports headers `from..=to` on top of `parent`, justified by the given authority set.
+fn import_headers(
+    chain_id: ChainId,
+    parent: TestHeader,
+    from: u32,
+    to: u32,
+    set_id: u64,
+    authorities: Vec<(Account, u64)>,
+) -> TestHeader {
+    let mut parent_header = parent;
+    for number in from..=to {
+        let mut header = test_header::<TestHeader>(number);
+        header.set_parent_hash(parent_header.hash());
+        let justification = make_justification_for_header(JustificationGeneratorParams {
+            header: header.clone(),
+            set_id,
+            authorities: authorities.clone(),
+            ..Default::default()
+        });
+        assert_ok!(submit_finality_proof(
+            chain_id,
+            header.clone(),
+            Some(justification)
+        ));
+        parent_header = header;
+    }
+    parent_header
+}
+
 #[test]
-fn importing_header_rejects_header_with_scheduled_change_delay() {
+fn importing_header_enacts_delayed_scheduled_change() {
     run_test(|| {
         let chain_id: ChainId = 1;
         let validation_header = init_with_origin(chain_id, 0).unwrap();
 
-        // Need to update the header digest to indicate that our header signals an authority set
-        // change. However, the change doesn't happen until the next block.
+        // Signal a change that is enacted once block 3 is finalized.
         let mut header = test_header::<TestHeader>(1);
         header.set_parent_hash(validation_header.hash());
-        header.digest = change_log(1);
+        header.digest = change_log(2);
+        let justification = make_default_justification(&header);
+        assert_ok!(submit_finality_proof(
+            chain_id,
+            header.clone(),
+            Some(justification)
+        ));
 
-        // Create a valid justification for the header
+        assert_eq!(
+            <CurrentAuthoritySet<TestRuntime>>::get(chain_id),
+            AuthoritySet {
+                authorities: authority_list(),
+                set_id: TEST_GRANDPA_SET_ID,
+                pending_change: Some(PendingChange {
+                    next_authorities: vec![(ALICE.into(), 1), (BOB.into(), 1)],
+                    enact_at: 3u32.encode(),
+                    forced: false,
+                }),
+            },
+        );
+
+        // Blocks up to and including block 3 are still finalized by the current set.
+        let header = import_headers(chain_id, header, 2, 3, TEST_GRANDPA_SET_ID, test_keyring());
+        assert_eq!(
+            <CurrentAuthoritySet<TestRuntime>>::get(chain_id),
+            AuthoritySet {
+                authorities: vec![(ALICE.into(), 1), (BOB.into(), 1)],
+                set_id: TEST_GRANDPA_SET_ID + 1,
+                pending_change: None,
+            },
+        );
+
+        // The next block must be finalized by the new set.
+        let mut next_header = test_header::<TestHeader>(4);
+        next_header.set_parent_hash(header.hash());
+        assert_err!(
+            submit_finality_proof(
+                chain_id,
+                next_header.clone(),
+                Some(make_default_justification(&next_header))
+            ),
+            <ErrorP<TestRuntime>>::InvalidJustification
+        );
+        import_headers(
+            chain_id,
+            header,
+            4,
+            4,
+            TEST_GRANDPA_SET_ID + 1,
+            vec![(ALICE, 1), (BOB, 1)],
+        );
+    })
+}
+
+#[test]
+fn importing_header_rejects_overlapping_authority_set_changes() {
+    run_test(|| {
+        let chain_id: ChainId = 1;
+        let validation_header = init_with_origin(chain_id, 0).unwrap();
+
+        let mut header = test_header::<TestHeader>(1);
+        header.set_parent_hash(validation_header.hash());
+        header.digest = change_log(2);
         let justification = make_default_justification(&header);
+        assert_ok!(submit_finality_proof(
+            chain_id,
+            header.clone(),
+            Some(justification)
+        ));
 
-        // Should not be allowed to import this header
+        // Another change can't be signaled before the pending one is enacted.
+        let mut next_header = test_header::<TestHeader>(2);
+        next_header.set_parent_hash(header.hash());
+        next_header.digest = forced_change_log(0, 0);
+        let justification = make_default_justification(&next_header);
         assert_err!(
-            submit_finality_proof(chain_id, header, Some(justification)),
-            <ErrorP<TestRuntime>>::UnsupportedScheduledChange
+            submit_finality_proof(chain_id, next_header, Some(justification)),
+            <ErrorP<TestRuntime>>::AuthorityChangeAlreadyPending
         );
     })
 }
 
-fn forced_change_log(delay: u32) -> Digest {
+fn forced_change_log(median_last_finalized: u32, delay: u32) -> Digest {
     let consensus_log = ConsensusLog::<u32>::ForcedChange(
-        delay,
+        median_last_finalized,
         ScheduledChange {
             next_authorities: vec![(ALICE.into(), 1), (BOB.into(), 1)],
             delay,
@@ -617,24 +726,572 @@ fn forced_change_log(delay: u32) -> Digest {
 }
 
 #[test]
-fn importing_header_rejects_header_with_forced_changes() {
+fn importing_header_enacts_forced_change() {
+    run_test(|| {
+        let chain_id: ChainId = 1;
+        let validation_header = init_with_origin(chain_id, 0).unwrap();
+        let header = import_headers(
+            chain_id,
+            validation_header,
+            1,
+            3,
+            TEST_GRANDPA_SET_ID,
+            test_keyring(),
+        );
+
+        // A forced change is signaled at block 4 based on the median last finalized block, the way
+        // `pallet_grandpa::note_stalled` does it. It's effective at block 6. The change is only
+        // tracked once block 4 is finalized by the current set.
+        let mut forced_header = test_header::<TestHeader>(4);
+        forced_header.set_parent_hash(header.hash());
+        forced_header.digest = forced_change_log(3, 2);
+        assert_err!(
+            submit_finality_proof(chain_id, forced_header.clone(), None),
+            <ErrorP<TestRuntime>>::MissingJustification
+        );
+        assert_ok!(submit_finality_proof(
+            chain_id,
+            forced_header.clone(),
+            Some(make_default_justification(&forced_header))
+        ));
+
+        assert_eq!(
+            <CurrentAuthoritySet<TestRuntime>>::get(chain_id).pending_change,
+            Some(PendingChange {
+                next_authorities: vec![(ALICE.into(), 1), (BOB.into(), 1)],
+                enact_at: 6u32.encode(),
+                forced: true,
+            }),
+        );
+
+        // Blocks before the one the change is effective at are still finalized by the current set.
+        let mut next_header = test_header::<TestHeader>(5);
+        next_header.set_parent_hash(forced_header.hash());
+        assert_err!(
+            submit_finality_proof(chain_id, next_header, None),
+            <ErrorP<TestRuntime>>::MissingJustification
+        );
+        let header = import_headers(
+            chain_id,
+            forced_header,
+            5,
+            5,
+            TEST_GRANDPA_SET_ID,
+            test_keyring(),
+        );
+
+        // Unlike a standard change, the block the forced change is effective at is already
+        // finalized by the new set.
+        let mut next_header = test_header::<TestHeader>(6);
+        next_header.set_parent_hash(header.hash());
+        assert_err!(
+            submit_finality_proof(
+                chain_id,
+                next_header.clone(),
+                Some(make_default_justification(&next_header))
+            ),
+            <ErrorP<TestRuntime>>::InvalidJustification
+        );
+        assert_err!(
+            submit_finality_proof(chain_id, next_header, None),
+            <ErrorP<TestRuntime>>::MissingJustification
+        );
+
+        // Rejected imports leave the pending change intact.
+        assert_eq!(
+            <CurrentAuthoritySet<TestRuntime>>::get(chain_id),
+            AuthoritySet {
+                authorities: authority_list(),
+                set_id: TEST_GRANDPA_SET_ID,
+                pending_change: Some(PendingChange {
+                    next_authorities: vec![(ALICE.into(), 1), (BOB.into(), 1)],
+                    enact_at: 6u32.encode(),
+                    forced: true,
+                }),
+            },
+        );
+
+        let header = import_headers(
+            chain_id,
+            header,
+            6,
+            6,
+            TEST_GRANDPA_SET_ID + 1,
+            vec![(ALICE, 1), (BOB, 1)],
+        );
+        assert_eq!(
+            <CurrentAuthoritySet<TestRuntime>>::get(chain_id),
+            AuthoritySet {
+                authorities: vec![(ALICE.into(), 1), (BOB.into(), 1)],
+                set_id: TEST_GRANDPA_SET_ID + 1,
+                pending_change: None,
+            },
+        );
+        assert!(
+            finalized_state_root::<TestRuntime, TestFeedChain>(chain_id, header.hash())
+                .unwrap()
+                .is_some()
+        );
+    })
+}
+
+#[test]
+fn forced_change_is_not_taken_from_unjustified_header() {
+    run_test(|| {
+        let chain_id: ChainId = 1;
+        let validation_header = init_with_origin(chain_id, 0).unwrap();
+        let next_authorities = vec![(ALICE, 1), (BOB, 1)];
+
+        // A header can't declare a forced change to the authorities signing its own justification.
+        let mut forced_header = test_header::<TestHeader>(1);
+        forced_header.set_parent_hash(validation_header.hash());
+        forced_header.digest = forced_change_log(0, 2);
+        assert_err!(
+            submit_finality_proof(chain_id, forced_header.clone(), None),
+            <ErrorP<TestRuntime>>::MissingJustification
+        );
+        let justification = make_justification_for_header(JustificationGeneratorParams {
+            header: forced_header.clone(),
+            set_id: TEST_GRANDPA_SET_ID + 1,
+            authorities: next_authorities.clone(),
+            ..Default::default()
+        });
+        assert_err!(
+            submit_finality_proof(chain_id, forced_header.clone(), Some(justification)),
+            <ErrorP<TestRuntime>>::InvalidJustification
+        );
+
+        // The headers on top of it are not imported either, so the change is never enacted.
+        let mut header = forced_header;
+        for number in 2..=3 {
+            let mut next_header = test_header::<TestHeader>(number);
+            next_header.set_parent_hash(header.hash());
+            let justification = make_justification_for_header(JustificationGeneratorParams {
+                header: next_header.clone(),
+                set_id: TEST_GRANDPA_SET_ID + 1,
+                authorities: next_authorities.clone(),
+                ..Default::default()
+            });
+            assert_err!(
+                submit_finality_proof(chain_id, next_header.clone(), Some(justification)),
+                <ErrorP<TestRuntime>>::InvalidBlock
+            );
+            header = next_header;
+        }
+
+        assert_eq!(
+            <CurrentAuthoritySet<TestRuntime>>::get(chain_id),
+            AuthoritySet {
+                authorities: authority_list(),
+                set_id: TEST_GRANDPA_SET_ID,
+                pending_change: None,
+            },
+        );
+        assert_eq!(
+            <ChainTip<TestRuntime>>::get(chain_id),
+            (0u32.encode(), validation_header.hash().encode())
+        );
+    })
+}
+
+#[test]
+fn header_without_justification_is_rejected() {
     run_test(|| {
         let chain_id: ChainId = 1;
         let validation_header = init_with_origin(chain_id, 0).unwrap();
 
-        // Need to update the header digest to indicate that it signals a forced authority set
-        // change.
         let mut header = test_header::<TestHeader>(1);
         header.set_parent_hash(validation_header.hash());
-        header.digest = forced_change_log(0);
+        assert_err!(
+            submit_finality_proof(chain_id, header.clone(), None),
+            <ErrorP<TestRuntime>>::MissingJustification
+        );
 
-        // Create a valid justification for the header
+        // A pending standard change doesn't stall the current set.
+        header.digest = change_log(2);
+        assert_err!(
+            submit_finality_proof(chain_id, header, None),
+            <ErrorP<TestRuntime>>::MissingJustification
+        );
+    })
+}
+
+#[test]
+fn migrates_authority_set_to_v1() {
+    run_test(|| {
+        let chain_id: ChainId = 1;
+        let key = <CurrentAuthoritySet<TestRuntime>>::hashed_key_for(chain_id);
+        // The authority set as encoded before the pending change was tracked.
+        frame_support::storage::unhashed::put(&key, &(authority_list(), TEST_GRANDPA_SET_ID));
+        assert!(<CurrentAuthoritySet<TestRuntime>>::try_get(chain_id).is_err());
+
+        migrate_to_v1::<TestRuntime>();
+
+        assert_eq!(
+            <CurrentAuthoritySet<TestRuntime>>::get(chain_id),
+            AuthoritySet {
+                authorities: authority_list(),
+                set_id: TEST_GRANDPA_SET_ID,
+                pending_change: None,
+            },
+        );
+        assert_eq!(
+            StorageVersion::get::<crate::Pallet<TestRuntime>>(),
+            StorageVersion::new(1)
+        );
+
+        // The migration is only applied once.
+        frame_support::storage::unhashed::put(&key, &(authority_list(), TEST_GRANDPA_SET_ID));
+        migrate_to_v1::<TestRuntime>();
+        assert!(<CurrentAuthoritySet<TestRuntime>>::try_get(chain_id).is_err());
+    })
+}
+
+#[test]
+fn importing_header_enacts_forced_change_without_delay() {
+    run_test(|| {
+        let chain_id: ChainId = 1;
+        let validation_header = init_with_origin(chain_id, 0).unwrap();
+
+        // A forced change without delay is effective at the block signaling it, which is still
+        // finalized by the current set.
+        let mut header = test_header::<TestHeader>(1);
+        header.set_parent_hash(validation_header.hash());
+        header.digest = forced_change_log(0, 0);
         let justification = make_default_justification(&header);
+        assert_ok!(submit_finality_proof(
+            chain_id,
+            header.clone(),
+            Some(justification)
+        ));
 
-        // Should not be allowed to import this header
+        assert_eq!(
+            <CurrentAuthoritySet<TestRuntime>>::get(chain_id),
+            AuthoritySet {
+                authorities: vec![(ALICE.into(), 1), (BOB.into(), 1)],
+                set_id: TEST_GRANDPA_SET_ID + 1,
+                pending_change: None,
+            },
+        );
+
+        // The next block must be finalized by the new set.
+        let mut next_header = test_header::<TestHeader>(2);
+        next_header.set_parent_hash(header.hash());
         assert_err!(
-            submit_finality_proof(chain_id, header, Some(justification)),
-            <ErrorP<TestRuntime>>::UnsupportedScheduledChange
+            submit_finality_proof(
+                chain_id,
+                next_header.clone(),
+                Some(make_default_justification(&next_header))
+            ),
+            <ErrorP<TestRuntime>>::InvalidJustification
         );
+        import_headers(
+            chain_id,
+            header,
+            2,
+            2,
+            TEST_GRANDPA_SET_ID + 1,
+            vec![(ALICE, 1), (BOB, 1)],
+        );
+    })
+}
+
+/// Builds a relay chain state that contains `head` as the head of the parachain and returns its
+/// root together with the proof of the head.
+fn relay_chain_state_with_head(para_id: ParaId, head: &TestHeader) -> (H256, StorageProof) {
+    let mut db = MemoryDB::<BlakeTwo256>::default();
+    let mut root = Default::default();
+    {
+        let mut trie = TrieDBMutV1::<BlakeTwo256>::new(&mut db, &mut root);
+        trie.insert(
+            &parachain_head_storage_key(para_id),
+            &head.encode().encode(),
+        )
+        .unwrap();
+        trie.insert(
+            &parachain_head_storage_key(para_id + 1),
+            &[1u8; 64].encode(),
+        )
+        .unwrap();
+    }
+
+    let nodes = db
+        .drain()
+        .into_values()
+        .filter(|(_, rc)| *rc > 0)
+        .map(|(node, _)| node)
+        .collect();
+    (root, StorageProof::new(nodes))
+}
+
+fn submit_parachain_block(
+    chain_id: ChainId,
+    header: TestHeader,
+    maybe_proof: Option<ParachainHeadProof<H256>>,
+) -> DispatchResult {
+    let block = SignedBlock {
+        block: generic::Block::<TestHeader, OpaqueExtrinsic> {
+            header,
+            extrinsics: valid_extrinsics(),
+        },
+        justifications: maybe_proof
+            .map(|proof| (PARACHAIN_HEAD_PROOF_ENGINE_ID, proof.encode()).into()),
+    };
+
+    validate_parachain_block::<TestRuntime, TestFeedChain, TestFeedChain>(
+        chain_id,
+        block.encode().as_slice(),
+    )?;
+    Ok(())
+}
+
+fn init_parachain(chain_id: ChainId, relay_chain_id: ChainId, para_id: ParaId) -> DispatchResult {
+    let init_data = ParachainInitializationData {
+        relay_chain_id,
+        para_id,
+    };
+    initialize_parachain::<TestRuntime>(chain_id, init_data.encode().as_slice())
+}
+
+#[test]
+fn parachain_requires_initialized_relay_chain() {
+    run_test(|| {
+        assert_err!(
+            init_parachain(2, 1, 100),
+            ErrorP::<TestRuntime>::UnknownRelayChain
+        );
+
+        init_with_origin(1, 0).unwrap();
+        assert_ok!(init_parachain(2, 1, 100));
+    })
+}
+
+#[test]
+fn uninitialized_parachain_is_accepted_after_initialization() {
+    run_test(|| {
+        let relay_chain_id: ChainId = 1;
+        let chain_id: ChainId = 2;
+        let para_id = 100;
+        let validation_header = init_with_origin(relay_chain_id, 0).unwrap();
+
+        let para_header = test_header::<TestHeader>(5);
+        let (_, proof) = finalize_relay_block_with_head(
+            relay_chain_id,
+            &validation_header,
+            para_id,
+            &para_header,
+        );
+
+        // parachain imported before the validation against the relay chain has no relay chain
+        assert_err!(
+            submit_parachain_block(chain_id, para_header.clone(), Some(proof.clone())),
+            ErrorP::<TestRuntime>::UnknownParachain
+        );
+
+        assert_ok!(init_parachain(chain_id, relay_chain_id, para_id));
+        assert_ok!(submit_parachain_block(chain_id, para_header, Some(proof)));
+    })
+}
+
+#[test]
+fn validates_parachain_block_against_relay_chain_state() {
+    run_test(|| {
+        let relay_chain_id: ChainId = 1;
+        let chain_id: ChainId = 2;
+        let para_id = 100;
+        let validation_header = init_with_origin(relay_chain_id, 0).unwrap();
+        assert_ok!(init_parachain(chain_id, relay_chain_id, para_id));
+
+        let para_header = test_header::<TestHeader>(5);
+        let (state_root, storage_proof) = relay_chain_state_with_head(para_id, &para_header);
+
+        let mut relay_header = test_header::<TestHeader>(1);
+        relay_header.set_parent_hash(validation_header.hash());
+        relay_header.set_state_root(state_root);
+        let proof = ParachainHeadProof {
+            relay_block_hash: relay_header.hash(),
+            storage_proof,
+        };
+
+        // the relay chain block is not finalized yet
+        assert_err!(
+            submit_parachain_block(chain_id, para_header.clone(), Some(proof.clone())),
+            ErrorP::<TestRuntime>::UnknownRelayChainBlock
+        );
+
+        assert_ok!(submit_finality_proof(
+            relay_chain_id,
+            relay_header.clone(),
+            Some(make_default_justification(&relay_header))
+        ));
+        assert_eq!(
+            finalized_state_root::<TestRuntime, TestFeedChain>(relay_chain_id, relay_header.hash()),
+            Ok(Some(state_root))
+        );
+
+        assert_ok!(submit_parachain_block(
+            chain_id,
+            para_header.clone(),
+            Some(proof.clone())
+        ));
+
+        assert_err!(
+            submit_parachain_block(chain_id, para_header.clone(), None),
+            ErrorP::<TestRuntime>::MissingParachainHeadProof
+        );
+
+        // the proof does not commit to a different head
+        assert_err!(
+            submit_parachain_block(chain_id, test_header::<TestHeader>(6), Some(proof.clone())),
+            ErrorP::<TestRuntime>::InvalidParachainHeadProof
+        );
+
+        // the proof is not valid against the state of a different parachain
+        assert_ok!(init_parachain(3, relay_chain_id, para_id + 1));
+        assert_err!(
+            submit_parachain_block(3, para_header.clone(), Some(proof.clone())),
+            ErrorP::<TestRuntime>::InvalidParachainHeadProof
+        );
+
+        // nothing is known about the relay chain once it is purged
+        assert_ok!(purge::<TestRuntime>(relay_chain_id));
+        assert_err!(
+            submit_parachain_block(chain_id, para_header, Some(proof)),
+            ErrorP::<TestRuntime>::UnknownRelayChainBlock
+        );
+    })
+}
+
+/// Finalizes a relay chain block on top of `parent` whose state contains `para_header` as the
+/// head of the parachain and returns it with the proof of the head.
+fn finalize_relay_block_with_head(
+    relay_chain_id: ChainId,
+    parent: &TestHeader,
+    para_id: ParaId,
+    para_header: &TestHeader,
+) -> (TestHeader, ParachainHeadProof<H256>) {
+    let (state_root, storage_proof) = relay_chain_state_with_head(para_id, para_header);
+    let mut relay_header = test_header::<TestHeader>(parent.number + 1);
+    relay_header.set_parent_hash(parent.hash());
+    relay_header.set_state_root(state_root);
+    assert_ok!(submit_finality_proof(
+        relay_chain_id,
+        relay_header.clone(),
+        Some(make_default_justification(&relay_header))
+    ));
+    let proof = ParachainHeadProof {
+        relay_block_hash: relay_header.hash(),
+        storage_proof,
+    };
+    (relay_header, proof)
+}
+
+#[test]
+fn parachain_blocks_must_extend_last_imported_block() {
+    run_test(|| {
+        let relay_chain_id: ChainId = 1;
+        let chain_id: ChainId = 2;
+        let para_id = 100;
+        let validation_header = init_with_origin(relay_chain_id, 0).unwrap();
+        assert_ok!(init_parachain(chain_id, relay_chain_id, para_id));
+
+        let para_header = test_header::<TestHeader>(5);
+        let (relay_header, proof) = finalize_relay_block_with_head(
+            relay_chain_id,
+            &validation_header,
+            para_id,
+            &para_header,
+        );
+        assert_ok!(submit_parachain_block(
+            chain_id,
+            para_header.clone(),
+            Some(proof.clone())
+        ));
+        assert_eq!(
+            <ChainTip<TestRuntime>>::get(chain_id),
+            (5u32.encode(), para_header.hash().encode())
+        );
+
+        // the same block can't be imported twice
+        assert_err!(
+            submit_parachain_block(chain_id, para_header.clone(), Some(proof)),
+            ErrorP::<TestRuntime>::InvalidBlock
+        );
+
+        // the parachain blocks can't be skipped
+        let mut skipping_header = test_header::<TestHeader>(7);
+        skipping_header.set_parent_hash(para_header.hash());
+        let (relay_header, proof) = finalize_relay_block_with_head(
+            relay_chain_id,
+            &relay_header,
+            para_id,
+            &skipping_header,
+        );
+        assert_err!(
+            submit_parachain_block(chain_id, skipping_header, Some(proof)),
+            ErrorP::<TestRuntime>::InvalidBlock
+        );
+
+        // a block of another fork is rejected
+        let mut fork_header = test_header::<TestHeader>(6);
+        fork_header.set_parent_hash(H256::repeat_byte(1));
+        let (relay_header, proof) =
+            finalize_relay_block_with_head(relay_chain_id, &relay_header, para_id, &fork_header);
+        assert_err!(
+            submit_parachain_block(chain_id, fork_header, Some(proof)),
+            ErrorP::<TestRuntime>::InvalidBlock
+        );
+
+        let mut next_header = test_header::<TestHeader>(6);
+        next_header.set_parent_hash(para_header.hash());
+        let (_, proof) =
+            finalize_relay_block_with_head(relay_chain_id, &relay_header, para_id, &next_header);
+        assert_ok!(submit_parachain_block(
+            chain_id,
+            next_header.clone(),
+            Some(proof)
+        ));
+        assert_eq!(
+            <ChainTip<TestRuntime>>::get(chain_id),
+            (6u32.encode(), next_header.hash().encode())
+        );
+
+        // the parachain starts over once it is purged
+        assert_ok!(purge_parachain::<TestRuntime>(chain_id));
+        assert!(<ChainTip<TestRuntime>>::try_get(chain_id).is_err());
+    })
+}
+
+#[test]
+fn prunes_state_roots_of_old_headers() {
+    run_test(|| {
+        let chain_id: ChainId = 1;
+        let validation_header = init_with_origin(chain_id, 0).unwrap();
+        import_headers(
+            chain_id,
+            validation_header,
+            1,
+            6,
+            TEST_GRANDPA_SET_ID,
+            test_keyring(),
+        );
+
+        // only the state roots of the last `HeadersToKeep` headers are kept
+        assert_eq!(
+            finalized_state_root::<TestRuntime, TestFeedChain>(
+                chain_id,
+                test_header::<TestHeader>(1).hash()
+            ),
+            Ok(None)
+        );
+        for number in 2..=6 {
+            assert_eq!(
+                finalized_state_root::<TestRuntime, TestFeedChain>(
+                    chain_id,
+                    test_header::<TestHeader>(number).hash()
+                ),
+                Ok(Some(Default::default()))
+            );
+        }
     })
 }
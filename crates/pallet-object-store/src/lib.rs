@@ -14,6 +14,13 @@
 // limitations under the License.
 
 //! Pallet object store, used for simple object storage on the network.
+//!
+//! Objects that are too large to fit into a single block are put in chunks with `put_chunk`,
+//! followed by `put_manifest` with their
+//! [`ObjectManifest`](subspace_core_primitives::objects::ObjectManifest), whose hash is the
+//! content root of the object. The manifest is only accepted if all of its chunks were put by the
+//! same account and their sizes add up to the size of the object. Chunks that are not part of a
+//! manifest within `PendingChunkExpiry` blocks expire and are removed.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 #![forbid(unsafe_code)]
@@ -30,15 +37,26 @@ use subspace_core_primitives::{crypto, Sha256Hash};
 #[frame_support::pallet]
 mod pallet {
     use frame_support::pallet_prelude::*;
+    use frame_support::sp_runtime::traits::{One, Saturating};
+    use frame_support::weights::PostDispatchInfo;
     use frame_system::pallet_prelude::*;
     use log::debug;
     use sp_std::prelude::*;
+    use subspace_core_primitives::objects::ObjectManifest;
     use subspace_core_primitives::{crypto, Sha256Hash};
 
     #[pallet::config]
     pub trait Config: frame_system::Config {
         /// `pallet-object-store` events
         type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+
+        /// Maximum number of chunks a manifest can refer to.
+        #[pallet::constant]
+        type MaxManifestChunks: Get<u32>;
+
+        /// Number of blocks after which a chunk that is not part of a manifest expires.
+        #[pallet::constant]
+        type PendingChunkExpiry: Get<Self::BlockNumber>;
     }
 
     /// Pallet object-store, used for storing arbitrary user-provided data combined into object-store.
@@ -46,6 +64,36 @@ mod pallet {
     #[pallet::generate_store(pub(super) trait Store)]
     pub struct Pallet<T>(_);
 
+    /// Sizes and expiry blocks of the chunks put by an account that are not part of a complete
+    /// object yet
+    #[pallet::storage]
+    #[pallet::getter(fn pending_chunks)]
+    pub(super) type PendingChunks<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Identity,
+        Sha256Hash,
+        (u64, T::BlockNumber),
+        OptionQuery,
+    >;
+
+    /// Pending chunks by the block number they expire at
+    #[pallet::storage]
+    pub(super) type ChunkExpiries<T: Config> = StorageDoubleMap<
+        _,
+        Twox64Concat,
+        T::BlockNumber,
+        Blake2_128Concat,
+        (T::AccountId, Sha256Hash),
+        (),
+        OptionQuery,
+    >;
+
+    /// Block number from which the chunk expiries are not swept yet
+    #[pallet::storage]
+    pub(super) type NextExpiryToSweep<T: Config> = StorageValue<_, T::BlockNumber, OptionQuery>;
+
     /// `pallet-object-store` events
     #[pallet::event]
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
@@ -56,6 +104,37 @@ mod pallet {
             object_id: Sha256Hash,
             object_size: u32,
         },
+        /// New chunk of an object was added.
+        ChunkSubmitted {
+            who: T::AccountId,
+            chunk_hash: Sha256Hash,
+            chunk_size: u64,
+        },
+        /// Manifest of an object put in chunks was added.
+        ManifestSubmitted {
+            who: T::AccountId,
+            content_root: Sha256Hash,
+            object_size: u64,
+            chunks: u32,
+        },
+    }
+
+    /// `pallet-object-store` errors
+    #[pallet::error]
+    pub enum Error<T> {
+        /// Object is not a valid manifest or doesn't match the size of its chunks
+        InvalidManifest,
+        /// Manifest refers to a chunk that was not put by the account
+        UnknownChunk,
+        /// Manifest refers to more chunks than allowed
+        TooManyChunks,
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_idle(now: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+            Self::remove_expired_chunks(now, remaining_weight)
+        }
     }
 
     #[pallet::call]
@@ -86,6 +165,153 @@ mod pallet {
 
             Ok(())
         }
+
+        // TODO: add proper weights
+        /// Put the encoded manifest of an object whose chunks were put with `put_chunk`
+        #[pallet::weight((
+            Pallet::<T>::put_manifest_weight(T::MaxManifestChunks::get()),
+            Pays::No
+        ))]
+        pub fn put_manifest(origin: OriginFor<T>, manifest: Vec<u8>) -> DispatchResultWithPostInfo {
+            let who = ensure_signed(origin)?;
+
+            let object_manifest =
+                ObjectManifest::from_object(&manifest).ok_or(Error::<T>::InvalidManifest)?;
+            ensure!(
+                !object_manifest.chunks.is_empty(),
+                Error::<T>::InvalidManifest
+            );
+            let chunks = object_manifest.chunks.len() as u32;
+            ensure!(
+                chunks <= T::MaxManifestChunks::get(),
+                Error::<T>::TooManyChunks
+            );
+
+            // all the chunks must be put already, not expired and make up the size of the object
+            let now = frame_system::Pallet::<T>::block_number();
+            let mut object_size = 0u64;
+            for chunk_hash in &object_manifest.chunks {
+                let (chunk_size, _) = PendingChunks::<T>::get(&who, chunk_hash)
+                    .filter(|(_, expires_at)| *expires_at > now)
+                    .ok_or(Error::<T>::UnknownChunk)?;
+                object_size = object_size
+                    .checked_add(chunk_size)
+                    .ok_or(Error::<T>::InvalidManifest)?;
+            }
+            ensure!(
+                object_size == object_manifest.size,
+                Error::<T>::InvalidManifest
+            );
+
+            for chunk_hash in &object_manifest.chunks {
+                PendingChunks::<T>::remove(&who, chunk_hash);
+            }
+
+            let content_root = crypto::sha256_hash(&manifest);
+
+            debug!(
+                target: "runtime:object-store",
+                "New manifest {} of object with {} chunks, size {} bytes",
+                hex::encode(&content_root),
+                object_manifest.chunks.len(),
+                object_manifest.size
+            );
+
+            Self::deposit_event(Event::ManifestSubmitted {
+                who,
+                content_root,
+                object_size: object_manifest.size,
+                chunks,
+            });
+
+            Ok(PostDispatchInfo {
+                actual_weight: Some(Self::put_manifest_weight(chunks)),
+                pays_fee: Pays::No,
+            })
+        }
+
+        // TODO: add proper weights
+        /// Put a chunk of an object that is too large to be put at once
+        #[pallet::weight((10_000, Pays::No))]
+        pub fn put_chunk(origin: OriginFor<T>, chunk: Vec<u8>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let chunk_size = chunk.len() as u64;
+            let chunk_hash = crypto::sha256_hash(&chunk);
+
+            debug!(
+                target: "runtime:object-store",
+                "New chunk {}, size {} bytes",
+                hex::encode(&chunk_hash),
+                chunk_size
+            );
+
+            // putting the same chunk again extends its expiry, the earlier expiry is ignored
+            let expires_at = frame_system::Pallet::<T>::block_number()
+                .saturating_add(T::PendingChunkExpiry::get());
+            PendingChunks::<T>::insert(&who, chunk_hash, (chunk_size, expires_at));
+            ChunkExpiries::<T>::insert(expires_at, (who.clone(), chunk_hash), ());
+
+            Self::deposit_event(Event::ChunkSubmitted {
+                who,
+                chunk_hash,
+                chunk_size,
+            });
+
+            Ok(())
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        /// Weight of `put_manifest` with the given number of chunks.
+        pub(super) fn put_manifest_weight(chunks: u32) -> Weight {
+            let chunks = Weight::from(chunks);
+            T::DbWeight::get()
+                .reads_writes(chunks, chunks)
+                .saturating_add(10_000)
+        }
+
+        /// Removes the pending chunks expired by the given block within the given weight and
+        /// returns the weight consumed.
+        pub(super) fn remove_expired_chunks(
+            now: T::BlockNumber,
+            remaining_weight: Weight,
+        ) -> Weight {
+            let db_weight = T::DbWeight::get();
+            // reading and updating the block number to sweep next
+            let mut consumed_weight = db_weight.reads_writes(1, 1);
+            // looking up the next expired chunk and removing it with its pending entry
+            let chunk_weight = db_weight.reads_writes(2, 2);
+            if consumed_weight > remaining_weight {
+                return 0;
+            }
+
+            // nothing expires before the first sweep as chunks always expire in the future
+            let mut expiry = NextExpiryToSweep::<T>::get().unwrap_or(now);
+            while expiry <= now && consumed_weight.saturating_add(chunk_weight) <= remaining_weight
+            {
+                match ChunkExpiries::<T>::iter_key_prefix(expiry).next() {
+                    Some((who, chunk_hash)) => {
+                        ChunkExpiries::<T>::remove(expiry, (&who, chunk_hash));
+                        // the chunk might be part of a manifest or put again in the meantime
+                        if matches!(
+                            PendingChunks::<T>::get(&who, chunk_hash),
+                            Some((_, expires_at)) if expires_at == expiry
+                        ) {
+                            PendingChunks::<T>::remove(&who, chunk_hash);
+                        }
+                        consumed_weight += chunk_weight;
+                    }
+                    None => {
+                        expiry += One::one();
+                        consumed_weight += db_weight.reads(1);
+                    }
+                }
+            }
+            NextExpiryToSweep::<T>::put(expiry);
+
+            consumed_weight
+        }
     }
 }
 
@@ -99,11 +325,15 @@ pub struct CallObject {
 }
 
 impl<T: Config> Call<T> {
-    /// Extract object location if an extrinsic corresponds to `put` call
+    /// Extract object location if an extrinsic corresponds to `put`, `put_manifest` or
+    /// `put_chunk` call
     pub fn extract_call_object(&self) -> Option<CallObject> {
         match self {
-            Self::put { object } => {
-                // `1` corresponds to `Call::put {}` enum variant encoding.
+            Self::put { object }
+            | Self::put_manifest { manifest: object }
+            | Self::put_chunk { chunk: object } => {
+                // `1` corresponds to `Call::put {}`, `Call::put_manifest {}` or
+                // `Call::put_chunk {}` enum variant encoding.
                 Some(CallObject {
                     hash: crypto::sha256_hash(object),
                     offset: 1,
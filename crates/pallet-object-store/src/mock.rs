@@ -14,7 +14,7 @@ frame_support::construct_runtime!(
         UncheckedExtrinsic = UncheckedExtrinsic,
     {
         System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
-        ObjectStore: crate::{Pallet, Call, Event<T>}
+        ObjectStore: crate::{Pallet, Call, Storage, Event<T>}
     }
 );
 
@@ -51,6 +51,8 @@ parameter_types! {
 
 impl crate::Config for Test {
     type Event = Event;
+    type MaxManifestChunks = ConstU32<4>;
+    type PendingChunkExpiry = ConstU64<10>;
 }
 
 pub fn new_test_ext() -> sp_io::TestExternalities {
@@ -1,8 +1,13 @@
 use crate::mock::{new_test_ext, Event, ObjectStore, Origin, System, Test};
-use frame_support::assert_ok;
+use crate::{Call as ObjectStoreCall, ChunkExpiries, Error};
+use frame_support::traits::Hooks;
+use frame_support::weights::{GetDispatchInfo, Weight};
+use frame_support::{assert_noop, assert_ok};
 use subspace_core_primitives::crypto;
+use subspace_core_primitives::objects::ObjectManifest;
 
 const ACCOUNT_ID: u64 = 100;
+const OTHER_ACCOUNT_ID: u64 = 101;
 
 #[test]
 fn can_do_put() {
@@ -20,3 +25,202 @@ fn can_do_put() {
         }));
     });
 }
+
+#[test]
+fn can_do_put_manifest() {
+    new_test_ext().execute_with(|| {
+        let object: Vec<u8> = (0..=255).collect();
+        let manifest = ObjectManifest::from_chunks(object.chunks(100));
+
+        assert_noop!(
+            ObjectStore::put_manifest(Origin::signed(ACCOUNT_ID), object),
+            Error::<Test>::InvalidManifest
+        );
+
+        // chunks must be put first
+        assert_noop!(
+            ObjectStore::put_manifest(Origin::signed(ACCOUNT_ID), manifest.to_object()),
+            Error::<Test>::UnknownChunk
+        );
+
+        for chunk in object.chunks(100) {
+            assert_ok!(ObjectStore::put_chunk(
+                Origin::signed(ACCOUNT_ID),
+                chunk.to_vec()
+            ));
+        }
+        System::assert_last_event(Event::ObjectStore(crate::Event::<Test>::ChunkSubmitted {
+            who: ACCOUNT_ID,
+            chunk_hash: crypto::sha256_hash(&object[200..]),
+            chunk_size: 56,
+        }));
+
+        // chunks of other accounts can't be referred to
+        assert_noop!(
+            ObjectStore::put_manifest(Origin::signed(OTHER_ACCOUNT_ID), manifest.to_object()),
+            Error::<Test>::UnknownChunk
+        );
+
+        assert_ok!(ObjectStore::put_manifest(
+            Origin::signed(ACCOUNT_ID),
+            manifest.to_object()
+        ));
+        for chunk_hash in &manifest.chunks {
+            assert_eq!(ObjectStore::pending_chunks(ACCOUNT_ID, chunk_hash), None);
+        }
+
+        System::assert_last_event(Event::ObjectStore(
+            crate::Event::<Test>::ManifestSubmitted {
+                who: ACCOUNT_ID,
+                content_root: manifest.content_root(),
+                object_size: 256,
+                chunks: 3,
+            },
+        ));
+
+        let call_object = ObjectStoreCall::<Test>::put_manifest {
+            manifest: manifest.to_object(),
+        }
+        .extract_call_object()
+        .unwrap();
+        assert_eq!(call_object.hash, manifest.content_root());
+    });
+}
+
+#[test]
+fn manifest_must_match_size_of_chunks() {
+    new_test_ext().execute_with(|| {
+        let object: Vec<u8> = (0..=255).collect();
+        let mut manifest = ObjectManifest::from_chunks(object.chunks(100));
+        manifest.size += 1;
+
+        for chunk in object.chunks(100) {
+            assert_ok!(ObjectStore::put_chunk(
+                Origin::signed(ACCOUNT_ID),
+                chunk.to_vec()
+            ));
+        }
+
+        assert_noop!(
+            ObjectStore::put_manifest(Origin::signed(ACCOUNT_ID), manifest.to_object()),
+            Error::<Test>::InvalidManifest
+        );
+    });
+}
+
+#[test]
+fn manifest_chunks_are_limited() {
+    new_test_ext().execute_with(|| {
+        let object: Vec<u8> = (0..=255).collect();
+        let manifest = ObjectManifest::from_chunks(object.chunks(50));
+        assert_eq!(manifest.chunks.len(), 6);
+
+        for chunk in object.chunks(50) {
+            assert_ok!(ObjectStore::put_chunk(
+                Origin::signed(ACCOUNT_ID),
+                chunk.to_vec()
+            ));
+        }
+
+        assert_noop!(
+            ObjectStore::put_manifest(Origin::signed(ACCOUNT_ID), manifest.to_object()),
+            Error::<Test>::TooManyChunks
+        );
+    });
+}
+
+#[test]
+fn put_manifest_is_weighed_by_chunks() {
+    new_test_ext().execute_with(|| {
+        let object: Vec<u8> = (0..=255).collect();
+        let manifest = ObjectManifest::from_chunks(object.chunks(100));
+
+        for chunk in object.chunks(100) {
+            assert_ok!(ObjectStore::put_chunk(
+                Origin::signed(ACCOUNT_ID),
+                chunk.to_vec()
+            ));
+        }
+
+        let call = ObjectStoreCall::<Test>::put_manifest {
+            manifest: manifest.to_object(),
+        };
+        assert_eq!(
+            call.get_dispatch_info().weight,
+            ObjectStore::put_manifest_weight(4)
+        );
+        let post_info =
+            ObjectStore::put_manifest(Origin::signed(ACCOUNT_ID), manifest.to_object()).unwrap();
+        assert_eq!(
+            post_info.actual_weight,
+            Some(ObjectStore::put_manifest_weight(3))
+        );
+    });
+}
+
+#[test]
+fn pending_chunks_expire() {
+    new_test_ext().execute_with(|| {
+        let object: Vec<u8> = (0..=255).collect();
+        let manifest = ObjectManifest::from_chunks(object.chunks(100));
+
+        for chunk in object.chunks(100) {
+            assert_ok!(ObjectStore::put_chunk(
+                Origin::signed(ACCOUNT_ID),
+                chunk.to_vec()
+            ));
+        }
+        ObjectStore::on_idle(1, Weight::MAX);
+        assert!(ObjectStore::pending_chunks(ACCOUNT_ID, manifest.chunks[0]).is_some());
+
+        // putting a chunk again extends its expiry
+        System::set_block_number(5);
+        assert_ok!(ObjectStore::put_chunk(
+            Origin::signed(ACCOUNT_ID),
+            object[..100].to_vec()
+        ));
+        assert_eq!(
+            ObjectStore::pending_chunks(ACCOUNT_ID, manifest.chunks[0]),
+            Some((100, 15))
+        );
+
+        // expired chunks can't be referred to even before they are removed
+        System::set_block_number(11);
+        assert_noop!(
+            ObjectStore::put_manifest(Origin::signed(ACCOUNT_ID), manifest.to_object()),
+            Error::<Test>::UnknownChunk
+        );
+
+        ObjectStore::on_idle(11, Weight::MAX);
+        assert!(ObjectStore::pending_chunks(ACCOUNT_ID, manifest.chunks[0]).is_some());
+        assert_eq!(
+            ObjectStore::pending_chunks(ACCOUNT_ID, manifest.chunks[1]),
+            None
+        );
+        assert_eq!(
+            ObjectStore::pending_chunks(ACCOUNT_ID, manifest.chunks[2]),
+            None
+        );
+        assert_eq!(ChunkExpiries::<Test>::iter_prefix(11).count(), 0);
+
+        System::set_block_number(15);
+        ObjectStore::on_idle(15, Weight::MAX);
+        assert_eq!(
+            ObjectStore::pending_chunks(ACCOUNT_ID, manifest.chunks[0]),
+            None
+        );
+        assert_eq!(ChunkExpiries::<Test>::iter().count(), 0);
+    });
+}
+
+#[test]
+fn can_extract_chunk_call_object() {
+    let chunk = vec![1, 2, 3, 4, 5];
+    let call_object = ObjectStoreCall::<Test>::put_chunk {
+        chunk: chunk.clone(),
+    }
+    .extract_call_object()
+    .unwrap();
+    assert_eq!(call_object.hash, crypto::sha256_hash(&chunk));
+    assert_eq!(call_object.offset, 1);
+}
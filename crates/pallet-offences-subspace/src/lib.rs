@@ -26,6 +26,7 @@ mod tests;
 
 use codec::{Decode, Encode};
 pub use pallet::*;
+use sp_consensus_slots::Slot;
 use sp_consensus_subspace::offence::{
     Offence, OffenceDetails, OffenceError, OnOffenceHandler, ReportOffence,
 };
@@ -108,7 +109,12 @@ mod pallet {
     }
 }
 
-impl<T: Config, O: Offence<FarmerPublicKey>> ReportOffence<FarmerPublicKey, O> for Pallet<T> {
+impl<T, O> ReportOffence<FarmerPublicKey, O> for Pallet<T>
+where
+    T: Config,
+    O: Offence<FarmerPublicKey>,
+    O::TimeSlot: Into<Slot>,
+{
     fn report_offence(offence: O) -> Result<(), OffenceError> {
         let offenders = offence.offenders();
         let time_slot = offence.time_slot();
@@ -123,7 +129,7 @@ impl<T: Config, O: Offence<FarmerPublicKey>> ReportOffence<FarmerPublicKey, O> f
             None => return Err(OffenceError::DuplicateReport),
         };
 
-        T::OnOffenceHandler::on_offence(&concurrent_offenders);
+        T::OnOffenceHandler::on_offence(&concurrent_offenders, time_slot.clone().into());
 
         // Deposit the event.
         Self::deposit_event(Event::Offence {
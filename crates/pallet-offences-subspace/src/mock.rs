@@ -24,6 +24,7 @@ use codec::Encode;
 use frame_support::traits::{ConstU32, ConstU64};
 use frame_support::weights::constants::RocksDbWeight;
 use frame_support::weights::Weight;
+use sp_consensus_slots::Slot;
 use sp_consensus_subspace::offence::{self, Kind, OffenceDetails};
 use sp_consensus_subspace::FarmerPublicKey;
 use sp_core::H256;
@@ -40,7 +41,7 @@ thread_local! {
 }
 
 impl<Offender> offence::OnOffenceHandler<Offender> for OnOffenceHandler {
-    fn on_offence(_offenders: &[OffenceDetails<Offender>]) {
+    fn on_offence(_offenders: &[OffenceDetails<Offender>], _offence_slot: Slot) {
         ON_OFFENCE_PERBILL.with(|f| {
             *f.borrow_mut() = vec![Perbill::from_percent(25)];
         });
@@ -109,7 +110,7 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
 pub const KIND: [u8; 16] = *b"test_report_1234";
 
 /// Returns all offence details for the specific `kind` happened at the specific time slot.
-pub fn offence_reports(kind: Kind, time_slot: u128) -> Vec<OffenceDetails<FarmerPublicKey>> {
+pub fn offence_reports(kind: Kind, time_slot: u64) -> Vec<OffenceDetails<FarmerPublicKey>> {
     <crate::ConcurrentReportsIndex<Runtime>>::get(&kind, &time_slot.encode())
         .into_iter()
         .map(|report_id| {
@@ -122,23 +123,23 @@ pub fn offence_reports(kind: Kind, time_slot: u128) -> Vec<OffenceDetails<Farmer
 #[derive(Clone)]
 pub struct Offence<T> {
     pub offenders: Vec<T>,
-    pub time_slot: u128,
+    pub time_slot: u64,
 }
 
 impl<T: Clone> offence::Offence<T> for Offence<T> {
     const ID: offence::Kind = KIND;
-    type TimeSlot = u128;
+    type TimeSlot = u64;
 
     fn offenders(&self) -> Vec<T> {
         self.offenders.clone()
     }
 
-    fn time_slot(&self) -> u128 {
+    fn time_slot(&self) -> u64 {
         self.time_slot
     }
 }
 
 /// Create the report id for the given `offender` and `time_slot` combination.
-pub fn report_id(time_slot: u128, offender: FarmerPublicKey) -> H256 {
+pub fn report_id(time_slot: u64, offender: FarmerPublicKey) -> H256 {
     OffencesSubspace::report_id::<Offence<FarmerPublicKey>>(&time_slot, &offender)
 }
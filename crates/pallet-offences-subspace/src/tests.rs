@@ -346,7 +346,7 @@ fn should_properly_sort_offences() {
         OffencesSubspace::report_offence(offence4).unwrap();
 
         // then
-        let same_kind_reports = Vec::<(u128, sp_core::H256)>::decode(
+        let same_kind_reports = Vec::<(u64, sp_core::H256)>::decode(
             &mut &crate::ReportsByKindIndex::<crate::mock::Runtime>::get(KIND)[..],
         )
         .unwrap();
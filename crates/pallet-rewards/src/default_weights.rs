@@ -23,4 +23,9 @@ impl crate::WeightInfo for () {
         // TODO: Correct value
         1
     }
+
+    fn set_reward_schedule() -> Weight {
+        // TODO: Correct value
+        1
+    }
 }
@@ -20,14 +20,23 @@
 #![warn(rust_2018_idioms, missing_debug_implementations)]
 
 mod default_weights;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
 
+use frame_support::dispatch::DispatchResult;
+use frame_support::ensure;
 use frame_support::traits::{Currency, Get};
 use frame_support::weights::Weight;
 pub use pallet::*;
+pub use sp_rewards::{BlockRewards, RewardDecay, RewardSchedule};
+use sp_runtime::traits::Zero;
 use subspace_runtime_primitives::{FindBlockRewardAddress, FindVotingRewardAddresses};
 
 pub trait WeightInfo {
     fn on_initialize() -> Weight;
+    fn set_reward_schedule() -> Weight;
 }
 
 #[frame_support::pallet]
@@ -36,9 +45,10 @@ mod pallet {
     use frame_support::pallet_prelude::*;
     use frame_support::traits::Currency;
     use frame_system::pallet_prelude::*;
+    use sp_rewards::RewardSchedule;
     use subspace_runtime_primitives::{FindBlockRewardAddress, FindVotingRewardAddresses};
 
-    type BalanceOf<T> =
+    pub(crate) type BalanceOf<T> =
         <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
 
     /// Pallet rewards for issuing rewards to block producers.
@@ -53,14 +63,18 @@ mod pallet {
 
         type Currency: Currency<Self::AccountId>;
 
-        /// Fixed reward for block producer.
+        /// Fixed reward for block producer, used until reward schedule is set.
         #[pallet::constant]
         type BlockReward: Get<BalanceOf<Self>>;
 
-        /// Fixed reward for voter.
+        /// Fixed reward for voter, used until reward schedule is set.
         #[pallet::constant]
         type VoteReward: Get<BalanceOf<Self>>;
 
+        /// Number of blocks after which a new reward schedule is enacted.
+        #[pallet::constant]
+        type RewardScheduleEnactmentDelay: Get<Self::BlockNumber>;
+
         type FindBlockRewardAddress: FindBlockRewardAddress<Self::AccountId>;
 
         type FindVotingRewardAddresses: FindVotingRewardAddresses<Self::AccountId>;
@@ -68,6 +82,18 @@ mod pallet {
         type WeightInfo: WeightInfo;
     }
 
+    /// Reward schedule in effect, fixed rewards from config are used if not set.
+    #[pallet::storage]
+    #[pallet::getter(fn reward_schedule)]
+    pub(super) type Schedule<T: Config> =
+        StorageValue<_, RewardSchedule<T::BlockNumber, BalanceOf<T>>>;
+
+    /// Reward schedule that will be enacted at specified block.
+    #[pallet::storage]
+    #[pallet::getter(fn pending_reward_schedule)]
+    pub(super) type PendingSchedule<T: Config> =
+        StorageValue<_, (T::BlockNumber, RewardSchedule<T::BlockNumber, BalanceOf<T>>)>;
+
     /// `pallet-rewards` events
     #[pallet::event]
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
@@ -82,6 +108,21 @@ mod pallet {
             voter: T::AccountId,
             reward: BalanceOf<T>,
         },
+        /// New reward schedule was set and will be enacted at specified block.
+        RewardScheduleSet {
+            enact_at: T::BlockNumber,
+            schedule: RewardSchedule<T::BlockNumber, BalanceOf<T>>,
+        },
+        /// Pending reward schedule was enacted.
+        RewardScheduleEnacted {
+            schedule: RewardSchedule<T::BlockNumber, BalanceOf<T>>,
+        },
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        /// Halving interval or linear decay duration is zero.
+        InvalidRewardSchedule,
     }
 
     #[pallet::hooks]
@@ -95,13 +136,98 @@ mod pallet {
             Self::do_finalize(now);
         }
     }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Set new reward schedule, which will be enacted after
+        /// [`Config::RewardScheduleEnactmentDelay`] blocks, replacing previously pending schedule
+        /// (if any).
+        #[pallet::weight(T::WeightInfo::set_reward_schedule())]
+        pub fn set_reward_schedule(
+            origin: OriginFor<T>,
+            schedule: RewardSchedule<T::BlockNumber, BalanceOf<T>>,
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+
+            Self::do_set_reward_schedule(schedule)
+        }
+    }
 }
 
 impl<T: Config> Pallet<T> {
-    fn do_initialize(_block_number: T::BlockNumber) {
+    /// Rewards expected at given (possibly future) block, taking pending reward schedule into
+    /// account and capping rewards by supply cap against current total issuance.
+    pub fn expected_rewards(block_number: T::BlockNumber) -> BlockRewards<BalanceOf<T>> {
+        let schedule = match PendingSchedule::<T>::get() {
+            Some((enact_at, schedule)) if enact_at <= block_number => Some(schedule),
+            _ => Schedule::<T>::get(),
+        };
+
+        match schedule {
+            Some(schedule) => {
+                let rewards = schedule.rewards_at(block_number);
+                let total_issuance = T::Currency::total_issuance();
+                BlockRewards {
+                    block_reward: schedule.capped(rewards.block_reward, total_issuance),
+                    vote_reward: schedule.capped(rewards.vote_reward, total_issuance),
+                }
+            }
+            None => BlockRewards {
+                block_reward: T::BlockReward::get(),
+                vote_reward: T::VoteReward::get(),
+            },
+        }
+    }
+
+    fn do_set_reward_schedule(
+        schedule: RewardSchedule<T::BlockNumber, BalanceOf<T>>,
+    ) -> DispatchResult {
+        ensure!(schedule.is_valid(), Error::<T>::InvalidRewardSchedule);
+
+        let enact_at =
+            frame_system::Pallet::<T>::block_number() + T::RewardScheduleEnactmentDelay::get();
+        PendingSchedule::<T>::put((enact_at, schedule));
+
+        Self::deposit_event(Event::RewardScheduleSet { enact_at, schedule });
+
+        Ok(())
+    }
+
+    /// Reward for current block, capped so that total issuance doesn't exceed supply cap.
+    fn reward(
+        block_number: T::BlockNumber,
+        reward_of: impl Fn(BlockRewards<BalanceOf<T>>) -> BalanceOf<T>,
+        fixed_reward: BalanceOf<T>,
+    ) -> BalanceOf<T> {
+        match Schedule::<T>::get() {
+            Some(schedule) => schedule.capped(
+                reward_of(schedule.rewards_at(block_number)),
+                T::Currency::total_issuance(),
+            ),
+            None => fixed_reward,
+        }
+    }
+
+    fn do_initialize(block_number: T::BlockNumber) {
+        if let Some((enact_at, schedule)) = PendingSchedule::<T>::get() {
+            if enact_at <= block_number {
+                PendingSchedule::<T>::kill();
+                Schedule::<T>::put(schedule);
+
+                Self::deposit_event(Event::RewardScheduleEnacted { schedule });
+            }
+        }
+
         // Block author may equivocate, in which case they'll not be present here
         if let Some(block_author) = T::FindBlockRewardAddress::find_block_reward_address() {
-            let reward = T::BlockReward::get();
+            let reward = Self::reward(
+                block_number,
+                |rewards| rewards.block_reward,
+                T::BlockReward::get(),
+            );
+            if reward.is_zero() {
+                return;
+            }
             T::Currency::deposit_creating(&block_author, reward);
 
             Self::deposit_event(Event::BlockReward {
@@ -111,10 +237,18 @@ impl<T: Config> Pallet<T> {
         }
     }
 
-    fn do_finalize(_block_number: T::BlockNumber) {
-        let reward = T::VoteReward::get();
-
+    fn do_finalize(block_number: T::BlockNumber) {
         for voter in T::FindVotingRewardAddresses::find_voting_reward_addresses() {
+            // Re-evaluated for every voter since each reward counts towards supply cap
+            let reward = Self::reward(
+                block_number,
+                |rewards| rewards.vote_reward,
+                T::VoteReward::get(),
+            );
+            if reward.is_zero() {
+                break;
+            }
+
             T::Currency::deposit_creating(&voter, reward);
 
             Self::deposit_event(Event::VoteReward { voter, reward });
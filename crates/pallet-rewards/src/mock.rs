@@ -0,0 +1,113 @@
+use crate::{self as pallet_rewards, Config};
+use frame_support::traits::{ConstU128, ConstU16, ConstU32, ConstU64};
+use sp_core::H256;
+use sp_runtime::testing::Header;
+use sp_runtime::traits::{BlakeTwo256, IdentityLookup};
+use std::cell::RefCell;
+use subspace_runtime_primitives::{FindBlockRewardAddress, FindVotingRewardAddresses};
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+type Balance = u128;
+
+pub const BLOCK_AUTHOR: u64 = 1;
+pub const BLOCK_REWARD: Balance = 100;
+pub const VOTE_REWARD: Balance = 10;
+pub const ENACTMENT_DELAY: u64 = 10;
+
+frame_support::construct_runtime!(
+    pub enum Test where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+    {
+        System: frame_system,
+        Balances: pallet_balances,
+        Rewards: pallet_rewards,
+    }
+);
+
+impl frame_system::Config for Test {
+    type BaseCallFilter = frame_support::traits::Everything;
+    type BlockWeights = ();
+    type BlockLength = ();
+    type DbWeight = ();
+    type Origin = Origin;
+    type Call = Call;
+    type Index = u64;
+    type BlockNumber = u64;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = u64;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = Event;
+    type BlockHashCount = ConstU64<250>;
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = pallet_balances::AccountData<Balance>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = ConstU16<42>;
+    type OnSetCode = ();
+    type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_balances::Config for Test {
+    type MaxLocks = ();
+    type MaxReserves = ();
+    type ReserveIdentifier = [u8; 8];
+    type Balance = Balance;
+    type DustRemoval = ();
+    type Event = Event;
+    type ExistentialDeposit = ConstU128<1>;
+    type AccountStore = System;
+    type WeightInfo = ();
+}
+
+thread_local! {
+    pub static VOTERS: RefCell<Vec<u64>> = RefCell::new(Default::default());
+}
+
+/// Sets voters of the following blocks
+pub fn set_voters(voters: Vec<u64>) {
+    VOTERS.with(|f| *f.borrow_mut() = voters);
+}
+
+pub struct MockFindBlockRewardAddress;
+
+impl FindBlockRewardAddress<u64> for MockFindBlockRewardAddress {
+    fn find_block_reward_address() -> Option<u64> {
+        Some(BLOCK_AUTHOR)
+    }
+}
+
+pub struct MockFindVotingRewardAddresses;
+
+impl FindVotingRewardAddresses<u64> for MockFindVotingRewardAddresses {
+    fn find_voting_reward_addresses() -> Vec<u64> {
+        VOTERS.with(|f| f.borrow().clone())
+    }
+}
+
+impl Config for Test {
+    type Event = Event;
+    type Currency = Balances;
+    type BlockReward = ConstU128<BLOCK_REWARD>;
+    type VoteReward = ConstU128<VOTE_REWARD>;
+    type RewardScheduleEnactmentDelay = ConstU64<ENACTMENT_DELAY>;
+    type FindBlockRewardAddress = MockFindBlockRewardAddress;
+    type FindVotingRewardAddresses = MockFindVotingRewardAddresses;
+    type WeightInfo = ();
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let t = frame_system::GenesisConfig::default()
+        .build_storage::<Test>()
+        .unwrap();
+
+    let mut ext = sp_io::TestExternalities::new(t);
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
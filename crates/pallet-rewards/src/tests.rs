@@ -0,0 +1,155 @@
+use crate::mock::{
+    new_test_ext, set_voters, Balances, Event, Origin, Rewards, System, Test, BLOCK_AUTHOR,
+    BLOCK_REWARD, ENACTMENT_DELAY, VOTE_REWARD,
+};
+use crate::{BlockRewards, Error, RewardDecay, RewardSchedule};
+use frame_support::traits::{Currency, Hooks};
+use frame_support::{assert_noop, assert_ok};
+use sp_runtime::DispatchError;
+
+type BlockNumber = u64;
+type Balance = u128;
+
+fn run_block(block_number: BlockNumber) {
+    System::set_block_number(block_number);
+    Rewards::on_initialize(block_number);
+    Rewards::on_finalize(block_number);
+}
+
+fn halving_schedule(
+    start: BlockNumber,
+    supply_cap: Option<Balance>,
+) -> RewardSchedule<BlockNumber, Balance> {
+    RewardSchedule {
+        start,
+        initial_block_reward: 1000,
+        initial_vote_reward: 100,
+        decay: RewardDecay::Halving { interval: 100 },
+        supply_cap,
+    }
+}
+
+#[test]
+fn fixed_rewards_without_schedule() {
+    new_test_ext().execute_with(|| {
+        set_voters(vec![2, 3]);
+        run_block(1);
+
+        assert_eq!(Balances::free_balance(BLOCK_AUTHOR), BLOCK_REWARD);
+        assert_eq!(Balances::free_balance(2), VOTE_REWARD);
+        assert_eq!(Balances::free_balance(3), VOTE_REWARD);
+    });
+}
+
+#[test]
+fn set_reward_schedule_requires_root() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            Rewards::set_reward_schedule(Origin::signed(1), halving_schedule(0, None)),
+            DispatchError::BadOrigin
+        );
+    });
+}
+
+#[test]
+fn invalid_reward_schedule_is_rejected() {
+    new_test_ext().execute_with(|| {
+        let mut schedule = halving_schedule(0, None);
+        schedule.decay = RewardDecay::Halving { interval: 0 };
+
+        assert_noop!(
+            Rewards::set_reward_schedule(Origin::root(), schedule),
+            Error::<Test>::InvalidRewardSchedule
+        );
+    });
+}
+
+#[test]
+fn reward_schedule_is_enacted_with_delay() {
+    new_test_ext().execute_with(|| {
+        let schedule = halving_schedule(0, None);
+        assert_ok!(Rewards::set_reward_schedule(Origin::root(), schedule));
+        let enact_at = 1 + ENACTMENT_DELAY;
+        System::assert_last_event(Event::Rewards(crate::Event::RewardScheduleSet {
+            enact_at,
+            schedule,
+        }));
+
+        // Expected rewards already account for pending schedule
+        assert_eq!(
+            Rewards::expected_rewards(enact_at - 1),
+            BlockRewards {
+                block_reward: BLOCK_REWARD,
+                vote_reward: VOTE_REWARD,
+            }
+        );
+        assert_eq!(
+            Rewards::expected_rewards(enact_at),
+            BlockRewards {
+                block_reward: 1000,
+                vote_reward: 100,
+            }
+        );
+        assert_eq!(
+            Rewards::expected_rewards(100),
+            BlockRewards {
+                block_reward: 500,
+                vote_reward: 50,
+            }
+        );
+
+        for block_number in 1..enact_at {
+            run_block(block_number);
+        }
+        assert_eq!(Rewards::reward_schedule(), None);
+        assert_eq!(
+            Balances::free_balance(BLOCK_AUTHOR),
+            BLOCK_REWARD * (enact_at - 1) as Balance
+        );
+
+        run_block(enact_at);
+        assert_eq!(Rewards::reward_schedule(), Some(schedule));
+        assert_eq!(Rewards::pending_reward_schedule(), None);
+        assert_eq!(
+            Balances::free_balance(BLOCK_AUTHOR),
+            BLOCK_REWARD * (enact_at - 1) as Balance + 1000
+        );
+
+        run_block(100);
+        assert_eq!(
+            Balances::free_balance(BLOCK_AUTHOR),
+            BLOCK_REWARD * (enact_at - 1) as Balance + 1000 + 500
+        );
+    });
+}
+
+#[test]
+fn rewards_are_capped_by_supply_cap() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(Rewards::set_reward_schedule(
+            Origin::root(),
+            halving_schedule(0, Some(2250))
+        ));
+        run_block(1 + ENACTMENT_DELAY);
+        assert_eq!(Balances::total_issuance(), 1000);
+
+        set_voters(vec![2, 3]);
+        run_block(2 + ENACTMENT_DELAY);
+        assert_eq!(Balances::total_issuance(), 2200);
+        assert_eq!(Balances::free_balance(2), 100);
+
+        // Only 50 left until the cap
+        assert_eq!(
+            Rewards::expected_rewards(3 + ENACTMENT_DELAY).block_reward,
+            50
+        );
+        run_block(3 + ENACTMENT_DELAY);
+        assert_eq!(Balances::free_balance(BLOCK_AUTHOR), 2050);
+        assert_eq!(Balances::free_balance(2), 100);
+        assert_eq!(Balances::free_balance(3), 100);
+        assert_eq!(Balances::total_issuance(), 2250);
+
+        run_block(4 + ENACTMENT_DELAY);
+        assert_eq!(Balances::total_issuance(), 2250);
+    });
+}
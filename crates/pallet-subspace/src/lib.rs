@@ -20,6 +20,7 @@
 
 mod default_weights;
 pub mod equivocation;
+pub mod migrations;
 
 #[cfg(all(feature = "std", test))]
 mod mock;
@@ -46,7 +47,8 @@ use sp_consensus_subspace::verification::{
     PieceCheckParams, VerificationError, VerifySolutionParams,
 };
 use sp_consensus_subspace::{
-    derive_randomness, verification, EquivocationProof, FarmerPublicKey, SignedVote, Vote,
+    derive_randomness, verification, EquivocationProof, EraSummary, FarmerPublicKey, SignedVote,
+    SolutionRangeAdjustment, Vote,
 };
 use sp_runtime::generic::DigestItem;
 use sp_runtime::traits::{
@@ -104,7 +106,7 @@ pub struct NormalEraChange;
 impl EraChangeTrigger for NormalEraChange {
     fn trigger<T: Config>(block_number: T::BlockNumber) {
         if <Pallet<T>>::should_era_change(block_number) {
-            <Pallet<T>>::enact_era_change();
+            <Pallet<T>>::enact_era_change(block_number);
         }
     }
 }
@@ -151,7 +153,7 @@ mod pallet {
     use frame_system::pallet_prelude::*;
     use sp_consensus_slots::Slot;
     use sp_consensus_subspace::inherents::{InherentError, InherentType, INHERENT_IDENTIFIER};
-    use sp_consensus_subspace::{EquivocationProof, FarmerPublicKey, SignedVote};
+    use sp_consensus_subspace::{EquivocationProof, EraSummary, FarmerPublicKey, SignedVote};
     use sp_runtime::traits::One;
     use sp_std::collections::btree_map::BTreeMap;
     use sp_std::prelude::*;
@@ -177,6 +179,64 @@ mod pallet {
         }
     }
 
+    pub(super) struct InitialEraParameters<T: Config> {
+        _config: T,
+    }
+
+    impl<T: Config> Get<EraParameters<T::BlockNumber>> for InitialEraParameters<T> {
+        fn get() -> EraParameters<T::BlockNumber> {
+            EraParameters {
+                era_duration: T::EraDuration::get(),
+                expected_votes_per_block: T::ExpectedVotesPerBlock::get(),
+                confirmation_depth_k: T::ConfirmationDepthK::get(),
+            }
+        }
+    }
+
+    pub(super) struct InitialEonParameters<T: Config> {
+        _config: T,
+    }
+
+    impl<T: Config> Get<EonParameters> for InitialEonParameters<T> {
+        fn get() -> EonParameters {
+            EonParameters {
+                eon_duration: T::EonDuration::get(),
+                eon_next_salt_reveal: T::EonNextSaltReveal::get(),
+            }
+        }
+    }
+
+    /// Consensus parameters that are applied per era, changes take effect at the next era
+    /// boundary.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Encode, Decode, TypeInfo)]
+    pub struct EraParameters<BlockNumber> {
+        /// The amount of time, in blocks, that era lasts.
+        pub era_duration: BlockNumber,
+        /// Number of votes expected per block, impacts solution range for votes.
+        pub expected_votes_per_block: u32,
+        /// Depth `K` after which a block enters the recorded history.
+        pub confirmation_depth_k: BlockNumber,
+    }
+
+    /// Consensus parameters that are applied per eon, changes take effect at the next eon
+    /// boundary.
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Encode, Decode, TypeInfo)]
+    pub struct EonParameters {
+        /// The amount of time, in slots, that eon lasts.
+        pub eon_duration: u64,
+        /// The amount of time within eon, in slots, after which next eon salt is revealed.
+        pub eon_next_salt_reveal: u64,
+    }
+
+    /// Entry of the farmer in block list
+    #[derive(Debug, Copy, Clone, PartialEq, Eq, Encode, Decode, TypeInfo)]
+    pub struct BlockListEntry {
+        /// Slot at which the offence was committed
+        pub offence_slot: Slot,
+        /// Index of the eon starting from which farmer is no longer block listed
+        pub expires_at_eon: u64,
+    }
+
     /// Override for next solution range adjustment
     #[derive(Debug, Encode, Decode, TypeInfo)]
     pub struct SolutionRangeOverride {
@@ -186,9 +246,13 @@ mod pallet {
         pub voting_solution_range: u64,
     }
 
+    /// The current storage version.
+    const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
+
     /// The Subspace Pallet
     #[pallet::pallet]
     #[pallet::generate_store(pub(super) trait Store)]
+    #[pallet::storage_version(STORAGE_VERSION)]
     #[pallet::without_storage_info]
     pub struct Pallet<T>(_);
 
@@ -202,23 +266,26 @@ mod pallet {
         #[pallet::constant]
         type GlobalRandomnessUpdateInterval: Get<Self::BlockNumber>;
 
-        /// The amount of time, in blocks, that each era should last.
-        /// NOTE: Currently it is not possible to change the era duration after
-        /// the chain has started. Attempting to do so will brick block production.
+        /// The amount of time, in blocks, that each era should last at genesis.
+        ///
+        /// Can be changed later with [`Pallet::schedule_consensus_parameters`].
         #[pallet::constant]
         type EraDuration: Get<Self::BlockNumber>;
 
-        /// The amount of time, in slots, that each eon should last.
-        /// NOTE: Currently it is not possible to change the eon duration after
-        /// the chain has started. Attempting to do so will brick block production.
+        /// The amount of time, in slots, that each eon should last at genesis.
+        ///
+        /// Can be changed later with [`Pallet::schedule_consensus_parameters`].
         #[pallet::constant]
         type EonDuration: Get<u64>;
 
-        /// The amount of time within eon, in slots, after which next eon salt should be revealed.
+        /// The amount of time within eon, in slots, after which next eon salt should be revealed
+        /// at genesis.
         ///
         /// The purpose of this is to allow to start tag recommitment a bit upfront, but not too
         /// soon. For instance, if eon duration is 7 days, this parameter may be set to 6 days worth
         /// of timeslots.
+        ///
+        /// Can be changed later with [`Pallet::schedule_consensus_parameters`].
         #[pallet::constant]
         type EonNextSaltReveal: Get<u64>;
 
@@ -242,7 +309,9 @@ mod pallet {
         type ExpectedBlockTime: Get<Self::Moment>;
 
         /// Depth `K` after which a block enters the recorded history (a global constant, as opposed
-        /// to the client-dependent transaction confirmation depth `k`).
+        /// to the client-dependent transaction confirmation depth `k`) at genesis.
+        ///
+        /// Can be changed later with [`Pallet::schedule_consensus_parameters`].
         #[pallet::constant]
         type ConfirmationDepthK: Get<Self::BlockNumber>;
 
@@ -259,14 +328,24 @@ mod pallet {
         #[pallet::constant]
         type RecordedHistorySegmentSize: Get<u32>;
 
-        /// Number of votes expected per block.
+        /// Number of votes expected per block at genesis.
         ///
-        /// This impacts solution range for votes in consensus.
+        /// This impacts solution range for votes in consensus. Can be changed later with
+        /// [`Pallet::schedule_consensus_parameters`].
         #[pallet::constant]
         type ExpectedVotesPerBlock: Get<u32>;
 
         type ShouldAdjustSolutionRange: Get<bool>;
 
+        /// Number of recent eras for which summaries are kept in [`EraHistory`].
+        #[pallet::constant]
+        type EraHistoryLength: Get<u32>;
+
+        /// The amount of time, in eons, that farmer stays in block list after committing an
+        /// offence.
+        #[pallet::constant]
+        type BlockListDuration: Get<u64>;
+
         /// Subspace requires periodic global randomness update.
         type GlobalRandomnessIntervalTrigger: GlobalRandomnessIntervalTrigger;
 
@@ -335,6 +414,8 @@ mod pallet {
             height: T::BlockNumber,
             parent_hash: T::Hash,
         },
+        /// Farmer was lifted from block list before expiry.
+        BlockListEntryLifted { public_key: FarmerPublicKey },
     }
 
     #[pallet::error]
@@ -347,6 +428,10 @@ mod pallet {
         SolutionRangeAdjustmentAlreadyEnabled,
         /// Rewards already active.
         RewardsAlreadyEnabled,
+        /// Farmer is not in block list.
+        NotInBlockList,
+        /// Durations must be non-zero and next salt must be revealed before the end of the eon.
+        InvalidConsensusParameters,
     }
 
     /// Current eon index.
@@ -389,6 +474,26 @@ mod pallet {
     #[pallet::storage]
     pub type NextSolutionRangeOverride<T> = StorageValue<_, SolutionRangeOverride>;
 
+    /// Era parameters in effect.
+    #[pallet::storage]
+    #[pallet::getter(fn era_parameters)]
+    pub type CurrentEraParameters<T: Config> =
+        StorageValue<_, EraParameters<T::BlockNumber>, ValueQuery, InitialEraParameters<T>>;
+
+    /// Era parameters that will take effect at the next era boundary.
+    #[pallet::storage]
+    pub type NextEraParameters<T: Config> = StorageValue<_, EraParameters<T::BlockNumber>>;
+
+    /// Eon parameters in effect.
+    #[pallet::storage]
+    #[pallet::getter(fn eon_parameters)]
+    pub type CurrentEonParameters<T: Config> =
+        StorageValue<_, EonParameters, ValueQuery, InitialEonParameters<T>>;
+
+    /// Eon parameters that will take effect at the next eon boundary.
+    #[pallet::storage]
+    pub type NextEonParameters<T> = StorageValue<_, EonParameters>;
+
     /// Salts used for challenges.
     #[pallet::storage]
     #[pallet::getter(fn salts)]
@@ -398,9 +503,37 @@ mod pallet {
     #[pallet::storage]
     pub type EraStartSlot<T> = StorageValue<_, Slot>;
 
+    /// Block number at which current era started, not set until the first era change.
+    #[pallet::storage]
+    pub(super) type EraStartBlock<T: Config> = StorageValue<_, T::BlockNumber>;
+
+    /// Slot at which current eon started, not set until the first eon change.
+    #[pallet::storage]
+    pub(super) type EonStartSlot<T> = StorageValue<_, Slot>;
+
+    /// Number of blocks produced during current era.
+    #[pallet::storage]
+    pub(super) type EraBlockCount<T> = StorageValue<_, u64, ValueQuery>;
+
+    /// Number of votes included in blocks of current era.
+    #[pallet::storage]
+    pub(super) type EraVoteCount<T> = StorageValue<_, u64, ValueQuery>;
+
+    /// Estimate of total space pledged to the network (in bytes), averaged over eras in
+    /// [`EraHistory`] and updated on every era change.
+    #[pallet::storage]
+    pub(super) type SpacePledgedEstimate<T> = StorageValue<_, u128>;
+
+    /// Summaries of recent eras, oldest first.
+    #[pallet::storage]
+    #[pallet::getter(fn era_history)]
+    pub type EraHistory<T> = StorageValue<_, Vec<EraSummary>, ValueQuery>;
+
     /// A set of blocked farmers keyed by their public key.
+    ///
+    /// Expired entries are ignored and removed when there is weight left at the end of a block.
     #[pallet::storage]
-    pub(super) type BlockList<T> = StorageMap<_, Twox64Concat, FarmerPublicKey, ()>;
+    pub(super) type BlockList<T> = StorageMap<_, Twox64Concat, FarmerPublicKey, BlockListEntry>;
 
     /// Mapping from segment index to corresponding merkle tree root of segment records.
     #[pallet::storage]
@@ -453,6 +586,14 @@ mod pallet {
         fn on_finalize(block_number: T::BlockNumber) {
             Self::do_finalize(block_number)
         }
+
+        fn on_idle(_block_number: T::BlockNumber, remaining_weight: Weight) -> Weight {
+            Self::remove_expired_block_list_entries(remaining_weight)
+        }
+
+        fn on_runtime_upgrade() -> Weight {
+            crate::migrations::migrate_to_v1::<T>()
+        }
     }
 
     #[pallet::call]
@@ -538,6 +679,31 @@ mod pallet {
 
             Ok(())
         }
+
+        /// Schedule change of consensus parameters. Era parameters take effect at the next era
+        /// boundary and eon parameters at the next eon boundary, replacing previously scheduled
+        /// changes.
+        #[pallet::weight(T::DbWeight::get().writes(2))]
+        pub fn schedule_consensus_parameters(
+            origin: OriginFor<T>,
+            era_parameters: Option<EraParameters<T::BlockNumber>>,
+            eon_parameters: Option<EonParameters>,
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+
+            Self::do_schedule_consensus_parameters(era_parameters, eon_parameters)
+        }
+
+        /// Lift farmer from block list before the entry expires.
+        #[pallet::weight(T::DbWeight::get().reads_writes(1, 1))]
+        pub fn lift_block_list_entry(
+            origin: OriginFor<T>,
+            farmer_public_key: FarmerPublicKey,
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+
+            Self::do_lift_block_list_entry(farmer_public_key)
+        }
     }
 
     #[pallet::inherent]
@@ -648,14 +814,22 @@ impl<T: Config> Pallet<T> {
     /// Determine whether an era change should take place at this block.
     /// Assumes that initialization has already taken place.
     fn should_era_change(block_number: T::BlockNumber) -> bool {
-        block_number % T::EraDuration::get() == Zero::zero()
+        let era_duration = CurrentEraParameters::<T>::get().era_duration;
+        let era_start_block = EraStartBlock::<T>::get().unwrap_or_else(|| {
+            // Before the first era change with stored start block eras were aligned to multiples
+            // of era duration, which never changed
+            let parent_block_number = block_number.saturating_sub(One::one());
+            parent_block_number - parent_block_number % era_duration
+        });
+
+        block_number.saturating_sub(era_start_block) >= era_duration
     }
 
     /// Determine whether an eon change should take place at this block.
     /// Assumes that initialization has already taken place.
     fn should_eon_change(_block_number: T::BlockNumber) -> bool {
         let diff = Self::current_slot().saturating_sub(Self::current_eon_start());
-        *diff >= T::EonDuration::get()
+        *diff >= CurrentEonParameters::<T>::get().eon_duration
     }
 
     /// DANGEROUS: Enact era change. Should be done on every block where `should_era_change` has
@@ -670,30 +844,38 @@ impl<T: Config> Pallet<T> {
     /// returned `true`, and the caller is the only caller of this function.
     ///
     /// This will update solution range used in consensus.
-    fn enact_era_change() {
+    fn enact_era_change(block_number: T::BlockNumber) {
         let slot_probability = T::SlotProbability::get();
 
         let current_slot = Self::current_slot();
+        // If Era start slot is not found it means we have just finished the first era
+        let era_start_slot = EraStartSlot::<T>::get().unwrap_or_else(GenesisSlot::<T>::get);
+        // Era that has just ended used current parameters, while the next one uses scheduled
+        // parameters, if any
+        let era_parameters = CurrentEraParameters::<T>::get();
+        let next_era_parameters = NextEraParameters::<T>::take().unwrap_or(era_parameters);
 
         SolutionRanges::<T>::mutate(|solution_ranges| {
             let next_solution_range;
             let next_voting_solution_range;
+            let adjustment;
             // Check if the solution range should be adjusted for next era.
             if !ShouldAdjustSolutionRange::<T>::get() {
                 next_solution_range = solution_ranges.current;
                 next_voting_solution_range = solution_ranges.current;
+                adjustment = SolutionRangeAdjustment::Disabled;
             } else if let Some(solution_range_override) = NextSolutionRangeOverride::<T>::take() {
                 next_solution_range = solution_range_override.solution_range;
                 next_voting_solution_range = solution_range_override.voting_solution_range;
+                adjustment = SolutionRangeAdjustment::Override;
             } else {
-                // If Era start slot is not found it means we have just finished the first era
-                let era_start_slot = EraStartSlot::<T>::get().unwrap_or_else(GenesisSlot::<T>::get);
                 let era_slot_count = u64::from(current_slot) - u64::from(era_start_slot);
 
                 // Now we need to re-calculate solution range. The idea here is to keep block production at
                 // the same pace while space pledged on the network changes. For this we adjust previous
                 // solution range according to actual and expected number of blocks per era.
-                let era_duration: u64 = T::EraDuration::get()
+                let era_duration: u64 = era_parameters
+                    .era_duration
                     .try_into()
                     .unwrap_or_else(|_| panic!("Era duration is always within u64; qed"));
 
@@ -721,28 +903,81 @@ impl<T: Config> Pallet<T> {
                 );
 
                 next_voting_solution_range = next_solution_range
-                    .saturating_mul(u64::from(T::ExpectedVotesPerBlock::get()) + 1);
+                    .saturating_mul(u64::from(next_era_parameters.expected_votes_per_block) + 1);
+                adjustment = SolutionRangeAdjustment::Computed;
             };
             solution_ranges.next.replace(next_solution_range);
             solution_ranges
                 .voting_next
                 .replace(next_voting_solution_range);
+
+            let era_summary = EraSummary {
+                start_slot: era_start_slot,
+                end_slot: current_slot,
+                blocks: EraBlockCount::<T>::take(),
+                votes: EraVoteCount::<T>::take(),
+                previous_solution_range: solution_ranges.current,
+                next_solution_range,
+                previous_voting_solution_range: solution_ranges.voting_current,
+                next_voting_solution_range,
+                adjustment,
+            };
+            EraHistory::<T>::mutate(|era_history| {
+                era_history.push(era_summary);
+                let excess = era_history
+                    .len()
+                    .saturating_sub(T::EraHistoryLength::get() as usize);
+                era_history.drain(..excess);
+
+                // Solution range of every era reflects space pledged in the era before it, averaging
+                // makes estimate resistant to luck in individual eras
+                let space_pledged_sum = era_history
+                    .iter()
+                    .map(|era_summary| {
+                        Self::space_pledged_for_solution_range(era_summary.next_solution_range)
+                    })
+                    .fold(0u128, |sum, space_pledged| {
+                        sum.saturating_add(space_pledged)
+                    });
+                if !era_history.is_empty() {
+                    SpacePledgedEstimate::<T>::put(space_pledged_sum / era_history.len() as u128);
+                }
+            });
         });
 
         EraStartSlot::<T>::put(current_slot);
+        EraStartBlock::<T>::put(block_number);
+        if next_era_parameters != era_parameters {
+            info!(
+                target: "runtime::subspace",
+                "🔃 Era parameters changed at block {block_number:?}: {next_era_parameters:?}",
+            );
+            CurrentEraParameters::<T>::put(next_era_parameters);
+        }
     }
 
     /// DANGEROUS: Enact an eon change. Should be done on every block where `should_eon_change` has
     /// returned `true`, and the caller is the only caller of this function.
     fn enact_eon_change(_block_number: T::BlockNumber) {
         let current_slot = *Self::current_slot();
-        let eon_index = current_slot
-            .checked_sub(*GenesisSlot::<T>::get())
-            .expect("Current slot is never lower than genesis slot; qed")
-            .checked_div(T::EonDuration::get())
+        let eon_duration = CurrentEonParameters::<T>::get().eon_duration;
+        let current_eon_start = *Self::current_eon_start();
+        // There might have been no blocks for the whole duration of some eons, those are skipped
+        let eons_passed = current_slot
+            .checked_sub(current_eon_start)
+            .expect("Current slot is never lower than current eon start; qed")
+            .checked_div(eon_duration)
             .expect("Eon duration is never zero; qed");
 
-        EonIndex::<T>::put(eon_index);
+        EonIndex::<T>::mutate(|eon_index| *eon_index += eons_passed);
+        EonStartSlot::<T>::put(Slot::from(current_eon_start + eons_passed * eon_duration));
+        if let Some(next_eon_parameters) = NextEonParameters::<T>::take() {
+            info!(
+                target: "runtime::subspace",
+                "🔃 Eon parameters changed at slot {current_slot}: {next_eon_parameters:?}",
+            );
+            CurrentEonParameters::<T>::put(next_eon_parameters);
+        }
         Salts::<T>::mutate(|salts| {
             salts.switch_next_block = true;
         });
@@ -752,7 +987,9 @@ impl<T: Config> Pallet<T> {
     /// `do_initialize` of the first block in the chain (as its result is based off of
     /// `GenesisSlot`).
     fn current_eon_start() -> Slot {
-        Self::eon_start(EonIndex::<T>::get())
+        // Before the first eon change with stored start slot eons were aligned to multiples of eon
+        // duration, which never changed
+        EonStartSlot::<T>::get().unwrap_or_else(|| Self::eon_start(EonIndex::<T>::get()))
     }
 
     fn eon_start(eon_index: u64) -> Slot {
@@ -762,7 +999,9 @@ impl<T: Config> Pallet<T> {
             "slot number is u64; it should relate in some way to wall clock time; if u64 is not \
             enough we should crash for safety; qed.";
 
-        let eon_start = eon_index.checked_mul(T::EonDuration::get()).expect(PROOF);
+        let eon_start = eon_index
+            .checked_mul(CurrentEonParameters::<T>::get().eon_duration)
+            .expect(PROOF);
 
         eon_start
             .checked_add(*GenesisSlot::<T>::get())
@@ -884,7 +1123,7 @@ impl<T: Config> Pallet<T> {
         }));
 
         let next_salt_reveal = Self::current_eon_start()
-            .checked_add(T::EonNextSaltReveal::get())
+            .checked_add(CurrentEonParameters::<T>::get().eon_next_salt_reveal)
             .expect("Will not overflow until the end of universe; qed");
         let current_slot = Self::current_slot();
         if current_slot >= next_salt_reveal {
@@ -907,6 +1146,7 @@ impl<T: Config> Pallet<T> {
 
         // Enact global randomness update, if necessary.
         T::GlobalRandomnessIntervalTrigger::trigger::<T>(block_number, por_randomness);
+        EraBlockCount::<T>::mutate(|era_block_count| *era_block_count += 1);
         // Enact era change, if necessary.
         T::EraChangeTrigger::trigger::<T>(block_number);
         // Enact eon change, if necessary.
@@ -922,7 +1162,11 @@ impl<T: Config> Pallet<T> {
 
         ParentVoteVerificationData::<T>::put(current_vote_verification_data::<T>(true));
 
-        ParentBlockVoters::<T>::put(CurrentBlockVoters::<T>::take().unwrap_or_default());
+        let current_block_voters = CurrentBlockVoters::<T>::take().unwrap_or_default();
+        EraVoteCount::<T>::mutate(|era_vote_count| {
+            *era_vote_count += current_block_voters.len() as u64;
+        });
+        ParentBlockVoters::<T>::put(current_block_voters);
     }
 
     fn do_report_equivocation(
@@ -967,7 +1211,9 @@ impl<T: Config> Pallet<T> {
 
         if let Some(solution_range) = solution_range_override {
             let voting_solution_range = voting_solution_range_override.unwrap_or_else(|| {
-                solution_range.saturating_mul(u64::from(T::ExpectedVotesPerBlock::get()) + 1)
+                solution_range.saturating_mul(
+                    u64::from(CurrentEraParameters::<T>::get().expected_votes_per_block) + 1,
+                )
             });
             SolutionRanges::<T>::mutate(|solution_ranges| {
                 // If solution range update is already scheduled, just update values
@@ -991,6 +1237,35 @@ impl<T: Config> Pallet<T> {
         Ok(())
     }
 
+    fn do_schedule_consensus_parameters(
+        era_parameters: Option<EraParameters<T::BlockNumber>>,
+        eon_parameters: Option<EonParameters>,
+    ) -> DispatchResult {
+        if let Some(era_parameters) = &era_parameters {
+            if era_parameters.era_duration.is_zero()
+                || era_parameters.confirmation_depth_k.is_zero()
+            {
+                return Err(Error::<T>::InvalidConsensusParameters.into());
+            }
+        }
+        if let Some(eon_parameters) = &eon_parameters {
+            if eon_parameters.eon_duration == 0
+                || eon_parameters.eon_next_salt_reveal >= eon_parameters.eon_duration
+            {
+                return Err(Error::<T>::InvalidConsensusParameters.into());
+            }
+        }
+
+        if let Some(era_parameters) = era_parameters {
+            NextEraParameters::<T>::put(era_parameters);
+        }
+        if let Some(eon_parameters) = eon_parameters {
+            NextEonParameters::<T>::put(eon_parameters);
+        }
+
+        Ok(())
+    }
+
     fn do_vote(signed_vote: SignedVote<T::BlockNumber, T::Hash, T::AccountId>) -> DispatchResult {
         let Vote::V0 {
             height,
@@ -999,7 +1274,7 @@ impl<T: Config> Pallet<T> {
             ..
         } = signed_vote.vote;
 
-        if BlockList::<T>::contains_key(&solution.public_key) {
+        if Self::is_in_block_list(&solution.public_key) {
             Err(DispatchError::Other("Equivocated"))
         } else {
             Self::deposit_event(Event::FarmerVote {
@@ -1013,6 +1288,35 @@ impl<T: Config> Pallet<T> {
         }
     }
 
+    fn do_lift_block_list_entry(farmer_public_key: FarmerPublicKey) -> DispatchResult {
+        if !Self::is_in_block_list(&farmer_public_key) {
+            return Err(Error::<T>::NotInBlockList.into());
+        }
+
+        BlockList::<T>::remove(&farmer_public_key);
+
+        Self::deposit_event(Event::BlockListEntryLifted {
+            public_key: farmer_public_key,
+        });
+
+        Ok(())
+    }
+
+    /// Adds farmer to block list for [`Config::BlockListDuration`] eons starting from current one,
+    /// existing entry is only replaced if it expires earlier.
+    fn block_list_farmer(farmer_public_key: FarmerPublicKey, offence_slot: Slot) {
+        let expires_at_eon = EonIndex::<T>::get().saturating_add(T::BlockListDuration::get());
+
+        BlockList::<T>::mutate(farmer_public_key, |maybe_entry| {
+            if maybe_entry.map_or(true, |entry| entry.expires_at_eon < expires_at_eon) {
+                maybe_entry.replace(BlockListEntry {
+                    offence_slot,
+                    expires_at_eon,
+                });
+            }
+        });
+    }
+
     fn do_enable_rewards(height: Option<T::BlockNumber>) -> DispatchResult {
         if EnableRewards::<T>::get().is_some() {
             return Err(Error::<T>::RewardsAlreadyEnabled.into());
@@ -1054,13 +1358,86 @@ impl<T: Config> Pallet<T> {
     pub fn submit_test_equivocation_report(
         equivocation_proof: EquivocationProof<T::Header>,
     ) -> Option<()> {
-        BlockList::<T>::insert(equivocation_proof.offender, ());
+        Self::block_list_farmer(equivocation_proof.offender, equivocation_proof.slot);
         Some(())
     }
 
-    /// Check if `farmer_public_key` is in block list (due to equivocation)
+    /// Check if `farmer_public_key` is in block list (due to equivocation) and entry didn't expire
+    /// yet
     pub fn is_in_block_list(farmer_public_key: &FarmerPublicKey) -> bool {
-        BlockList::<T>::contains_key(farmer_public_key)
+        Self::remaining_block_list_eons(farmer_public_key).is_some()
+    }
+
+    /// Number of eons (including current one) until `farmer_public_key` is removed from block
+    /// list, `None` if farmer is not in block list
+    pub fn remaining_block_list_eons(farmer_public_key: &FarmerPublicKey) -> Option<u64> {
+        BlockList::<T>::get(farmer_public_key)?
+            .expires_at_eon
+            .checked_sub(EonIndex::<T>::get())
+            .filter(|remaining_eons| *remaining_eons > 0)
+    }
+
+    /// Removes the expired block list entries within `remaining_weight`, returns consumed weight
+    fn remove_expired_block_list_entries(remaining_weight: Weight) -> Weight {
+        let db_weight = T::DbWeight::get();
+        // Reading the current eon index
+        let mut consumed_weight = db_weight.reads(1);
+        if consumed_weight > remaining_weight {
+            return 0;
+        }
+
+        let eon_index = EonIndex::<T>::get();
+        let mut expired = Vec::new();
+        for (farmer_public_key, entry) in BlockList::<T>::iter() {
+            // Reading the entry and removing it if expired
+            let entry_weight = db_weight.reads_writes(1, 1);
+            if consumed_weight.saturating_add(entry_weight) > remaining_weight {
+                break;
+            }
+            consumed_weight += db_weight.reads(1);
+            if entry.expires_at_eon <= eon_index {
+                consumed_weight += db_weight.writes(1);
+                expired.push(farmer_public_key);
+            }
+        }
+        for farmer_public_key in expired {
+            BlockList::<T>::remove(farmer_public_key);
+        }
+
+        consumed_weight
+    }
+
+    /// Depth `K` after which a block enters the recorded history
+    pub fn confirmation_depth_k() -> T::BlockNumber {
+        CurrentEraParameters::<T>::get().confirmation_depth_k
+    }
+
+    /// Estimate of total space pledged to the network (in bytes) derived from solution ranges of
+    /// recent eras
+    pub fn total_space_pledged() -> u128 {
+        SpacePledgedEstimate::<T>::get().unwrap_or_else(|| {
+            Self::space_pledged_for_solution_range(SolutionRanges::<T>::get().current)
+        })
+    }
+
+    /// Estimate of total space pledged to the network (in bytes) that corresponds to given
+    /// solution range.
+    ///
+    /// Size of the largest possible plot is used as lower bound, such that estimate doesn't drop
+    /// close to zero with very large solution ranges (like initial one on a small network).
+    fn space_pledged_for_solution_range(solution_range: u64) -> u128 {
+        let slot_probability = T::SlotProbability::get();
+        let piece_size = u128::try_from(PIECE_SIZE)
+            .expect("Piece size is definitely small enough to fit into u128; qed");
+        // Operations reordered to avoid data loss, but essentially are:
+        // u64::MAX * SlotProbability / (solution_range / PIECE_SIZE)
+        let space_pledged = u128::from(u64::MAX)
+            .saturating_mul(piece_size)
+            .saturating_mul(u128::from(slot_probability.0))
+            / u128::from(solution_range.max(1))
+            / u128::from(slot_probability.1);
+
+        space_pledged.max(u128::from(T::MaxPlotSize::get()).saturating_mul(piece_size))
     }
 
     /// Size of the archived history of the blockchain in bytes
@@ -1271,7 +1648,7 @@ fn check_vote<T: Config>(
     let height = *height;
     let slot = *slot;
 
-    if BlockList::<T>::contains_key(&solution.public_key) {
+    if Pallet::<T>::is_in_block_list(&solution.public_key) {
         return Err(CheckVoteError::BlockListed);
     }
 
@@ -1531,7 +1908,7 @@ impl<T: Config> subspace_runtime_primitives::FindBlockRewardAddress<T::AccountId
         CurrentBlockAuthorInfo::<T>::get().and_then(|(public_key, _slot, reward_address)| {
             // Equivocation might have happened in this block, if so - no reward for block
             // author
-            if !BlockList::<T>::contains_key(&public_key) {
+            if !Self::is_in_block_list(&public_key) {
                 // Rewards might be disabled, in which case no block reward either
                 if let Some(height) = EnableRewards::<T>::get() {
                     if frame_system::Pallet::<T>::current_block_number() >= height {
@@ -1591,9 +1968,9 @@ impl<T: Config> frame_support::traits::Randomness<T::Hash, T::BlockNumber> for P
 }
 
 impl<T: Config> OnOffenceHandler<FarmerPublicKey> for Pallet<T> {
-    fn on_offence(offenders: &[OffenceDetails<FarmerPublicKey>]) {
+    fn on_offence(offenders: &[OffenceDetails<FarmerPublicKey>], offence_slot: Slot) {
         for offender in offenders {
-            BlockList::<T>::insert(offender.offender.clone(), ());
+            Self::block_list_farmer(offender.offender.clone(), offence_slot);
         }
     }
 }
@@ -0,0 +1,48 @@
+// Copyright (C) 2022 Subspace Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage migrations of the Subspace pallet.
+
+use crate::pallet::{BlockList, BlockListEntry, Config, CurrentSlot, EonIndex, Pallet};
+use frame_support::traits::{Get, GetStorageVersion, StorageVersion};
+use frame_support::weights::Weight;
+
+/// Migrates block list entries stored before their expiry was tracked, they are block listed for
+/// [`Config::BlockListDuration`] eons starting from the current one.
+pub fn migrate_to_v1<T: Config>() -> Weight {
+    if Pallet::<T>::on_chain_storage_version() >= 1 {
+        return T::DbWeight::get().reads(1);
+    }
+
+    let offence_slot = CurrentSlot::<T>::get();
+    let expires_at_eon = EonIndex::<T>::get().saturating_add(T::BlockListDuration::get());
+    let mut translated = 0u64;
+    BlockList::<T>::translate::<(), _>(|_farmer_public_key, ()| {
+        translated += 1;
+        Some(BlockListEntry {
+            offence_slot,
+            expires_at_eon,
+        })
+    });
+    StorageVersion::new(1).put::<Pallet<T>>();
+
+    log::info!(
+        target: "runtime::subspace",
+        "Migrated {} block list entries to storage version 1",
+        translated,
+    );
+
+    T::DbWeight::get().reads_writes(translated + 3, translated + 1)
+}
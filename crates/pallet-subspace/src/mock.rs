@@ -149,6 +149,8 @@ parameter_types! {
     pub const ReportLongevity: u64 = 34;
     pub const MaxPlotSize: u64 = 10 * 2u64.pow(18);
     pub const ShouldAdjustSolutionRange: bool = false;
+    pub const EraHistoryLength: u32 = 2;
+    pub const BlockListDuration: u64 = 2;
 }
 
 impl Config for Test {
@@ -173,6 +175,8 @@ impl Config for Test {
 
     type WeightInfo = ();
     type ShouldAdjustSolutionRange = ShouldAdjustSolutionRange;
+    type EraHistoryLength = EraHistoryLength;
+    type BlockListDuration = BlockListDuration;
 }
 
 pub fn go_to_block(
@@ -23,19 +23,23 @@ use crate::mock::{
     INITIAL_SOLUTION_RANGE, SLOT_PROBABILITY,
 };
 use crate::{
-    pallet, BlockList, Call, CheckVoteError, Config, CurrentBlockAuthorInfo, CurrentBlockVoters,
-    CurrentSlot, Error, ParentBlockAuthorInfo, ParentBlockVoters, RecordsRoot,
+    pallet, BlockList, BlockListEntry, Call, CheckVoteError, Config, CurrentBlockAuthorInfo,
+    CurrentBlockVoters, CurrentSlot, EonParameters, EraParameters, Error, NextEonParameters,
+    NextEraParameters, ParentBlockAuthorInfo, ParentBlockVoters, RecordsRoot,
     SubspaceEquivocationOffence, WeightInfo,
 };
 use codec::Encode;
-use frame_support::weights::{GetDispatchInfo, Pays};
+use frame_support::storage::unhashed;
+use frame_support::traits::{GetStorageVersion, Hooks, StorageVersion};
+use frame_support::weights::{GetDispatchInfo, Pays, Weight};
 use frame_support::{assert_err, assert_ok};
 use frame_system::{EventRecord, Phase};
 use schnorrkel::Keypair;
 use sp_consensus_slots::Slot;
 use sp_consensus_subspace::verification::VerificationError;
 use sp_consensus_subspace::{
-    FarmerPublicKey, FarmerSignature, GlobalRandomnesses, Salts, SolutionRanges, Vote,
+    EraSummary, FarmerPublicKey, FarmerSignature, GlobalRandomnesses, Salts,
+    SolutionRangeAdjustment, SolutionRanges, Vote,
 };
 use sp_core::crypto::UncheckedFrom;
 use sp_runtime::traits::{BlockNumberProvider, Header};
@@ -45,6 +49,7 @@ use sp_runtime::transaction_validity::{
 use sp_runtime::DispatchError;
 use std::assert_matches::assert_matches;
 use std::collections::BTreeMap;
+use subspace_core_primitives::PIECE_SIZE;
 use subspace_runtime_primitives::{FindBlockRewardAddress, FindVotingRewardAddresses};
 use subspace_solving::REWARD_SIGNING_CONTEXT;
 
@@ -284,6 +289,108 @@ fn solution_range_should_not_update_when_disabled() {
     })
 }
 
+#[test]
+fn era_history_is_recorded() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+
+        assert_eq!(<Test as Config>::EraDuration::get(), 4);
+        assert_eq!(<Test as Config>::EraHistoryLength::get(), 2);
+
+        progress_to_block(&keypair, 1, 1);
+        let genesis_slot = Subspace::genesis_slot();
+        assert!(Subspace::era_history().is_empty());
+
+        assert_ok!(Subspace::enable_solution_range_adjustment(
+            Origin::root(),
+            Some(1_000_000),
+            Some(2_000_000),
+        ));
+        assert_eq!(
+            Subspace::total_space_pledged(),
+            u128::from(u64::MAX) * PIECE_SIZE as u128 * u128::from(SLOT_PROBABILITY.0)
+                / 1_000_000
+                / u128::from(SLOT_PROBABILITY.1)
+        );
+
+        // First era ends with overridden solution range
+        progress_to_block(&keypair, 4, 1);
+        assert_eq!(
+            Subspace::era_history(),
+            vec![EraSummary {
+                start_slot: genesis_slot,
+                end_slot: genesis_slot + 3,
+                blocks: 4,
+                votes: 0,
+                previous_solution_range: 1_000_000,
+                next_solution_range: 1_000_000,
+                previous_voting_solution_range: 2_000_000,
+                next_voting_solution_range: 2_000_000,
+                adjustment: SolutionRangeAdjustment::Override,
+            }]
+        );
+
+        // Next era is adjusted as usual
+        progress_to_block(&keypair, 8, 1);
+        let era_history = Subspace::era_history();
+        assert_eq!(era_history.len(), 2);
+        assert_eq!(era_history[1].start_slot, genesis_slot + 3);
+        assert_eq!(era_history[1].end_slot, genesis_slot + 7);
+        assert_eq!(era_history[1].blocks, 4);
+        assert_eq!(era_history[1].previous_solution_range, 1_000_000);
+        assert_eq!(
+            Some(era_history[1].next_solution_range),
+            Subspace::solution_ranges().next
+        );
+        assert_eq!(era_history[1].adjustment, SolutionRangeAdjustment::Computed);
+
+        // Only the most recent eras are kept
+        progress_to_block(&keypair, 12, 1);
+        let era_history = Subspace::era_history();
+        assert_eq!(era_history.len(), 2);
+        assert_eq!(era_history[0].end_slot, genesis_slot + 7);
+        assert_eq!(era_history[1].end_slot, genesis_slot + 11);
+    });
+}
+
+#[test]
+fn total_space_pledged_is_smoothed_over_eras() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+        let space_pledged = |solution_range: u64| {
+            u128::from(u64::MAX) * PIECE_SIZE as u128 * u128::from(SLOT_PROBABILITY.0)
+                / u128::from(solution_range)
+                / u128::from(SLOT_PROBABILITY.1)
+        };
+
+        progress_to_block(&keypair, 1, 1);
+        // Initial solution range corresponds to less space than a single plot
+        assert_eq!(
+            Subspace::total_space_pledged(),
+            u128::from(<Test as Config>::MaxPlotSize::get()) * PIECE_SIZE as u128
+        );
+
+        assert_ok!(Subspace::enable_solution_range_adjustment(
+            Origin::root(),
+            Some(1_000_000),
+            None,
+        ));
+        // Estimate follows current solution range until the first era change
+        assert_eq!(Subspace::total_space_pledged(), space_pledged(1_000_000));
+
+        progress_to_block(&keypair, 4, 1);
+        assert_eq!(Subspace::total_space_pledged(), space_pledged(1_000_000));
+
+        progress_to_block(&keypair, 8, 1);
+        let next_solution_range = Subspace::solution_ranges().next.unwrap();
+        assert_ne!(next_solution_range, 1_000_000);
+        assert_eq!(
+            Subspace::total_space_pledged(),
+            (space_pledged(1_000_000) + space_pledged(next_solution_range)) / 2
+        );
+    });
+}
+
 #[test]
 fn can_update_salt_on_eon_change() {
     new_test_ext().execute_with(|| {
@@ -338,6 +445,175 @@ fn can_update_salt_on_eon_change() {
     })
 }
 
+#[test]
+fn era_parameters_change_at_next_era() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+
+        assert_eq!(<Test as Config>::EraDuration::get(), 4);
+        assert_ok!(Subspace::enable_solution_range_adjustment(
+            Origin::root(),
+            None,
+            None
+        ));
+
+        progress_to_block(&keypair, 2, 1);
+        let initial_era_parameters = Subspace::era_parameters();
+        let new_era_parameters = EraParameters {
+            era_duration: 6,
+            expected_votes_per_block: 4,
+            confirmation_depth_k: 5,
+        };
+        assert_ok!(Subspace::schedule_consensus_parameters(
+            Origin::root(),
+            Some(new_era_parameters),
+            None,
+        ));
+
+        // Almost era edge, nothing changed yet
+        progress_to_block(&keypair, 3, 1);
+        assert_eq!(Subspace::era_parameters(), initial_era_parameters);
+        assert_eq!(Subspace::confirmation_depth_k(), 10);
+
+        // Era edge, era that has just ended used old parameters and the next one uses new ones
+        progress_to_block(&keypair, 4, 1);
+        assert_eq!(Subspace::era_parameters(), new_era_parameters);
+        assert_eq!(Subspace::confirmation_depth_k(), 5);
+        let solution_ranges = Subspace::solution_ranges();
+        assert_eq!(
+            solution_ranges.voting_next,
+            Some(solution_ranges.next.unwrap() * 5)
+        );
+        assert_eq!(Subspace::era_history().len(), 1);
+        assert_eq!(Subspace::era_history()[0].blocks, 4);
+
+        // Old era edge is not an era edge anymore
+        progress_to_block(&keypair, 8, 1);
+        assert_eq!(Subspace::era_history().len(), 1);
+
+        // Block production continues with eras of new duration
+        progress_to_block(&keypair, 10, 1);
+        assert_eq!(Subspace::era_history().len(), 2);
+        assert_eq!(Subspace::era_history()[1].blocks, 6);
+
+        progress_to_block(&keypair, 16, 1);
+        assert_eq!(Subspace::era_history()[1].blocks, 6);
+        assert_eq!(
+            Subspace::era_history()[1].end_slot,
+            Subspace::current_slot()
+        );
+    });
+}
+
+#[test]
+fn eon_parameters_change_at_next_eon() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+
+        assert_eq!(<Test as Config>::EonDuration::get(), 6);
+        assert_eq!(<Test as Config>::EonNextSaltReveal::get(), 3);
+
+        progress_to_block(&keypair, 1, 1);
+        let new_eon_parameters = EonParameters {
+            eon_duration: 10,
+            eon_next_salt_reveal: 5,
+        };
+        assert_ok!(Subspace::schedule_consensus_parameters(
+            Origin::root(),
+            None,
+            Some(new_eon_parameters),
+        ));
+
+        // Eon edge according to old parameters
+        progress_to_block(&keypair, 7, 1);
+        assert_eq!(Subspace::eon_index(), 1);
+        assert_eq!(Subspace::eon_parameters(), new_eon_parameters);
+        assert!(Subspace::salts().switch_next_block);
+
+        // Salt is revealed according to new parameters
+        progress_to_block(&keypair, 11, 1);
+        assert!(Subspace::salts().next.is_none());
+        progress_to_block(&keypair, 12, 1);
+        assert!(Subspace::salts().next.is_some());
+
+        // Eon edge according to new parameters
+        progress_to_block(&keypair, 16, 1);
+        assert_eq!(Subspace::eon_index(), 1);
+        progress_to_block(&keypair, 17, 1);
+        assert_eq!(Subspace::eon_index(), 2);
+        assert!(Subspace::salts().switch_next_block);
+
+        // Eons without blocks are skipped
+        go_to_block(&keypair, 18, u64::from(Subspace::current_slot()) + 25, 1);
+        assert_eq!(Subspace::eon_index(), 4);
+
+        // Block production continues
+        progress_to_block(&keypair, 20, 1);
+        assert_eq!(Subspace::eon_index(), 4);
+        assert!(!Subspace::salts().switch_next_block);
+    });
+}
+
+#[test]
+fn schedule_consensus_parameters_validates_input() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+
+        progress_to_block(&keypair, 1, 1);
+
+        let era_parameters = EraParameters {
+            era_duration: 6,
+            expected_votes_per_block: 4,
+            confirmation_depth_k: 5,
+        };
+        let eon_parameters = EonParameters {
+            eon_duration: 10,
+            eon_next_salt_reveal: 5,
+        };
+
+        assert_err!(
+            Subspace::schedule_consensus_parameters(
+                Origin::signed(1),
+                Some(era_parameters),
+                Some(eon_parameters),
+            ),
+            DispatchError::BadOrigin,
+        );
+        assert_err!(
+            Subspace::schedule_consensus_parameters(
+                Origin::root(),
+                Some(EraParameters {
+                    era_duration: 0,
+                    ..era_parameters
+                }),
+                None,
+            ),
+            Error::<Test>::InvalidConsensusParameters,
+        );
+        assert_err!(
+            Subspace::schedule_consensus_parameters(
+                Origin::root(),
+                None,
+                Some(EonParameters {
+                    eon_next_salt_reveal: 10,
+                    ..eon_parameters
+                }),
+            ),
+            Error::<Test>::InvalidConsensusParameters,
+        );
+        assert!(NextEraParameters::<Test>::get().is_none());
+        assert!(NextEonParameters::<Test>::get().is_none());
+
+        assert_ok!(Subspace::schedule_consensus_parameters(
+            Origin::root(),
+            Some(era_parameters),
+            Some(eon_parameters),
+        ));
+        assert_eq!(NextEraParameters::<Test>::get(), Some(era_parameters));
+        assert_eq!(NextEonParameters::<Test>::get(), Some(eon_parameters));
+    });
+}
+
 #[test]
 fn report_equivocation_current_session_works() {
     new_test_ext().execute_with(|| {
@@ -391,6 +667,124 @@ fn report_equivocation_old_session_works() {
     })
 }
 
+#[test]
+fn block_list_entry_expires() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+
+        progress_to_block(&keypair, 1, 1);
+
+        let farmer_public_key = FarmerPublicKey::unchecked_from(keypair.public.to_bytes());
+        let offence_slot = CurrentSlot::<Test>::get();
+        let equivocation_proof = generate_equivocation_proof(&keypair, offence_slot);
+
+        assert_eq!(
+            Subspace::remaining_block_list_eons(&farmer_public_key),
+            None
+        );
+
+        Subspace::report_equivocation(Origin::none(), Box::new(equivocation_proof)).unwrap();
+
+        assert_eq!(
+            BlockList::<Test>::get(&farmer_public_key),
+            Some(BlockListEntry {
+                offence_slot,
+                expires_at_eon: 2,
+            })
+        );
+        assert_eq!(
+            Subspace::remaining_block_list_eons(&farmer_public_key),
+            Some(2)
+        );
+
+        // Next eon
+        progress_to_block(&keypair, 7, 1);
+        assert_eq!(Subspace::eon_index(), 1);
+        assert!(Subspace::is_in_block_list(&farmer_public_key));
+        assert_eq!(
+            Subspace::remaining_block_list_eons(&farmer_public_key),
+            Some(1)
+        );
+
+        // Entry expires once block list duration has passed
+        progress_to_block(&keypair, 13, 1);
+        assert_eq!(Subspace::eon_index(), 2);
+        assert!(!Subspace::is_in_block_list(&farmer_public_key));
+        assert_eq!(
+            Subspace::remaining_block_list_eons(&farmer_public_key),
+            None
+        );
+
+        // Expired entry is removed once there is weight left for it
+        Subspace::on_idle(13, Weight::MAX);
+        assert!(!BlockList::<Test>::contains_key(&farmer_public_key));
+    });
+}
+
+#[test]
+fn lift_block_list_entry_works() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+
+        progress_to_block(&keypair, 1, 1);
+
+        let farmer_public_key = FarmerPublicKey::unchecked_from(keypair.public.to_bytes());
+
+        assert_err!(
+            Subspace::lift_block_list_entry(Origin::root(), farmer_public_key.clone()),
+            Error::<Test>::NotInBlockList,
+        );
+
+        let equivocation_proof = generate_equivocation_proof(&keypair, CurrentSlot::<Test>::get());
+        Subspace::report_equivocation(Origin::none(), Box::new(equivocation_proof)).unwrap();
+        assert!(Subspace::is_in_block_list(&farmer_public_key));
+
+        assert_err!(
+            Subspace::lift_block_list_entry(Origin::signed(1), farmer_public_key.clone()),
+            DispatchError::BadOrigin,
+        );
+
+        assert_ok!(Subspace::lift_block_list_entry(
+            Origin::root(),
+            farmer_public_key.clone()
+        ));
+        assert!(!Subspace::is_in_block_list(&farmer_public_key));
+        System::assert_last_event(Event::Subspace(crate::Event::BlockListEntryLifted {
+            public_key: farmer_public_key,
+        }));
+    });
+}
+
+#[test]
+fn migrate_block_list_to_v1() {
+    new_test_ext().execute_with(|| {
+        let keypair = Keypair::generate();
+
+        progress_to_block(&keypair, 7, 1);
+        assert_eq!(Subspace::eon_index(), 1);
+
+        // Block list entries used to be stored without any value
+        let farmer_public_key = FarmerPublicKey::unchecked_from(keypair.public.to_bytes());
+        unhashed::put_raw(&BlockList::<Test>::hashed_key_for(&farmer_public_key), &[]);
+        StorageVersion::new(0).put::<Subspace>();
+
+        crate::migrations::migrate_to_v1::<Test>();
+
+        assert_eq!(Subspace::on_chain_storage_version(), 1);
+        assert_eq!(
+            BlockList::<Test>::get(&farmer_public_key),
+            Some(BlockListEntry {
+                offence_slot: CurrentSlot::<Test>::get(),
+                expires_at_eon: 3,
+            })
+        );
+        assert_eq!(
+            Subspace::remaining_block_list_eons(&farmer_public_key),
+            Some(2)
+        );
+    });
+}
+
 #[test]
 fn report_equivocation_invalid_equivocation_proof() {
     new_test_ext().execute_with(|| {
@@ -682,7 +1076,10 @@ fn vote_block_listed() {
 
         BlockList::<Test>::insert(
             FarmerPublicKey::unchecked_from(keypair.public.to_bytes()),
-            (),
+            BlockListEntry {
+                offence_slot: Slot::from(0),
+                expires_at_eon: 1,
+            },
         );
 
         // Can't submit vote right after genesis block
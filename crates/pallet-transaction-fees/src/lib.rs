@@ -20,15 +20,20 @@
 #![warn(rust_2018_idioms, missing_debug_implementations)]
 
 mod default_weights;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
 
 use codec::{Codec, Decode, Encode};
-use frame_support::sp_runtime::traits::Zero;
+use frame_support::sp_runtime::traits::{One, Zero};
 use frame_support::sp_runtime::SaturatedConversion;
 use frame_support::traits::{Currency, Get};
 use frame_support::weights::Weight;
 pub use pallet::*;
 use scale_info::TypeInfo;
-use subspace_runtime_primitives::FindBlockRewardAddress;
+use sp_transaction_fees::FeeBreakdown;
+use subspace_runtime_primitives::{FindBlockRewardAddress, OnExecutionReceipt};
 
 type BalanceOf<T> =
     <<T as Config>::Currency as Currency<<T as frame_system::Config>::AccountId>>::Balance;
@@ -73,11 +78,29 @@ mod pallet {
         #[pallet::constant]
         type StorageFeesEscrowBlockTax: Get<(u64, u64)>;
 
+        /// How much (ratio) of compute fees collected in a block should be given to the executor
+        /// once the execution receipt of the block can't be challenged anymore (with remaining
+        /// issued to farmer immediately).
+        #[pallet::constant]
+        type ExecutorComputeFeesShare: Get<(u64, u64)>;
+
+        /// Number of blocks after which the executor share of compute fees of a block whose
+        /// execution receipt is still not confirmed goes into storage fees escrow instead.
+        ///
+        /// Must be longer than the challenge period of the execution receipts.
+        #[pallet::constant]
+        type ExecutorFeesTimeout: Get<Self::BlockNumber>;
+
+        /// Maximum number of blocks whose executor share of compute fees is checked for the
+        /// timeout in one block.
+        #[pallet::constant]
+        type MaxExecutorFeesBlocks: Get<u32>;
+
         /// How many credits there is in circulation.
         #[pallet::constant]
         type CreditSupply: Get<BalanceOf<Self>>;
 
-        /// How much space there is on the network.
+        /// How much space there is on the network, normally estimated by consensus.
         #[pallet::constant]
         type TotalSpacePledged: Get<u128>;
 
@@ -99,6 +122,18 @@ mod pallet {
     #[pallet::getter(fn storage_fees_escrow)]
     pub(super) type CollectedStorageFeesEscrow<T> = StorageValue<_, BalanceOf<T>, ValueQuery>;
 
+    /// Executor share of compute fees collected in a block, waiting for the execution receipt of
+    /// the block to be confirmed or reverted.
+    #[pallet::storage]
+    #[pallet::getter(fn pending_executor_fees)]
+    pub(super) type PendingExecutorFees<T: Config> =
+        StorageMap<_, Twox64Concat, T::BlockNumber, BalanceOf<T>>;
+
+    /// Number of the oldest block that might still have executor fees pending, set on the first
+    /// timeout check.
+    #[pallet::storage]
+    pub(super) type NextExecutorFeesBlock<T: Config> = StorageValue<_, T::BlockNumber, OptionQuery>;
+
     /// Temporary value (cleared at block finalization) which contains cached value of
     /// `TransactionByteFee` for current block.
     #[pallet::storage]
@@ -145,6 +180,23 @@ mod pallet {
             /// Amount of collected compute fees.
             amount: BalanceOf<T>,
         },
+        /// Executor share of compute fees of a block whose execution receipt was reverted or not
+        /// confirmed in time went into storage fees escrow.
+        ExecutorComputeFeesEscrowed {
+            /// Block the compute fees were collected in.
+            block_number: T::BlockNumber,
+            /// Amount of collected compute fees.
+            amount: BalanceOf<T>,
+        },
+        /// Executor share of compute fees.
+        ExecutorComputeFeesReward {
+            /// Receiver of the compute fees.
+            who: T::AccountId,
+            /// Block the compute fees were collected in.
+            block_number: T::BlockNumber,
+            /// Amount of collected compute fees.
+            amount: BalanceOf<T>,
+        },
         /// Tips.
         TipsReward {
             /// Receiver of the tip.
@@ -161,7 +213,7 @@ mod pallet {
     {
         fn on_initialize(now: BlockNumberFor<T>) -> Weight {
             Self::do_initialize(now);
-            T::WeightInfo::on_initialize()
+            T::WeightInfo::on_initialize().saturating_add(Self::escrow_timed_out_executor_fees(now))
         }
 
         fn on_finalize(now: BlockNumberFor<T>) {
@@ -187,8 +239,7 @@ where
         });
     }
 
-    // TODO: Fees will be split between farmers and executors in the future
-    fn do_finalize(_n: T::BlockNumber) {
+    fn do_finalize(n: T::BlockNumber) {
         TransactionByteFee::<T>::take();
 
         let collected_fees = CollectedBlockFees::<T>::take()
@@ -231,12 +282,22 @@ where
                 });
             }
 
+            // Take a portion of compute fees collected in this block as an executor reward, it
+            // is issued once the execution receipt of this block can't be challenged anymore.
+            let executor_compute_fees_reward = collected_fees.compute
+                / T::ExecutorComputeFeesShare::get().1.into()
+                * T::ExecutorComputeFeesShare::get().0.into();
+            if !executor_compute_fees_reward.is_zero() {
+                PendingExecutorFees::<T>::insert(n, executor_compute_fees_reward);
+            }
+
             // Issue compute fees reward.
-            if !collected_fees.compute.is_zero() {
-                T::Currency::deposit_creating(&block_author, collected_fees.compute);
+            let compute_fees_reward = collected_fees.compute - executor_compute_fees_reward;
+            if !compute_fees_reward.is_zero() {
+                T::Currency::deposit_creating(&block_author, compute_fees_reward);
                 Self::deposit_event(Event::<T>::ComputeFeesReward {
                     who: block_author.clone(),
-                    amount: collected_fees.compute,
+                    amount: compute_fees_reward,
                 });
             }
 
@@ -268,6 +329,60 @@ where
         }
     }
 
+    /// Moves the pending executor fees of the blocks whose execution receipts were not confirmed
+    /// within [`Config::ExecutorFeesTimeout`] blocks into storage fees escrow and returns the
+    /// weight consumed.
+    ///
+    /// Blocks are checked in order and the check stops at the first block with pending fees that
+    /// didn't time out yet. At most [`Config::MaxExecutorFeesBlocks`] blocks are looked at, the
+    /// rest is left for the following blocks.
+    fn escrow_timed_out_executor_fees(n: T::BlockNumber) -> Weight {
+        let db_weight = T::DbWeight::get();
+        let mut weight = db_weight.reads_writes(1, 1);
+
+        // Nothing could have been pending before the first check
+        let mut block_number = NextExecutorFeesBlock::<T>::get().unwrap_or(n);
+
+        for _ in 0..T::MaxExecutorFeesBlocks::get() {
+            if block_number >= n {
+                break;
+            }
+
+            weight = weight.saturating_add(db_weight.reads(1));
+            if let Some(amount) = PendingExecutorFees::<T>::get(block_number) {
+                if block_number + T::ExecutorFeesTimeout::get() > n {
+                    break;
+                }
+
+                weight = weight.saturating_add(db_weight.reads_writes(1, 2));
+                PendingExecutorFees::<T>::remove(block_number);
+                Self::escrow_executor_fees(block_number, amount);
+            }
+
+            block_number += One::one();
+        }
+
+        NextExecutorFeesBlock::<T>::put(block_number);
+
+        weight
+    }
+
+    /// Moves the executor share of compute fees collected in given block into storage fees escrow.
+    fn escrow_executor_fees(block_number: T::BlockNumber, amount: BalanceOf<T>) {
+        let original_storage_fees_escrow = CollectedStorageFeesEscrow::<T>::get();
+        let storage_fees_escrow = original_storage_fees_escrow + amount;
+        CollectedStorageFeesEscrow::<T>::put(storage_fees_escrow);
+
+        Self::deposit_event(Event::<T>::StorageFeesEscrowChange {
+            before: original_storage_fees_escrow,
+            after: storage_fees_escrow,
+        });
+        Self::deposit_event(Event::<T>::ExecutorComputeFeesEscrowed {
+            block_number,
+            amount,
+        });
+    }
+
     pub fn transaction_byte_fee() -> BalanceOf<T> {
         if let Some(transaction_byte_fee) = TransactionByteFee::<T>::get() {
             return transaction_byte_fee;
@@ -291,6 +406,24 @@ where
         transaction_byte_fee
     }
 
+    /// Breakdown of given storage and compute fees along with the inputs of the transaction byte
+    /// fee.
+    pub fn fee_breakdown(
+        transaction_byte_fee: BalanceOf<T>,
+        storage_fee: BalanceOf<T>,
+        compute_fee: BalanceOf<T>,
+    ) -> FeeBreakdown<BalanceOf<T>> {
+        FeeBreakdown {
+            storage_fee,
+            compute_fee,
+            transaction_byte_fee,
+            credit_supply: T::CreditSupply::get(),
+            total_space_pledged: T::TotalSpacePledged::get(),
+            blockchain_history_size: T::BlockchainHistorySize::get(),
+            min_replication_factor: T::MinReplicationFactor::get(),
+        }
+    }
+
     pub fn note_transaction_fees(
         storage_fee: BalanceOf<T>,
         compute_fee: BalanceOf<T>,
@@ -306,3 +439,26 @@ where
         });
     }
 }
+
+impl<T: Config> OnExecutionReceipt<T::BlockNumber, T::AccountId> for Pallet<T>
+where
+    BalanceOf<T>: From<u64>,
+{
+    fn on_receipt_confirmed(primary_number: T::BlockNumber, executor: T::AccountId) {
+        if let Some(amount) = PendingExecutorFees::<T>::take(primary_number) {
+            T::Currency::deposit_creating(&executor, amount);
+            Self::deposit_event(Event::<T>::ExecutorComputeFeesReward {
+                who: executor,
+                block_number: primary_number,
+                amount,
+            });
+        }
+    }
+
+    fn on_receipt_reverted(primary_number: T::BlockNumber) {
+        // The executor of an invalid execution receipt is not rewarded.
+        if let Some(amount) = PendingExecutorFees::<T>::take(primary_number) {
+            Self::escrow_executor_fees(primary_number, amount);
+        }
+    }
+}
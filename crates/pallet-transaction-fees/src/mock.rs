@@ -0,0 +1,120 @@
+use crate::{self as pallet_transaction_fees, Config};
+use frame_support::parameter_types;
+use frame_support::traits::{ConstU128, ConstU16, ConstU32, ConstU64};
+use sp_core::H256;
+use sp_runtime::testing::Header;
+use sp_runtime::traits::{BlakeTwo256, IdentityLookup};
+use std::cell::RefCell;
+use subspace_runtime_primitives::FindBlockRewardAddress;
+
+type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
+type Block = frame_system::mocking::MockBlock<Test>;
+type BlockNumber = u64;
+type Balance = u128;
+
+pub const BLOCK_AUTHOR: u64 = 1;
+pub const EXECUTOR: u64 = 2;
+
+frame_support::construct_runtime!(
+    pub enum Test where
+        Block = Block,
+        NodeBlock = Block,
+        UncheckedExtrinsic = UncheckedExtrinsic,
+    {
+        System: frame_system,
+        Balances: pallet_balances,
+        TransactionFees: pallet_transaction_fees,
+    }
+);
+
+impl frame_system::Config for Test {
+    type BaseCallFilter = frame_support::traits::Everything;
+    type BlockWeights = ();
+    type BlockLength = ();
+    type DbWeight = ();
+    type Origin = Origin;
+    type Call = Call;
+    type Index = u64;
+    type BlockNumber = BlockNumber;
+    type Hash = H256;
+    type Hashing = BlakeTwo256;
+    type AccountId = u64;
+    type Lookup = IdentityLookup<Self::AccountId>;
+    type Header = Header;
+    type Event = Event;
+    type BlockHashCount = ConstU64<250>;
+    type Version = ();
+    type PalletInfo = PalletInfo;
+    type AccountData = pallet_balances::AccountData<Balance>;
+    type OnNewAccount = ();
+    type OnKilledAccount = ();
+    type SystemWeightInfo = ();
+    type SS58Prefix = ConstU16<42>;
+    type OnSetCode = ();
+    type MaxConsumers = ConstU32<16>;
+}
+
+impl pallet_balances::Config for Test {
+    type MaxLocks = ();
+    type MaxReserves = ();
+    type ReserveIdentifier = [u8; 8];
+    type Balance = Balance;
+    type DustRemoval = ();
+    type Event = Event;
+    type ExistentialDeposit = ConstU128<1>;
+    type AccountStore = System;
+    type WeightInfo = ();
+}
+
+thread_local! {
+    pub static BLOCK_AUTHOR_EQUIVOCATED: RefCell<bool> = RefCell::new(false);
+}
+
+/// Marks the block author of the following blocks as equivocated or not
+pub fn set_block_author_equivocated(equivocated: bool) {
+    BLOCK_AUTHOR_EQUIVOCATED.with(|f| *f.borrow_mut() = equivocated);
+}
+
+pub struct MockFindBlockRewardAddress;
+
+impl FindBlockRewardAddress<u64> for MockFindBlockRewardAddress {
+    fn find_block_reward_address() -> Option<u64> {
+        if BLOCK_AUTHOR_EQUIVOCATED.with(|f| *f.borrow()) {
+            None
+        } else {
+            Some(BLOCK_AUTHOR)
+        }
+    }
+}
+
+parameter_types! {
+    pub const StorageFeesEscrowBlockReward: (u64, u64) = (1, 10);
+    pub const StorageFeesEscrowBlockTax: (u64, u64) = (1, 10);
+    pub const ExecutorComputeFeesShare: (u64, u64) = (1, 2);
+}
+
+impl Config for Test {
+    type Event = Event;
+    type MinReplicationFactor = ConstU16<1>;
+    type StorageFeesEscrowBlockReward = StorageFeesEscrowBlockReward;
+    type StorageFeesEscrowBlockTax = StorageFeesEscrowBlockTax;
+    type ExecutorComputeFeesShare = ExecutorComputeFeesShare;
+    type ExecutorFeesTimeout = ConstU64<5>;
+    type MaxExecutorFeesBlocks = ConstU32<3>;
+    type CreditSupply = ConstU128<1_000_000>;
+    type TotalSpacePledged = ConstU128<1_000_000>;
+    type BlockchainHistorySize = ConstU128<1_000>;
+    type Currency = Balances;
+    type FindBlockRewardAddress = MockFindBlockRewardAddress;
+    type WeightInfo = ();
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    let t = frame_system::GenesisConfig::default()
+        .build_storage::<Test>()
+        .unwrap();
+
+    let mut ext = sp_io::TestExternalities::new(t);
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
@@ -0,0 +1,185 @@
+use crate::mock::{
+    new_test_ext, set_block_author_equivocated, Balances, Event, System, Test, TransactionFees,
+    BLOCK_AUTHOR, EXECUTOR,
+};
+use crate::{NextExecutorFeesBlock, PendingExecutorFees};
+use frame_support::traits::{Currency, Hooks};
+use subspace_runtime_primitives::OnExecutionReceipt;
+
+type BlockNumber = u64;
+type Balance = u128;
+
+fn run_block(block_number: BlockNumber, storage_fee: Balance, compute_fee: Balance, tip: Balance) {
+    System::set_block_number(block_number);
+    TransactionFees::on_initialize(block_number);
+    TransactionFees::note_transaction_fees(storage_fee, compute_fee, tip);
+    TransactionFees::on_finalize(block_number);
+}
+
+/// Everything collected is either issued, in storage fees escrow or waiting for executors
+fn assert_conserved(total_collected: Balance) {
+    let pending_executor_fees = PendingExecutorFees::<Test>::iter_values().sum::<Balance>();
+    assert_eq!(
+        Balances::total_issuance() + TransactionFees::storage_fees_escrow() + pending_executor_fees,
+        total_collected
+    );
+}
+
+#[test]
+fn fees_are_split_between_farmer_and_executor() {
+    new_test_ext().execute_with(|| {
+        run_block(1, 1000, 1000, 100);
+
+        // 90% of storage fees, 50% of compute fees and all tips
+        assert_eq!(Balances::free_balance(BLOCK_AUTHOR), 900 + 500 + 100);
+        assert_eq!(TransactionFees::storage_fees_escrow(), 100);
+        assert_eq!(TransactionFees::pending_executor_fees(1), Some(500));
+        assert_eq!(Balances::free_balance(EXECUTOR), 0);
+        assert_conserved(2100);
+
+        // Execution receipt of block 1 is not confirmed yet
+        run_block(2, 0, 0, 0);
+        assert_eq!(Balances::free_balance(BLOCK_AUTHOR), 1500 + 10);
+        assert_eq!(TransactionFees::storage_fees_escrow(), 90);
+        assert_eq!(TransactionFees::pending_executor_fees(1), Some(500));
+        assert_eq!(Balances::free_balance(EXECUTOR), 0);
+        assert_conserved(2100);
+
+        TransactionFees::on_receipt_confirmed(1, EXECUTOR);
+        run_block(3, 0, 0, 0);
+        assert_eq!(Balances::free_balance(BLOCK_AUTHOR), 1510 + 9);
+        assert_eq!(TransactionFees::storage_fees_escrow(), 81);
+        assert_eq!(TransactionFees::pending_executor_fees(1), None);
+        assert_eq!(Balances::free_balance(EXECUTOR), 500);
+        assert_conserved(2100);
+        System::assert_has_event(Event::TransactionFees(
+            crate::Event::ExecutorComputeFeesReward {
+                who: EXECUTOR,
+                block_number: 1,
+                amount: 500,
+            },
+        ));
+    });
+}
+
+#[test]
+fn executor_fees_go_to_escrow_on_revert() {
+    new_test_ext().execute_with(|| {
+        run_block(1, 0, 100, 0);
+        run_block(2, 0, 200, 0);
+        assert_conserved(300);
+
+        // Execution receipt of block 1 is reverted by a fraud proof
+        TransactionFees::on_receipt_reverted(1);
+        assert_eq!(TransactionFees::pending_executor_fees(1), None);
+        assert_eq!(TransactionFees::storage_fees_escrow(), 50);
+        assert_conserved(300);
+        System::assert_has_event(Event::TransactionFees(
+            crate::Event::StorageFeesEscrowChange {
+                before: 0,
+                after: 50,
+            },
+        ));
+        System::assert_has_event(Event::TransactionFees(
+            crate::Event::ExecutorComputeFeesEscrowed {
+                block_number: 1,
+                amount: 50,
+            },
+        ));
+
+        // Confirming the replacing receipt doesn't issue the fees again
+        TransactionFees::on_receipt_confirmed(1, EXECUTOR);
+        assert_eq!(Balances::free_balance(EXECUTOR), 0);
+
+        // Other blocks are not affected
+        TransactionFees::on_receipt_confirmed(2, EXECUTOR);
+        assert_eq!(Balances::free_balance(EXECUTOR), 100);
+        assert_eq!(PendingExecutorFees::<Test>::iter().count(), 0);
+        assert_conserved(300);
+    });
+}
+
+#[test]
+fn executor_fees_go_to_escrow_after_timeout() {
+    new_test_ext().execute_with(|| {
+        run_block(1, 0, 100, 0);
+        assert_eq!(TransactionFees::pending_executor_fees(1), Some(50));
+
+        // Execution receipt of block 1 is not confirmed until the timeout
+        for block_number in 2..=5 {
+            run_block(block_number, 0, 0, 0);
+            assert_eq!(TransactionFees::pending_executor_fees(1), Some(50));
+        }
+
+        run_block(6, 0, 0, 0);
+        assert_eq!(TransactionFees::pending_executor_fees(1), None);
+        assert_eq!(Balances::free_balance(EXECUTOR), 0);
+        assert_conserved(100);
+        System::assert_has_event(Event::TransactionFees(
+            crate::Event::StorageFeesEscrowChange {
+                before: 0,
+                after: 50,
+            },
+        ));
+        System::assert_has_event(Event::TransactionFees(
+            crate::Event::ExecutorComputeFeesEscrowed {
+                block_number: 1,
+                amount: 50,
+            },
+        ));
+
+        // Confirming the receipt afterwards doesn't issue the fees again
+        TransactionFees::on_receipt_confirmed(1, EXECUTOR);
+        run_block(7, 0, 0, 0);
+        assert_eq!(Balances::free_balance(EXECUTOR), 0);
+        assert_conserved(100);
+    });
+}
+
+#[test]
+fn executor_fees_timeout_is_bounded_per_block() {
+    new_test_ext().execute_with(|| {
+        for block_number in 1..=5 {
+            run_block(block_number, 0, 100, 0);
+        }
+        assert_conserved(500);
+
+        // At most 3 blocks are looked at in one block
+        run_block(11, 0, 0, 0);
+        assert_eq!(PendingExecutorFees::<Test>::iter().count(), 2);
+        assert_conserved(500);
+
+        run_block(12, 0, 0, 0);
+        assert_eq!(PendingExecutorFees::<Test>::iter().count(), 0);
+        assert_conserved(500);
+    });
+}
+
+#[test]
+fn executor_fees_timeout_starts_at_first_block() {
+    new_test_ext().execute_with(|| {
+        // Upgraded chain that never issued executor fees before
+        assert_eq!(NextExecutorFeesBlock::<Test>::get(), None);
+        run_block(1_000_000, 0, 0, 0);
+        assert_eq!(NextExecutorFeesBlock::<Test>::get(), Some(1_000_000));
+    });
+}
+
+#[test]
+fn all_fees_go_to_escrow_on_equivocation() {
+    new_test_ext().execute_with(|| {
+        set_block_author_equivocated(true);
+        run_block(1, 1000, 1000, 100);
+
+        assert_eq!(Balances::free_balance(BLOCK_AUTHOR), 0);
+        assert_eq!(TransactionFees::storage_fees_escrow(), 2100);
+        assert_eq!(TransactionFees::pending_executor_fees(1), None);
+        assert_conserved(2100);
+
+        set_block_author_equivocated(false);
+        run_block(2, 0, 0, 0);
+        assert_eq!(Balances::free_balance(BLOCK_AUTHOR), 210);
+        assert_eq!(TransactionFees::storage_fees_escrow(), 1890);
+        assert_conserved(2100);
+    });
+}
@@ -47,7 +47,8 @@ use std::time::Duration;
 use subspace_archiving::archiver::ArchivedSegment;
 use subspace_core_primitives::Solution;
 use subspace_rpc_primitives::{
-    FarmerMetadata, RewardSignatureResponse, RewardSigningInfo, SlotInfo, SolutionResponse,
+    EraSummary, FarmerMetadata, RewardSignatureResponse, RewardSigningInfo, SlotInfo,
+    SolutionRangeAdjustment, SolutionRangeAnalytics, SolutionResponse,
 };
 
 const SOLUTION_TIMEOUT: Duration = Duration::from_secs(2);
@@ -60,6 +61,10 @@ pub trait SubspaceRpcApi {
     #[method(name = "subspace_getFarmerMetadata")]
     fn get_farmer_metadata(&self) -> RpcResult<FarmerMetadata>;
 
+    /// Get history of solution range adjustments and estimate of total space pledged
+    #[method(name = "subspace_getSolutionRangeAnalytics")]
+    fn get_solution_range_analytics(&self) -> RpcResult<SolutionRangeAnalytics>;
+
     #[method(name = "subspace_submitSolutionResponse")]
     fn submit_solution_response(&self, solution_response: SolutionResponse) -> RpcResult<()>;
 
@@ -198,6 +203,51 @@ where
         })
     }
 
+    fn get_solution_range_analytics(&self) -> RpcResult<SolutionRangeAnalytics> {
+        let best_block_id = BlockId::Hash(self.client.info().best_hash);
+        let runtime_api = self.client.runtime_api();
+
+        let solution_range_analytics: Result<SolutionRangeAnalytics, ApiError> = try {
+            let solution_ranges = runtime_api.solution_ranges(&best_block_id)?;
+
+            SolutionRangeAnalytics {
+                era_history: runtime_api
+                    .era_history(&best_block_id)?
+                    .into_iter()
+                    .map(|era_summary| EraSummary {
+                        start_slot: era_summary.start_slot.into(),
+                        end_slot: era_summary.end_slot.into(),
+                        blocks: era_summary.blocks,
+                        votes: era_summary.votes,
+                        previous_solution_range: era_summary.previous_solution_range,
+                        next_solution_range: era_summary.next_solution_range,
+                        previous_voting_solution_range: era_summary.previous_voting_solution_range,
+                        next_voting_solution_range: era_summary.next_voting_solution_range,
+                        adjustment: match era_summary.adjustment {
+                            sp_consensus_subspace::SolutionRangeAdjustment::Disabled => {
+                                SolutionRangeAdjustment::Disabled
+                            }
+                            sp_consensus_subspace::SolutionRangeAdjustment::Override => {
+                                SolutionRangeAdjustment::Override
+                            }
+                            sp_consensus_subspace::SolutionRangeAdjustment::Computed => {
+                                SolutionRangeAdjustment::Computed
+                            }
+                        },
+                    })
+                    .collect(),
+                solution_range: solution_ranges.current,
+                voting_solution_range: solution_ranges.voting_current,
+                total_space_pledged: runtime_api.total_space_pledged(&best_block_id)?,
+            }
+        };
+
+        solution_range_analytics.map_err(|error| {
+            error!("Failed to get data from runtime API: {}", error);
+            JsonRpseeError::Custom("Internal error".to_string())
+        })
+    }
+
     fn submit_solution_response(&self, solution_response: SolutionResponse) -> RpcResult<()> {
         let solution_response_senders = self.solution_response_senders.clone();
 
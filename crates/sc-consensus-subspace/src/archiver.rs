@@ -14,7 +14,10 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{ArchivedSegmentNotification, SubspaceLink, SubspaceNotificationSender};
+use crate::{
+    ArchivedSegmentNotification, ImportedBlockNotification, SubspaceLink,
+    SubspaceNotificationSender,
+};
 use codec::Encode;
 use futures::{future, SinkExt, StreamExt};
 use log::{debug, error, info};
@@ -25,7 +28,9 @@ use sp_blockchain::HeaderBackend;
 use sp_consensus_subspace::{FarmerPublicKey, SubspaceApi};
 use sp_objects::ObjectsApi;
 use sp_runtime::generic::BlockId;
-use sp_runtime::traits::{Block as BlockT, CheckedSub, Header, One, Saturating, Zero};
+use sp_runtime::traits::{
+    AtLeast32BitUnsigned, Block as BlockT, CheckedSub, Header, One, Saturating, Zero,
+};
 use std::sync::Arc;
 use std::time::Duration;
 use subspace_archiving::archiver::{ArchivedSegment, Archiver};
@@ -75,6 +80,49 @@ where
     }
 }
 
+/// Confirmation depth K at the best block, K is a consensus parameter that can change at era
+/// boundary, so it must be read from the runtime rather than cached.
+pub(crate) fn best_confirmation_depth_k<Block, Client>(
+    client: &Client,
+) -> Result<BlockNumber, String>
+where
+    Block: BlockT,
+    Client: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    Client::Api: SubspaceApi<Block, FarmerPublicKey>,
+{
+    let confirmation_depth_k = client
+        .runtime_api()
+        .confirmation_depth_k(&BlockId::Hash(client.info().best_hash))
+        .map_err(|error| {
+            format!("Failed to get `confirmation_depth_k` from runtime API: {error}")
+        })?;
+
+    TryInto::<BlockNumber>::try_into(confirmation_depth_k)
+        .map_err(|_| "Confirmation depth K can't be converted into BlockNumber".to_string())
+}
+
+/// Range of blocks (inclusive) that need to be archived once `block_number` is imported, given
+/// last archived block and confirmation depth K at that point.
+///
+/// Normally exactly one block is archived, but when confirmation depth K decreases there are
+/// several blocks to catch up with, and none at all until K blocks are imported when it increases.
+pub(crate) fn blocks_to_archive<Number>(
+    last_archived_block_number: Option<Number>,
+    block_number: Number,
+    confirmation_depth_k: Number,
+) -> Option<(Number, Number)>
+where
+    Number: AtLeast32BitUnsigned + Copy,
+{
+    let block_to_archive = block_number.checked_sub(&confirmation_depth_k)?;
+
+    match last_archived_block_number {
+        Some(last_archived_block_number) => (last_archived_block_number < block_to_archive)
+            .then(|| (last_archived_block_number + One::one(), block_to_archive)),
+        None => Some((block_to_archive, block_to_archive)),
+    }
+}
+
 /// Start an archiver that will listen for imported blocks and archive blocks at `K` depth,
 /// producing pieces and root blocks (root blocks are then added back to the blockchain as
 /// `store_root_block` extrinsic).
@@ -95,15 +143,9 @@ pub fn start_subspace_archiver<Block, Client>(
 {
     let best_block_id = BlockId::Hash(client.info().best_hash);
 
-    let confirmation_depth_k = TryInto::<BlockNumber>::try_into(
-        client
-            .runtime_api()
-            .confirmation_depth_k(&best_block_id)
-            .expect("Failed to get `confirmation_depth_k` from runtime API"),
-    )
-    .unwrap_or_else(|_| {
-        panic!("Confirmation depth K can't be converted into BlockNumber");
-    });
+    let confirmation_depth_k =
+        best_confirmation_depth_k(client.as_ref()).unwrap_or_else(|error| panic!("{}", error));
+    subspace_link.ensure_root_blocks_capacity(confirmation_depth_k);
     let record_size = client
         .runtime_api()
         .record_size(&best_block_id)
@@ -248,6 +290,7 @@ pub fn start_subspace_archiver<Block, Client>(
                 subspace_link.imported_block_notification_stream.subscribe();
             let archived_segment_notification_sender =
                 subspace_link.archived_segment_notification_sender.clone();
+            let subspace_link = subspace_link.clone();
 
             async move {
                 // Farmers may have not received all previous segments, send them now.
@@ -265,68 +308,92 @@ pub fn start_subspace_archiver<Block, Client>(
 
                 let mut last_archived_block_number =
                     archiver.last_archived_block_number().map(Into::into);
+                let mut confirmation_depth_k = confirmation_depth_k;
 
-                while let Some((block_number, mut root_block_sender)) =
-                    imported_block_notification_stream.next().await
+                while let Some(ImportedBlockNotification {
+                    block_number,
+                    mut root_block_sender,
+                    ..
+                }) = imported_block_notification_stream.next().await
                 {
-                    let block_to_archive =
-                        match block_number.checked_sub(&confirmation_depth_k.into()) {
-                            Some(block_to_archive) => block_to_archive,
-                            None => {
-                                continue;
+                    match best_confirmation_depth_k(client.as_ref()) {
+                        Ok(new_confirmation_depth_k) => {
+                            if new_confirmation_depth_k != confirmation_depth_k {
+                                info!(
+                                    target: "subspace",
+                                    "Confirmation depth K changed from {} to {} at block {}",
+                                    confirmation_depth_k,
+                                    new_confirmation_depth_k,
+                                    block_number,
+                                );
+                                confirmation_depth_k = new_confirmation_depth_k;
+                                subspace_link.ensure_root_blocks_capacity(confirmation_depth_k);
                             }
-                        };
-
-                    if let Some(last_archived_block) = &mut last_archived_block_number {
-                        if *last_archived_block >= block_to_archive {
-                            // This block was already archived, skip
-                            continue;
                         }
-
-                        *last_archived_block = block_to_archive;
-                    } else {
-                        last_archived_block_number.replace(block_to_archive);
+                        Err(error) => {
+                            // Previous value is used until runtime can be queried again
+                            error!(target: "subspace", "{}", error);
+                        }
                     }
 
-                    debug!(target: "subspace", "Archiving block {:?}", block_to_archive);
-
-                    let block = client
-                        .block(&BlockId::Number(block_to_archive))
-                        .expect("Older block by number must always exist")
-                        .expect("Older block by number must always exist");
-
-                    let block_object_mappings = client
-                        .runtime_api()
-                        .validated_object_call_hashes(&BlockId::Number(block_to_archive))
-                        .and_then(|calls| {
-                            client.runtime_api().extract_block_object_mapping(
-                                &BlockId::Number(block_to_archive.saturating_sub(One::one())),
-                                block.block.clone(),
-                                calls,
-                            )
-                        })
-                        .unwrap_or_default();
-
-                    let encoded_block = block.encode();
-                    debug!(
-                        target: "subspace",
-                        "Encoded block {} has size of {:.2} kiB",
-                        block_to_archive,
-                        encoded_block.len() as f32 / 1024.0
-                    );
-                    for archived_segment in archiver.add_block(encoded_block, block_object_mappings)
-                    {
-                        let root_block = archived_segment.root_block;
-
-                        if is_authoring_blocks {
-                            send_archived_segment_notification(
-                                &archived_segment_notification_sender,
-                                archived_segment,
-                            )
-                            .await;
+                    let (blocks_to_archive_from, block_to_archive) = match blocks_to_archive(
+                        last_archived_block_number,
+                        block_number,
+                        confirmation_depth_k.into(),
+                    ) {
+                        Some(blocks_to_archive) => blocks_to_archive,
+                        None => {
+                            continue;
                         }
+                    };
+                    last_archived_block_number.replace(block_to_archive);
+
+                    let mut block_to_archive_next = blocks_to_archive_from;
+                    while block_to_archive_next <= block_to_archive {
+                        let block_to_archive = block_to_archive_next;
+                        block_to_archive_next += One::one();
+
+                        debug!(target: "subspace", "Archiving block {:?}", block_to_archive);
+
+                        let block = client
+                            .block(&BlockId::Number(block_to_archive))
+                            .expect("Older block by number must always exist")
+                            .expect("Older block by number must always exist");
+
+                        let block_object_mappings = client
+                            .runtime_api()
+                            .validated_object_call_hashes(&BlockId::Number(block_to_archive))
+                            .and_then(|calls| {
+                                client.runtime_api().extract_block_object_mapping(
+                                    &BlockId::Number(block_to_archive.saturating_sub(One::one())),
+                                    block.block.clone(),
+                                    calls,
+                                )
+                            })
+                            .unwrap_or_default();
+
+                        let encoded_block = block.encode();
+                        debug!(
+                            target: "subspace",
+                            "Encoded block {} has size of {:.2} kiB",
+                            block_to_archive,
+                            encoded_block.len() as f32 / 1024.0
+                        );
+                        for archived_segment in
+                            archiver.add_block(encoded_block, block_object_mappings)
+                        {
+                            let root_block = archived_segment.root_block;
+
+                            if is_authoring_blocks {
+                                send_archived_segment_notification(
+                                    &archived_segment_notification_sender,
+                                    archived_segment,
+                                )
+                                .await;
+                            }
 
-                        let _ = root_block_sender.send(root_block).await;
+                            let _ = root_block_sender.send(root_block).await;
+                        }
                     }
                 }
             }
@@ -131,6 +131,20 @@ pub struct ArchivedSegmentNotification {
     pub acknowledgement_sender: TracingUnboundedSender<()>,
 }
 
+/// Notification with number and hash of the imported block and sender for root blocks that must
+/// appear in the next block.
+#[derive(Debug, Clone)]
+pub struct ImportedBlockNotification<Block: BlockT> {
+    /// Number of the imported block.
+    pub block_number: NumberFor<Block>,
+    /// Hash of the imported block.
+    pub block_hash: Block::Hash,
+    /// Sender for root blocks produced as the result of archiving of the imported block.
+    ///
+    /// This must be dropped or else block import pipeline will get stuck.
+    pub root_block_sender: mpsc::Sender<RootBlock>,
+}
+
 /// Errors encountered by the Subspace authorship task.
 #[derive(Debug, thiserror::Error)]
 pub enum Error<Header: HeaderT> {
@@ -563,7 +577,7 @@ pub struct SubspaceLink<Block: BlockT> {
     archived_segment_notification_sender: SubspaceNotificationSender<ArchivedSegmentNotification>,
     archived_segment_notification_stream: SubspaceNotificationStream<ArchivedSegmentNotification>,
     imported_block_notification_stream:
-        SubspaceNotificationStream<(NumberFor<Block>, mpsc::Sender<RootBlock>)>,
+        SubspaceNotificationStream<ImportedBlockNotification<Block>>,
     /// Root blocks that are expected to appear in the corresponding blocks, used for block
     /// validation
     root_blocks: Arc<Mutex<LruCache<NumberFor<Block>, Vec<RootBlock>>>>,
@@ -598,7 +612,7 @@ impl<Block: BlockT> SubspaceLink<Block> {
     /// Get stream with notifications about each imported block.
     pub fn imported_block_notification_stream(
         &self,
-    ) -> SubspaceNotificationStream<(NumberFor<Block>, mpsc::Sender<RootBlock>)> {
+    ) -> SubspaceNotificationStream<ImportedBlockNotification<Block>> {
         self.imported_block_notification_stream.clone()
     }
 
@@ -610,6 +624,15 @@ impl<Block: BlockT> SubspaceLink<Block> {
             .cloned()
             .unwrap_or_default()
     }
+
+    /// Grow cache of expected root blocks such that it covers confirmation depth K. Cache is never
+    /// shrunk, such that root blocks expected under previous larger K are not evicted.
+    pub(crate) fn ensure_root_blocks_capacity(&self, confirmation_depth_k: BlockNumber) {
+        let mut root_blocks = self.root_blocks.lock();
+        if root_blocks.cap() < confirmation_depth_k as usize {
+            root_blocks.resize(confirmation_depth_k as usize);
+        }
+    }
 }
 
 /// A verifier for Subspace blocks.
@@ -847,7 +870,7 @@ pub struct SubspaceBlockImport<Block: BlockT, Client, I, CAW, CIDP> {
     inner: I,
     client: Arc<Client>,
     imported_block_notification_sender:
-        SubspaceNotificationSender<(NumberFor<Block>, mpsc::Sender<RootBlock>)>,
+        SubspaceNotificationSender<ImportedBlockNotification<Block>>,
     subspace_link: SubspaceLink<Block>,
     can_author_with: CAW,
     create_inherent_data_providers: CIDP,
@@ -883,10 +906,9 @@ where
     fn new(
         client: Arc<Client>,
         block_import: I,
-        imported_block_notification_sender: SubspaceNotificationSender<(
-            NumberFor<Block>,
-            mpsc::Sender<RootBlock>,
-        )>,
+        imported_block_notification_sender: SubspaceNotificationSender<
+            ImportedBlockNotification<Block>,
+        >,
         subspace_link: SubspaceLink<Block>,
         can_author_with: CAW,
         create_inherent_data_providers: CIDP,
@@ -1197,7 +1219,11 @@ where
         let (root_block_sender, root_block_receiver) = mpsc::channel(0);
 
         self.imported_block_notification_sender
-            .notify(move || (block_number, root_block_sender));
+            .notify(move || ImportedBlockNotification {
+                block_number,
+                block_hash,
+                root_block_sender,
+            });
 
         let root_blocks: Vec<RootBlock> = root_block_receiver.collect().await;
 
@@ -1253,17 +1279,9 @@ where
     let (imported_block_notification_sender, imported_block_notification_stream) =
         notification::channel("subspace_imported_block_notification_stream");
 
-    let best_block_id = BlockId::Hash(client.info().best_hash);
-
-    let confirmation_depth_k = TryInto::<BlockNumber>::try_into(
-        client
-            .runtime_api()
-            .confirmation_depth_k(&best_block_id)
-            .expect("Failed to get `confirmation_depth_k` from runtime API"),
-    )
-    .unwrap_or_else(|_| {
-        panic!("Confirmation depth K can't be converted into BlockNumber");
-    });
+    // Cache is resized by archiver whenever confirmation depth K changes
+    let confirmation_depth_k = archiver::best_confirmation_depth_k(client.as_ref())
+        .unwrap_or_else(|error| panic!("{}", error));
 
     let link = SubspaceLink {
         config,
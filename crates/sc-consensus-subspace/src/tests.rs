@@ -17,6 +17,7 @@
 
 //! PoC testsuite
 
+use crate::archiver::blocks_to_archive;
 use crate::{
     find_pre_digest, start_subspace, Config, NewSlotNotification, SubspaceLink, SubspaceParams,
     SubspaceVerifier,
@@ -857,6 +858,52 @@ fn verify_slots_are_strictly_increasing() {
     );
 }
 
+#[test]
+fn archiving_follows_confirmation_depth_k_changes_at_era_boundaries() {
+    // K decreases from 3 to 1 at the era starting with block 10 and increases to 4 at the era
+    // starting with block 20
+    let confirmation_depth_k_at = |block_number: u32| match block_number {
+        0..=9 => 3,
+        10..=19 => 1,
+        _ => 4,
+    };
+
+    // Genesis block is archived on archiver start
+    let mut last_archived_block_number = Some(0);
+    let mut archived_blocks = Vec::new();
+    for block_number in 1..=30 {
+        let blocks_to_archive = blocks_to_archive(
+            last_archived_block_number,
+            block_number,
+            confirmation_depth_k_at(block_number),
+        );
+
+        match block_number {
+            // Not deep enough yet
+            1..=3 => assert_eq!(blocks_to_archive, None),
+            // Catching up once K decreases
+            10 => assert_eq!(blocks_to_archive, Some((7, 9))),
+            // Waiting for K blocks to be imported once K increases
+            20..=22 => assert_eq!(blocks_to_archive, None),
+            _ => assert_eq!(
+                blocks_to_archive,
+                Some((
+                    block_number - confirmation_depth_k_at(block_number),
+                    block_number - confirmation_depth_k_at(block_number)
+                ))
+            ),
+        }
+
+        if let Some((blocks_to_archive_from, block_to_archive)) = blocks_to_archive {
+            archived_blocks.extend(blocks_to_archive_from..=block_to_archive);
+            last_archived_block_number.replace(block_to_archive);
+        }
+    }
+
+    // Every block is archived exactly once and in order regardless of K changes
+    assert_eq!(archived_blocks, (1..=26).collect::<Vec<_>>());
+}
+
 // TODO: Runtime at the moment doesn't implement transactions support, so root block extrinsic
 //  verification fails in tests (`submit_test_store_root_block()` doesn't submit extrinsic as such).
 // // Check that block import results in archiving working.
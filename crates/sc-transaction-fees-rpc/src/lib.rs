@@ -0,0 +1,117 @@
+// Copyright (C) 2022 Subspace Labs, Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! RPC api for quoting storage and compute fees of transactions.
+
+#![warn(missing_docs)]
+
+#[cfg(test)]
+mod tests;
+
+use jsonrpsee::core::{Error as JsonRpseeError, RpcResult};
+use jsonrpsee::proc_macros::rpc;
+use parity_scale_codec::{Codec, Decode};
+use serde::Serialize;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::generic::BlockId;
+use sp_runtime::traits::Block as BlockT;
+use sp_transaction_fees::{FeeBreakdown, TransactionFeesApi as TransactionFeesRuntimeApi};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// Provides rpc methods for quoting transaction fees.
+#[rpc(client, server)]
+pub trait TransactionFeesRpcApi<BlockHash, Balance> {
+    /// Quote fees of storing `size` bytes in the history of the blockchain.
+    #[method(name = "transactionFees_storageFeeQuote")]
+    fn storage_fee_quote(
+        &self,
+        size: u32,
+        at: Option<BlockHash>,
+    ) -> RpcResult<FeeBreakdown<Balance>>;
+
+    /// Query fees of the encoded extrinsic with storage and compute fees split.
+    #[method(name = "transactionFees_queryFeeBreakdown")]
+    fn query_fee_breakdown(
+        &self,
+        encoded_xt: Bytes,
+        at: Option<BlockHash>,
+    ) -> RpcResult<FeeBreakdown<Balance>>;
+}
+
+/// Implements the [`TransactionFeesRpcApiServer`] trait for quoting transaction fees.
+pub struct TransactionFeesRpc<Block, Client> {
+    client: Arc<Client>,
+    _phantom: PhantomData<Block>,
+}
+
+impl<Block, Client> TransactionFeesRpc<Block, Client> {
+    /// Creates a new instance of the `TransactionFeesRpc` handler.
+    pub fn new(client: Arc<Client>) -> Self {
+        Self {
+            client,
+            _phantom: PhantomData::default(),
+        }
+    }
+}
+
+impl<Block, Client> TransactionFeesRpc<Block, Client>
+where
+    Block: BlockT,
+    Client: HeaderBackend<Block>,
+{
+    fn block_id(&self, at: Option<Block::Hash>) -> BlockId<Block> {
+        BlockId::Hash(at.unwrap_or_else(|| self.client.info().best_hash))
+    }
+}
+
+impl<Block, Client, Balance> TransactionFeesRpcApiServer<Block::Hash, Balance>
+    for TransactionFeesRpc<Block, Client>
+where
+    Block: BlockT,
+    Client: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+    Client::Api: TransactionFeesRuntimeApi<Block, Balance>,
+    Balance: Codec + Serialize + Send + Sync + 'static,
+{
+    fn storage_fee_quote(
+        &self,
+        size: u32,
+        at: Option<Block::Hash>,
+    ) -> RpcResult<FeeBreakdown<Balance>> {
+        self.client
+            .runtime_api()
+            .storage_fee_quote(&self.block_id(at), size)
+            .map_err(|error| JsonRpseeError::Custom(error.to_string()))
+    }
+
+    fn query_fee_breakdown(
+        &self,
+        encoded_xt: Bytes,
+        at: Option<Block::Hash>,
+    ) -> RpcResult<FeeBreakdown<Balance>> {
+        let encoded_len = encoded_xt.len() as u32;
+        let uxt = Block::Extrinsic::decode(&mut &*encoded_xt).map_err(|error| {
+            JsonRpseeError::Custom(format!("Unable to decode extrinsic: {}", error))
+        })?;
+
+        self.client
+            .runtime_api()
+            .query_fee_breakdown(&self.block_id(at), uxt, encoded_len)
+            .map_err(|error| JsonRpseeError::Custom(error.to_string()))
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{TransactionFeesRpc, TransactionFeesRpcApiServer};
+use jsonrpsee::core::Error as JsonRpseeError;
+use parity_scale_codec::Encode;
+use sp_api::{ApiRef, ProvideRuntimeApi};
+use sp_blockchain::{BlockStatus, HeaderBackend, Info};
+use sp_core::{Bytes, H256};
+use sp_runtime::generic::BlockId;
+use sp_runtime::testing::{Block as RawBlock, ExtrinsicWrapper, Header};
+use sp_runtime::traits::NumberFor;
+use sp_transaction_fees::{FeeBreakdown, TransactionFeesApi};
+use std::sync::Arc;
+
+type Block = RawBlock<ExtrinsicWrapper<u64>>;
+
+const TRANSACTION_BYTE_FEE: u128 = 10;
+
+#[derive(Default)]
+struct TestApi;
+
+struct RuntimeApi;
+
+impl ProvideRuntimeApi<Block> for TestApi {
+    type Api = RuntimeApi;
+
+    fn runtime_api(&self) -> ApiRef<'_, Self::Api> {
+        RuntimeApi.into()
+    }
+}
+
+sp_api::mock_impl_runtime_apis! {
+    impl TransactionFeesApi<Block, u128> for RuntimeApi {
+        fn storage_fee_quote(size: u32) -> FeeBreakdown<u128> {
+            FeeBreakdown {
+                storage_fee: TRANSACTION_BYTE_FEE * u128::from(size),
+                transaction_byte_fee: TRANSACTION_BYTE_FEE,
+                ..FeeBreakdown::default()
+            }
+        }
+
+        fn query_fee_breakdown(uxt: ExtrinsicWrapper<u64>, len: u32) -> FeeBreakdown<u128> {
+            // Extrinsic is the storage fee and its length is the compute fee, so that tests can
+            // check what was passed to the runtime
+            FeeBreakdown {
+                storage_fee: u128::from(uxt.0),
+                compute_fee: u128::from(len),
+                transaction_byte_fee: TRANSACTION_BYTE_FEE,
+                ..FeeBreakdown::default()
+            }
+        }
+    }
+}
+
+impl HeaderBackend<Block> for TestApi {
+    fn header(&self, _id: BlockId<Block>) -> sp_blockchain::Result<Option<Header>> {
+        Ok(None)
+    }
+
+    fn info(&self) -> Info<Block> {
+        Info {
+            best_hash: H256::repeat_byte(1),
+            best_number: 1,
+            genesis_hash: Default::default(),
+            finalized_hash: Default::default(),
+            finalized_number: 0,
+            finalized_state: None,
+            number_leaves: 1,
+            block_gap: None,
+        }
+    }
+
+    fn status(&self, _id: BlockId<Block>) -> sp_blockchain::Result<BlockStatus> {
+        Ok(BlockStatus::Unknown)
+    }
+
+    fn number(&self, _hash: H256) -> sp_blockchain::Result<Option<NumberFor<Block>>> {
+        Ok(None)
+    }
+
+    fn hash(&self, _number: NumberFor<Block>) -> sp_blockchain::Result<Option<H256>> {
+        Ok(None)
+    }
+}
+
+fn rpc() -> impl TransactionFeesRpcApiServer<H256, u128> {
+    TransactionFeesRpc::<Block, _>::new(Arc::new(TestApi))
+}
+
+#[test]
+fn storage_fee_quote_is_forwarded_to_runtime() {
+    let fee_breakdown = rpc().storage_fee_quote(100, None).unwrap();
+
+    assert_eq!(fee_breakdown.storage_fee, 1_000);
+    assert_eq!(fee_breakdown.compute_fee, 0);
+    assert_eq!(fee_breakdown.transaction_byte_fee, TRANSACTION_BYTE_FEE);
+}
+
+#[test]
+fn query_fee_breakdown_decodes_extrinsic() {
+    let encoded_xt = ExtrinsicWrapper::from(42u64).encode();
+    let encoded_len = encoded_xt.len() as u128;
+
+    let fee_breakdown = rpc()
+        .query_fee_breakdown(Bytes(encoded_xt), Some(H256::repeat_byte(2)))
+        .unwrap();
+
+    assert_eq!(fee_breakdown.storage_fee, 42);
+    assert_eq!(fee_breakdown.compute_fee, encoded_len);
+}
+
+#[test]
+fn query_fee_breakdown_rejects_invalid_extrinsic() {
+    assert!(matches!(
+        rpc().query_fee_breakdown(Bytes(vec![1, 2, 3]), None),
+        Err(JsonRpseeError::Custom(_))
+    ));
+}
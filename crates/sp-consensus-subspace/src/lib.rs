@@ -242,6 +242,40 @@ impl Default for SolutionRanges {
     }
 }
 
+/// How solution range for the next era was determined.
+#[derive(Decode, Encode, PartialEq, Eq, Clone, Copy, Debug, TypeInfo)]
+pub enum SolutionRangeAdjustment {
+    /// Solution range adjustment is disabled, solution range stayed the same.
+    Disabled,
+    /// Solution range was overridden by root.
+    Override,
+    /// Solution range was adjusted according to actual and expected number of slots per block.
+    Computed,
+}
+
+/// Summary of a finished era and solution range adjustment at the end of it.
+#[derive(Decode, Encode, PartialEq, Eq, Clone, Copy, Debug, TypeInfo)]
+pub struct EraSummary {
+    /// Slot at which era started.
+    pub start_slot: Slot,
+    /// Slot at which era ended.
+    pub end_slot: Slot,
+    /// Number of blocks produced during era.
+    pub blocks: u64,
+    /// Number of votes included in blocks of era.
+    pub votes: u64,
+    /// Solution range used during era.
+    pub previous_solution_range: u64,
+    /// Solution range for the next era.
+    pub next_solution_range: u64,
+    /// Voting solution range used during era.
+    pub previous_voting_solution_range: u64,
+    /// Voting solution range for the next era.
+    pub next_voting_solution_range: u64,
+    /// How solution range for the next era was determined.
+    pub adjustment: SolutionRangeAdjustment,
+}
+
 /// Derive on-chain randomness from tag signature.
 ///
 /// NOTE: If you are not the signer then you must verify the local challenge before calling this
@@ -273,6 +307,11 @@ pub struct Salts {
 
 sp_api::decl_runtime_apis! {
     /// API necessary for block authorship with Subspace.
+    ///
+    /// Version history:
+    /// - 2: added `remaining_block_list_eons`.
+    /// - 3: added `era_history` and `total_space_pledged`.
+    #[api_version(3)]
     pub trait SubspaceApi<RewardAddress: Encode + Decode> {
         /// Depth `K` after which a block enters the recorded history (a global constant, as opposed
         /// to the client-dependent transaction confirmation depth `k`).
@@ -322,9 +361,21 @@ sp_api::decl_runtime_apis! {
         /// Check if `farmer_public_key` is in block list (due to equivocation)
         fn is_in_block_list(farmer_public_key: &FarmerPublicKey) -> bool;
 
+        /// Number of eons (including current one) until `farmer_public_key` is removed from block
+        /// list, `None` if farmer is not in block list
+        fn remaining_block_list_eons(farmer_public_key: &FarmerPublicKey) -> Option<u64>;
+
         /// Total number of pieces in a blockchain
         fn total_pieces() -> u64;
 
+        /// Summaries of recent eras (oldest first) with solution range adjustments at the end of
+        /// each of them.
+        fn era_history() -> Vec<EraSummary>;
+
+        /// Estimate of total space pledged to the network (in bytes) derived from solution ranges
+        /// of recent eras.
+        fn total_space_pledged() -> u128;
+
         /// Get the merkle tree root of records for specified segment index
         fn records_root(segment_index: u64) -> Option<Sha256Hash>;
 
@@ -27,6 +27,7 @@
 
 use codec::{Decode, Encode};
 use scale_info::TypeInfo;
+use sp_consensus_slots::Slot;
 use sp_std::vec::Vec;
 
 /// The kind of an offence, is a byte string representing some kind identifier
@@ -106,11 +107,13 @@ pub trait OnOffenceHandler<Offender> {
     /// as well. The implementer should cater for a case, where
     /// the same farmers were reported for the same offence
     /// in the past (see `OffenceCount`).
-    fn on_offence(offenders: &[OffenceDetails<Offender>]);
+    ///
+    /// All of the offenders committed the offence at `offence_slot`.
+    fn on_offence(offenders: &[OffenceDetails<Offender>], offence_slot: Slot);
 }
 
 impl<Offender> OnOffenceHandler<Offender> for () {
-    fn on_offence(_offenders: &[OffenceDetails<Offender>]) {}
+    fn on_offence(_offenders: &[OffenceDetails<Offender>], _offence_slot: Slot) {}
 }
 
 /// A details about an offending authority for a particular kind of offence.
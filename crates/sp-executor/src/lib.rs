@@ -19,6 +19,8 @@
 
 use parity_scale_codec::{Decode, Encode};
 use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
 use sp_consensus_slots::Slot;
 use sp_core::crypto::KeyTypeId;
 use sp_core::H256;
@@ -27,7 +29,7 @@ use sp_runtime::OpaqueExtrinsic;
 use sp_runtime_interface::pass_by::PassBy;
 use sp_std::borrow::Cow;
 use sp_std::vec::Vec;
-use sp_trie::StorageProof;
+use sp_trie::CompactProof;
 use subspace_core_primitives::{BlockNumber, Randomness, Sha256Hash};
 use subspace_runtime_primitives::{AccountId, Hash as PHash};
 
@@ -173,6 +175,8 @@ impl<Extrinsic: Encode> From<SignedBundle<Extrinsic>> for SignedOpaqueBundle {
 
 /// Receipt of state execution.
 #[derive(Debug, Decode, Encode, TypeInfo, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
 pub struct ExecutionReceipt<Number, Hash, SecondaryHash> {
     /// Primary block number.
     pub primary_number: Number,
@@ -184,6 +188,9 @@ pub struct ExecutionReceipt<Number, Hash, SecondaryHash> {
     pub trace: Vec<SecondaryHash>,
     /// The merkle root of `trace`.
     pub trace_root: Sha256Hash,
+    /// Root of the ordered trie over the SCALE-encoded records of the extrinsics dropped from the
+    /// secondary block as being invalid.
+    pub invalid_extrinsics_root: SecondaryHash,
 }
 
 impl<Number: Encode, Hash: Encode, SecondaryHash: Encode>
@@ -286,6 +293,8 @@ impl ExecutionPhase {
 /// Error type of fraud proof verification on primary node.
 #[derive(Debug)]
 pub enum VerificationError {
+    /// Failed to reconstruct the storage proof from the compact proof against `pre_state_root`.
+    InvalidCompactProof,
     /// Failed to pass the execution proof check.
     BadProof(sp_std::boxed::Box<dyn sp_state_machine::Error>),
     /// The `post_state_root` calculated by farmer does not match the one declared in [`FraudProof`].
@@ -311,8 +320,8 @@ pub struct FraudProof {
     pub pre_state_root: H256,
     /// State root after the fraudulent transaction.
     pub post_state_root: H256,
-    /// Proof recorded during the computation.
-    pub proof: StorageProof,
+    /// Proof recorded during the computation, in the compact encoding to save the block space.
+    pub proof: CompactProof,
     /// Execution phase.
     pub execution_phase: ExecutionPhase,
 }
@@ -342,23 +351,6 @@ impl BundleEquivocationProof {
     pub fn hash(&self) -> H256 {
         BlakeTwo256::hash_of(self)
     }
-
-    // TODO: remove this later.
-    /// Constructs a dummy bundle equivocation proof.
-    pub fn dummy_at(slot_number: u64) -> Self {
-        let dummy_header = BundleHeader {
-            primary_hash: PHash::default(),
-            slot_number,
-            extrinsics_root: H256::default(),
-        };
-        Self {
-            offender: AccountId::decode(&mut sp_runtime::traits::TrailingZeroInput::zeroes())
-                .expect("Failed to create zero account"),
-            slot: Slot::default(),
-            first_header: dummy_header.clone(),
-            second_header: dummy_header,
-        }
-    }
 }
 
 /// Represents an invalid transaction proof.
@@ -367,6 +359,10 @@ pub struct InvalidTransactionProof;
 
 sp_api::decl_runtime_apis! {
     /// API necessary for executor pallet.
+    ///
+    /// Version history:
+    /// - 2: added `execution_receipt`.
+    #[api_version(2)]
     pub trait ExecutorApi<SecondaryHash: Encode + Decode> {
         /// Submits the execution receipt via an unsigned extrinsic.
         fn submit_execution_receipt_unsigned(
@@ -406,6 +402,12 @@ sp_api::decl_runtime_apis! {
 
         /// Returns the maximum receipt drift.
         fn maximum_receipt_drift() -> NumberFor<Block>;
+
+        /// Returns the verified execution receipt of given primary block number stored in the
+        /// state, `None` if it does not exist or has been pruned.
+        fn execution_receipt(
+            primary_number: NumberFor<Block>,
+        ) -> Option<ExecutionReceipt<NumberFor<Block>, Block::Hash, SecondaryHash>>;
     }
 }
 
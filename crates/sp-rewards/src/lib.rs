@@ -0,0 +1,157 @@
+// Copyright (C) 2022 Subspace Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Primitives of rewards pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![warn(missing_docs)]
+
+#[cfg(test)]
+mod tests;
+
+use parity_scale_codec::{Codec, Decode, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+use sp_runtime::traits::{AtLeast32BitUnsigned, Saturating, UniqueSaturatedInto, Zero};
+use sp_runtime::Perquintill;
+
+/// How rewards decrease over time.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Encode, Decode, TypeInfo, MaxEncodedLen)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub enum RewardDecay<BlockNumber> {
+    /// Rewards stay constant.
+    Constant,
+    /// Rewards are halved every `interval` blocks.
+    Halving {
+        /// Number of blocks between halvings.
+        interval: BlockNumber,
+    },
+    /// Rewards decrease linearly and reach zero after `duration` blocks.
+    Linear {
+        /// Number of blocks until rewards reach zero.
+        duration: BlockNumber,
+    },
+}
+
+/// Issuance curve of block and vote rewards.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Encode, Decode, TypeInfo, MaxEncodedLen)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct RewardSchedule<BlockNumber, Balance> {
+    /// Block number decay is counted from.
+    pub start: BlockNumber,
+    /// Reward for block producer at `start`.
+    pub initial_block_reward: Balance,
+    /// Reward for voter at `start`.
+    pub initial_vote_reward: Balance,
+    /// How rewards decrease over time.
+    pub decay: RewardDecay<BlockNumber>,
+    /// Total issuance after which no more rewards are issued.
+    pub supply_cap: Option<Balance>,
+}
+
+/// Rewards issued in a block.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct BlockRewards<Balance> {
+    /// Reward for block producer.
+    pub block_reward: Balance,
+    /// Reward for each voter.
+    pub vote_reward: Balance,
+}
+
+impl<BlockNumber, Balance> RewardSchedule<BlockNumber, Balance>
+where
+    BlockNumber: AtLeast32BitUnsigned + Copy,
+    Balance: AtLeast32BitUnsigned + Copy,
+{
+    /// Schedule that issues the same rewards forever.
+    pub fn constant(block_reward: Balance, vote_reward: Balance) -> Self {
+        Self {
+            start: Zero::zero(),
+            initial_block_reward: block_reward,
+            initial_vote_reward: vote_reward,
+            decay: RewardDecay::Constant,
+            supply_cap: None,
+        }
+    }
+
+    /// Whether parameters of the schedule make sense.
+    pub fn is_valid(&self) -> bool {
+        match self.decay {
+            RewardDecay::Constant => true,
+            RewardDecay::Halving { interval } => !interval.is_zero(),
+            RewardDecay::Linear { duration } => !duration.is_zero(),
+        }
+    }
+
+    /// Rewards at given block according to the issuance curve, supply cap is not applied.
+    pub fn rewards_at(&self, block_number: BlockNumber) -> BlockRewards<Balance> {
+        BlockRewards {
+            block_reward: self.decayed(self.initial_block_reward, block_number),
+            vote_reward: self.decayed(self.initial_vote_reward, block_number),
+        }
+    }
+
+    /// Limits reward so that total issuance doesn't exceed supply cap.
+    pub fn capped(&self, reward: Balance, total_issuance: Balance) -> Balance {
+        match self.supply_cap {
+            Some(supply_cap) => reward.min(supply_cap.saturating_sub(total_issuance)),
+            None => reward,
+        }
+    }
+
+    fn decayed(&self, reward: Balance, block_number: BlockNumber) -> Balance {
+        let elapsed = block_number.saturating_sub(self.start);
+
+        match self.decay {
+            RewardDecay::Constant => reward,
+            RewardDecay::Halving { interval } => {
+                let halvings = elapsed / interval;
+                let mut reward = reward;
+                let mut halving = BlockNumber::zero();
+                // Reward becomes zero after at most as many halvings as there are bits in it
+                while halving < halvings && !reward.is_zero() {
+                    reward /= 2u32.into();
+                    halving += BlockNumber::one();
+                }
+                reward
+            }
+            RewardDecay::Linear { duration } => {
+                if elapsed >= duration {
+                    return Zero::zero();
+                }
+
+                let remaining: u64 = (duration - elapsed).unique_saturated_into();
+                let duration: u64 = duration.unique_saturated_into();
+                Perquintill::from_rational(remaining, duration).mul_floor(reward)
+            }
+        }
+    }
+}
+
+sp_api::decl_runtime_apis! {
+    /// API for querying rewards.
+    pub trait RewardsApi<BlockNumber: Codec, Balance: Codec> {
+        /// Returns rewards expected at given (possibly future) block according to the current
+        /// reward schedule or the pending one if it will be enacted by then.
+        ///
+        /// Supply cap is applied against current total issuance.
+        fn expected_rewards(block_number: BlockNumber) -> BlockRewards<Balance>;
+    }
+}
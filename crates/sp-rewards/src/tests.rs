@@ -0,0 +1,72 @@
+use crate::{BlockRewards, RewardDecay, RewardSchedule};
+
+fn schedule(decay: RewardDecay<u32>) -> RewardSchedule<u32, u128> {
+    RewardSchedule {
+        start: 10,
+        initial_block_reward: 1000,
+        initial_vote_reward: 100,
+        decay,
+        supply_cap: None,
+    }
+}
+
+#[test]
+fn constant_rewards() {
+    let schedule = schedule(RewardDecay::Constant);
+
+    assert_eq!(
+        schedule.rewards_at(u32::MAX),
+        BlockRewards {
+            block_reward: 1000,
+            vote_reward: 100,
+        }
+    );
+}
+
+#[test]
+fn halving_rewards() {
+    let schedule = schedule(RewardDecay::Halving { interval: 100 });
+
+    // Before start
+    assert_eq!(schedule.rewards_at(0).block_reward, 1000);
+    assert_eq!(schedule.rewards_at(109).block_reward, 1000);
+    assert_eq!(schedule.rewards_at(110).block_reward, 500);
+    assert_eq!(schedule.rewards_at(210).block_reward, 250);
+    assert_eq!(schedule.rewards_at(210).vote_reward, 25);
+    assert_eq!(
+        schedule.rewards_at(u32::MAX),
+        BlockRewards {
+            block_reward: 0,
+            vote_reward: 0,
+        }
+    );
+}
+
+#[test]
+fn linear_rewards() {
+    let schedule = schedule(RewardDecay::Linear { duration: 100 });
+
+    assert_eq!(schedule.rewards_at(10).block_reward, 1000);
+    assert_eq!(schedule.rewards_at(35).block_reward, 750);
+    assert_eq!(schedule.rewards_at(60).vote_reward, 50);
+    assert_eq!(schedule.rewards_at(110).block_reward, 0);
+    assert_eq!(schedule.rewards_at(u32::MAX).block_reward, 0);
+}
+
+#[test]
+fn supply_cap() {
+    let mut schedule = schedule(RewardDecay::Constant);
+    assert_eq!(schedule.capped(1000, u128::MAX), 1000);
+
+    schedule.supply_cap = Some(10_000);
+    assert_eq!(schedule.capped(1000, 5_000), 1000);
+    assert_eq!(schedule.capped(1000, 9_500), 500);
+    assert_eq!(schedule.capped(1000, 20_000), 0);
+}
+
+#[test]
+fn validity() {
+    assert!(schedule(RewardDecay::Constant).is_valid());
+    assert!(!schedule(RewardDecay::Halving { interval: 0 }).is_valid());
+    assert!(!schedule(RewardDecay::Linear { duration: 0 }).is_valid());
+}
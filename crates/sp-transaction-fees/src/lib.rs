@@ -0,0 +1,61 @@
+// Copyright (C) 2022 Subspace Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Primitives of transaction fees pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![warn(missing_docs)]
+
+#[cfg(test)]
+mod tests;
+
+use parity_scale_codec::{Codec, Decode, Encode};
+use scale_info::TypeInfo;
+#[cfg(feature = "std")]
+use serde::{Deserialize, Serialize};
+
+/// Breakdown of transaction fees into storage and compute fees, along with the inputs the
+/// transaction byte fee was derived from.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct FeeBreakdown<Balance> {
+    /// Fee for storing the bytes in the history of the blockchain.
+    pub storage_fee: Balance,
+    /// Fee for everything else (base fee, weight fee and the rest of the length fee).
+    pub compute_fee: Balance,
+    /// Current price of one byte of storage.
+    pub transaction_byte_fee: Balance,
+    /// How many credits there is in circulation.
+    pub credit_supply: Balance,
+    /// How much space there is on the network.
+    pub total_space_pledged: u128,
+    /// How big is the history of the blockchain in archived state.
+    pub blockchain_history_size: u128,
+    /// Minimum desired number of replicas of the blockchain to be stored by the network.
+    pub min_replication_factor: u16,
+}
+
+sp_api::decl_runtime_apis! {
+    /// API for quoting transaction fees with storage and compute fees split.
+    pub trait TransactionFeesApi<Balance: Codec> {
+        /// Returns fees of storing `size` bytes in the history of the blockchain, compute fee is
+        /// always zero.
+        fn storage_fee_quote(size: u32) -> FeeBreakdown<Balance>;
+
+        /// Returns fees of the extrinsic of encoded length `len`, tip is not included.
+        fn query_fee_breakdown(uxt: Block::Extrinsic, len: u32) -> FeeBreakdown<Balance>;
+    }
+}
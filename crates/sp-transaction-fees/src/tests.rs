@@ -0,0 +1,47 @@
+use crate::FeeBreakdown;
+use parity_scale_codec::{Decode, Encode};
+
+fn fee_breakdown() -> FeeBreakdown<u128> {
+    FeeBreakdown {
+        storage_fee: 1_000,
+        compute_fee: 234,
+        transaction_byte_fee: 10,
+        credit_supply: 1_000_000,
+        total_space_pledged: 100_000,
+        blockchain_history_size: 10_000,
+        min_replication_factor: 1,
+    }
+}
+
+#[test]
+fn fee_breakdown_serializes_in_camel_case() {
+    let fee_breakdown = fee_breakdown();
+
+    let json = serde_json::to_value(&fee_breakdown).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "storageFee": 1_000,
+            "computeFee": 234,
+            "transactionByteFee": 10,
+            "creditSupply": 1_000_000,
+            "totalSpacePledged": 100_000,
+            "blockchainHistorySize": 10_000,
+            "minReplicationFactor": 1,
+        })
+    );
+    assert_eq!(
+        serde_json::from_value::<FeeBreakdown<u128>>(json).unwrap(),
+        fee_breakdown
+    );
+}
+
+#[test]
+fn fee_breakdown_encoding_round_trip() {
+    let fee_breakdown = fee_breakdown();
+
+    assert_eq!(
+        FeeBreakdown::<u128>::decode(&mut fee_breakdown.encode().as_slice()).unwrap(),
+        fee_breakdown
+    );
+}
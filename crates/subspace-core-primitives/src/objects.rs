@@ -19,10 +19,13 @@
 //! * for objects within a block
 //! * for objects within a piece
 //! * for global objects in the global history of the blockchain
+//!
+//! Objects that are too large to fit into a single block are uploaded in chunks, followed by an
+//! [`ObjectManifest`] object, whose hash is the content root of the whole object.
 
 #[cfg(not(feature = "std"))]
 extern crate alloc;
-use crate::Sha256Hash;
+use crate::{crypto, Sha256Hash};
 #[cfg(not(feature = "std"))]
 use alloc::vec::Vec;
 use parity_scale_codec::{Decode, Encode};
@@ -149,3 +152,64 @@ impl GlobalObject {
         }
     }
 }
+
+/// Prefix of the objects that are encoded [`ObjectManifest`]s.
+pub const OBJECT_MANIFEST_PREFIX: [u8; 4] = *b"SOMF";
+
+/// Manifest of an object uploaded in chunks, stored as an object itself.
+///
+/// The hash of the encoded manifest object is the content root of the whole object, which can be
+/// reassembled by concatenating its chunks.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct ObjectManifest {
+    /// Size of the whole object in bytes
+    pub size: u64,
+    /// Hashes of the chunks in the order they make up the object
+    pub chunks: Vec<Sha256Hash>,
+}
+
+impl ObjectManifest {
+    /// Creates manifest of the object made up of given chunks.
+    pub fn from_chunks<'a, I>(chunks: I) -> Self
+    where
+        I: IntoIterator<Item = &'a [u8]>,
+    {
+        let mut size = 0;
+        let chunks = chunks
+            .into_iter()
+            .map(|chunk| {
+                size += chunk.len() as u64;
+                crypto::sha256_hash(chunk)
+            })
+            .collect();
+
+        Self { size, chunks }
+    }
+
+    /// Encodes manifest as an object.
+    pub fn to_object(&self) -> Vec<u8> {
+        let mut object = OBJECT_MANIFEST_PREFIX.to_vec();
+        self.encode_to(&mut object);
+        object
+    }
+
+    /// Decodes manifest from an object, returns `None` if object is not a manifest.
+    pub fn from_object(object: &[u8]) -> Option<Self> {
+        let mut encoded_manifest = object.strip_prefix(&OBJECT_MANIFEST_PREFIX)?;
+        let manifest = Self::decode(&mut encoded_manifest).ok()?;
+        // Trailing bytes mean this is not a manifest, but some object that happens to start with
+        // the same bytes
+        if encoded_manifest.is_empty() {
+            Some(manifest)
+        } else {
+            None
+        }
+    }
+
+    /// Content root of the whole object, which is the hash of the manifest object.
+    pub fn content_root(&self) -> Sha256Hash {
+        crypto::sha256_hash(&self.to_object())
+    }
+}
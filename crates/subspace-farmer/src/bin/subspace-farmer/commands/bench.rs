@@ -270,14 +270,23 @@ pub(crate) async fn bench(
             .map(tokio::task::spawn_blocking)
             .collect::<FuturesUnordered<_>>();
         while let Some(result) = tasks.next().await {
-            if let Err(error) = result {
-                tracing::error!(%error, "Discovered error while recommitments bench")
+            match result {
+                Ok(Err(error)) => {
+                    tracing::error!(%error, "Discovered error while recommitments bench")
+                }
+                Err(error) => {
+                    tracing::error!(%error, "Discovered error while recommitments bench")
+                }
+                Ok(Ok(())) => {}
             }
         }
 
+        let took = start.elapsed();
+
+        println!("Recommitment took {}", HumanReadableDuration(took));
         println!(
-            "Recommitment took {}",
-            HumanReadableDuration(start.elapsed())
+            "{:.2}M/s average recommitment throughput",
+            actual_space_pledged as f64 / 1000. / 1000. / took.as_secs_f64()
         );
     }
 
@@ -107,6 +107,7 @@ pub(crate) async fn farm(
         recorded_history_segment_size,
         Arc::clone(&multi_farming.plots),
         object_mappings.clone(),
+        multi_farming.networking_nodes.first().cloned(),
     );
     let _stop_handle = ws_server.start(rpc_server.into_rpc())?;
 
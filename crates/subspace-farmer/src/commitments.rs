@@ -4,20 +4,25 @@ mod tests;
 
 use crate::plot::{PieceOffset, Plot};
 use arc_swap::ArcSwapOption;
-use commitment_databases::{CommitmentDatabases, CreateDbEntryResult, DbEntry};
+use commitment_databases::{
+    clear_checkpoint, load_checkpoint, open_commitment_db, save_checkpoint, CommitmentDatabases,
+    CreateDbEntryResult, DbEntry, PartialCommitment,
+};
 use event_listener_primitives::{Bag, HandlerId};
 use parking_lot::Mutex;
 use rayon::prelude::*;
-use rocksdb::DB;
+use rocksdb::WriteBatch;
 use std::io;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{mpsc, Arc};
 use subspace_core_primitives::{Piece, Salt, Tag, PIECE_SIZE};
 use subspace_solving::create_tag;
 use thiserror::Error;
 use tracing::trace;
 
 const BATCH_SIZE: u64 = (16 * 1024 * 1024 / PIECE_SIZE) as u64;
+/// How many batches of pieces reader is allowed to read ahead of tag creation
+const READ_AHEAD_BATCHES: usize = 2;
 
 #[derive(Debug, Error)]
 pub enum CommitmentError {
@@ -27,6 +32,8 @@ pub enum CommitmentError {
     CommitmentDb(rocksdb::Error),
     #[error("Plot error: {0}")]
     Plot(io::Error),
+    #[error("Failed to spawn plot reader thread: {0}")]
+    ReaderThread(io::Error),
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -92,10 +99,11 @@ impl Commitments {
     pub fn create(&self, salt: Salt, plot: Plot) -> Result<(), CommitmentError> {
         let mut commitment_databases = self.inner.commitment_databases.lock();
 
-        let db_entry = match commitment_databases.create_db_entry(salt)? {
+        let (db_entry, resume) = match commitment_databases.create_db_entry(salt)? {
             Some(CreateDbEntryResult {
                 db_entry,
                 removed_entry_salt,
+                resume,
             }) => {
                 if let Some(salt) = removed_entry_salt {
                     self.inner
@@ -107,7 +115,7 @@ impl Commitments {
                     .handlers
                     .status_change
                     .call_simple(&CommitmentStatusChange::Creating { salt });
-                db_entry
+                (db_entry, resume)
             }
             None => {
                 return Ok(());
@@ -125,34 +133,76 @@ impl Commitments {
         let db_path = self.inner.base_directory.join(hex::encode(salt));
 
         let db = {
-            let db = DB::open_default(db_path).map_err(CommitmentError::CommitmentDb)?;
+            let db = Arc::new(open_commitment_db(db_path)?);
             let piece_count = plot.piece_count();
-            for batch_start in (0..piece_count).step_by(BATCH_SIZE as usize) {
-                let pieces_to_process = (batch_start + BATCH_SIZE).min(piece_count) - batch_start;
-                // TODO: Read next batch while creating tags for the previous one for faster
-                //  recommitment.
-                let pieces = plot
-                    .read_pieces(batch_start, pieces_to_process)
-                    .map_err(CommitmentError::Plot)?;
+            let resume_from = if resume { load_checkpoint(&db)? } else { 0 };
+
+            // Expose commitment created so far, such that farming can continue while it is being
+            // created
+            db_entry.set_partial(Some(PartialCommitment::new(Arc::clone(&db), resume_from)));
+
+            if resume_from > 0 {
+                trace!(
+                    salt = %hex::encode(salt),
+                    resume_from,
+                    piece_count,
+                    "Resuming interrupted commitment creation"
+                );
+            }
+
+            // Pieces are read in a separate thread, such that the next batch is being read while
+            // tags are created for the previous one
+            let (pieces_sender, pieces_receiver) = mpsc::sync_channel(READ_AHEAD_BATCHES);
+            std::thread::Builder::new()
+                .name(format!("commitments-{}", hex::encode(salt)))
+                .spawn(move || {
+                    for batch_start in (resume_from..piece_count).step_by(BATCH_SIZE as usize) {
+                        let pieces_to_process =
+                            (batch_start + BATCH_SIZE).min(piece_count) - batch_start;
+                        let result = plot
+                            .read_pieces(batch_start, pieces_to_process)
+                            .map(|pieces| (batch_start, pieces));
+                        let is_error = result.is_err();
+
+                        // Receiver is gone, commitment creation was aborted
+                        if pieces_sender.send(result).is_err() || is_error {
+                            break;
+                        }
+                    }
+                })
+                .map_err(CommitmentError::ReaderThread)?;
+
+            for result in pieces_receiver {
+                let (batch_start, pieces) = result.map_err(CommitmentError::Plot)?;
 
                 let tags: Vec<Tag> = pieces
                     .par_chunks_exact(PIECE_SIZE)
                     .map(|piece| create_tag(piece, salt))
                     .collect();
 
+                let next_offset = batch_start + tags.len() as u64;
+                let mut batch = WriteBatch::default();
                 for (tag, offset) in tags.iter().zip(batch_start..) {
-                    db.put(tag, offset.to_le_bytes())
-                        .map_err(CommitmentError::CommitmentDb)?;
+                    batch.put(tag, offset.to_le_bytes());
+                }
+                save_checkpoint(&db, &mut batch, next_offset);
+                db.write(batch).map_err(CommitmentError::CommitmentDb)?;
+
+                if let Some(partial) = db_entry.partial() {
+                    partial.set_next_offset(next_offset);
                 }
             }
 
+            clear_checkpoint(&db)?;
+
             db
         };
 
-        db_guard.replace(Arc::new(db));
+        db_guard.replace(db);
         // Drop guard because locks need to be taken in a specific order or else will result in a
         // deadlock
         drop(db_guard);
+        db_entry.set_partial(None);
 
         let mut commitment_databases = self.inner.commitment_databases.lock();
 
@@ -250,7 +300,10 @@ impl Commitments {
         Ok(())
     }
 
-    /// Finds the commitment falling in the range of the challenge
+    /// Finds the commitment falling in the range of the challenge.
+    ///
+    /// If commitment for `salt` is still being created, only pieces committed to so far are
+    /// searched, see [`Commitments::find_by_range_uncommitted`] for the rest.
     pub(crate) fn find_by_range(
         &self,
         target: Tag,
@@ -259,8 +312,10 @@ impl Commitments {
     ) -> Option<(Tag, PieceOffset)> {
         let db_entry = self.get_local_db_entry(&salt)?;
 
-        let db_guard = db_entry.try_lock()?;
-        let db = db_guard.clone()?;
+        let db = match db_entry.try_lock().and_then(|db_guard| db_guard.clone()) {
+            Some(db) => db,
+            None => Arc::clone(db_entry.partial()?.db()),
+        };
         let iter = db.raw_iterator();
 
         // Take the best out of 10 solutions
@@ -275,6 +330,75 @@ impl Commitments {
         solutions.into_iter().next()
     }
 
+    /// Finds the commitment falling in the range of the challenge among pieces that commitment for
+    /// `salt` doesn't cover yet because it is still being created. Tags for such pieces are created
+    /// on the fly, so this is as expensive as reading uncommitted part of the plot, which is why the
+    /// search is skipped altogether if there are more than `max_pieces` uncommitted pieces.
+    ///
+    /// Returns `Ok(None)` if there is no commitment in progress for `salt`.
+    pub(crate) fn find_by_range_uncommitted(
+        &self,
+        target: Tag,
+        range: u64,
+        salt: Salt,
+        plot: &Plot,
+        max_pieces: u64,
+    ) -> Result<Option<(Tag, PieceOffset)>, CommitmentError> {
+        let partial = match self
+            .get_local_db_entry(&salt)
+            .and_then(|db_entry| db_entry.partial())
+        {
+            Some(partial) => partial,
+            None => {
+                return Ok(None);
+            }
+        };
+
+        let next_offset = partial.next_offset();
+        let piece_count = plot.piece_count();
+        let uncommitted_pieces = piece_count.saturating_sub(next_offset);
+        if uncommitted_pieces > max_pieces {
+            trace!(
+                salt = %hex::encode(salt),
+                uncommitted_pieces,
+                max_pieces,
+                "Too many uncommitted pieces, skipping search"
+            );
+            return Ok(None);
+        }
+
+        let target = u64::from_be_bytes(target);
+        let mut best_solution = None::<(u64, Tag, PieceOffset)>;
+
+        for batch_start in (next_offset..piece_count).step_by(BATCH_SIZE as usize) {
+            let pieces_to_process = (batch_start + BATCH_SIZE).min(piece_count) - batch_start;
+            let pieces = plot
+                .read_pieces(batch_start, pieces_to_process)
+                .map_err(CommitmentError::Plot)?;
+
+            let batch_best_solution = pieces
+                .par_chunks_exact(PIECE_SIZE)
+                .enumerate()
+                .map(|(index, piece)| {
+                    let tag = create_tag(piece, salt);
+                    let distance = subspace_core_primitives::bidirectional_distance(
+                        &target,
+                        &u64::from_be_bytes(tag),
+                    );
+                    (distance, tag, batch_start + index as u64)
+                })
+                .filter(|(distance, _tag, _offset)| *distance <= range / 2)
+                .min_by_key(|(distance, _tag, _offset)| *distance);
+
+            best_solution = best_solution
+                .into_iter()
+                .chain(batch_best_solution)
+                .min_by_key(|(distance, _tag, _offset)| *distance);
+        }
+
+        Ok(best_solution.map(|(_distance, tag, offset)| (tag, offset)))
+    }
+
     pub fn on_status_change(
         &self,
         callback: Arc<dyn Fn(&CommitmentStatusChange) + Send + Sync + 'static>,
@@ -1,12 +1,15 @@
 use super::CommitmentError;
+use crate::plot::PieceOffset;
+use arc_swap::ArcSwapOption;
 use lru::LruCache;
 use parking_lot::Mutex;
-use rocksdb::{Options, DB};
+use rocksdb::{Options, WriteBatch, DB};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
 use std::ops::Deref;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use subspace_core_primitives::Salt;
 use tracing::error;
@@ -14,6 +17,10 @@ use tracing::error;
 // Cache size is just enough for last 2 salts to be stored
 const COMMITMENTS_CACHE_SIZE: usize = 2;
 const COMMITMENTS_KEY: &[u8] = b"commitments";
+/// Column family of commitment database that stores progress of its creation, such that tags are
+/// never mixed with it
+const PROGRESS_COLUMN_FAMILY: &str = "progress";
+const NEXT_OFFSET_KEY: &[u8] = b"next-offset";
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 enum CommitmentStatus {
@@ -26,11 +33,83 @@ enum CommitmentStatus {
 pub(super) struct CreateDbEntryResult {
     pub(super) db_entry: Arc<DbEntry>,
     pub(super) removed_entry_salt: Option<Salt>,
+    /// Whether previous attempt to create commitment was interrupted and should be resumed from
+    /// the checkpoint stored in commitment database
+    pub(super) resume: bool,
+}
+
+/// Opens commitment database along with column family for its creation progress
+pub(super) fn open_commitment_db(path: impl AsRef<Path>) -> Result<DB, CommitmentError> {
+    let mut options = Options::default();
+    options.create_if_missing(true);
+    options.create_missing_column_families(true);
+
+    DB::open_cf(&options, path, [PROGRESS_COLUMN_FAMILY]).map_err(CommitmentError::CommitmentDb)
+}
+
+/// Reads offset up to which commitment was created before previous attempt was interrupted
+pub(super) fn load_checkpoint(db: &DB) -> Result<PieceOffset, CommitmentError> {
+    Ok(db
+        .get_cf(progress_column_family(db), NEXT_OFFSET_KEY)
+        .map_err(CommitmentError::CommitmentDb)?
+        .and_then(|bytes| bytes.try_into().ok())
+        .map(PieceOffset::from_le_bytes)
+        .unwrap_or_default())
+}
+
+/// Adds offset up to which commitment is created to the batch with tags, such that progress is
+/// persisted atomically with tags it covers and creation can be resumed after restart
+pub(super) fn save_checkpoint(db: &DB, batch: &mut WriteBatch, next_offset: PieceOffset) {
+    batch.put_cf(
+        progress_column_family(db),
+        NEXT_OFFSET_KEY,
+        next_offset.to_le_bytes(),
+    );
+}
+
+pub(super) fn clear_checkpoint(db: &DB) -> Result<(), CommitmentError> {
+    db.delete_cf(progress_column_family(db), NEXT_OFFSET_KEY)
+        .map_err(CommitmentError::CommitmentDb)
+}
+
+fn progress_column_family(db: &DB) -> &rocksdb::ColumnFamily {
+    db.cf_handle(PROGRESS_COLUMN_FAMILY)
+        .expect("Commitment database is always opened with progress column family; qed")
+}
+
+/// Database of commitment that is still being created, contains tags for pieces up to
+/// `next_offset`
+pub(super) struct PartialCommitment {
+    db: Arc<DB>,
+    next_offset: AtomicU64,
+}
+
+impl PartialCommitment {
+    pub(super) fn new(db: Arc<DB>, next_offset: PieceOffset) -> Self {
+        Self {
+            db,
+            next_offset: AtomicU64::new(next_offset),
+        }
+    }
+
+    pub(super) fn db(&self) -> &Arc<DB> {
+        &self.db
+    }
+
+    /// Offset of the first piece that is not yet committed to
+    pub(super) fn next_offset(&self) -> PieceOffset {
+        self.next_offset.load(Ordering::Acquire)
+    }
+
+    pub(super) fn set_next_offset(&self, next_offset: PieceOffset) {
+        self.next_offset.store(next_offset, Ordering::Release);
+    }
 }
 
 pub(super) struct DbEntry {
     salt: Salt,
     db: Mutex<Option<Arc<DB>>>,
+    partial: ArcSwapOption<PartialCommitment>,
 }
 
 impl fmt::Debug for DbEntry {
@@ -51,6 +130,15 @@ impl DbEntry {
     pub(super) fn salt(&self) -> &Salt {
         &self.salt
     }
+
+    /// Partial commitment, only present while commitment is being created
+    pub(super) fn partial(&self) -> Option<Arc<PartialCommitment>> {
+        self.partial.load_full()
+    }
+
+    pub(super) fn set_partial(&self, partial: Option<PartialCommitment>) {
+        self.partial.store(partial.map(Arc::new));
+    }
 }
 
 #[derive(Debug)]
@@ -84,38 +172,20 @@ impl CommitmentDatabases {
             metadata_db: Arc::new(metadata_db),
         };
 
-        if commitment_databases
+        // Open databases that were fully created during previous run, in progress commitments are
+        // left on disk such that their creation can be resumed
+        for (salt, _status) in commitment_databases
             .metadata_cache
-            .drain_filter(|salt, status| match status {
-                CommitmentStatus::InProgress => {
-                    if let Err(error) =
-                        std::fs::remove_dir_all(base_directory.join(hex::encode(salt)))
-                    {
-                        error!(
-                            salt = %hex::encode(salt),
-                            %error,
-                            "Failed to remove old in progress commitment",
-                        );
-                    }
-                    true
-                }
-                CommitmentStatus::Created => false,
-            })
-            .next()
-            .is_some()
+            .iter()
+            .filter(|(_salt, status)| matches!(status, CommitmentStatus::Created))
         {
-            commitment_databases.persist_metadata_cache()?;
-        }
-
-        // Open databases that were fully created during previous run
-        for salt in commitment_databases.metadata_cache.keys() {
-            let db = DB::open(&Options::default(), base_directory.join(hex::encode(salt)))
-                .map_err(CommitmentError::CommitmentDb)?;
+            let db = open_commitment_db(base_directory.join(hex::encode(salt)))?;
             commitment_databases.databases.put(
                 *salt,
                 Arc::new(DbEntry {
                     salt: *salt,
                     db: Mutex::new(Some(Arc::new(db))),
+                    partial: ArcSwapOption::empty(),
                 }),
             );
         }
@@ -157,9 +227,13 @@ impl CommitmentDatabases {
             return Ok(None);
         }
 
+        let resume = self.metadata_cache.get(&salt) == Some(&CommitmentStatus::InProgress);
+        self.remove_stale_in_progress(&salt)?;
+
         let db_entry = Arc::new(DbEntry {
             salt,
             db: Mutex::new(None),
+            partial: ArcSwapOption::empty(),
         });
         let mut removed_entry_salt = None;
 
@@ -182,6 +256,7 @@ impl CommitmentDatabases {
                 // Take a lock to make sure database was released by whatever user there was and we
                 // have an exclusive access to it, then drop it
                 old_db_entry.db.lock().take();
+                old_db_entry.set_partial(None);
 
                 if let Err(error) = std::fs::remove_dir_all(old_db_path) {
                     error!(
@@ -198,9 +273,44 @@ impl CommitmentDatabases {
         Ok(Some(CreateDbEntryResult {
             db_entry,
             removed_entry_salt,
+            resume,
         }))
     }
 
+    /// Removes in progress commitments left from previous run for salts other than `salt`, they
+    /// will never be resumed
+    fn remove_stale_in_progress(&mut self, salt: &Salt) -> Result<(), CommitmentError> {
+        let stale_salts = self
+            .metadata_cache
+            .iter()
+            .filter(|(stale_salt, status)| {
+                matches!(status, CommitmentStatus::InProgress)
+                    && *stale_salt != salt
+                    && !self.databases.contains(*stale_salt)
+            })
+            .map(|(stale_salt, _status)| *stale_salt)
+            .collect::<Vec<_>>();
+
+        if stale_salts.is_empty() {
+            return Ok(());
+        }
+
+        for stale_salt in stale_salts {
+            self.metadata_cache.remove(&stale_salt);
+            if let Err(error) =
+                std::fs::remove_dir_all(self.base_directory.join(hex::encode(stale_salt)))
+            {
+                error!(
+                    salt = %hex::encode(stale_salt),
+                    %error,
+                    "Failed to remove old in progress commitment",
+                );
+            }
+        }
+
+        self.persist_metadata_cache()
+    }
+
     pub(super) fn mark_in_progress(&mut self, salt: Salt) -> Result<(), CommitmentError> {
         self.update_status(salt, CommitmentStatus::InProgress)
     }
@@ -1,9 +1,14 @@
+use crate::commitments::commitment_databases::{
+    load_checkpoint, open_commitment_db, save_checkpoint, CommitmentDatabases, PartialCommitment,
+};
 use crate::commitments::Commitments;
 use crate::plot::Plot;
 use rand::prelude::*;
 use rand::rngs::StdRng;
+use rocksdb::{WriteBatch, DB};
 use std::sync::Arc;
 use subspace_core_primitives::{FlatPieces, Salt, Tag, PIECE_SIZE};
+use subspace_solving::create_tag;
 use tempfile::TempDir;
 
 fn init() {
@@ -32,6 +37,140 @@ async fn create() {
     assert_eq!(correct_tag, tag);
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn resume_interrupted_creation() {
+    init();
+    let base_directory = TempDir::new().unwrap();
+    let salt: Salt = [1u8; 8];
+    let resume_from = 8;
+
+    let plot = Plot::open_or_create(&base_directory, [0; 32].into(), u64::MAX).unwrap();
+
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut pieces: FlatPieces = vec![0u8; 16 * PIECE_SIZE].try_into().unwrap();
+    rng.fill(pieces.as_mut());
+    let tags = pieces
+        .as_pieces()
+        .map(|piece| create_tag(piece, salt))
+        .collect::<Vec<_>>();
+    let piece_indexes = (0..).take(pieces.count()).collect();
+    plot.write_many(Arc::new(pieces), piece_indexes).unwrap();
+
+    // Simulate commitment creation that was interrupted after processing first pieces, whose tags
+    // were written together with the checkpoint
+    {
+        let mut commitment_databases =
+            CommitmentDatabases::new(base_directory.path().join("commitments")).unwrap();
+        commitment_databases.create_db_entry(salt).unwrap().unwrap();
+        let db = open_commitment_db(
+            base_directory
+                .path()
+                .join("commitments")
+                .join(hex::encode(salt)),
+        )
+        .unwrap();
+        let mut batch = WriteBatch::default();
+        save_checkpoint(&db, &mut batch, resume_from);
+        db.write(batch).unwrap();
+    }
+
+    let commitments = Commitments::new(base_directory.path().join("commitments")).unwrap();
+    commitments.create(salt, plot).unwrap();
+
+    // Checkpoint is removed once commitment is created
+    {
+        let db_entry = commitments.get_local_db_entry(&salt).unwrap();
+        let db_guard = db_entry.lock();
+        assert_eq!(load_checkpoint(db_guard.as_ref().unwrap()).unwrap(), 0);
+    }
+
+    // Pieces before checkpoint were not processed again
+    for (offset, tag) in tags.iter().enumerate() {
+        let found = commitments.find_by_range(*tag, 0, salt);
+        if (offset as u64) < resume_from {
+            assert!(found.is_none());
+        } else {
+            assert_eq!(found, Some((*tag, offset as u64)));
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn find_while_in_progress() {
+    init();
+    let base_directory = TempDir::new().unwrap();
+    let salt: Salt = [1u8; 8];
+    let committed = 8;
+
+    let plot = Plot::open_or_create(&base_directory, [0; 32].into(), u64::MAX).unwrap();
+    let commitments = Commitments::new(base_directory.path().join("commitments")).unwrap();
+
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut pieces: FlatPieces = vec![0u8; 16 * PIECE_SIZE].try_into().unwrap();
+    rng.fill(pieces.as_mut());
+    let tags = pieces
+        .as_pieces()
+        .map(|piece| create_tag(piece, salt))
+        .collect::<Vec<_>>();
+    let piece_indexes = (0..).take(pieces.count()).collect();
+    plot.write_many(Arc::new(pieces), piece_indexes).unwrap();
+
+    // Simulate commitment creation that has only processed first pieces so far
+    let db_entry = {
+        let mut commitment_databases = commitments.inner.commitment_databases.lock();
+        let db_entry = commitment_databases
+            .create_db_entry(salt)
+            .unwrap()
+            .unwrap()
+            .db_entry;
+        let (current, next) = commitment_databases.get_db_entries();
+        commitments.inner.current.swap(current);
+        commitments.inner.next.swap(next);
+        db_entry
+    };
+    let _db_guard = db_entry.lock();
+    let db = DB::open_default(base_directory.path().join("partial")).unwrap();
+    for (offset, tag) in tags.iter().enumerate().take(committed as usize) {
+        db.put(tag, (offset as u64).to_le_bytes()).unwrap();
+    }
+    db_entry.set_partial(Some(PartialCommitment::new(Arc::new(db), committed)));
+
+    for (offset, tag) in tags.iter().enumerate() {
+        let offset = offset as u64;
+        let found = commitments.find_by_range(*tag, 0, salt);
+        let found_uncommitted = commitments
+            .find_by_range_uncommitted(*tag, 0, salt, &plot, u64::MAX)
+            .unwrap();
+        if offset < committed {
+            assert_eq!(found, Some((*tag, offset)));
+            assert!(found_uncommitted.is_none());
+        } else {
+            assert!(found.is_none());
+            assert_eq!(found_uncommitted, Some((*tag, offset)));
+        }
+    }
+
+    // Search is skipped when uncommitted pieces don't fit into the budget
+    let uncommitted = tags.len() as u64 - committed;
+    assert!(commitments
+        .find_by_range_uncommitted(tags[committed as usize], 0, salt, &plot, uncommitted - 1)
+        .unwrap()
+        .is_none());
+    assert_eq!(
+        commitments
+            .find_by_range_uncommitted(tags[committed as usize], 0, salt, &plot, uncommitted)
+            .unwrap(),
+        Some((tags[committed as usize], committed))
+    );
+
+    // Nothing to scan once commitment is created
+    db_entry.set_partial(None);
+    assert!(commitments
+        .find_by_range_uncommitted(tags[committed as usize], 0, salt, &plot, u64::MAX)
+        .unwrap()
+        .is_none());
+}
+
 // TODO: Tests for recommitting in background
 
 #[tokio::test(flavor = "multi_thread")]
@@ -19,6 +19,10 @@ use thiserror::Error;
 use tokio::task::JoinHandle;
 use tracing::{debug, error, info, trace, warn};
 
+/// Maximum number of uncommitted pieces (16 MiB worth) that are read and tagged on the fly in a
+/// slot while commitment is being created, search among them is skipped if there are more
+const MAX_UNCOMMITTED_PIECES_PER_SLOT: u64 = 4096;
+
 #[derive(Debug, Error)]
 pub enum FarmingError {
     #[error("jsonrpsee error: {0}")]
@@ -203,6 +207,24 @@ async fn subscribe_to_slot_info<T: RpcClient>(
                             slot_info.voting_solution_range,
                             slot_info.salt,
                         )
+                    })
+                    .or_else(|| {
+                        // Commitment for this salt might still be in progress (at the beginning of
+                        // the eon for instance), check pieces that were not committed to yet
+                        commitments
+                            .find_by_range_uncommitted(
+                                target,
+                                slot_info
+                                    .solution_range
+                                    .max(slot_info.voting_solution_range),
+                                slot_info.salt,
+                                &plot,
+                                MAX_UNCOMMITTED_PIECES_PER_SLOT,
+                            )
+                            .unwrap_or_else(|error| {
+                                warn!(%error, "Failed to search uncommitted pieces");
+                                None
+                            })
                     });
                 match maybe_tag {
                     Some((tag, piece_offset)) => {
@@ -26,6 +26,7 @@ pub struct MultiFarming {
     pub commitments: Vec<Commitments>,
     farmings: Vec<Farming>,
     archiving: Archiving,
+    pub networking_nodes: Vec<subspace_networking::Node>,
     networking_node_runners: Vec<subspace_networking::NodeRunner>,
 }
 
@@ -79,6 +80,7 @@ impl MultiFarming {
         let mut subspace_codecs = Vec::with_capacity(plot_sizes.len());
         let mut commitments = Vec::with_capacity(plot_sizes.len());
         let mut farmings = Vec::with_capacity(plot_sizes.len());
+        let mut networking_nodes = Vec::with_capacity(plot_sizes.len());
         let mut networking_node_runners = Vec::with_capacity(plot_sizes.len());
 
         let mut results = plot_sizes
@@ -192,6 +194,7 @@ impl MultiFarming {
             .detach();
 
             bootstrap_nodes.extend(listen_on);
+            networking_nodes.push(node);
             networking_node_runners.push(node_runner);
 
             plots.push(plot);
@@ -237,6 +240,7 @@ impl MultiFarming {
             commitments,
             farmings,
             archiving,
+            networking_nodes,
             networking_node_runners,
         })
     }
@@ -11,7 +11,7 @@ use codec::{Codec, Decode, Encode};
 use hash_db::{HashDB, Hasher, Prefix};
 use sc_client_api::backend;
 use sc_client_api::execution_extensions::ExtensionsFactory;
-use sp_api::{ProvideRuntimeApi, StateBackend, StorageProof};
+use sp_api::{ProvideRuntimeApi, StateBackend};
 use sp_core::traits::{CodeExecutor, FetchRuntimeCode, RuntimeCode, SpawnNamed};
 use sp_core::H256;
 use sp_executor::fraud_proof_ext::FraudProofExt;
@@ -20,7 +20,7 @@ use sp_externalities::Extensions;
 use sp_runtime::generic::BlockId;
 use sp_runtime::traits::{BlakeTwo256, Block as BlockT, HashFor};
 use sp_state_machine::{TrieBackend, TrieBackendStorage};
-use sp_trie::DBValue;
+use sp_trie::{CompactProof, DBValue};
 use std::marker::PhantomData;
 use std::sync::Arc;
 
@@ -50,12 +50,15 @@ where
 
     /// Returns a storage proof which can be used to reconstruct a partial state trie to re-run
     /// the execution by someone who does not own the whole state.
+    ///
+    /// The proof is compacted against the state root the execution starts from, i.e., the
+    /// `pre_state_root` of the fraud proof.
     pub fn prove_execution<DB: HashDB<HashFor<Block>, DBValue>>(
         &self,
         at: BlockId<Block>,
         execution_phase: &ExecutionPhase,
         delta_changes: Option<(DB, Block::Hash)>,
-    ) -> sp_blockchain::Result<StorageProof> {
+    ) -> sp_blockchain::Result<CompactProof> {
         let state = self.backend.state_at(at)?;
 
         let trie_backend = state.as_trie_backend().ok_or_else(|| {
@@ -70,9 +73,9 @@ where
 
         // TODO: avoid using the String API specified by `proving_method()`
         // https://github.com/paritytech/substrate/discussions/11095
-        if let Some((delta, post_delta_root)) = delta_changes {
+        let (proof, root) = if let Some((delta, post_delta_root)) = delta_changes {
             let delta_backend = create_delta_backend(trie_backend, delta, post_delta_root);
-            sp_state_machine::prove_execution_on_trie_backend(
+            let (_ret, proof) = sp_state_machine::prove_execution_on_trie_backend(
                 &delta_backend,
                 &mut Default::default(),
                 &*self.executor,
@@ -80,11 +83,10 @@ where
                 execution_phase.proving_method(),
                 execution_phase.call_data(),
                 &runtime_code,
-            )
-            .map(|(_ret, proof)| proof)
-            .map_err(Into::into)
+            )?;
+            (proof, post_delta_root)
         } else {
-            sp_state_machine::prove_execution_on_trie_backend(
+            let (_ret, proof) = sp_state_machine::prove_execution_on_trie_backend(
                 trie_backend,
                 &mut Default::default(),
                 &*self.executor,
@@ -92,10 +94,17 @@ where
                 execution_phase.proving_method(),
                 execution_phase.call_data(),
                 &runtime_code,
-            )
-            .map(|(_ret, proof)| proof)
-            .map_err(Into::into)
-        }
+            )?;
+            (proof, *trie_backend.root())
+        };
+
+        proof
+            .into_compact_proof::<HashFor<Block>>(root)
+            .map_err(|e| {
+                sp_blockchain::Error::Backend(format!(
+                    "Failed to convert storage proof into compact proof: {e:?}"
+                ))
+            })
     }
 
     /// Runs the execution using the partial state constructed from the given storage proof and
@@ -108,7 +117,7 @@ where
         at: BlockId<Block>,
         execution_phase: &ExecutionPhase,
         pre_execution_root: H256,
-        proof: StorageProof,
+        proof: CompactProof,
     ) -> sp_blockchain::Result<Vec<u8>> {
         let state = self.backend.state_at(at)?;
 
@@ -122,6 +131,14 @@ where
             .runtime_code()
             .map_err(sp_blockchain::Error::RuntimeCode)?;
 
+        let (proof, _root) = proof
+            .to_storage_proof::<BlakeTwo256>(Some(&pre_execution_root))
+            .map_err(|e| {
+                sp_blockchain::Error::Backend(format!(
+                    "Failed to reconstruct storage proof from compact proof: {e:?}"
+                ))
+            })?;
+
         sp_state_machine::execution_proof_check::<BlakeTwo256, _, _>(
             pre_execution_root,
             proof,
@@ -266,9 +283,13 @@ where
             heap_pages: None,
         };
 
+        let (proof, _root) = proof
+            .to_storage_proof::<BlakeTwo256>(Some(pre_state_root))
+            .map_err(|_| VerificationError::InvalidCompactProof)?;
+
         let execution_result = sp_state_machine::execution_proof_check::<BlakeTwo256, _, _>(
             *pre_state_root,
-            proof.clone(),
+            proof,
             &mut Default::default(),
             &self.executor,
             self.spawn_handle.clone(),
@@ -89,6 +89,7 @@ fn main() -> Result<(), Error> {
             let runner = cli.create_runner(cmd)?;
             runner.sync_run(|config| cmd.run(config.chain_spec, config.network))?
         }
+        Some(Subcommand::ChainSpec(cmd)) => cmd.run()?,
         Some(Subcommand::CheckBlock(cmd)) => {
             let runner = cli.create_runner(cmd)?;
             set_default_ss58_version(&runner.config().chain_spec);
@@ -391,7 +392,12 @@ fn main() -> Result<(), Error> {
                         primary_chain_node
                             .imported_block_notification_stream
                             .subscribe()
-                            .then(|(block_number, _)| async move { block_number }),
+                            .then(|imported_block_notification| async move {
+                                (
+                                    imported_block_notification.block_hash,
+                                    imported_block_notification.block_number,
+                                )
+                            }),
                         primary_chain_node
                             .new_slot_notification_stream
                             .subscribe()
@@ -401,6 +407,7 @@ fn main() -> Result<(), Error> {
                                     slot_notification.new_slot_info.global_challenge,
                                 )
                             }),
+                        secondary_chain_cli.archive_execution_receipts,
                     );
 
                     let secondary_chain_node = secondary_chain_node_fut.await?;
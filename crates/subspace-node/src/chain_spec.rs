@@ -23,6 +23,7 @@ use crate::chain_spec_utils::{
 use crate::secondary_chain;
 use crate::secondary_chain::chain_spec::ExecutionChainSpec;
 use sc_chain_spec::ChainSpecExtension;
+use sc_service::config::MultiaddrWithPeerId;
 use sc_service::ChainType;
 use sc_telemetry::TelemetryEndpoints;
 use serde::{Deserialize, Serialize};
@@ -266,6 +267,70 @@ pub fn local_config() -> Result<ConsensusChainSpec, String> {
     ))
 }
 
+/// Genesis of a custom network, see [`crate::ChainSpecCmd`].
+pub(crate) struct CustomGenesis {
+    pub(crate) name: String,
+    pub(crate) id: String,
+    pub(crate) chain_type: ChainType,
+    pub(crate) boot_nodes: Vec<MultiaddrWithPeerId>,
+    pub(crate) telemetry_endpoints: Option<TelemetryEndpoints>,
+    pub(crate) protocol_id: Option<String>,
+    pub(crate) sudo_account: AccountId,
+    pub(crate) balances: Vec<(AccountId, Balance)>,
+    // who, start, period, period_count, per_period
+    pub(crate) vesting: Vec<(AccountId, BlockNumber, BlockNumber, u32, Balance)>,
+    pub(crate) executor_authority: (AccountId, ExecutorId),
+    pub(crate) enable_rewards: bool,
+    pub(crate) enable_storage_access: bool,
+    pub(crate) execution_chain_spec: ExecutionChainSpec,
+}
+
+pub(crate) fn custom_config(genesis: CustomGenesis) -> Result<ConsensusChainSpec, String> {
+    let wasm_binary = WASM_BINARY.ok_or_else(|| "Wasm binary not available".to_string())?;
+    let CustomGenesis {
+        name,
+        id,
+        chain_type,
+        boot_nodes,
+        telemetry_endpoints,
+        protocol_id,
+        sudo_account,
+        balances,
+        vesting,
+        executor_authority,
+        enable_rewards,
+        enable_storage_access,
+        execution_chain_spec,
+    } = genesis;
+
+    Ok(ConsensusChainSpec::from_genesis(
+        &name,
+        &id,
+        chain_type,
+        move || {
+            subspace_genesis_config(
+                wasm_binary,
+                sudo_account.clone(),
+                balances.clone(),
+                vesting.clone(),
+                executor_authority.clone(),
+                enable_rewards,
+                enable_storage_access,
+            )
+        },
+        boot_nodes,
+        telemetry_endpoints,
+        protocol_id.as_deref(),
+        None,
+        // Properties
+        Some(chain_spec_properties()),
+        // Extensions
+        ChainSpecExtensions {
+            execution_chain_spec,
+        },
+    ))
+}
+
 /// Configure initial storage state for FRAME modules.
 fn subspace_genesis_config(
     wasm_binary: &[u8],
@@ -0,0 +1,354 @@
+// Copyright (C) 2022 Subspace Labs, Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Generation of chain specs for custom networks from a declarative config file.
+//!
+//! Config can be either TOML or JSON (if file has `.json` extension), amounts are specified in
+//! SSC:
+//! ```toml
+//! name = "Subspace devnet"
+//! id = "subspace_devnet"
+//! chain_type = "live"
+//! protocol_id = "subspace-devnet"
+//! boot_nodes = ["/dns/bootstrap.devnet.example/tcp/30333/p2p/12D3KooW..."]
+//! sudo = "5CXTmJEusve5ixyJufqHThmy4qUrrm6FyLCR7QfE4bbyMTNC"
+//!
+//! [[balances]]
+//! account = "5CXTmJEusve5ixyJufqHThmy4qUrrm6FyLCR7QfE4bbyMTNC"
+//! amount = 1000
+//!
+//! [[vesting]]
+//! account = "5CXTmJEusve5ixyJufqHThmy4qUrrm6FyLCR7QfE4bbyMTNC"
+//! start = 100
+//! period = 10
+//! period_count = 10
+//! per_period = 10
+//!
+//! [consensus]
+//! enable_rewards = true
+//! enable_storage_access = true
+//!
+//! [executor]
+//! account = "5Df6w8CgYY8kTRwCu8bjBsFu46fy4nFa61xk6dUbL6G4fFjQ"
+//! authority = "5FuuXk1TL8DKQMvg7mcqmP8t9FhxUdzTcYC9aFmebiTLmASx"
+//!
+//! [secondary_chain]
+//! name = "Subspace devnet execution"
+//! id = "subspace_devnet_execution"
+//!
+//! [[secondary_chain.balances]]
+//! account = "5CXTmJEusve5ixyJufqHThmy4qUrrm6FyLCR7QfE4bbyMTNC"
+//! amount = 1000
+//! ```
+//!
+//! Only `enable_rewards` and `enable_storage_access` consensus parameters are part of the runtime
+//! genesis config, the rest of them (slot probability, era and eon durations, confirmation depth
+//! `K`, initial solution range, etc.) are constants of the runtime and can't be customized here.
+
+#[cfg(test)]
+mod tests;
+
+use crate::chain_spec::{self, CustomGenesis};
+use crate::secondary_chain;
+use clap::Parser;
+use sc_service::config::MultiaddrWithPeerId;
+use sc_service::{ChainSpec, ChainType};
+use sc_telemetry::TelemetryEndpoints;
+use serde::Deserialize;
+use sp_core::crypto::Ss58Codec;
+use sp_executor::ExecutorId;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::PathBuf;
+use subspace_runtime_primitives::{AccountId, Balance, BlockNumber, SSC};
+
+/// Type of the chain.
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum ChainTypeConfig {
+    Development,
+    Local,
+    Live,
+}
+
+impl From<ChainTypeConfig> for ChainType {
+    fn from(chain_type: ChainTypeConfig) -> Self {
+        match chain_type {
+            ChainTypeConfig::Development => ChainType::Development,
+            ChainTypeConfig::Local => ChainType::Local,
+            ChainTypeConfig::Live => ChainType::Live,
+        }
+    }
+}
+
+/// Endowed account, amount is in SSC.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BalanceConfig {
+    account: String,
+    amount: u128,
+}
+
+/// Vesting schedule, `per_period` is in SSC.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct VestingConfig {
+    account: String,
+    start: BlockNumber,
+    period: BlockNumber,
+    period_count: u32,
+    per_period: u128,
+}
+
+/// Consensus parameters that can be set at genesis, see module documentation for parameters that
+/// can't be.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConsensusConfig {
+    #[serde(default)]
+    enable_rewards: bool,
+    #[serde(default)]
+    enable_storage_access: bool,
+}
+
+/// Executor authority.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExecutorConfig {
+    account: String,
+    authority: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TelemetryEndpointConfig {
+    url: String,
+    verbosity: u8,
+}
+
+/// Secondary chain genesis.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SecondaryChainConfig {
+    name: String,
+    id: String,
+    protocol_id: Option<String>,
+    #[serde(default)]
+    balances: Vec<BalanceConfig>,
+}
+
+/// Declarative description of the chain spec.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ChainSpecConfig {
+    name: String,
+    id: String,
+    chain_type: ChainTypeConfig,
+    protocol_id: Option<String>,
+    #[serde(default)]
+    boot_nodes: Vec<MultiaddrWithPeerId>,
+    #[serde(default)]
+    telemetry_endpoints: Vec<TelemetryEndpointConfig>,
+    sudo: String,
+    #[serde(default)]
+    balances: Vec<BalanceConfig>,
+    #[serde(default)]
+    vesting: Vec<VestingConfig>,
+    consensus: ConsensusConfig,
+    executor: ExecutorConfig,
+    secondary_chain: SecondaryChainConfig,
+}
+
+/// Parse config from JSON or TOML.
+fn parse_config(contents: &str, is_json: bool) -> Result<ChainSpecConfig, String> {
+    if is_json {
+        serde_json::from_str(contents).map_err(|error| error.to_string())
+    } else {
+        toml::from_str(contents).map_err(|error| error.to_string())
+    }
+}
+
+fn parse_account(address: &str) -> Result<AccountId, String> {
+    AccountId::from_ss58check(address)
+        .map_err(|error| format!("Invalid account address {address}: {error:?}"))
+}
+
+fn to_balance(amount: u128) -> Result<Balance, String> {
+    amount
+        .checked_mul(SSC)
+        .ok_or_else(|| format!("Amount {amount} SSC is too large"))
+}
+
+fn parse_balances(balances: &[BalanceConfig]) -> Result<Vec<(AccountId, Balance)>, String> {
+    let mut accounts = BTreeSet::new();
+
+    balances
+        .iter()
+        .map(|BalanceConfig { account, amount }| {
+            let account_id = parse_account(account)?;
+            if *amount == 0 {
+                return Err(format!("Balance of {account} must not be zero"));
+            }
+            if !accounts.insert(account_id.clone()) {
+                return Err(format!("Balance of {account} is specified more than once"));
+            }
+
+            Ok((account_id, to_balance(*amount)?))
+        })
+        .collect()
+}
+
+impl ChainSpecConfig {
+    /// Validate config and turn it into genesis parameters.
+    fn into_genesis(self) -> Result<CustomGenesis, String> {
+        let sudo_account = parse_account(&self.sudo)?;
+        let balances = parse_balances(&self.balances)?;
+
+        let mut vested_amounts = BTreeMap::<AccountId, Balance>::new();
+        let vesting = self
+            .vesting
+            .iter()
+            .map(|vesting| {
+                let account_id = parse_account(&vesting.account)?;
+                if vesting.period == 0 || vesting.period_count == 0 || vesting.per_period == 0 {
+                    return Err(format!(
+                        "Vesting schedule of {} must have non-zero period, period count and \
+                        amount per period",
+                        vesting.account
+                    ));
+                }
+                let per_period = to_balance(vesting.per_period)?;
+                let vested_amount = vested_amounts.entry(account_id.clone()).or_default();
+                *vested_amount = per_period
+                    .checked_mul(Balance::from(vesting.period_count))
+                    .and_then(|total| vested_amount.checked_add(total))
+                    .ok_or_else(|| {
+                        format!("Total vested amount of {} is too large", vesting.account)
+                    })?;
+
+                Ok((
+                    account_id,
+                    vesting.start,
+                    vesting.period,
+                    vesting.period_count,
+                    per_period,
+                ))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        // Vested tokens are locked, so they must be present in the balance already
+        for (account_id, vested_amount) in vested_amounts {
+            let balance = balances
+                .iter()
+                .find_map(|(balance_account_id, balance)| {
+                    (balance_account_id == &account_id).then(|| *balance)
+                })
+                .unwrap_or_default();
+            if balance < vested_amount {
+                return Err(format!(
+                    "Balance of {} is less than its total vested amount",
+                    account_id.to_ss58check()
+                ));
+            }
+        }
+
+        let executor_authority = (
+            parse_account(&self.executor.account)?,
+            ExecutorId::from_ss58check(&self.executor.authority).map_err(|error| {
+                format!(
+                    "Invalid executor authority {}: {error:?}",
+                    self.executor.authority
+                )
+            })?,
+        );
+
+        let telemetry_endpoints = if self.telemetry_endpoints.is_empty() {
+            None
+        } else {
+            Some(
+                TelemetryEndpoints::new(
+                    self.telemetry_endpoints
+                        .into_iter()
+                        .map(|TelemetryEndpointConfig { url, verbosity }| (url, verbosity))
+                        .collect(),
+                )
+                .map_err(|error| error.to_string())?,
+            )
+        };
+
+        let execution_chain_spec = secondary_chain::chain_spec::custom_config(
+            &self.secondary_chain.name,
+            &self.secondary_chain.id,
+            self.secondary_chain.protocol_id.as_deref(),
+            parse_balances(&self.secondary_chain.balances)?,
+        );
+
+        Ok(CustomGenesis {
+            name: self.name,
+            id: self.id,
+            chain_type: self.chain_type.into(),
+            boot_nodes: self.boot_nodes,
+            telemetry_endpoints,
+            protocol_id: self.protocol_id,
+            sudo_account,
+            balances,
+            vesting,
+            executor_authority,
+            enable_rewards: self.consensus.enable_rewards,
+            enable_storage_access: self.consensus.enable_storage_access,
+            execution_chain_spec,
+        })
+    }
+}
+
+/// The `chain-spec` command used to generate chain spec of a custom network from a config file.
+#[derive(Debug, Parser)]
+pub struct ChainSpecCmd {
+    /// Path to TOML or JSON (with `.json` extension) file describing the chain.
+    #[clap(long, parse(from_os_str))]
+    pub config: PathBuf,
+
+    /// Directory where `chain-spec.json` and `chain-spec-raw.json` will be written.
+    #[clap(long, parse(from_os_str), default_value = ".")]
+    pub output_dir: PathBuf,
+}
+
+impl ChainSpecCmd {
+    /// Run the chain-spec command
+    pub fn run(&self) -> sc_cli::Result<()> {
+        let config_contents = fs::read_to_string(&self.config)?;
+        let is_json = self
+            .config
+            .extension()
+            .and_then(|extension| extension.to_str())
+            == Some("json");
+        let config = parse_config(&config_contents, is_json)
+            .map_err(|error| sc_cli::Error::Input(format!("Failed to parse config: {error}")))?;
+
+        let chain_spec = chain_spec::custom_config(config.into_genesis().map_err(|error| {
+            sc_cli::Error::Input(format!("Invalid chain spec config: {error}"))
+        })?)?;
+
+        fs::create_dir_all(&self.output_dir)?;
+        for (file_name, raw) in [("chain-spec.json", false), ("chain-spec-raw.json", true)] {
+            let path = self.output_dir.join(file_name);
+            fs::write(&path, chain_spec.as_json(raw)?)?;
+            println!("{} written", path.display());
+        }
+
+        Ok(())
+    }
+}
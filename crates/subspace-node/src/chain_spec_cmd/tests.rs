@@ -0,0 +1,207 @@
+// Copyright (C) 2022 Subspace Labs, Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use super::{parse_config, ChainSpecConfig};
+use sp_core::crypto::Ss58Codec;
+use sp_core::{sr25519, Pair};
+use subspace_runtime_primitives::{AccountId, SSC};
+
+fn public_key(seed: &str) -> sr25519::Public {
+    sr25519::Pair::from_string(&format!("//{seed}"), None)
+        .unwrap()
+        .public()
+}
+
+fn address(seed: &str) -> String {
+    AccountId::from(public_key(seed)).to_ss58check()
+}
+
+/// TOML config with given balances and vesting schedules of `(account, amount)` form, vesting
+/// schedules have 10 periods.
+fn toml_config(balances: &[(&str, u128)], vesting: &[(&str, u128)]) -> String {
+    let mut config = format!(
+        r#"
+name = "Subspace devnet"
+id = "subspace_devnet"
+chain_type = "live"
+sudo = "{sudo}"
+
+[consensus]
+enable_rewards = true
+
+[executor]
+account = "{executor}"
+authority = "{executor_authority}"
+
+[secondary_chain]
+name = "Subspace devnet execution"
+id = "subspace_devnet_execution"
+"#,
+        sudo = address("Alice"),
+        executor = address("Bob"),
+        executor_authority = public_key("Bob").to_ss58check(),
+    );
+    for (account, amount) in balances {
+        config.push_str(&format!(
+            "\n[[balances]]\naccount = \"{account}\"\namount = {amount}\n"
+        ));
+    }
+    for (account, per_period) in vesting {
+        config.push_str(&format!(
+            "\n[[vesting]]\naccount = \"{account}\"\nstart = 100\nperiod = 10\nperiod_count = 10\n\
+            per_period = {per_period}\n"
+        ));
+    }
+    config
+}
+
+fn into_genesis_error(config: &str) -> String {
+    parse_config(config, false)
+        .unwrap()
+        .into_genesis()
+        .err()
+        .unwrap()
+}
+
+#[test]
+fn toml_and_json_configs_are_equivalent() {
+    let alice = address("Alice");
+    let charlie = address("Charlie");
+    let toml_config = toml_config(&[(&alice, 1_000), (&charlie, 500)], &[(&charlie, 10)]);
+    let json_config = serde_json::json!({
+        "name": "Subspace devnet",
+        "id": "subspace_devnet",
+        "chain_type": "live",
+        "sudo": alice,
+        "balances": [
+            { "account": alice, "amount": 1_000 },
+            { "account": charlie, "amount": 500 },
+        ],
+        "vesting": [
+            {
+                "account": charlie,
+                "start": 100,
+                "period": 10,
+                "period_count": 10,
+                "per_period": 10,
+            },
+        ],
+        "consensus": { "enable_rewards": true },
+        "executor": {
+            "account": address("Bob"),
+            "authority": public_key("Bob").to_ss58check(),
+        },
+        "secondary_chain": {
+            "name": "Subspace devnet execution",
+            "id": "subspace_devnet_execution",
+        },
+    })
+    .to_string();
+
+    let from_toml = parse_config(&toml_config, false)
+        .and_then(ChainSpecConfig::into_genesis)
+        .unwrap();
+    let from_json = parse_config(&json_config, true)
+        .and_then(ChainSpecConfig::into_genesis)
+        .unwrap();
+
+    assert_eq!(from_toml.name, from_json.name);
+    assert_eq!(from_toml.id, from_json.id);
+    assert_eq!(from_toml.sudo_account, from_json.sudo_account);
+    assert_eq!(from_toml.balances, from_json.balances);
+    assert_eq!(from_toml.vesting, from_json.vesting);
+    assert_eq!(from_toml.executor_authority, from_json.executor_authority);
+    assert_eq!(from_toml.enable_rewards, from_json.enable_rewards);
+    assert_eq!(
+        from_toml.enable_storage_access,
+        from_json.enable_storage_access
+    );
+
+    assert_eq!(
+        from_toml.balances,
+        vec![
+            (AccountId::from(public_key("Alice")), 1_000 * SSC),
+            (AccountId::from(public_key("Charlie")), 500 * SSC),
+        ]
+    );
+    assert!(from_toml.enable_rewards);
+    assert!(!from_toml.enable_storage_access);
+
+    // Formats are not interchangeable
+    assert!(parse_config(&toml_config, true).is_err());
+    assert!(parse_config(&json_config, false).is_err());
+}
+
+#[test]
+fn duplicate_balances_are_rejected() {
+    let alice = address("Alice");
+
+    assert_eq!(
+        into_genesis_error(&toml_config(&[(&alice, 1_000), (&alice, 500)], &[])),
+        format!("Balance of {alice} is specified more than once")
+    );
+}
+
+#[test]
+fn vesting_exceeding_balance_is_rejected() {
+    let alice = address("Alice");
+    let charlie = address("Charlie");
+
+    // 10 periods of 10 SSC fit into the balance exactly
+    assert!(
+        parse_config(&toml_config(&[(&charlie, 100)], &[(&charlie, 10)]), false)
+            .unwrap()
+            .into_genesis()
+            .is_ok()
+    );
+    assert_eq!(
+        into_genesis_error(&toml_config(&[(&charlie, 99)], &[(&charlie, 10)])),
+        format!("Balance of {charlie} is less than its total vested amount")
+    );
+    // Schedules of the same account add up
+    assert_eq!(
+        into_genesis_error(&toml_config(
+            &[(&charlie, 150)],
+            &[(&charlie, 10), (&charlie, 10)]
+        )),
+        format!("Balance of {charlie} is less than its total vested amount")
+    );
+    // Account without balance can't have vesting schedule
+    assert_eq!(
+        into_genesis_error(&toml_config(&[(&alice, 1_000)], &[(&charlie, 10)])),
+        format!("Balance of {charlie} is less than its total vested amount")
+    );
+}
+
+#[test]
+fn invalid_ss58_addresses_are_rejected() {
+    let alice = address("Alice");
+    // Flip the last character, which breaks the checksum
+    let mut invalid = alice.clone();
+    let last = invalid.pop().unwrap();
+    invalid.push(if last == 'a' { 'b' } else { 'a' });
+
+    assert!(into_genesis_error(&toml_config(&[(&invalid, 1_000)], &[]))
+        .starts_with(&format!("Invalid account address {invalid}:")));
+    assert!(
+        into_genesis_error(&toml_config(&[(&alice, 1_000)], &[(&invalid, 10)]))
+            .starts_with(&format!("Invalid account address {invalid}:"))
+    );
+    assert!(into_genesis_error(
+        &toml_config(&[(&alice, 1_000)], &[]).replace(&alice, "not-an-address")
+    )
+    .starts_with("Invalid account address not-an-address:"));
+}
@@ -17,11 +17,13 @@
 //! Subspace Node library.
 
 mod chain_spec;
+mod chain_spec_cmd;
 mod chain_spec_utils;
 mod import_blocks_from_dsn;
 mod secondary_chain;
 
 pub use crate::chain_spec::{ChainSpecExtensions, ConsensusChainSpec};
+pub use crate::chain_spec_cmd::ChainSpecCmd;
 pub use crate::import_blocks_from_dsn::ImportBlocksFromDsnCmd;
 pub use crate::secondary_chain::chain_spec::ExecutionChainSpec;
 pub use crate::secondary_chain::cli::SecondaryChainCli;
@@ -128,6 +130,9 @@ pub enum Subcommand {
     /// Build a chain specification.
     BuildSpec(sc_cli::BuildSpecCmd),
 
+    /// Generate chain specification of a custom network from a config file.
+    ChainSpec(ChainSpecCmd),
+
     /// Validate blocks.
     CheckBlock(sc_cli::CheckBlockCmd),
 
@@ -19,7 +19,7 @@
 use crate::chain_spec_utils::{
     chain_spec_properties, get_account_id_from_seed, SerializableChainSpec,
 };
-use cirrus_runtime::AccountId;
+use cirrus_runtime::{AccountId, Balance};
 use sc_service::ChainType;
 use sp_core::crypto::Ss58Codec;
 use subspace_runtime_primitives::SSC;
@@ -116,7 +116,42 @@ pub fn gemini_config() -> ExecutionChainSpec {
     )
 }
 
+/// Chain spec of a custom network with given pre-funded accounts.
+pub(crate) fn custom_config(
+    name: &str,
+    id: &str,
+    protocol_id: Option<&str>,
+    balances: Vec<(AccountId, Balance)>,
+) -> ExecutionChainSpec {
+    ExecutionChainSpec::from_genesis(
+        name,
+        id,
+        ChainType::Custom(name.to_string()),
+        move || genesis_config(balances.clone()),
+        // Bootnodes
+        vec![],
+        // Telemetry
+        None,
+        // Protocol ID
+        protocol_id,
+        None,
+        // Properties
+        Some(chain_spec_properties()),
+        // Extensions
+        None,
+    )
+}
+
 fn testnet_genesis(endowed_accounts: Vec<AccountId>) -> cirrus_runtime::GenesisConfig {
+    genesis_config(
+        endowed_accounts
+            .into_iter()
+            .map(|k| (k, 1_000 * SSC))
+            .collect(),
+    )
+}
+
+fn genesis_config(balances: Vec<(AccountId, Balance)>) -> cirrus_runtime::GenesisConfig {
     cirrus_runtime::GenesisConfig {
         system: cirrus_runtime::SystemConfig {
             code: cirrus_runtime::WASM_BINARY
@@ -124,12 +159,6 @@ fn testnet_genesis(endowed_accounts: Vec<AccountId>) -> cirrus_runtime::GenesisC
                 .to_vec(),
         },
         transaction_payment: Default::default(),
-        balances: cirrus_runtime::BalancesConfig {
-            balances: endowed_accounts
-                .iter()
-                .cloned()
-                .map(|k| (k, 1_000 * SSC))
-                .collect(),
-        },
+        balances: cirrus_runtime::BalancesConfig { balances },
     }
 }
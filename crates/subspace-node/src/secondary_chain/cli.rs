@@ -39,10 +39,24 @@ pub enum Subcommand {
     Benchmark(frame_benchmarking_cli::BenchmarkCmd),
 }
 
+/// Arguments accepted by the secondary chain, on top of the regular [`RunCmd`] ones.
+#[derive(Debug, Parser)]
+struct SecondaryChainArgs {
+    #[clap(flatten)]
+    run: RunCmd,
+
+    /// Keep all execution receipts instead of pruning the old ones.
+    #[clap(long)]
+    archive_execution_receipts: bool,
+}
+
 pub struct SecondaryChainCli {
     /// Run a node.
     pub run: RunCmd,
 
+    /// Keep all execution receipts instead of pruning the old ones.
+    pub archive_execution_receipts: bool,
+
     /// The base path that should be used by the secondary chain.
     pub base_path: Option<PathBuf>,
 
@@ -59,10 +73,16 @@ impl SecondaryChainCli {
         chain_spec: ExecutionChainSpec,
         secondary_chain_args: impl Iterator<Item = &'a String>,
     ) -> Self {
+        let SecondaryChainArgs {
+            run,
+            archive_execution_receipts,
+        } = SecondaryChainArgs::parse_from(secondary_chain_args);
+
         Self {
             base_path,
             chain_spec,
-            run: RunCmd::parse_from(secondary_chain_args),
+            run,
+            archive_execution_receipts,
         }
     }
 }
@@ -0,0 +1,529 @@
+// Copyright (C) 2022 Subspace Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Retrieval of objects from archived history of the Subspace Network.
+//!
+//! Objects are located by [`GlobalObject`] mappings, which point at the beginning of the SCALE
+//! encoded object within a piece. [`ObjectRetriever`] reads the records of as many consecutive
+//! pieces as needed, either directly or by decoding whole segments when an object crosses a
+//! segment boundary, and returns the exact object bytes.
+//!
+//! Objects mapped by their SHA-256 hash are retrieved with [`ObjectRetriever::retrieve_object`],
+//! which checks the object against the hash. Objects of feeds can be mapped by custom keys that
+//! are not derived from their content, those are retrieved with
+//! [`ObjectRetriever::retrieve_mapped_object`] instead.
+
+#![warn(missing_docs)]
+
+#[cfg(test)]
+mod tests;
+
+use async_trait::async_trait;
+use parity_scale_codec::{Compact, CompactLen, Decode, Encode};
+use std::error::Error;
+use subspace_archiving::archiver::{Segment, SegmentItem};
+use subspace_core_primitives::objects::{GlobalObject, ObjectManifest};
+use subspace_core_primitives::{crypto, Piece, PieceIndex, Sha256Hash};
+use tracing::{debug, error};
+
+/// Maximum expected size of one object in bytes
+const MAX_OBJECT_SIZE: usize = 5 * 1024 * 1024;
+/// Maximum expected size of one object uploaded in chunks in bytes
+const MAX_CHUNKED_OBJECT_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// Source of pieces of archived history, e.g. local plot or DSN.
+#[async_trait]
+pub trait PieceGetter {
+    /// Get piece by its index, returns `Ok(None)` if piece is not available.
+    async fn get_piece(
+        &self,
+        piece_index: PieceIndex,
+    ) -> Result<Option<Piece>, Box<dyn Error + Send + Sync + 'static>>;
+}
+
+/// Source of object mappings.
+#[async_trait]
+pub trait ObjectMappingGetter {
+    /// Get location of the object in archived history by its hash, returns `Ok(None)` if object
+    /// is not known.
+    async fn get_object_mapping(
+        &self,
+        object_hash: Sha256Hash,
+    ) -> Result<Option<GlobalObject>, Box<dyn Error + Send + Sync + 'static>>;
+}
+
+/// Object retrieval error
+#[derive(Debug, thiserror::Error)]
+pub enum ObjectRetrievalError {
+    /// Failed to get object mapping
+    #[error("Failed to get mapping of object {object_hash}: {error}")]
+    ObjectMapping {
+        /// Hex encoded object hash
+        object_hash: String,
+        /// Underlying error
+        error: Box<dyn Error + Send + Sync + 'static>,
+    },
+    /// Failed to get piece
+    #[error("Failed to get piece {piece_index}: {error}")]
+    PieceGetter {
+        /// Piece index
+        piece_index: PieceIndex,
+        /// Underlying error
+        error: Box<dyn Error + Send + Sync + 'static>,
+    },
+    /// Piece is not available
+    #[error("Piece {0} not found")]
+    PieceNotFound(PieceIndex),
+    /// Invalid data length prefix of the object
+    #[error("Invalid data length prefix found: 0x{0:02x}")]
+    InvalidLengthPrefix(u8),
+    /// Failed to decode data length of the object
+    #[error("Failed to read object data length: {0}")]
+    InvalidLength(parity_scale_codec::Error),
+    /// Failed to decode segment
+    #[error("Failed to decode segment {segment_index} of archival history: {error}")]
+    SegmentDecoding {
+        /// Segment index
+        segment_index: u64,
+        /// Underlying error
+        error: parity_scale_codec::Error,
+    },
+    /// No block item at the offset in the segment
+    #[error("Failed to find block item at offset {offset} in segment {segment_index}")]
+    SegmentItemNotFound {
+        /// Offset in the segment
+        offset: u64,
+        /// Segment index
+        segment_index: u64,
+    },
+    /// Object is larger than expected
+    #[error("Object of {size} bytes exceeds max object size")]
+    ObjectTooLarge {
+        /// Size of the object in bytes
+        size: u64,
+    },
+    /// Object doesn't match its hash
+    #[error("Object doesn't match its hash {object_hash}")]
+    InvalidObjectHash {
+        /// Hex encoded object hash
+        object_hash: String,
+    },
+    /// Chunk of the object is not known
+    #[error("Chunk {chunk_hash} of object not found")]
+    ChunkNotFound {
+        /// Hex encoded chunk hash
+        chunk_hash: String,
+    },
+    /// Chunks of the object don't match the size in its manifest
+    #[error("Object has {size} bytes, while its manifest expects {expected_size}")]
+    InvalidObjectSize {
+        /// Size of the chunks in bytes
+        size: u64,
+        /// Size in the manifest in bytes
+        expected_size: u64,
+    },
+}
+
+/// Retrieves objects from archived history using provided object mappings and pieces.
+#[derive(Debug)]
+pub struct ObjectRetriever<OM, PG> {
+    record_size: u32,
+    merkle_num_leaves: u32,
+    object_mappings: OM,
+    piece_getter: PG,
+}
+
+impl<OM, PG> ObjectRetriever<OM, PG>
+where
+    OM: ObjectMappingGetter,
+    PG: PieceGetter,
+{
+    /// Create new object retriever
+    pub fn new(
+        record_size: u32,
+        recorded_history_segment_size: u32,
+        object_mappings: OM,
+        piece_getter: PG,
+    ) -> Self {
+        Self {
+            record_size,
+            merkle_num_leaves: recorded_history_segment_size / record_size * 2,
+            object_mappings,
+            piece_getter,
+        }
+    }
+
+    /// Retrieve object by its hash, returns `Ok(None)` if object is not known.
+    ///
+    /// Retrieved object is checked against its hash. If the object is an [`ObjectManifest`],
+    /// the whole object is reassembled from its chunks.
+    pub async fn retrieve_object(
+        &self,
+        object_hash: Sha256Hash,
+    ) -> Result<Option<Vec<u8>>, ObjectRetrievalError> {
+        let global_object = match self.object_mapping(object_hash).await? {
+            Some(global_object) => global_object,
+            None => {
+                debug!(object_hash = %hex::encode(object_hash), "Object not found");
+
+                return Ok(None);
+            }
+        };
+
+        let object = self.fetch_object(global_object).await?;
+        if crypto::sha256_hash(&object) != object_hash {
+            return Err(ObjectRetrievalError::InvalidObjectHash {
+                object_hash: hex::encode(object_hash),
+            });
+        }
+
+        self.reassemble_object(object).await.map(Some)
+    }
+
+    /// Retrieve object by the key of its mapping along with the location of the object, returns
+    /// `Ok(None)` if object is not known.
+    ///
+    /// Unlike [`Self::retrieve_object`], retrieved object is not checked against the key, since
+    /// objects of feeds can be mapped by keys that are not derived from their content. Chunks of
+    /// an object that is an [`ObjectManifest`] are still checked against their hashes.
+    pub async fn retrieve_mapped_object(
+        &self,
+        key: Sha256Hash,
+    ) -> Result<Option<(GlobalObject, Vec<u8>)>, ObjectRetrievalError> {
+        let global_object = match self.object_mapping(key).await? {
+            Some(global_object) => global_object,
+            None => {
+                debug!(key = %hex::encode(key), "Object not found");
+
+                return Ok(None);
+            }
+        };
+
+        let object = self.fetch_object(global_object).await?;
+
+        self.reassemble_object(object)
+            .await
+            .map(|object| Some((global_object, object)))
+    }
+
+    /// Reassemble the whole object if given object is an [`ObjectManifest`], otherwise return it
+    /// as is.
+    async fn reassemble_object(&self, object: Vec<u8>) -> Result<Vec<u8>, ObjectRetrievalError> {
+        match ObjectManifest::from_object(&object) {
+            Some(manifest) => self.fetch_chunked_object(manifest).await,
+            None => Ok(object),
+        }
+    }
+
+    /// Get location of the object in archived history by its hash.
+    pub async fn object_mapping(
+        &self,
+        object_hash: Sha256Hash,
+    ) -> Result<Option<GlobalObject>, ObjectRetrievalError> {
+        self.object_mappings
+            .get_object_mapping(object_hash)
+            .await
+            .map_err(|error| ObjectRetrievalError::ObjectMapping {
+                object_hash: hex::encode(object_hash),
+                error,
+            })
+    }
+
+    /// Reassemble object uploaded in chunks by retrieving and concatenating all chunks listed in
+    /// its manifest, each chunk is checked against its hash.
+    pub async fn fetch_chunked_object(
+        &self,
+        manifest: ObjectManifest,
+    ) -> Result<Vec<u8>, ObjectRetrievalError> {
+        if manifest.size > MAX_CHUNKED_OBJECT_SIZE {
+            return Err(ObjectRetrievalError::ObjectTooLarge {
+                size: manifest.size,
+            });
+        }
+
+        // Size in the manifest is not trusted until all chunks are retrieved, so buffer is not
+        // preallocated
+        let mut data = Vec::new();
+        for chunk_hash in manifest.chunks {
+            let global_object = self.object_mapping(chunk_hash).await?.ok_or_else(|| {
+                ObjectRetrievalError::ChunkNotFound {
+                    chunk_hash: hex::encode(chunk_hash),
+                }
+            })?;
+
+            let chunk = self.fetch_object(global_object).await?;
+            if crypto::sha256_hash(&chunk) != chunk_hash {
+                return Err(ObjectRetrievalError::InvalidObjectHash {
+                    object_hash: hex::encode(chunk_hash),
+                });
+            }
+
+            if data.len() as u64 + chunk.len() as u64 > manifest.size {
+                return Err(ObjectRetrievalError::InvalidObjectSize {
+                    size: data.len() as u64 + chunk.len() as u64,
+                    expected_size: manifest.size,
+                });
+            }
+
+            data.extend_from_slice(&chunk);
+        }
+
+        if data.len() as u64 != manifest.size {
+            return Err(ObjectRetrievalError::InvalidObjectSize {
+                size: data.len() as u64,
+                expected_size: manifest.size,
+            });
+        }
+
+        Ok(data)
+    }
+
+    /// Fetch object that starts at given location by reading necessary pieces and putting
+    /// necessary bytes together.
+    ///
+    /// Object is not checked against any hash, since objects can be mapped by keys that are not
+    /// derived from their content.
+    pub async fn fetch_object(
+        &self,
+        global_object: GlobalObject,
+    ) -> Result<Vec<u8>, ObjectRetrievalError> {
+        let piece_index = global_object.piece_index();
+        let offset = global_object.offset();
+
+        // Try fast object assembling
+        if let Some(data) = self.fetch_object_fast(piece_index, offset).await? {
+            return Ok(data);
+        }
+
+        self.fetch_object_regular(piece_index, offset).await
+    }
+
+    /// Fast object fetching in case object doesn't cross piece (super fast) or segment (just
+    /// fast) boundary, returns `Ok(None)` if fast retrieval possibility is not guaranteed.
+    async fn fetch_object_fast(
+        &self,
+        piece_index: PieceIndex,
+        offset: u16,
+    ) -> Result<Option<Vec<u8>>, ObjectRetrievalError> {
+        // We care if the offset is before the last 2 bytes of a piece because if not we might be
+        // able to do very fast object retrieval without assembling and processing the whole
+        // segment. `-2` is because last 2 bytes might contain padding if a piece is the last piece
+        // in the segment.
+        let before_last_two_bytes = u32::from(offset) <= self.record_size - 1 - 2;
+
+        // We care about whether piece index points to the last data piece in the segment because
+        // if not we might be able to do very fast object retrieval without assembling and
+        // processing the whole segment.
+        let last_data_piece_in_segment = {
+            let piece_position_in_segment = piece_index % u64::from(self.merkle_num_leaves);
+            let last_piece_position_in_segment = u64::from(self.merkle_num_leaves) / 2 - 1;
+
+            piece_position_in_segment >= last_piece_position_in_segment
+        };
+
+        // How much bytes are definitely available starting at `piece_index` and `offset` without
+        // crossing segment boundary
+        let bytes_available_in_segment = {
+            let data_shards = u64::from(self.merkle_num_leaves / 2);
+            let piece_position = piece_index % u64::from(self.merkle_num_leaves);
+
+            // `-2` is because last 2 bytes might contain padding if a piece is the last piece in
+            // the segment.
+            (data_shards - piece_position) * u64::from(self.record_size) - u64::from(offset) - 2
+        };
+
+        if last_data_piece_in_segment && !before_last_two_bytes {
+            // Fast retrieval possibility is not guaranteed
+            return Ok(None);
+        }
+
+        // Cache of read records that were already read, starting with piece at index
+        // `piece_index`
+        let mut read_records_data = Vec::<u8>::with_capacity(self.record_size as usize * 2);
+        let mut next_piece_index = piece_index;
+
+        read_records_data.extend_from_slice(&self.read_record(next_piece_index).await?);
+        next_piece_index += 1;
+
+        // Let's see how many bytes encode compact length encoding of the data, see
+        // https://docs.substrate.io/v3/advanced/scale-codec/#compactgeneral-integers for
+        // details.
+        let data_length_bytes_length: u32 = match read_records_data[offset as usize] % 4 {
+            0 => 1,
+            1 => 2,
+            2 => 4,
+            _ => {
+                return Err(ObjectRetrievalError::InvalidLengthPrefix(
+                    read_records_data[offset as usize],
+                ));
+            }
+        };
+
+        // Same as `before_last_two_bytes`, but accounts for compact encoding of data length
+        let length_before_last_two_bytes =
+            u32::from(offset) + data_length_bytes_length < self.record_size - 1 - 2;
+        // Similar to `length_before_last_two_bytes`, but uses the whole record if needed
+        let length_before_record_end =
+            u32::from(offset) + data_length_bytes_length < self.record_size - 1;
+
+        let data_length_result = if length_before_last_two_bytes {
+            Compact::<u32>::decode(&mut &read_records_data[offset as usize..])
+        } else if !last_data_piece_in_segment {
+            if !length_before_record_end {
+                // Need the next piece to read the length of data
+                read_records_data.extend_from_slice(&self.read_record(next_piece_index).await?);
+                next_piece_index += 1;
+            }
+
+            Compact::<u32>::decode(&mut &read_records_data[offset as usize..])
+        } else {
+            // Super fast read is not possible
+            return Ok(None);
+        };
+
+        let Compact(data_length) =
+            data_length_result.map_err(ObjectRetrievalError::InvalidLength)?;
+
+        if u64::from(data_length_bytes_length + data_length) > bytes_available_in_segment {
+            // Not enough data without crossing segment boundary
+            return Ok(None);
+        }
+
+        let mut data =
+            read_records_data[offset as usize + data_length_bytes_length as usize..].to_vec();
+        drop(read_records_data);
+
+        // Read more pieces until we have enough data
+        while data.len() <= data_length as usize {
+            data.extend_from_slice(&self.read_record(next_piece_index).await?);
+            next_piece_index += 1;
+        }
+
+        // Trim the excess
+        data.truncate(data_length as usize);
+
+        Ok(Some(data))
+    }
+
+    /// Fetch object that can cross segment boundary, which requires assembling and iterating
+    /// over full segments.
+    async fn fetch_object_regular(
+        &self,
+        piece_index: PieceIndex,
+        offset: u16,
+    ) -> Result<Vec<u8>, ObjectRetrievalError> {
+        let segment_index = piece_index / u64::from(self.merkle_num_leaves);
+        let piece_position_in_segment = piece_index % u64::from(self.merkle_num_leaves);
+        let offset_in_segment =
+            piece_position_in_segment * u64::from(self.record_size) + u64::from(offset);
+
+        let mut data = {
+            let Segment::V0 { items } = self.read_segment(segment_index).await?;
+            // Unconditional progress is enum variant + compact encoding of number of elements
+            let mut progress = 1 + Compact::compact_len(&(items.len() as u64));
+            let segment_item = items
+                .into_iter()
+                .find(|item| {
+                    // Add number of bytes in encoded version of segment item
+                    progress += item.encoded_size();
+
+                    // Our data is within another segment item, which will have wrapping data
+                    // structure, hence strictly `>` here
+                    progress > offset_in_segment as usize
+                })
+                .ok_or(ObjectRetrievalError::SegmentItemNotFound {
+                    offset: offset_in_segment,
+                    segment_index,
+                })?;
+
+            match segment_item {
+                SegmentItem::Block { bytes, .. }
+                | SegmentItem::BlockStart { bytes, .. }
+                | SegmentItem::BlockContinuation { bytes, .. } => {
+                    // Rewind back progress to the beginning of the number of bytes
+                    progress -= bytes.len();
+                    // Get a chunk of the bytes starting at the position we care about
+                    Vec::from(&bytes[offset_in_segment as usize - progress..])
+                }
+                segment_item => {
+                    error!(
+                        ?segment_item,
+                        offset_in_segment, segment_index, "Unexpected segment item",
+                    );
+
+                    return Err(ObjectRetrievalError::SegmentItemNotFound {
+                        offset: offset_in_segment,
+                        segment_index,
+                    });
+                }
+            }
+        };
+
+        if let Ok(data) = Vec::<u8>::decode(&mut data.as_slice()) {
+            return Ok(data);
+        }
+
+        for segment_index in segment_index + 1.. {
+            let Segment::V0 { items } = self.read_segment(segment_index).await?;
+            for segment_item in items {
+                if let SegmentItem::BlockContinuation { bytes, .. } = segment_item {
+                    data.extend_from_slice(&bytes);
+
+                    if let Ok(data) = Vec::<u8>::decode(&mut data.as_slice()) {
+                        return Ok(data);
+                    }
+                }
+            }
+
+            if data.len() >= MAX_OBJECT_SIZE {
+                break;
+            }
+        }
+
+        Err(ObjectRetrievalError::ObjectTooLarge {
+            size: data.len() as u64,
+        })
+    }
+
+    /// Read the whole segment by its index (just records, skipping witnesses)
+    async fn read_segment(&self, segment_index: u64) -> Result<Segment, ObjectRetrievalError> {
+        let first_piece_in_segment = segment_index * u64::from(self.merkle_num_leaves);
+        let mut segment_bytes =
+            Vec::<u8>::with_capacity((self.merkle_num_leaves * self.record_size) as usize);
+
+        for piece_index in (first_piece_in_segment..).take(self.merkle_num_leaves as usize / 2) {
+            segment_bytes.extend_from_slice(&self.read_record(piece_index).await?);
+        }
+
+        Segment::decode(&mut segment_bytes.as_slice()).map_err(|error| {
+            ObjectRetrievalError::SegmentDecoding {
+                segment_index,
+                error,
+            }
+        })
+    }
+
+    /// Read record of the piece, stripping its Merkle witness
+    async fn read_record(&self, piece_index: PieceIndex) -> Result<Vec<u8>, ObjectRetrievalError> {
+        let piece = self
+            .piece_getter
+            .get_piece(piece_index)
+            .await
+            .map_err(|error| ObjectRetrievalError::PieceGetter { piece_index, error })?
+            .ok_or(ObjectRetrievalError::PieceNotFound(piece_index))?;
+
+        Ok(piece[..self.record_size as usize].to_vec())
+    }
+}
@@ -0,0 +1,242 @@
+use crate::{ObjectMappingGetter, ObjectRetrievalError, ObjectRetriever, PieceGetter};
+use async_trait::async_trait;
+use parity_scale_codec::Encode;
+use std::collections::HashMap;
+use std::error::Error;
+use subspace_archiving::archiver::Archiver;
+use subspace_core_primitives::objects::{
+    BlockObject, BlockObjectMapping, GlobalObject, ObjectManifest, PieceObject,
+};
+use subspace_core_primitives::{
+    crypto, Piece, PieceIndex, Sha256Hash, PIECE_SIZE, SHA256_HASH_SIZE,
+};
+
+const MERKLE_NUM_LEAVES: usize = 8_usize;
+const WITNESS_SIZE: usize = SHA256_HASH_SIZE * MERKLE_NUM_LEAVES.log2() as usize;
+const RECORD_SIZE: usize = PIECE_SIZE - WITNESS_SIZE;
+const SEGMENT_SIZE: usize = RECORD_SIZE * MERKLE_NUM_LEAVES / 2;
+
+#[derive(Default)]
+struct TestPieceGetter(HashMap<PieceIndex, Piece>);
+
+#[async_trait]
+impl PieceGetter for TestPieceGetter {
+    async fn get_piece(
+        &self,
+        piece_index: PieceIndex,
+    ) -> Result<Option<Piece>, Box<dyn Error + Send + Sync + 'static>> {
+        Ok(self.0.get(&piece_index).cloned())
+    }
+}
+
+#[derive(Default)]
+struct TestObjectMappings(HashMap<Sha256Hash, GlobalObject>);
+
+#[async_trait]
+impl ObjectMappingGetter for TestObjectMappings {
+    async fn get_object_mapping(
+        &self,
+        object_hash: Sha256Hash,
+    ) -> Result<Option<GlobalObject>, Box<dyn Error + Send + Sync + 'static>> {
+        Ok(self.0.get(&object_hash).copied())
+    }
+}
+
+fn filler(size: usize) -> Vec<u8> {
+    (0..size).map(|i| i as u8).collect()
+}
+
+/// Archives a block with given objects separated by filler bytes and returns retriever for them
+fn create_retriever(objects: &[Vec<u8>]) -> ObjectRetriever<TestObjectMappings, TestPieceGetter> {
+    let mut block = Vec::new();
+    let mut block_object_mapping = BlockObjectMapping::default();
+    for object in objects {
+        block.extend(filler(123));
+        block_object_mapping.objects.push(BlockObject::V0 {
+            hash: crypto::sha256_hash(object),
+            offset: block.len() as u32,
+        });
+        object.encode_to(&mut block);
+    }
+
+    let mut archiver = Archiver::new(RECORD_SIZE, SEGMENT_SIZE).unwrap();
+    let mut archived_segments = archiver.add_block(block, block_object_mapping);
+    // Push everything out of the archiver
+    archived_segments.extend(archiver.add_block(filler(SEGMENT_SIZE), Default::default()));
+
+    let mut piece_getter = TestPieceGetter::default();
+    let mut object_mappings = TestObjectMappings::default();
+    for (segment_index, archived_segment) in archived_segments.into_iter().enumerate() {
+        let piece_index_offset = (MERKLE_NUM_LEAVES * segment_index) as u64;
+
+        for (position, piece) in archived_segment.pieces.as_pieces().enumerate() {
+            piece_getter.0.insert(
+                piece_index_offset + position as u64,
+                piece.try_into().unwrap(),
+            );
+        }
+        for (position, piece_object_mapping) in
+            archived_segment.object_mapping.into_iter().enumerate()
+        {
+            for piece_object in piece_object_mapping.objects {
+                let PieceObject::V0 { hash, offset } = piece_object;
+                object_mappings.0.insert(
+                    hash,
+                    GlobalObject::V0 {
+                        piece_index: piece_index_offset + position as u64,
+                        offset,
+                    },
+                );
+            }
+        }
+    }
+
+    ObjectRetriever::new(
+        RECORD_SIZE as u32,
+        SEGMENT_SIZE as u32,
+        object_mappings,
+        piece_getter,
+    )
+}
+
+#[tokio::test]
+async fn retrieve_objects() {
+    let small_object = filler(100);
+    let cross_piece_object = filler(RECORD_SIZE + 500);
+    let cross_segment_object = filler(SEGMENT_SIZE + 1000);
+    let objects = vec![
+        small_object,
+        cross_piece_object,
+        cross_segment_object,
+        vec![42; 10],
+    ];
+
+    let object_retriever = create_retriever(&objects);
+
+    for object in objects {
+        assert_eq!(
+            object_retriever
+                .retrieve_object(crypto::sha256_hash(&object))
+                .await
+                .unwrap(),
+            Some(object)
+        );
+    }
+
+    assert_eq!(
+        object_retriever
+            .retrieve_object(crypto::sha256_hash(b"unknown"))
+            .await
+            .unwrap(),
+        None
+    );
+}
+
+#[tokio::test]
+async fn retrieve_chunked_object() {
+    let chunks = vec![filler(RECORD_SIZE), vec![1; 1000], vec![2; 10]];
+    let manifest = ObjectManifest::from_chunks(chunks.iter().map(Vec::as_slice));
+
+    let mut objects = chunks.clone();
+    objects.push(manifest.to_object());
+    let object_retriever = create_retriever(&objects);
+
+    assert_eq!(
+        object_retriever
+            .retrieve_object(manifest.content_root())
+            .await
+            .unwrap(),
+        Some(chunks.concat())
+    );
+}
+
+#[tokio::test]
+async fn missing_piece() {
+    let object = filler(100);
+    let mut object_retriever = create_retriever(&[object.clone()]);
+    object_retriever.piece_getter.0.clear();
+
+    assert!(matches!(
+        object_retriever
+            .retrieve_object(crypto::sha256_hash(&object))
+            .await,
+        Err(ObjectRetrievalError::PieceNotFound(0))
+    ));
+}
+
+#[tokio::test]
+async fn invalid_object_hash() {
+    let object = filler(100);
+    let object_hash = crypto::sha256_hash(&object);
+    let mut object_retriever = create_retriever(&[object]);
+
+    // Map another hash to the same location
+    let global_object = object_retriever.object_mappings.0[&object_hash];
+    let fake_hash = crypto::sha256_hash(b"fake");
+    object_retriever
+        .object_mappings
+        .0
+        .insert(fake_hash, global_object);
+
+    assert!(matches!(
+        object_retriever.retrieve_object(fake_hash).await,
+        Err(ObjectRetrievalError::InvalidObjectHash { .. })
+    ));
+    // Fetching by location doesn't check the hash
+    assert_eq!(
+        object_retriever.fetch_object(global_object).await.unwrap(),
+        filler(100)
+    );
+}
+
+#[tokio::test]
+async fn retrieve_object_mapped_by_custom_key() {
+    let object = filler(100);
+    let object_hash = crypto::sha256_hash(&object);
+    let mut object_retriever = create_retriever(&[object.clone()]);
+
+    // Feeds can map objects by keys that are not derived from their content
+    let global_object = object_retriever.object_mappings.0[&object_hash];
+    let custom_key = crypto::sha256_hash(b"custom key");
+    object_retriever
+        .object_mappings
+        .0
+        .insert(custom_key, global_object);
+
+    assert!(matches!(
+        object_retriever.retrieve_object(custom_key).await,
+        Err(ObjectRetrievalError::InvalidObjectHash { .. })
+    ));
+    assert_eq!(
+        object_retriever
+            .retrieve_mapped_object(custom_key)
+            .await
+            .unwrap(),
+        Some((global_object, object))
+    );
+    assert_eq!(
+        object_retriever
+            .retrieve_mapped_object(crypto::sha256_hash(b"unknown"))
+            .await
+            .unwrap(),
+        None
+    );
+}
+
+#[tokio::test]
+async fn chunked_object_larger_than_manifest() {
+    let chunks = vec![vec![1; 1000], vec![2; 10]];
+    let mut manifest = ObjectManifest::from_chunks(chunks.iter().map(Vec::as_slice));
+    // Size in the manifest is not trusted
+    manifest.size = 500;
+
+    let object_retriever = create_retriever(&chunks);
+
+    assert!(matches!(
+        object_retriever.fetch_chunked_object(manifest).await,
+        Err(ObjectRetrievalError::InvalidObjectSize {
+            size: 1000,
+            expected_size: 500,
+        })
+    ));
+}
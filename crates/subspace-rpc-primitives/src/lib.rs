@@ -86,3 +86,54 @@ pub struct RewardSignatureResponse {
     /// Pre-header or vote hash signature.
     pub signature: Option<RewardSignature>,
 }
+
+/// How solution range for the next era was determined.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SolutionRangeAdjustment {
+    /// Solution range adjustment is disabled, solution range stayed the same.
+    Disabled,
+    /// Solution range was overridden by root.
+    Override,
+    /// Solution range was adjusted according to actual and expected number of slots per block.
+    Computed,
+}
+
+/// Summary of a finished era and solution range adjustment at the end of it.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EraSummary {
+    /// Slot at which era started.
+    pub start_slot: SlotNumber,
+    /// Slot at which era ended.
+    pub end_slot: SlotNumber,
+    /// Number of blocks produced during era.
+    pub blocks: u64,
+    /// Number of votes included in blocks of era.
+    pub votes: u64,
+    /// Solution range used during era.
+    pub previous_solution_range: u64,
+    /// Solution range for the next era.
+    pub next_solution_range: u64,
+    /// Voting solution range used during era.
+    pub previous_voting_solution_range: u64,
+    /// Voting solution range for the next era.
+    pub next_voting_solution_range: u64,
+    /// How solution range for the next era was determined.
+    pub adjustment: SolutionRangeAdjustment,
+}
+
+/// Solution range adjustment analytics
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SolutionRangeAnalytics {
+    /// Summaries of recent eras, oldest first
+    pub era_history: Vec<EraSummary>,
+    /// Solution range for block authoring in current era
+    pub solution_range: u64,
+    /// Solution range for voting in current era
+    pub voting_solution_range: u64,
+    /// Estimate of total space pledged to the network (in bytes) derived from solution ranges of
+    /// recent eras
+    pub total_space_pledged: u128,
+}
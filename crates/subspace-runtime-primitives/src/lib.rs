@@ -62,6 +62,11 @@ pub const STORAGE_FEES_ESCROW_BLOCK_REWARD: (u64, u64) = (1, 10000);
 /// (with remaining issued to farmer immediately).
 // TODO: Proper value here
 pub const STORAGE_FEES_ESCROW_BLOCK_TAX: (u64, u64) = (1, 10);
+/// How much (ratio) of compute fees collected in a block should be given to the executor once the
+/// execution receipt of the block can't be challenged anymore (with remaining issued to farmer
+/// immediately).
+// TODO: Proper value here
+pub const EXECUTOR_COMPUTE_FEES_SHARE: (u64, u64) = (1, 2);
 
 /// The smallest unit of the token is called Shannon.
 pub const SHANNON: Balance = 1;
@@ -161,6 +166,17 @@ pub trait FindBlockRewardAddress<RewardAddress> {
     fn find_block_reward_address() -> Option<RewardAddress>;
 }
 
+/// A trait for handling the execution receipts of primary blocks once they can't be challenged
+/// anymore or were reverted, e.g. to reward their executors.
+pub trait OnExecutionReceipt<BlockNumber, AccountId> {
+    /// The execution receipt of given primary block authored by `executor` is past the challenge
+    /// period.
+    fn on_receipt_confirmed(primary_number: BlockNumber, executor: AccountId);
+
+    /// The execution receipt of given primary block was reverted by a fraud proof.
+    fn on_receipt_reverted(primary_number: BlockNumber);
+}
+
 /// A trait for finding the addresses for voting reward based on transactions found in the block.
 pub trait FindVotingRewardAddresses<RewardAddress> {
     /// Find the addresses for voting rewards based on transactions found in the block.
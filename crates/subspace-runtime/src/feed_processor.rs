@@ -1,11 +1,12 @@
 use crate::{FeedId, Runtime};
 use codec::{Decode, Encode};
+use pallet_beefy_finality_verifier::chain::Chain as BeefyChain;
 use pallet_feeds::feed_processor::{FeedMetadata, FeedObjectMapping, FeedProcessor};
-use pallet_grandpa_finality_verifier::chain::Chain;
+use pallet_grandpa_finality_verifier::chain::{Chain, SignedBlock};
 use scale_info::TypeInfo;
 use sp_api::HeaderT;
 use sp_core::Hasher;
-use sp_runtime::traits::BlakeTwo256;
+use sp_runtime::traits::{BlakeTwo256, Keccak256};
 use sp_runtime::{generic, DispatchError};
 use sp_std::prelude::*;
 
@@ -18,6 +19,14 @@ impl Chain for PolkadotLike {
     type Hasher = BlakeTwo256;
 }
 
+impl BeefyChain for PolkadotLike {
+    type BlockNumber = u32;
+    type Hash = <BlakeTwo256 as Hasher>::Out;
+    type Header = generic::Header<u32, BlakeTwo256>;
+    type Hasher = BlakeTwo256;
+    type MmrHasher = Keccak256;
+}
+
 /// Type used to represent a FeedId or ChainId
 struct GrandpaValidator<C>(C);
 
@@ -36,7 +45,7 @@ impl<C: Chain> FeedProcessor<FeedId> for GrandpaValidator<C> {
     }
 
     fn object_mappings(&self, _feed_id: FeedId, object: &[u8]) -> Vec<FeedObjectMapping> {
-        extract_substrate_object_mapping::<C>(object)
+        extract_substrate_object_mapping::<C::Header>(object)
     }
 
     fn delete(&self, feed_id: FeedId) -> sp_runtime::DispatchResult {
@@ -44,22 +53,59 @@ impl<C: Chain> FeedProcessor<FeedId> for GrandpaValidator<C> {
     }
 }
 
-struct ParachainImporter<C>(C);
+struct BeefyValidator<C>(C);
+
+impl<C: BeefyChain> FeedProcessor<FeedId> for BeefyValidator<C> {
+    fn init(&self, feed_id: FeedId, data: &[u8]) -> sp_runtime::DispatchResult {
+        pallet_beefy_finality_verifier::initialize::<Runtime, C>(feed_id, data)
+    }
+
+    fn put(&self, feed_id: FeedId, object: &[u8]) -> Result<Option<FeedMetadata>, DispatchError> {
+        Ok(Some(
+            pallet_beefy_finality_verifier::validate_finalized_block::<Runtime, C>(
+                feed_id, object,
+            )?
+            .encode(),
+        ))
+    }
+
+    fn object_mappings(&self, _feed_id: FeedId, object: &[u8]) -> Vec<FeedObjectMapping> {
+        extract_substrate_object_mapping::<C::Header>(object)
+    }
+
+    fn delete(&self, feed_id: FeedId) -> sp_runtime::DispatchResult {
+        pallet_beefy_finality_verifier::purge::<Runtime>(feed_id)
+    }
+}
+
+/// Parachain `C` validated against the finalized state of its relay chain `R`.
+struct ParachainImporter<C, R>(C, R);
+
+impl<C: Chain, R: Chain> FeedProcessor<FeedId> for ParachainImporter<C, R> {
+    fn init(&self, feed_id: FeedId, data: &[u8]) -> sp_runtime::DispatchResult {
+        pallet_grandpa_finality_verifier::initialize_parachain::<Runtime>(feed_id, data)
+    }
 
-impl<C: Chain> FeedProcessor<FeedId> for ParachainImporter<C> {
-    fn put(&self, _feed_id: FeedId, object: &[u8]) -> Result<Option<FeedMetadata>, DispatchError> {
-        let block = C::decode_block::<Runtime>(object)?;
+    fn put(&self, feed_id: FeedId, object: &[u8]) -> Result<Option<FeedMetadata>, DispatchError> {
         Ok(Some(
-            (block.block.header.hash(), *block.block.header.number()).encode(),
+            pallet_grandpa_finality_verifier::validate_parachain_block::<Runtime, C, R>(
+                feed_id, object,
+            )?
+            .encode(),
         ))
     }
+
     fn object_mappings(&self, _feed_id: FeedId, object: &[u8]) -> Vec<FeedObjectMapping> {
-        extract_substrate_object_mapping::<C>(object)
+        extract_substrate_object_mapping::<C::Header>(object)
+    }
+
+    fn delete(&self, feed_id: FeedId) -> sp_runtime::DispatchResult {
+        pallet_grandpa_finality_verifier::purge_parachain::<Runtime>(feed_id)
     }
 }
 
-fn extract_substrate_object_mapping<C: Chain>(object: &[u8]) -> Vec<FeedObjectMapping> {
-    let block = match C::decode_block::<Runtime>(object) {
+fn extract_substrate_object_mapping<Header: HeaderT>(object: &[u8]) -> Vec<FeedObjectMapping> {
+    let block = match SignedBlock::<Header>::decode(&mut &*object) {
         Ok(block) => block,
         // we just return empty if we failed to decode as this is not called in runtime
         Err(_) => return vec![],
@@ -88,8 +134,12 @@ pub enum FeedProcessorKind {
     ContentAddressable,
     /// Polkadot like relay chain Feed processor that validates grandpa justifications and indexes the entire block
     PolkadotLike,
-    /// Parachain Feed processor that just indexes the entire block
+    /// Parachain Feed processor that validates the block against the finalized state of its relay chain feed and indexes the entire block
+    ///
+    /// Feeds created before the validation was introduced must be re-initialized by their owner with `Feeds::update` and the parachain init data before new blocks are accepted
     ParachainLike,
+    /// Polkadot like relay chain Feed processor that validates BEEFY commitments and MMR proofs and indexes the entire block
+    PolkadotLikeBeefy,
 }
 
 impl Default for FeedProcessorKind {
@@ -104,6 +154,7 @@ pub(crate) fn feed_processor(
     match feed_processor_kind {
         FeedProcessorKind::PolkadotLike => Box::new(GrandpaValidator(PolkadotLike)),
         FeedProcessorKind::ContentAddressable => Box::new(()),
-        FeedProcessorKind::ParachainLike => Box::new(ParachainImporter(PolkadotLike)),
+        FeedProcessorKind::ParachainLike => Box::new(ParachainImporter(PolkadotLike, PolkadotLike)),
+        FeedProcessorKind::PolkadotLikeBeefy => Box::new(BeefyValidator(PolkadotLike)),
     }
 }
@@ -1,9 +1,10 @@
-use crate::{Balances, Call, Runtime, TransactionFees};
+use crate::{Balances, Call, Runtime, TransactionFees, TransactionPayment, UncheckedExtrinsic};
 use codec::Encode;
 use frame_support::traits::{Currency, ExistenceRequirement, Get, Imbalance, WithdrawReasons};
 use pallet_balances::NegativeImbalance;
 use sp_runtime::traits::{DispatchInfoOf, PostDispatchInfoOf, Zero};
 use sp_runtime::transaction_validity::{InvalidTransaction, TransactionValidityError};
+use sp_transaction_fees::FeeBreakdown;
 use subspace_runtime_primitives::{AccountId, Balance};
 
 pub struct TransactionByteFee;
@@ -106,3 +107,36 @@ impl pallet_transaction_payment::OnChargeTransaction<Runtime> for OnChargeTransa
         Ok(())
     }
 }
+
+/// Breakdown of fees of storing `size` bytes in the history of the blockchain.
+pub(crate) fn storage_fee_quote(size: u32) -> FeeBreakdown<Balance> {
+    let transaction_byte_fee = TransactionByteFee::get();
+
+    TransactionFees::fee_breakdown(
+        transaction_byte_fee,
+        transaction_byte_fee * Balance::from(size),
+        Zero::zero(),
+    )
+}
+
+/// Breakdown of fees of the extrinsic, split into storage and compute fees the same way as
+/// [`OnChargeTransaction`] does it.
+pub(crate) fn query_fee_breakdown(uxt: UncheckedExtrinsic, len: u32) -> FeeBreakdown<Balance> {
+    let transaction_byte_fee = TransactionByteFee::get();
+    let storage_fee = transaction_byte_fee
+        * Balance::try_from(uxt.function.encoded_size())
+            .expect("Size of the call never exceeds balance units; qed");
+
+    let (storage_fee, compute_fee) =
+        match TransactionPayment::query_fee_details(uxt, len).inclusion_fee {
+            Some(inclusion_fee) => {
+                let fee = inclusion_fee.inclusion_fee();
+                let storage_fee = storage_fee.min(fee);
+                (storage_fee, fee - storage_fee)
+            }
+            // Unsigned extrinsics don't pay any fees
+            None => (Zero::zero(), Zero::zero()),
+        };
+
+    TransactionFees::fee_breakdown(transaction_byte_fee, storage_fee, compute_fee)
+}
@@ -33,7 +33,9 @@ include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
 use crate::feed_processor::feed_processor;
 pub use crate::feed_processor::FeedProcessorKind;
-use crate::fees::{OnChargeTransaction, TransactionByteFee};
+use crate::fees::{
+    query_fee_breakdown, storage_fee_quote, OnChargeTransaction, TransactionByteFee,
+};
 use crate::object_mapping::extract_block_object_mapping;
 use crate::signed_extensions::{CheckStorageAccess, DisablePallets};
 use codec::{Decode, Encode};
@@ -48,12 +50,13 @@ use pallet_feeds::feed_processor::FeedProcessor;
 use sp_api::{impl_runtime_apis, BlockT, HashT, HeaderT};
 use sp_consensus_subspace::digests::CompatibleDigestItem;
 use sp_consensus_subspace::{
-    derive_randomness, EquivocationProof, FarmerPublicKey, GlobalRandomnesses, Salts, SignedVote,
-    SolutionRanges, Vote,
+    derive_randomness, EquivocationProof, EraSummary, FarmerPublicKey, GlobalRandomnesses, Salts,
+    SignedVote, SolutionRanges, Vote,
 };
 use sp_core::crypto::{ByteArray, KeyTypeId};
 use sp_core::OpaqueMetadata;
 use sp_executor::{FraudProof, OpaqueBundle};
+use sp_rewards::BlockRewards;
 use sp_runtime::traits::{AccountIdLookup, BlakeTwo256, NumberFor, Zero};
 use sp_runtime::transaction_validity::{TransactionSource, TransactionValidity};
 use sp_runtime::{
@@ -61,15 +64,17 @@ use sp_runtime::{
 };
 use sp_std::borrow::Cow;
 use sp_std::prelude::*;
+use sp_transaction_fees::FeeBreakdown;
 #[cfg(feature = "std")]
 use sp_version::NativeVersion;
 use sp_version::RuntimeVersion;
 use subspace_core_primitives::objects::BlockObjectMapping;
-use subspace_core_primitives::{Randomness, RootBlock, Sha256Hash, PIECE_SIZE};
+use subspace_core_primitives::{Randomness, RootBlock, Sha256Hash};
 use subspace_runtime_primitives::{
     opaque, AccountId, Balance, BlockNumber, Hash, Index, Moment, Signature, CONFIRMATION_DEPTH_K,
-    MAX_PLOT_SIZE, MIN_REPLICATION_FACTOR, RECORDED_HISTORY_SEGMENT_SIZE, RECORD_SIZE, SHANNON,
-    SSC, STORAGE_FEES_ESCROW_BLOCK_REWARD, STORAGE_FEES_ESCROW_BLOCK_TAX,
+    EXECUTOR_COMPUTE_FEES_SHARE, MAX_PLOT_SIZE, MIN_REPLICATION_FACTOR,
+    RECORDED_HISTORY_SEGMENT_SIZE, RECORD_SIZE, SHANNON, SSC, STORAGE_FEES_ESCROW_BLOCK_REWARD,
+    STORAGE_FEES_ESCROW_BLOCK_TAX,
 };
 
 sp_runtime::impl_opaque_keys! {
@@ -131,6 +136,8 @@ const GLOBAL_RANDOMNESS_UPDATE_INTERVAL: BlockNumber = 100;
 
 /// Era duration in blocks.
 const ERA_DURATION_IN_BLOCKS: BlockNumber = 2016;
+/// Number of recent eras for which summaries are kept on chain.
+const ERA_HISTORY_LENGTH: u32 = 32;
 
 const EQUIVOCATION_REPORT_LONGEVITY: BlockNumber = 256;
 
@@ -141,6 +148,9 @@ const EON_NEXT_SALT_REVEAL: u64 = EON_DURATION_IN_SLOTS
     .checked_sub(3600 * 24)
     .expect("Offset is smaller than eon duration; qed");
 
+/// Farmers stay in block list for 4 eons (4 weeks) after committing an offence
+const BLOCK_LIST_DURATION_IN_EONS: u64 = 4;
+
 // We assume initial plot size starts with the a single recorded history segment (which is erasure
 // coded of course, hence `*2`).
 const INITIAL_SOLUTION_RANGE: u64 =
@@ -263,6 +273,8 @@ impl pallet_subspace::Config for Runtime {
     type RecordedHistorySegmentSize = ConstU32<RECORDED_HISTORY_SEGMENT_SIZE>;
     type ExpectedVotesPerBlock = ExpectedVotesPerBlock;
     type ShouldAdjustSolutionRange = ShouldAdjustSolutionRange;
+    type EraHistoryLength = ConstU32<ERA_HISTORY_LENGTH>;
+    type BlockListDuration = ConstU64<BLOCK_LIST_DURATION_IN_EONS>;
     type GlobalRandomnessIntervalTrigger = pallet_subspace::NormalGlobalRandomnessInterval;
     type EraChangeTrigger = pallet_subspace::NormalEraChange;
     type EonChangeTrigger = pallet_subspace::NormalEonChange;
@@ -301,6 +313,10 @@ impl pallet_balances::Config for Runtime {
 parameter_types! {
     pub const StorageFeesEscrowBlockReward: (u64, u64) = STORAGE_FEES_ESCROW_BLOCK_REWARD;
     pub const StorageFeesEscrowBlockTax: (u64, u64) = STORAGE_FEES_ESCROW_BLOCK_TAX;
+    pub const ExecutorComputeFeesShare: (u64, u64) = EXECUTOR_COMPUTE_FEES_SHARE;
+    // Receipts are confirmed once pruned, which takes at least the pruning depth, twice that
+    // leaves room for the execution chain to lag behind
+    pub ExecutorFeesTimeout: BlockNumber = 2 * ReceiptsPruningDepth::get();
 }
 
 pub struct CreditSupply;
@@ -315,15 +331,7 @@ pub struct TotalSpacePledged;
 
 impl Get<u128> for TotalSpacePledged {
     fn get() -> u128 {
-        let piece_size = u128::try_from(PIECE_SIZE)
-            .expect("Piece size is definitely small enough to fit into u128; qed");
-        // Operations reordered to avoid data loss, but essentially are:
-        // u64::MAX * SlotProbability / (solution_range / PIECE_SIZE)
-        u128::from(u64::MAX)
-            .saturating_mul(piece_size)
-            .saturating_mul(u128::from(SlotProbability::get().0))
-            / u128::from(Subspace::solution_ranges().current)
-            / u128::from(SlotProbability::get().1)
+        Subspace::total_space_pledged()
     }
 }
 
@@ -340,6 +348,9 @@ impl pallet_transaction_fees::Config for Runtime {
     type MinReplicationFactor = ConstU16<MIN_REPLICATION_FACTOR>;
     type StorageFeesEscrowBlockReward = StorageFeesEscrowBlockReward;
     type StorageFeesEscrowBlockTax = StorageFeesEscrowBlockTax;
+    type ExecutorComputeFeesShare = ExecutorComputeFeesShare;
+    type ExecutorFeesTimeout = ExecutorFeesTimeout;
+    type MaxExecutorFeesBlocks = ConstU32<16>;
     type CreditSupply = CreditSupply;
     type TotalSpacePledged = TotalSpacePledged;
     type BlockchainHistorySize = BlockchainHistorySize;
@@ -384,6 +395,9 @@ impl pallet_offences_subspace::Config for Runtime {
 parameter_types! {
     pub const ReceiptsPruningDepth: BlockNumber = 256;
     pub const MaximumReceiptDrift: BlockNumber = 128;
+    pub const DisputeTimeout: BlockNumber = 32;
+    pub const DisputeBond: Balance = 100 * SSC;
+    pub const MaxDisputesPerReceipt: u32 = 16;
 }
 
 impl pallet_executor::Config for Runtime {
@@ -391,11 +405,19 @@ impl pallet_executor::Config for Runtime {
     type SecondaryHash = cirrus_primitives::Hash;
     type ReceiptsPruningDepth = ReceiptsPruningDepth;
     type MaximumReceiptDrift = MaximumReceiptDrift;
+    type DisputeTimeout = DisputeTimeout;
+    type Currency = Balances;
+    type DisputeBond = DisputeBond;
+    type MaxDisputesPerReceipt = MaxDisputesPerReceipt;
+    type OnExecutionReceipt = TransactionFees;
 }
 
 parameter_types! {
     pub const BlockReward: Balance = SSC / (ExpectedVotesPerBlock::get() as Balance + 1);
     pub const VoteReward: Balance = SSC / (ExpectedVotesPerBlock::get() as Balance + 1);
+    // One day, which gives farmers a notice period to react to the changed rewards and is well
+    // beyond the confirmation depth K, so the new schedule is archived before it is enacted.
+    pub const RewardScheduleEnactmentDelay: BlockNumber = (24 * 60 * 60 * 1000 / MILLISECS_PER_BLOCK) as BlockNumber;
 }
 
 impl pallet_rewards::Config for Runtime {
@@ -403,6 +425,7 @@ impl pallet_rewards::Config for Runtime {
     type Currency = Balances;
     type BlockReward = BlockReward;
     type VoteReward = VoteReward;
+    type RewardScheduleEnactmentDelay = RewardScheduleEnactmentDelay;
     type FindBlockRewardAddress = Subspace;
     type FindVotingRewardAddresses = Subspace;
     type WeightInfo = ();
@@ -413,6 +436,10 @@ pub type FeedId = u64;
 parameter_types! {
     // Limit maximum number of feeds per account
     pub const MaxFeeds: u32 = 100;
+    // Objects of up to a few gigabytes when every chunk fills a block
+    pub const MaxManifestChunks: u32 = 1024;
+    // One day to put the rest of the chunks and the manifest of an object
+    pub const PendingChunkExpiry: BlockNumber = 24 * 60 * 60 * 1000 / MILLISECS_PER_BLOCK as BlockNumber;
 }
 
 impl pallet_feeds::Config for Runtime {
@@ -420,6 +447,8 @@ impl pallet_feeds::Config for Runtime {
     type FeedId = FeedId;
     type FeedProcessorKind = FeedProcessorKind;
     type MaxFeeds = MaxFeeds;
+    type MaxManifestChunks = MaxManifestChunks;
+    type PendingChunkExpiry = PendingChunkExpiry;
 
     fn feed_processor(
         feed_processor_kind: Self::FeedProcessorKind,
@@ -430,10 +459,17 @@ impl pallet_feeds::Config for Runtime {
 
 impl pallet_grandpa_finality_verifier::Config for Runtime {
     type ChainId = FeedId;
+    type HeadersToKeep = ConstU32<256>;
+}
+
+impl pallet_beefy_finality_verifier::Config for Runtime {
+    type ChainId = FeedId;
 }
 
 impl pallet_object_store::Config for Runtime {
     type Event = Event;
+    type MaxManifestChunks = MaxManifestChunks;
+    type PendingChunkExpiry = PendingChunkExpiry;
 }
 
 parameter_types! {
@@ -476,6 +512,8 @@ construct_runtime!(
 
         Vesting: orml_vesting = 13,
 
+        BeefyFinalityVerifier: pallet_beefy_finality_verifier = 14,
+
         // Reserve some room for other pallets as we'll remove sudo pallet eventually.
         Sudo: pallet_sudo = 100,
     }
@@ -664,13 +702,21 @@ impl_runtime_apis! {
 
     impl sp_consensus_subspace::SubspaceApi<Block, FarmerPublicKey> for Runtime {
         fn confirmation_depth_k() -> <<Block as BlockT>::Header as HeaderT>::Number {
-            <Self as pallet_subspace::Config>::ConfirmationDepthK::get()
+            Subspace::confirmation_depth_k()
         }
 
         fn total_pieces() -> u64 {
             <pallet_subspace::Pallet<Runtime>>::total_pieces()
         }
 
+        fn era_history() -> Vec<EraSummary> {
+            Subspace::era_history()
+        }
+
+        fn total_space_pledged() -> u128 {
+            Subspace::total_space_pledged()
+        }
+
         fn max_plot_size() -> u64 {
             <Self as pallet_subspace::Config>::MaxPlotSize::get()
         }
@@ -733,6 +779,10 @@ impl_runtime_apis! {
             Subspace::is_in_block_list(farmer_public_key)
         }
 
+        fn remaining_block_list_eons(farmer_public_key: &FarmerPublicKey) -> Option<u64> {
+            Subspace::remaining_block_list_eons(farmer_public_key)
+        }
+
         fn records_root(segment_index: u64) -> Option<Sha256Hash> {
             Subspace::records_root(segment_index)
         }
@@ -794,6 +844,12 @@ impl_runtime_apis! {
         fn maximum_receipt_drift() -> NumberFor<Block> {
             MaximumReceiptDrift::get()
         }
+
+        fn execution_receipt(
+            primary_number: NumberFor<Block>,
+        ) -> Option<sp_executor::ExecutionReceipt<NumberFor<Block>, <Block as BlockT>::Hash, cirrus_primitives::Hash>> {
+            Executor::receipts(primary_number)
+        }
     }
 
     impl sp_session::SessionKeys<Block> for Runtime {
@@ -829,6 +885,22 @@ impl_runtime_apis! {
         }
     }
 
+    impl sp_transaction_fees::TransactionFeesApi<Block, Balance> for Runtime {
+        fn storage_fee_quote(size: u32) -> FeeBreakdown<Balance> {
+            storage_fee_quote(size)
+        }
+
+        fn query_fee_breakdown(uxt: <Block as BlockT>::Extrinsic, len: u32) -> FeeBreakdown<Balance> {
+            query_fee_breakdown(uxt, len)
+        }
+    }
+
+    impl sp_rewards::RewardsApi<Block, BlockNumber, Balance> for Runtime {
+        fn expected_rewards(block_number: BlockNumber) -> BlockRewards<Balance> {
+            Rewards::expected_rewards(block_number)
+        }
+    }
+
     #[cfg(feature = "runtime-benchmarks")]
     impl frame_benchmarking::Benchmark<Block> for Runtime {
         fn benchmark_metadata(extra: bool) -> (
@@ -0,0 +1,125 @@
+use codec::Encode;
+use frame_support::sp_io;
+use frame_support::traits::{Currency, Hooks};
+use frame_support::weights::{GetDispatchInfo, PostDispatchInfo};
+use pallet_transaction_payment::OnChargeTransaction;
+use sp_core::sr25519;
+use sp_runtime::generic::Era;
+use sp_runtime::MultiSignature;
+use sp_transaction_fees::runtime_decl_for_TransactionFeesApi::TransactionFeesApi;
+use subspace_runtime::{
+    Address, Balances, Call, Runtime, SignedExtra, System, TransactionFees, TransactionPayment,
+    UncheckedExtrinsic,
+};
+use subspace_runtime_primitives::{AccountId, Balance, SSC};
+
+type FeeCharger = <Runtime as pallet_transaction_payment::Config>::OnChargeTransaction;
+
+const INITIAL_BALANCE: Balance = 1_000 * SSC;
+
+fn new_test_ext() -> sp_io::TestExternalities {
+    let t = frame_system::GenesisConfig::default()
+        .build_storage::<Runtime>()
+        .unwrap();
+
+    let mut t: sp_io::TestExternalities = t.into();
+
+    t.execute_with(|| {
+        System::set_block_number(1);
+        TransactionFees::on_initialize(1);
+    });
+
+    t
+}
+
+fn signed_extrinsic(who: AccountId, call: Call) -> UncheckedExtrinsic {
+    let extra: SignedExtra = (
+        frame_system::CheckNonZeroSender::new(),
+        frame_system::CheckSpecVersion::new(),
+        frame_system::CheckTxVersion::new(),
+        frame_system::CheckGenesis::new(),
+        frame_system::CheckMortality::from(Era::Immortal),
+        frame_system::CheckNonce::from(0),
+        frame_system::CheckWeight::new(),
+        pallet_transaction_payment::ChargeTransactionPayment::from(0),
+        Default::default(),
+        Default::default(),
+    );
+
+    // Signature is not checked when fees are queried or charged
+    UncheckedExtrinsic::new_signed(
+        call,
+        Address::Id(who),
+        MultiSignature::from(sr25519::Signature::from_raw([0; 64])),
+        extra,
+    )
+}
+
+#[test]
+fn fee_breakdown_matches_charged_fees() {
+    new_test_ext().execute_with(|| {
+        let who = AccountId::from([1; 32]);
+        Balances::make_free_balance_be(&who, INITIAL_BALANCE);
+
+        let call = Call::System(frame_system::Call::remark {
+            remark: vec![0; 100],
+        });
+        let uxt = signed_extrinsic(who.clone(), call.clone());
+        let len = uxt.encoded_size() as u32;
+
+        let fee_breakdown =
+            <Runtime as TransactionFeesApi<subspace_runtime::Block, Balance>>::query_fee_breakdown(
+                uxt.clone(),
+                len,
+            );
+        assert_eq!(
+            fee_breakdown.storage_fee,
+            fee_breakdown.transaction_byte_fee * Balance::from(call.encoded_size() as u32)
+        );
+        assert!(fee_breakdown.compute_fee > 0);
+
+        // Charge the fee the same way transaction payment does it for this extrinsic
+        let info = call.get_dispatch_info();
+        let fee = TransactionPayment::compute_fee(len, &info, 0);
+        assert_eq!(fee, fee_breakdown.storage_fee + fee_breakdown.compute_fee);
+
+        let liquidity_info = FeeCharger::withdraw_fee(&who, &call, &info, fee, 0).unwrap();
+        FeeCharger::correct_and_deposit_fee(
+            &who,
+            &info,
+            &PostDispatchInfo::default(),
+            fee,
+            0,
+            liquidity_info,
+        )
+        .unwrap();
+        assert_eq!(
+            INITIAL_BALANCE - Balances::free_balance(&who),
+            fee_breakdown.storage_fee + fee_breakdown.compute_fee
+        );
+
+        // There is no block author, so all the collected fees go into storage fees escrow
+        TransactionFees::on_finalize(1);
+        assert_eq!(
+            TransactionFees::storage_fees_escrow(),
+            fee_breakdown.storage_fee + fee_breakdown.compute_fee
+        );
+    });
+}
+
+#[test]
+fn storage_fee_quote_has_no_compute_fee() {
+    new_test_ext().execute_with(|| {
+        let fee_breakdown =
+            <Runtime as TransactionFeesApi<subspace_runtime::Block, Balance>>::storage_fee_quote(
+                100,
+            );
+
+        assert_eq!(
+            fee_breakdown.storage_fee,
+            fee_breakdown.transaction_byte_fee * 100
+        );
+        assert_eq!(fee_breakdown.compute_fee, 0);
+        assert_eq!(fee_breakdown.credit_supply, Balances::total_issuance());
+    });
+}
@@ -1 +1,2 @@
+mod fees;
 mod object_mapping;
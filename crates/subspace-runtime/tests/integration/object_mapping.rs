@@ -3,7 +3,7 @@ use frame_support::sp_io;
 use hex_literal::hex;
 use sp_objects::runtime_decl_for_ObjectsApi::ObjectsApi;
 use sp_runtime::traits::{BlakeTwo256, Hash as HashT};
-use subspace_core_primitives::objects::BlockObjectMapping;
+use subspace_core_primitives::objects::{BlockObjectMapping, ObjectManifest};
 use subspace_core_primitives::{crypto, Sha256Hash};
 use subspace_runtime::{
     Block, Call, FeedProcessorKind, Feeds, Header, Origin, Runtime, System, UncheckedExtrinsic,
@@ -152,6 +152,55 @@ fn object_mapping() {
     );
 }
 
+#[test]
+fn chunked_object_mapping() {
+    let object: Vec<u8> = (0..=255).cycle().take(1000).collect();
+    let chunks = object.chunks(400).map(<[u8]>::to_vec).collect::<Vec<_>>();
+    let manifest = ObjectManifest::from_chunks(chunks.iter().map(Vec::as_slice)).to_object();
+    let mut extrinsics = chunks
+        .iter()
+        .map(|chunk| UncheckedExtrinsic {
+            signature: None,
+            function: Call::Feeds(pallet_feeds::Call::put_chunk {
+                feed_id: 0,
+                chunk: chunk.clone(),
+            }),
+        })
+        .collect::<Vec<_>>();
+    extrinsics.push(UncheckedExtrinsic {
+        signature: None,
+        function: Call::Feeds(pallet_feeds::Call::put_manifest {
+            feed_id: 0,
+            manifest: manifest.clone(),
+        }),
+    });
+    let block = Block {
+        header: Header {
+            parent_hash: Default::default(),
+            number: Default::default(),
+            state_root: Default::default(),
+            extrinsics_root: Default::default(),
+            digest: Default::default(),
+        },
+        extrinsics,
+    };
+
+    let successful_calls = get_successful_calls(block.clone());
+    let encoded_block = block.encode();
+    let BlockObjectMapping { objects } = new_test_ext()
+        .execute_with(|| Runtime::extract_block_object_mapping(block, successful_calls));
+
+    // Every chunk and the manifest are mapped by their content.
+    assert_eq!(objects.len(), chunks.len() + 1);
+    for (object, data) in objects.iter().zip(chunks.iter().chain([&manifest])) {
+        assert_eq!(object.hash(), crypto::sha256_hash(data));
+        assert_eq!(
+            &encoded_block[object.offset() as usize..][..data.encoded_size()],
+            &data.encode()
+        );
+    }
+}
+
 fn get_successful_calls(block: Block) -> Vec<Hash> {
     block
         .extrinsics
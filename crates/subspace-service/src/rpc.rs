@@ -31,6 +31,7 @@ use sc_consensus_subspace::{
 use sc_consensus_subspace_rpc::{SubspaceRpc, SubspaceRpcApiServer};
 use sc_rpc::SubscriptionTaskExecutor;
 use sc_rpc_api::DenyUnsafe;
+use sc_transaction_fees_rpc::{TransactionFeesRpc, TransactionFeesRpcApiServer};
 use sc_transaction_pool_api::TransactionPool;
 use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder;
@@ -75,6 +76,7 @@ where
         + 'static,
     C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>
         + pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>
+        + sp_transaction_fees::TransactionFeesApi<Block, Balance>
         + BlockBuilder<Block>
         + sp_consensus_subspace::SubspaceApi<Block, FarmerPublicKey>,
     P: TransactionPool + 'static,
@@ -92,6 +94,7 @@ where
 
     module.merge(SystemRpc::new(client.clone(), pool, deny_unsafe).into_rpc())?;
     module.merge(TransactionPaymentRpc::new(client.clone()).into_rpc())?;
+    module.merge(TransactionFeesRpc::new(client.clone()).into_rpc())?;
 
     module.merge(
         SubspaceRpc::new(
@@ -23,6 +23,9 @@
 //!
 //! The block builder utility is used in the node as an abstraction over the runtime api to
 //! initialize a block, to push extrinsics and to finalize a block.
+//!
+//! The extrinsics failing the validity checks while being applied are rolled back and dropped
+//! from the block, they are reported in [`BuiltBlock::invalid_extrinsics`] instead.
 
 #![warn(missing_docs)]
 
@@ -31,11 +34,12 @@ use codec::Encode;
 use sp_api::{
 	ApiExt, ApiRef, Core, ProvideRuntimeApi, StorageChanges, StorageProof, TransactionOutcome,
 };
-use sp_blockchain::{ApplyExtrinsicFailed, Error};
+use sp_blockchain::Error;
 use sp_core::ExecutionContext;
 use sp_runtime::{
 	generic::BlockId,
 	traits::{Block as BlockT, Hash, HashFor, Header as HeaderT, NumberFor, One},
+	transaction_validity::TransactionValidityError,
 	Digest,
 };
 
@@ -80,6 +84,17 @@ impl From<bool> for RecordProof {
 	}
 }
 
+/// An extrinsic dropped by [`BlockBuilder`] as it failed the validity checks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidExtrinsic<Extrinsic> {
+	/// Index of the extrinsic in the list of extrinsics given to the block builder.
+	pub index: usize,
+	/// The invalid extrinsic.
+	pub extrinsic: Extrinsic,
+	/// Why the extrinsic is invalid.
+	pub error: TransactionValidityError,
+}
+
 /// A block that was build by [`BlockBuilder`] plus some additional data.
 ///
 /// This additional data includes the `storage_changes`, these changes can be applied to the
@@ -93,6 +108,8 @@ pub struct BuiltBlock<Block: BlockT, StateBackend: backend::StateBackend<HashFor
 	pub storage_changes: StorageChanges<StateBackend, Block>,
 	/// An optional proof that was recorded while building the block.
 	pub proof: Option<StorageProof>,
+	/// The extrinsics that were not included in the block as being invalid.
+	pub invalid_extrinsics: Vec<InvalidExtrinsic<Block::Extrinsic>>,
 }
 
 impl<Block: BlockT, StateBackend: backend::StateBackend<HashFor<Block>>>
@@ -187,28 +204,37 @@ where
 		Ok(Self { parent_hash, extrinsics, api, block_id, backend, estimated_header_size })
 	}
 
-	/// Execute the block's list of extrinsics.
-	fn execute_extrinsics(&self) -> Result<(), Error> {
-		let block_id = &self.block_id;
+	/// Applies an extrinsic, the changes are rolled back if the extrinsic is invalid.
+	///
+	/// Only the runtime api errors are returned as `Err(_)`, which abort the block building.
+	fn apply_extrinsic(
+		&self,
+		xt: &Block::Extrinsic,
+	) -> Result<Result<(), TransactionValidityError>, Error> {
+		self.api.execute_in_transaction(|api| {
+			match api.apply_extrinsic_with_context(
+				&self.block_id,
+				ExecutionContext::BlockConstruction,
+				xt.clone(),
+			) {
+				Ok(Ok(_)) => TransactionOutcome::Commit(Ok(Ok(()))),
+				Ok(Err(tx_validity)) => TransactionOutcome::Rollback(Ok(Err(tx_validity))),
+				Err(e) => TransactionOutcome::Rollback(Err(Error::from(e))),
+			}
+		})
+	}
+
+	/// Execute the block's list of extrinsics, returns the invalid ones.
+	fn execute_extrinsics(&self) -> Result<Vec<InvalidExtrinsic<Block::Extrinsic>>, Error> {
+		let mut invalid_extrinsics = Vec::new();
 
-		for xt in &self.extrinsics {
-			// TODO: rethink what to do if an error occurs when executing the transaction.
-			self.api.execute_in_transaction(|api| {
-				match api.apply_extrinsic_with_context(
-					block_id,
-					ExecutionContext::BlockConstruction,
-					xt.clone(),
-				) {
-					Ok(Ok(_)) => TransactionOutcome::Commit(Ok(())),
-					Ok(Err(tx_validity)) => TransactionOutcome::Rollback(Err(
-						ApplyExtrinsicFailed::Validity(tx_validity).into(),
-					)),
-					Err(e) => TransactionOutcome::Rollback(Err(Error::from(e))),
-				}
-			})?;
+		for (index, xt) in self.extrinsics.iter().enumerate() {
+			if let Err(error) = self.apply_extrinsic(xt)? {
+				invalid_extrinsics.push(InvalidExtrinsic { index, extrinsic: xt.clone(), error });
+			}
 		}
 
-		Ok(())
+		Ok(invalid_extrinsics)
 	}
 
 	fn collect_storage_changes(
@@ -231,21 +257,8 @@ where
 				return self.collect_storage_changes()
 			}
 
-			// TODO: rethink what to do if an error occurs when executing the transaction.
-			self.api.execute_in_transaction(|api| {
-				let res = api.apply_extrinsic_with_context(
-					&self.block_id,
-					ExecutionContext::BlockConstruction,
-					xt.clone(),
-				);
-				match res {
-					Ok(Ok(_)) => TransactionOutcome::Commit(Ok(())),
-					Ok(Err(tx_validity)) => TransactionOutcome::Rollback(Err(
-						ApplyExtrinsicFailed::Validity(tx_validity).into(),
-					)),
-					Err(e) => TransactionOutcome::Rollback(Err(Error::from(e))),
-				}
-			})?;
+			// The invalid extrinsics have no effect on the state, same as in `build()`.
+			let _ = self.apply_extrinsic(xt)?;
 		}
 
 		Err(Error::Execution(Box::new(format!(
@@ -263,13 +276,17 @@ where
 		self.collect_storage_changes()
 	}
 
-	/// Consume the builder to build a valid `Block` containing all pushed extrinsics.
+	/// Consume the builder to build a valid `Block` containing all the valid pushed extrinsics.
 	///
-	/// Returns the build `Block`, the changes to the storage and an optional `StorageProof`
-	/// supplied by `self.api`, combined as [`BuiltBlock`].
+	/// Returns the build `Block`, the changes to the storage, an optional `StorageProof`
+	/// supplied by `self.api` and the dropped invalid extrinsics, combined as [`BuiltBlock`].
 	/// The storage proof will be `Some(_)` when proof recording was enabled.
 	pub fn build(mut self) -> Result<BuiltBlock<Block, backend::StateBackendFor<B, Block>>, Error> {
-		self.execute_extrinsics()?;
+		let invalid_extrinsics = self.execute_extrinsics()?;
+
+		for invalid_extrinsic in invalid_extrinsics.iter().rev() {
+			self.extrinsics.remove(invalid_extrinsic.index);
+		}
 
 		let header = self
 			.api
@@ -291,6 +308,7 @@ where
 			block: <Block as BlockT>::new(header, self.extrinsics),
 			storage_changes,
 			proof,
+			invalid_extrinsics,
 		})
 	}
 
@@ -335,8 +353,12 @@ mod tests {
 	use super::*;
 	use sp_blockchain::HeaderBackend;
 	use sp_core::Blake2Hasher;
+	use sp_runtime::transaction_validity::InvalidTransaction;
 	use sp_state_machine::Backend;
-	use substrate_test_runtime_client::{DefaultTestClientBuilderExt, TestClientBuilderExt};
+	use substrate_test_runtime_client::{
+		runtime::{Extrinsic, Transfer},
+		AccountKeyring, DefaultTestClientBuilderExt, TestClientBuilderExt,
+	};
 
 	#[test]
 	fn block_building_storage_proof_does_not_include_runtime_by_default() {
@@ -370,4 +392,44 @@ mod tests {
 			.unwrap_err()
 			.contains("Database missing expected key"),);
 	}
+
+	#[test]
+	fn block_building_drops_invalid_extrinsics() {
+		let builder = substrate_test_runtime_client::TestClientBuilder::new();
+		let backend = builder.backend();
+		let client = builder.build();
+
+		let transfer = |nonce| -> Extrinsic {
+			Transfer {
+				from: AccountKeyring::Alice.into(),
+				to: AccountKeyring::Bob.into(),
+				amount: 1,
+				nonce,
+			}
+			.into_signed_tx()
+		};
+
+		let block = BlockBuilder::new(
+			&client,
+			client.info().best_hash,
+			client.info().best_number,
+			RecordProof::No,
+			Default::default(),
+			&*backend,
+			vec![transfer(0), transfer(5), transfer(1)],
+		)
+		.unwrap()
+		.build()
+		.unwrap();
+
+		assert_eq!(block.block.extrinsics, vec![transfer(0), transfer(1)]);
+		assert_eq!(
+			block.invalid_extrinsics,
+			vec![InvalidExtrinsic {
+				index: 1,
+				extrinsic: transfer(5),
+				error: InvalidTransaction::Stale.into(),
+			}]
+		);
+	}
 }
@@ -1,22 +1,42 @@
 //! Schema for executor in the aux-db.
 
+use crate::InvalidExtrinsicRecord;
 use codec::{Decode, Encode};
 use sc_client_api::backend::AuxStore;
 use sp_blockchain::{Error as ClientError, Result as ClientResult};
-use sp_executor::ExecutionReceipt;
+use sp_executor::{ExecutionReceipt, SignedOpaqueBundle};
 use sp_runtime::traits::{Block as BlockT, NumberFor, One, SaturatedConversion};
 use subspace_core_primitives::BlockNumber;
 
 const EXECUTION_RECEIPT_KEY: &[u8] = b"execution_receipt";
 const EXECUTION_RECEIPT_START: &[u8] = b"execution_receipt_start";
 const EXECUTION_RECEIPT_BLOCK_NUMBER: &[u8] = b"execution_receipt_block_number";
+const EXECUTION_RECEIPT_PRIMARY_HASH: &[u8] = b"execution_receipt_primary_hash";
+const INVALID_EXTRINSICS_KEY: &[u8] = b"invalid_extrinsics";
 /// Prune the execution receipts when they reach this number.
 const PRUNING_DEPTH: BlockNumber = 1000;
 
+const PRODUCED_BUNDLE_KEY: &[u8] = b"produced_bundle";
+const PRODUCED_BUNDLE_SLOTS: &[u8] = b"produced_bundle_slots";
+/// Number of the most recent bundles produced by this node that are kept.
+const PRODUCED_BUNDLES_TO_KEEP: usize = 1000;
+
 fn execution_receipt_key(block_hash: impl Encode) -> Vec<u8> {
 	(EXECUTION_RECEIPT_KEY, block_hash).encode()
 }
 
+fn primary_hash_key(primary_hash: impl Encode) -> Vec<u8> {
+	(EXECUTION_RECEIPT_PRIMARY_HASH, primary_hash).encode()
+}
+
+fn invalid_extrinsics_key(block_hash: impl Encode) -> Vec<u8> {
+	(INVALID_EXTRINSICS_KEY, block_hash).encode()
+}
+
+fn produced_bundle_key(slot_number: u64) -> Vec<u8> {
+	(PRODUCED_BUNDLE_KEY, slot_number).encode()
+}
+
 fn load_decode<Backend: AuxStore, T: Decode>(
 	backend: &Backend,
 	key: &[u8],
@@ -33,17 +53,23 @@ fn load_decode<Backend: AuxStore, T: Decode>(
 
 /// Write the execution receipt of a block to aux storage, optionally prune the receipts that are
 /// too old.
+///
+/// No receipts will be pruned if `archive_execution_receipts` is `true`.
 pub(super) fn write_execution_receipt<Backend: AuxStore, Block: BlockT, PBlock: BlockT>(
 	backend: &Backend,
 	(block_hash, block_number): (Block::Hash, NumberFor<Block>),
 	best_execution_chain_number: NumberFor<Block>,
 	execution_receipt: &ExecutionReceipt<NumberFor<PBlock>, PBlock::Hash, Block::Hash>,
+	archive_execution_receipts: bool,
 ) -> Result<(), sp_blockchain::Error> {
 	let block_number_key = (EXECUTION_RECEIPT_BLOCK_NUMBER, block_number).encode();
 	let mut hashes_at_block_number =
 		load_decode::<_, Vec<Block::Hash>>(backend, block_number_key.as_slice())?
 			.unwrap_or_default();
-	hashes_at_block_number.push(block_hash);
+	// Blocks derived from different primary forks can be at the same height.
+	if !hashes_at_block_number.contains(&block_hash) {
+		hashes_at_block_number.push(block_hash);
+	}
 
 	let first_saved_receipt = load_decode::<_, NumberFor<Block>>(backend, EXECUTION_RECEIPT_START)?
 		.unwrap_or(block_number);
@@ -52,10 +78,15 @@ pub(super) fn write_execution_receipt<Backend: AuxStore, Block: BlockT, PBlock:
 
 	let mut keys_to_delete = vec![];
 
-	if let Some(delete_receipts_to) = best_execution_chain_number
-		.saturated_into::<BlockNumber>()
-		.checked_sub(PRUNING_DEPTH)
-	{
+	let maybe_delete_receipts_to = if archive_execution_receipts {
+		None
+	} else {
+		best_execution_chain_number
+			.saturated_into::<BlockNumber>()
+			.checked_sub(PRUNING_DEPTH)
+	};
+
+	if let Some(delete_receipts_to) = maybe_delete_receipts_to {
 		new_first_saved_receipt = Into::<NumberFor<Block>>::into(delete_receipts_to) + One::one();
 		for receipt_to_delete in first_saved_receipt.saturated_into()..=delete_receipts_to {
 			let delete_block_number_key =
@@ -64,9 +95,17 @@ pub(super) fn write_execution_receipt<Backend: AuxStore, Block: BlockT, PBlock:
 			if let Some(hashes_to_delete) =
 				load_decode::<_, Vec<Block::Hash>>(backend, delete_block_number_key.as_slice())?
 			{
-				keys_to_delete.extend(
-					hashes_to_delete.into_iter().map(|h| (EXECUTION_RECEIPT_KEY, h).encode()),
-				);
+				for hash_to_delete in hashes_to_delete {
+					if let Some(receipt) =
+						load_execution_receipt::<_, Block::Hash, NumberFor<PBlock>, PBlock::Hash>(
+							backend,
+							hash_to_delete,
+						)? {
+						keys_to_delete.push(primary_hash_key(receipt.primary_hash));
+					}
+					keys_to_delete.push(execution_receipt_key(hash_to_delete));
+					keys_to_delete.push(invalid_extrinsics_key(hash_to_delete));
+				}
 				keys_to_delete.push(delete_block_number_key);
 			}
 		}
@@ -75,6 +114,10 @@ pub(super) fn write_execution_receipt<Backend: AuxStore, Block: BlockT, PBlock:
 	backend.insert_aux(
 		&[
 			(execution_receipt_key(block_hash).as_slice(), execution_receipt.encode().as_slice()),
+			(
+				primary_hash_key(execution_receipt.primary_hash).as_slice(),
+				block_hash.encode().as_slice(),
+			),
 			(block_number_key.as_slice(), hashes_at_block_number.encode().as_slice()),
 			(EXECUTION_RECEIPT_START, new_first_saved_receipt.encode().as_slice()),
 		],
@@ -83,7 +126,7 @@ pub(super) fn write_execution_receipt<Backend: AuxStore, Block: BlockT, PBlock:
 }
 
 /// Load the execution receipt associated with a block.
-pub(super) fn load_execution_receipt<Backend, Hash, Number, PHash>(
+pub fn load_execution_receipt<Backend, Hash, Number, PHash>(
 	backend: &Backend,
 	block_hash: Hash,
 ) -> ClientResult<Option<ExecutionReceipt<Number, PHash, Hash>>>
@@ -96,6 +139,80 @@ where
 	load_decode(backend, execution_receipt_key(block_hash).as_slice())
 }
 
+/// Load the execution receipt of the secondary block derived from the given primary block.
+pub fn load_execution_receipt_by_primary_hash<Backend, Hash, Number, PHash>(
+	backend: &Backend,
+	primary_hash: PHash,
+) -> ClientResult<Option<ExecutionReceipt<Number, PHash, Hash>>>
+where
+	Backend: AuxStore,
+	Hash: Encode + Decode,
+	Number: Decode,
+	PHash: Encode + Decode,
+{
+	match load_decode::<_, Hash>(backend, primary_hash_key(primary_hash).as_slice())? {
+		Some(block_hash) => load_execution_receipt(backend, block_hash),
+		None => Ok(None),
+	}
+}
+
+/// Write the extrinsics dropped from a block as being invalid to aux storage, they are pruned
+/// together with the execution receipt of the block.
+pub(super) fn write_invalid_extrinsics<Backend: AuxStore, Hash: Encode>(
+	backend: &Backend,
+	block_hash: Hash,
+	invalid_extrinsics: &[InvalidExtrinsicRecord],
+) -> Result<(), sp_blockchain::Error> {
+	backend.insert_aux(
+		&[(invalid_extrinsics_key(block_hash).as_slice(), invalid_extrinsics.encode().as_slice())],
+		&[],
+	)
+}
+
+/// Load the extrinsics dropped from a block as being invalid.
+pub fn load_invalid_extrinsics<Backend: AuxStore, Hash: Encode>(
+	backend: &Backend,
+	block_hash: Hash,
+) -> ClientResult<Vec<InvalidExtrinsicRecord>> {
+	load_decode(backend, invalid_extrinsics_key(block_hash).as_slice())
+		.map(|maybe_invalid_extrinsics| maybe_invalid_extrinsics.unwrap_or_default())
+}
+
+/// Write the bundle produced by this node to aux storage, only the most recent
+/// `PRODUCED_BUNDLES_TO_KEEP` bundles are kept.
+pub(super) fn write_produced_bundle<Backend: AuxStore>(
+	backend: &Backend,
+	bundle: &SignedOpaqueBundle,
+) -> Result<(), sp_blockchain::Error> {
+	let slot_number = bundle.opaque_bundle.header.slot_number;
+
+	let mut slots = load_decode::<_, Vec<u64>>(backend, PRODUCED_BUNDLE_SLOTS)?.unwrap_or_default();
+	if !slots.contains(&slot_number) {
+		slots.push(slot_number);
+	}
+
+	let keys_to_delete = slots
+		.drain(..slots.len().saturating_sub(PRODUCED_BUNDLES_TO_KEEP))
+		.map(produced_bundle_key)
+		.collect::<Vec<_>>();
+
+	backend.insert_aux(
+		&[
+			(produced_bundle_key(slot_number).as_slice(), bundle.encode().as_slice()),
+			(PRODUCED_BUNDLE_SLOTS, slots.encode().as_slice()),
+		],
+		&keys_to_delete.iter().map(|k| &k[..]).collect::<Vec<&[u8]>>()[..],
+	)
+}
+
+/// Load the bundle produced by this node at given slot.
+pub fn load_produced_bundle<Backend: AuxStore>(
+	backend: &Backend,
+	slot_number: u64,
+) -> ClientResult<Option<SignedOpaqueBundle>> {
+	load_decode(backend, produced_bundle_key(slot_number).as_slice())
+}
+
 pub(super) fn target_receipt_is_pruned(
 	best_execution_chain_number: BlockNumber,
 	target_block: BlockNumber,
@@ -108,6 +225,7 @@ mod tests {
 	use super::*;
 	use cirrus_test_service::runtime::Block;
 	use sp_core::hash::H256;
+	use sp_runtime::transaction_validity::InvalidTransaction;
 	use subspace_runtime_primitives::{BlockNumber, Hash};
 	use subspace_test_runtime::Block as PBlock;
 
@@ -120,6 +238,7 @@ mod tests {
 			secondary_hash: H256::random(),
 			trace: Default::default(),
 			trace_root: Default::default(),
+			invalid_extrinsics_root: Default::default(),
 		}
 	}
 
@@ -148,6 +267,7 @@ mod tests {
 				(hash, number),
 				number - 1, // Ideally, the receipt of previous block has been included when writing the receipt of current block.
 				receipt,
+				false,
 			)
 			.unwrap()
 		};
@@ -155,17 +275,32 @@ mod tests {
 		assert_eq!(receipt_start(), None);
 
 		// Create PRUNING_DEPTH receipts.
-		let block_hash_list = (1..=PRUNING_DEPTH)
+		let (block_hash_list, primary_hash_list): (Vec<_>, Vec<_>) = (1..=PRUNING_DEPTH)
 			.map(|block_number| {
 				let receipt = create_execution_receipt(block_number);
 				let block_hash = Hash::random();
+				let primary_hash = receipt.primary_hash;
 				write_receipt_at(block_hash, block_number, &receipt);
-				assert_eq!(receipt_at(block_hash), Some(receipt));
+				assert_eq!(receipt_at(block_hash), Some(receipt.clone()));
+				let invalid_extrinsics = vec![InvalidExtrinsicRecord {
+					bundle_hash: Some(H256::random()),
+					extrinsic: vec![block_number as u8],
+					error: InvalidTransaction::Stale.into(),
+				}];
+				write_invalid_extrinsics(&client, block_hash, &invalid_extrinsics).unwrap();
+				assert_eq!(
+					load_invalid_extrinsics(&client, block_hash).unwrap(),
+					invalid_extrinsics
+				);
+				assert_eq!(
+					load_execution_receipt_by_primary_hash(&client, primary_hash).unwrap(),
+					Some(receipt)
+				);
 				assert_eq!(hashes_at(block_number), Some(vec![block_hash]));
 				assert_eq!(receipt_start(), Some(1));
-				block_hash
+				(block_hash, primary_hash)
 			})
-			.collect::<Vec<_>>();
+			.unzip();
 
 		assert!(!target_receipt_is_pruned(PRUNING_DEPTH, 1));
 
@@ -190,8 +325,14 @@ mod tests {
 
 		// ER of block #1 should be pruned.
 		assert!(receipt_at(block_hash_list[0]).is_none());
+		// invalid extrinsics should be pruned as well.
+		assert!(load_invalid_extrinsics(&client, block_hash_list[0]).unwrap().is_empty());
 		// block number mapping should be pruned as well.
 		assert!(hashes_at(1).is_none());
+		// primary hash mapping should be pruned as well.
+		assert!(load_decode::<_, Hash>(&client, primary_hash_key(primary_hash_list[0]).as_slice())
+			.unwrap()
+			.is_none());
 		assert!(target_receipt_is_pruned(PRUNING_DEPTH + 1, 1));
 		assert_eq!(receipt_start(), Some(2));
 
@@ -247,6 +388,7 @@ mod tests {
 				(hash, number),
 				best_execution_chain_number,
 				receipt,
+				false,
 			)
 			.unwrap()
 		};
@@ -316,4 +458,89 @@ mod tests {
 		});
 		assert_eq!(receipt_start(), Some(4));
 	}
+
+	#[test]
+	fn execution_receipts_should_not_be_pruned_in_archive_mode() {
+		let client = substrate_test_runtime_client::new();
+
+		let receipt_start = || {
+			load_decode::<_, BlockNumber>(&client, EXECUTION_RECEIPT_START.to_vec().as_slice())
+				.unwrap()
+		};
+
+		let receipt_at = |block_hash: Hash| load_execution_receipt(&client, block_hash).unwrap();
+
+		let receipts = (1..=PRUNING_DEPTH + 3)
+			.map(|block_number| {
+				let receipt = create_execution_receipt(block_number);
+				let block_hash = Hash::random();
+				write_execution_receipt::<_, Block, PBlock>(
+					&client,
+					(block_hash, block_number),
+					block_number - 1,
+					&receipt,
+					true,
+				)
+				.unwrap();
+				(block_hash, receipt)
+			})
+			.collect::<Vec<_>>();
+
+		assert_eq!(receipt_start(), Some(1));
+
+		for (block_hash, receipt) in receipts {
+			assert_eq!(receipt_at(block_hash), Some(receipt.clone()));
+			assert_eq!(
+				load_execution_receipt_by_primary_hash(&client, receipt.primary_hash).unwrap(),
+				Some(receipt)
+			);
+		}
+	}
+
+	#[test]
+	fn produced_bundles_should_be_pruned() {
+		use sp_core::Pair;
+		use sp_executor::{BundleHeader, ExecutorPair, OpaqueBundle};
+
+		let client = substrate_test_runtime_client::new();
+		let pair = ExecutorPair::from_string("//Alice", None).unwrap();
+
+		let create_bundle = |slot_number: u64| {
+			let opaque_bundle = OpaqueBundle {
+				header: BundleHeader {
+					primary_hash: H256::random(),
+					slot_number,
+					extrinsics_root: Default::default(),
+				},
+				opaque_extrinsics: Vec::new(),
+			};
+			SignedOpaqueBundle {
+				signature: pair.sign(opaque_bundle.hash().as_ref()),
+				signer: pair.public(),
+				opaque_bundle,
+			}
+		};
+
+		// Slots are not necessarily consecutive.
+		let bundles = (1..=PRODUCED_BUNDLES_TO_KEEP as u64 + 2)
+			.map(|index| {
+				let bundle = create_bundle(index * 2);
+				write_produced_bundle(&client, &bundle).unwrap();
+				assert_eq!(load_produced_bundle(&client, index * 2).unwrap(), Some(bundle.clone()));
+				bundle
+			})
+			.collect::<Vec<_>>();
+
+		// The two oldest bundles should be pruned.
+		assert!(load_produced_bundle(&client, 2).unwrap().is_none());
+		assert!(load_produced_bundle(&client, 4).unwrap().is_none());
+		assert_eq!(load_produced_bundle(&client, 6).unwrap(), Some(bundles[2].clone()));
+		assert_eq!(
+			load_decode::<_, Vec<u64>>(&client, PRODUCED_BUNDLE_SLOTS)
+				.unwrap()
+				.unwrap()
+				.len(),
+			PRODUCED_BUNDLES_TO_KEEP
+		);
+	}
 }
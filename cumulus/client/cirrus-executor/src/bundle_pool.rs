@@ -0,0 +1,110 @@
+//! Pool of the transaction bundles received from the executor gossip network.
+
+use parking_lot::Mutex;
+use sp_core::H256;
+use sp_executor::{OpaqueBundle, SignedOpaqueBundle};
+use std::{collections::HashMap, sync::Arc};
+
+/// Bundles that passed the checks of executor gossip and haven't been included in the primary
+/// chain yet.
+///
+/// Only the bundles within the last [`crate::BUNDLE_EQUIVOCATION_SLOT_WINDOW`] slots are kept.
+#[derive(Clone, Default)]
+pub struct BundlePool {
+	bundles: Arc<Mutex<HashMap<H256, SignedOpaqueBundle>>>,
+}
+
+impl BundlePool {
+	/// Adds a bundle to the pool, dropping the ones too old relative to its slot.
+	pub(crate) fn add(&self, bundle: SignedOpaqueBundle) {
+		let slot_number = bundle.opaque_bundle.header.slot_number;
+		let mut bundles = self.bundles.lock();
+		bundles.insert(bundle.hash(), bundle);
+		retain_within_slot_window(&mut bundles, slot_number);
+	}
+
+	/// Drops the bundles too old relative to the new slot, which keeps the pool bounded even if
+	/// no more bundles are received.
+	pub(crate) fn on_new_slot(&self, slot_number: u64) {
+		retain_within_slot_window(&mut self.bundles.lock(), slot_number);
+	}
+
+	/// Removes the bundles included in a primary block.
+	pub(crate) fn remove_included(&self, included: &[OpaqueBundle]) {
+		let mut bundles = self.bundles.lock();
+		for bundle in included {
+			bundles.remove(&bundle.hash());
+		}
+	}
+
+	/// Returns the pending bundles ordered by slot.
+	pub fn pending_bundles(&self) -> Vec<SignedOpaqueBundle> {
+		let mut bundles = self.bundles.lock().values().cloned().collect::<Vec<_>>();
+		bundles.sort_by_key(|bundle| bundle.opaque_bundle.header.slot_number);
+		bundles
+	}
+}
+
+fn retain_within_slot_window(bundles: &mut HashMap<H256, SignedOpaqueBundle>, slot_number: u64) {
+	bundles.retain(|_, bundle| {
+		bundle
+			.opaque_bundle
+			.header
+			.slot_number
+			.saturating_add(crate::BUNDLE_EQUIVOCATION_SLOT_WINDOW) >=
+			slot_number
+	});
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::Pair;
+	use sp_executor::{BundleHeader, ExecutorPair};
+
+	fn create_bundle(slot_number: u64) -> SignedOpaqueBundle {
+		let pair = ExecutorPair::from_string("//Alice", None).unwrap();
+		let opaque_bundle = OpaqueBundle {
+			header: BundleHeader {
+				primary_hash: Default::default(),
+				slot_number,
+				extrinsics_root: Default::default(),
+			},
+			opaque_extrinsics: Vec::new(),
+		};
+		SignedOpaqueBundle {
+			signature: pair.sign(opaque_bundle.hash().as_ref()),
+			signer: pair.public(),
+			opaque_bundle,
+		}
+	}
+
+	#[test]
+	fn bundle_pool_keeps_pending_bundles_within_slot_window() {
+		let pool = BundlePool::default();
+		let pending_slots = || {
+			pool.pending_bundles()
+				.into_iter()
+				.map(|bundle| bundle.opaque_bundle.header.slot_number)
+				.collect::<Vec<_>>()
+		};
+
+		pool.add(create_bundle(2));
+		pool.add(create_bundle(1));
+		pool.add(create_bundle(3));
+		assert_eq!(pending_slots(), vec![1, 2, 3]);
+
+		pool.remove_included(&[create_bundle(2).opaque_bundle]);
+		assert_eq!(pending_slots(), vec![1, 3]);
+
+		let new_slot = crate::BUNDLE_EQUIVOCATION_SLOT_WINDOW + 2;
+		pool.add(create_bundle(new_slot));
+		assert_eq!(pending_slots(), vec![3, new_slot]);
+
+		// New slots drop the outdated bundles without any new bundle received.
+		pool.on_new_slot(new_slot + crate::BUNDLE_EQUIVOCATION_SLOT_WINDOW);
+		assert_eq!(pending_slots(), vec![new_slot]);
+		pool.on_new_slot(new_slot + crate::BUNDLE_EQUIVOCATION_SLOT_WINDOW + 1);
+		assert!(pending_slots().is_empty());
+	}
+}
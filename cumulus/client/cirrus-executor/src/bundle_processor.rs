@@ -1,10 +1,11 @@
-use crate::{ExecutionReceiptFor, SignedExecutionReceiptFor};
-use cirrus_block_builder::{BlockBuilder, BuiltBlock, RecordProof};
+use crate::{BundlePool, ExecutionReceiptFor, SignedExecutionReceiptFor};
+use cirrus_block_builder::{BlockBuilder, BuiltBlock, InvalidExtrinsic, RecordProof};
 use cirrus_primitives::{AccountId, SecondaryApi};
 use codec::{Decode, Encode};
+use parking_lot::Mutex;
 use rand::{seq::SliceRandom, SeedableRng};
 use rand_chacha::ChaCha8Rng;
-use sc_client_api::{AuxStore, BlockBackend};
+use sc_client_api::{AuxStore, BlockBackend, Finalizer};
 use sc_consensus::{
 	BlockImport, BlockImportParams, ForkChoiceStrategy, ImportResult, StateAction, StorageChanges,
 };
@@ -13,7 +14,7 @@ use sc_utils::mpsc::TracingUnboundedSender;
 use sp_api::{NumberFor, ProvideRuntimeApi, TransactionFor};
 use sp_blockchain::HeaderBackend;
 use sp_consensus::BlockOrigin;
-use sp_core::ByteArray;
+use sp_core::{ByteArray, H256};
 use sp_executor::{
 	ExecutionReceipt, ExecutorApi, ExecutorId, ExecutorSignature, OpaqueBundle,
 	SignedExecutionReceipt,
@@ -21,8 +22,9 @@ use sp_executor::{
 use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
 use sp_runtime::{
 	generic::BlockId,
-	traits::{Block as BlockT, Header as HeaderT, One},
-	RuntimeAppPublic,
+	traits::{Block as BlockT, CheckedSub, Hash as HashT, HashFor, Header as HeaderT, One, Zero},
+	transaction_validity::TransactionValidityError,
+	RuntimeAppPublic, StateVersion,
 };
 use std::{
 	borrow::Cow,
@@ -35,6 +37,13 @@ use subspace_core_primitives::Randomness;
 
 const LOG_TARGET: &str = "bundle-processor";
 
+/// Secondary blocks derived from the primary blocks this deep in the best primary chain are
+/// finalized, the secondary forks abandoned due to the primary reorgs below them are pruned.
+///
+/// The primary chain is not expected to be reorged that deep, it must not be smaller than the
+/// confirmation depth K of the primary chain.
+const FINALIZATION_DEPTH: u32 = 256;
+
 /// Shuffles the extrinsics in a deterministic way.
 ///
 /// The extrinsics are grouped by the signer. The extrinsics without a signer, i.e., unsigned
@@ -78,7 +87,30 @@ fn shuffle_extrinsics<Extrinsic: Debug>(
 	shuffled_extrinsics
 }
 
-pub(crate) struct BundleProcessor<Block, PBlock, Client, PClient, Backend>
+/// An extrinsic dropped from the secondary block as it failed the validity checks.
+///
+/// The invalid extrinsics are expected to be filtered out by the bundle author, these records
+/// are the input for building the invalid transaction proof against it.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct InvalidExtrinsicRecord {
+	/// Hash of the bundle the extrinsic was included in, `None` if the extrinsic is not from a
+	/// bundle, e.g., the extrinsic for the runtime upgrade.
+	pub bundle_hash: Option<H256>,
+	/// SCALE-encoded extrinsic.
+	pub extrinsic: Vec<u8>,
+	/// Why the extrinsic is invalid.
+	pub error: TransactionValidityError,
+}
+
+/// Returns the root of the ordered trie over the SCALE-encoded invalid extrinsic records of a
+/// block, which is committed in the execution receipt of the block.
+pub fn invalid_extrinsics_root<Hashing: HashT>(
+	records: &[InvalidExtrinsicRecord],
+) -> Hashing::Output {
+	Hashing::ordered_trie_root(records.iter().map(Encode::encode).collect(), StateVersion::V1)
+}
+
+pub(crate) struct BundleProcessor<Block, PBlock, Client, PClient, TransactionPool, Backend>
 where
 	Block: BlockT,
 	PBlock: BlockT,
@@ -86,16 +118,22 @@ where
 	primary_chain_client: Arc<PClient>,
 	primary_network: Arc<NetworkService<PBlock, PBlock::Hash>>,
 	client: Arc<Client>,
+	transaction_pool: Arc<TransactionPool>,
 	execution_receipt_sender:
 		Arc<TracingUnboundedSender<SignedExecutionReceiptFor<PBlock, Block::Hash>>>,
 	backend: Arc<Backend>,
 	is_authority: bool,
 	keystore: SyncCryptoStorePtr,
+	archive_execution_receipts: bool,
+	bundle_pool: BundlePool,
+	/// Serializes the execution receipt writes of the blocks processed in parallel on different
+	/// primary forks, which update the same receipt indexes in the aux storage.
+	execution_receipt_write_lock: Arc<Mutex<()>>,
 	_phantom_data: PhantomData<PBlock>,
 }
 
-impl<Block, PBlock, Client, PClient, Backend> Clone
-	for BundleProcessor<Block, PBlock, Client, PClient, Backend>
+impl<Block, PBlock, Client, PClient, TransactionPool, Backend> Clone
+	for BundleProcessor<Block, PBlock, Client, PClient, TransactionPool, Backend>
 where
 	Block: BlockT,
 	PBlock: BlockT,
@@ -105,21 +143,29 @@ where
 			primary_chain_client: self.primary_chain_client.clone(),
 			primary_network: self.primary_network.clone(),
 			client: self.client.clone(),
+			transaction_pool: self.transaction_pool.clone(),
 			execution_receipt_sender: self.execution_receipt_sender.clone(),
 			backend: self.backend.clone(),
 			is_authority: self.is_authority,
 			keystore: self.keystore.clone(),
+			archive_execution_receipts: self.archive_execution_receipts,
+			bundle_pool: self.bundle_pool.clone(),
+			execution_receipt_write_lock: self.execution_receipt_write_lock.clone(),
 			_phantom_data: self._phantom_data,
 		}
 	}
 }
 
-impl<Block, PBlock, Client, PClient, Backend>
-	BundleProcessor<Block, PBlock, Client, PClient, Backend>
+impl<Block, PBlock, Client, PClient, TransactionPool, Backend>
+	BundleProcessor<Block, PBlock, Client, PClient, TransactionPool, Backend>
 where
 	Block: BlockT,
 	PBlock: BlockT,
-	Client: HeaderBackend<Block> + BlockBackend<Block> + AuxStore + ProvideRuntimeApi<Block>,
+	Client: HeaderBackend<Block>
+		+ BlockBackend<Block>
+		+ AuxStore
+		+ ProvideRuntimeApi<Block>
+		+ Finalizer<Block, Backend>,
 	Client::Api: SecondaryApi<Block, AccountId>
 		+ sp_block_builder::BlockBuilder<Block>
 		+ sp_api::ApiExt<
@@ -133,27 +179,36 @@ where
 	>,
 	PClient: HeaderBackend<PBlock> + ProvideRuntimeApi<PBlock>,
 	PClient::Api: ExecutorApi<PBlock, Block::Hash>,
+	TransactionPool: sc_transaction_pool_api::TransactionPool<Block = Block>,
 	Backend: sc_client_api::Backend<Block>,
 {
+	#[allow(clippy::too_many_arguments)]
 	pub(crate) fn new(
 		primary_chain_client: Arc<PClient>,
 		primary_network: Arc<NetworkService<PBlock, PBlock::Hash>>,
 		client: Arc<Client>,
+		transaction_pool: Arc<TransactionPool>,
 		execution_receipt_sender: Arc<
 			TracingUnboundedSender<SignedExecutionReceiptFor<PBlock, Block::Hash>>,
 		>,
 		backend: Arc<Backend>,
 		is_authority: bool,
 		keystore: SyncCryptoStorePtr,
+		archive_execution_receipts: bool,
+		bundle_pool: BundlePool,
 	) -> Self {
 		Self {
 			primary_chain_client,
 			primary_network,
 			client,
+			transaction_pool,
 			execution_receipt_sender,
 			backend,
 			is_authority,
 			keystore,
+			archive_execution_receipts,
+			bundle_pool,
+			execution_receipt_write_lock: Arc::new(Mutex::new(())),
 			_phantom_data: PhantomData::default(),
 		}
 	}
@@ -165,10 +220,12 @@ where
 		shuffling_seed: Randomness,
 		maybe_new_runtime: Option<Cow<'static, [u8]>>,
 	) -> Result<(), sp_blockchain::Error> {
-		let parent_hash = self.client.info().best_hash;
-		let parent_number = self.client.info().best_number;
+		let (parent_hash, parent_number) = self.secondary_parent(primary_hash)?;
 
-		let mut extrinsics = self.bundles_to_extrinsics(parent_hash, bundles, shuffling_seed)?;
+		self.bundle_pool.remove_included(&bundles);
+
+		let mut extrinsics =
+			self.bundles_to_extrinsics(parent_hash, bundles.clone(), shuffling_seed)?;
 
 		if let Some(new_runtime) = maybe_new_runtime {
 			let encoded_set_code = self
@@ -190,20 +247,25 @@ where
 			extrinsics,
 		)?;
 
-		let BuiltBlock { block, storage_changes, proof: _ } = block_builder.build()?;
+		let BuiltBlock { block, storage_changes, proof: _, invalid_extrinsics } =
+			block_builder.build()?;
 
 		let (header, body) = block.deconstruct();
 		let state_root = *header.state_root();
 		let header_hash = header.hash();
 		let header_number = *header.number();
 
+		// The secondary chain follows the primary chain, the new block is the best one if it's
+		// derived from the primary block on the best primary chain, which also switches the
+		// secondary best chain on the primary reorgs.
+		let is_primary_best = self.primary_chain_client.hash(primary_number)? == Some(primary_hash);
+
 		let block_import_params = {
 			let mut import_block = BlockImportParams::new(BlockOrigin::Own, header);
 			import_block.body = Some(body);
 			import_block.state_action =
 				StateAction::ApplyChanges(StorageChanges::Changes(storage_changes));
-			// TODO: double check the fork choice is correct, see also ParachainBlockImport.
-			import_block.fork_choice = Some(ForkChoiceStrategy::LongestChain);
+			import_block.fork_choice = Some(ForkChoiceStrategy::Custom(is_primary_best));
 			import_block
 		};
 
@@ -231,6 +293,12 @@ where
 			},
 		}
 
+		let invalid_extrinsics_root = if invalid_extrinsics.is_empty() {
+			invalid_extrinsics_root::<HashFor<Block>>(&[])
+		} else {
+			self.handle_invalid_extrinsics(header_hash, &bundles, invalid_extrinsics)?
+		};
+
 		let mut roots =
 			self.client.runtime_api().intermediate_roots(&BlockId::Hash(header_hash))?;
 
@@ -264,6 +332,7 @@ where
 			secondary_hash: header_hash,
 			trace,
 			trace_root,
+			invalid_extrinsics_root,
 		};
 
 		let best_execution_chain_number = self
@@ -280,12 +349,20 @@ where
 			"Consensus chain number must larger than execution chain number by at least 1"
 		);
 
-		crate::aux_schema::write_execution_receipt::<_, Block, PBlock>(
-			&*self.client,
-			(header_hash, header_number),
-			best_execution_chain_number,
-			&execution_receipt,
-		)?;
+		{
+			let _write_guard = self.execution_receipt_write_lock.lock();
+			crate::aux_schema::write_execution_receipt::<_, Block, PBlock>(
+				&*self.client,
+				(header_hash, header_number),
+				best_execution_chain_number,
+				&execution_receipt,
+				self.archive_execution_receipts,
+			)?;
+		}
+
+		if is_primary_best {
+			self.finalize_secondary_block(primary_number)?;
+		}
 
 		// TODO: The applied txs can be fully removed from the transaction pool
 
@@ -347,6 +424,148 @@ where
 		}
 	}
 
+	/// Returns the secondary block on top of which the block derived from the given primary block
+	/// must be built, i.e., the one derived from the parent of the primary block.
+	fn secondary_parent(
+		&self,
+		primary_hash: PBlock::Hash,
+	) -> Result<(Block::Hash, NumberFor<Block>), sp_blockchain::Error> {
+		let primary_header =
+			self.primary_chain_client.header(BlockId::Hash(primary_hash))?.ok_or_else(|| {
+				sp_blockchain::Error::Backend(format!(
+					"Header for primary block {:?} not found",
+					primary_hash
+				))
+			})?;
+
+		// The primary parent is genesis.
+		if *primary_header.number() <= One::one() {
+			return Ok((self.client.info().genesis_hash, Zero::zero()))
+		}
+
+		let primary_parent_hash = *primary_header.parent_hash();
+
+		match crate::aux_schema::load_execution_receipt_by_primary_hash::<
+			_,
+			Block::Hash,
+			NumberFor<PBlock>,
+			PBlock::Hash,
+		>(&*self.client, primary_parent_hash)?
+		{
+			Some(parent_receipt) => {
+				let parent_hash = parent_receipt.secondary_hash;
+				let parent_number = self.client.number(parent_hash)?.ok_or_else(|| {
+					sp_blockchain::Error::Backend(format!(
+						"Number for secondary block {:?} not found",
+						parent_hash
+					))
+				})?;
+				Ok((parent_hash, parent_number))
+			},
+			// The worker processes the unprocessed primary ancestors first, building on top of
+			// an arbitrary secondary block instead would result in an invalid receipt.
+			None => Err(sp_blockchain::Error::Backend(format!(
+				"Execution receipt of primary parent {:?} not found, it must be processed first",
+				primary_parent_hash
+			))),
+		}
+	}
+
+	/// Returns `true` if the bundles of the given primary block have been processed, i.e., there
+	/// is an execution receipt derived from it.
+	pub(crate) fn is_processed(
+		&self,
+		primary_hash: PBlock::Hash,
+	) -> Result<bool, sp_blockchain::Error> {
+		Ok(crate::aux_schema::load_execution_receipt_by_primary_hash::<
+			_,
+			Block::Hash,
+			NumberFor<PBlock>,
+			PBlock::Hash,
+		>(&*self.client, primary_hash)?
+		.is_some())
+	}
+
+	/// Finalizes the secondary block derived from the primary block that is
+	/// [`FINALIZATION_DEPTH`] deep in the best primary chain, which prunes the secondary forks
+	/// abandoned below it.
+	fn finalize_secondary_block(
+		&self,
+		primary_number: NumberFor<PBlock>,
+	) -> Result<(), sp_blockchain::Error> {
+		let to_finalize = match primary_number.checked_sub(&FINALIZATION_DEPTH.into()) {
+			Some(to_finalize) if !to_finalize.is_zero() => to_finalize,
+			_ => return Ok(()),
+		};
+
+		let primary_hash = match self.primary_chain_client.hash(to_finalize)? {
+			Some(primary_hash) => primary_hash,
+			None => return Ok(()),
+		};
+
+		let secondary_hash = match crate::aux_schema::load_execution_receipt_by_primary_hash::<
+			_,
+			Block::Hash,
+			NumberFor<PBlock>,
+			PBlock::Hash,
+		>(&*self.client, primary_hash)?
+		{
+			Some(receipt) => receipt.secondary_hash,
+			None => return Ok(()),
+		};
+
+		match self.client.number(secondary_hash)? {
+			Some(secondary_number) if secondary_number > self.client.info().finalized_number =>
+				self.client.finalize_block(BlockId::Hash(secondary_hash), None, true),
+			_ => Ok(()),
+		}
+	}
+
+	/// Evicts the extrinsics dropped from the block as being invalid from the transaction pool and
+	/// records them along with the bundles they came from, returning the root over the records.
+	fn handle_invalid_extrinsics(
+		&self,
+		block_hash: Block::Hash,
+		bundles: &[OpaqueBundle],
+		invalid_extrinsics: Vec<InvalidExtrinsic<Block::Extrinsic>>,
+	) -> Result<Block::Hash, sp_blockchain::Error> {
+		let mut invalid_tx_hashes = Vec::with_capacity(invalid_extrinsics.len());
+
+		let records = invalid_extrinsics
+			.into_iter()
+			.map(|InvalidExtrinsic { extrinsic, error, .. }| {
+				let encoded_extrinsic = extrinsic.encode();
+				let bundle_hash = bundles
+					.iter()
+					.find(|bundle| {
+						bundle
+							.opaque_extrinsics
+							.iter()
+							.any(|opaque_extrinsic| opaque_extrinsic.encode() == encoded_extrinsic)
+					})
+					.map(|bundle| bundle.hash());
+
+				let tx_hash = self.transaction_pool.hash_of(&extrinsic);
+				tracing::debug!(
+					target: LOG_TARGET,
+					?tx_hash,
+					?bundle_hash,
+					?error,
+					"Evicting invalid extrinsic from the transaction pool"
+				);
+				invalid_tx_hashes.push(tx_hash);
+
+				InvalidExtrinsicRecord { bundle_hash, extrinsic: encoded_extrinsic, error }
+			})
+			.collect::<Vec<_>>();
+
+		self.transaction_pool.remove_invalid(&invalid_tx_hashes);
+
+		crate::aux_schema::write_invalid_extrinsics(&*self.client, block_hash, &records)?;
+
+		Ok(invalid_extrinsics_root::<HashFor<Block>>(&records))
+	}
+
 	fn bundles_to_extrinsics(
 		&self,
 		parent_hash: Block::Hash,
@@ -482,7 +701,10 @@ where
 mod tests {
 	use super::*;
 	use sp_keyring::sr25519::Keyring;
-	use sp_runtime::traits::{BlakeTwo256, Hash as HashT};
+	use sp_runtime::{
+		traits::BlakeTwo256,
+		transaction_validity::{InvalidTransaction, UnknownTransaction},
+	};
 
 	#[test]
 	fn shuffle_extrinsics_should_work() {
@@ -521,4 +743,30 @@ mod tests {
 		let roots = vec![root1, root2, root3];
 		crate::merkle_tree::construct_trace_merkle_tree(roots).unwrap();
 	}
+
+	#[test]
+	fn invalid_extrinsics_root_should_commit_to_records() {
+		let record = |extrinsic: u8, error| InvalidExtrinsicRecord {
+			bundle_hash: Some(H256::repeat_byte(extrinsic)),
+			extrinsic: vec![extrinsic],
+			error,
+		};
+		let payment = record(1, InvalidTransaction::Payment.into());
+		let unknown = record(2, UnknownTransaction::CannotLookup.into());
+
+		assert_eq!(
+			invalid_extrinsics_root::<BlakeTwo256>(&[]),
+			BlakeTwo256::ordered_trie_root(Vec::new(), StateVersion::V1)
+		);
+
+		let root = invalid_extrinsics_root::<BlakeTwo256>(&[payment.clone(), unknown.clone()]);
+		assert_ne!(root, invalid_extrinsics_root::<BlakeTwo256>(&[]));
+		// The order of records, i.e., of the dropped extrinsics in the block, is committed.
+		assert_ne!(root, invalid_extrinsics_root::<BlakeTwo256>(&[unknown, payment.clone()]));
+		// So is the reason why an extrinsic is invalid.
+		assert_ne!(
+			invalid_extrinsics_root::<BlakeTwo256>(&[payment]),
+			invalid_extrinsics_root::<BlakeTwo256>(&[record(1, InvalidTransaction::Stale.into())])
+		);
+	}
 }
@@ -2,7 +2,7 @@ use crate::worker::ExecutorSlotInfo;
 use cirrus_primitives::{AccountId, SecondaryApi};
 use codec::{Decode, Encode};
 use futures::{select, FutureExt};
-use sc_client_api::BlockBackend;
+use sc_client_api::{AuxStore, BlockBackend};
 use sc_transaction_pool_api::InPoolTransaction;
 use sc_utils::mpsc::TracingUnboundedSender;
 use sp_api::ProvideRuntimeApi;
@@ -60,7 +60,7 @@ impl<Block, PBlock, Client, PClient, TransactionPool>
 where
 	Block: BlockT,
 	PBlock: BlockT,
-	Client: HeaderBackend<Block> + BlockBackend<Block> + ProvideRuntimeApi<Block>,
+	Client: HeaderBackend<Block> + BlockBackend<Block> + AuxStore + ProvideRuntimeApi<Block>,
 	Client::Api: SecondaryApi<Block, AccountId> + BlockBuilder<Block>,
 	PClient: ProvideRuntimeApi<PBlock>,
 	PClient::Api: ExecutorApi<PBlock, Block::Hash>,
@@ -178,7 +178,11 @@ where
 						tracing::error!(target: LOG_TARGET, error = ?e, "Failed to send transaction bundle");
 					}
 
-					Ok(Some(signed_bundle.into()))
+					let signed_opaque_bundle = signed_bundle.into();
+
+					crate::aux_schema::write_produced_bundle(&*self.client, &signed_opaque_bundle)?;
+
+					Ok(Some(signed_opaque_bundle))
 				},
 				Ok(None) => Err(sp_blockchain::Error::Application(Box::from(
 					"This should not happen as the existence of key was just checked",
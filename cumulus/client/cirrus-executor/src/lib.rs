@@ -57,6 +57,7 @@
 //! [Computation section]: https://subspace.network/news/subspace-network-whitepaper
 
 mod aux_schema;
+mod bundle_pool;
 mod bundle_processor;
 mod bundle_producer;
 mod merkle_tree;
@@ -68,11 +69,12 @@ use crate::{
 	bundle_processor::BundleProcessor, bundle_producer::BundleProducer, worker::BlockInfo,
 };
 use cirrus_block_builder::{BlockBuilder, RecordProof};
-use cirrus_client_executor_gossip::{Action, GossipMessageHandler};
+use cirrus_client_executor_gossip::{Action, GossipMessageHandler, HandlerError, Misbehaviour};
 use cirrus_primitives::{AccountId, SecondaryApi};
 use codec::{Decode, Encode};
 use futures::{FutureExt, Stream};
-use sc_client_api::{AuxStore, BlockBackend};
+use parking_lot::Mutex;
+use sc_client_api::{AuxStore, BlockBackend, Finalizer};
 use sc_network::NetworkService;
 use sc_utils::mpsc::TracingUnboundedSender;
 use sp_api::ProvideRuntimeApi;
@@ -84,8 +86,9 @@ use sp_core::{
 	H256,
 };
 use sp_executor::{
-	Bundle, BundleEquivocationProof, ExecutionPhase, ExecutionReceipt, ExecutorApi, ExecutorId,
-	FraudProof, InvalidTransactionProof, OpaqueBundle, SignedBundle, SignedExecutionReceipt,
+	BundleEquivocationProof, BundleHeader, ExecutionPhase, ExecutionReceipt, ExecutorApi,
+	ExecutorId, FraudProof, InvalidTransactionProof, OpaqueBundle, SignedBundle,
+	SignedExecutionReceipt,
 };
 use sp_keystore::SyncCryptoStorePtr;
 use sp_runtime::{
@@ -93,13 +96,27 @@ use sp_runtime::{
 	traits::{Block as BlockT, HashFor, Header as HeaderT, NumberFor, One, Saturating, Zero},
 	RuntimeAppPublic, SaturatedConversion,
 };
-use sp_trie::StorageProof;
-use std::{borrow::Cow, sync::Arc};
+use sp_trie::CompactProof;
+use std::{borrow::Cow, collections::HashMap, sync::Arc};
 use subspace_core_primitives::{BlockNumber, Randomness, Sha256Hash};
+use subspace_runtime_primitives::AccountId as PAccountId;
+
+pub use crate::{
+	aux_schema::{
+		load_execution_receipt, load_execution_receipt_by_primary_hash, load_invalid_extrinsics,
+		load_produced_bundle,
+	},
+	bundle_pool::BundlePool,
+	bundle_processor::{invalid_extrinsics_root, InvalidExtrinsicRecord},
+};
 
 /// The logging target.
 const LOG_TARGET: &str = "cirrus::executor";
 
+/// Number of slots for which the headers of the bundles received from the network are kept
+/// for detecting the bundle equivocation.
+const BUNDLE_EQUIVOCATION_SLOT_WINDOW: u64 = 256;
+
 /// The implementation of the Cirrus `Executor`.
 pub struct Executor<Block, PBlock, Client, PClient, TransactionPool, Backend, E>
 where
@@ -113,7 +130,10 @@ where
 	transaction_pool: Arc<TransactionPool>,
 	backend: Arc<Backend>,
 	code_executor: Arc<E>,
-	bundle_processor: BundleProcessor<Block, PBlock, Client, PClient, Backend>,
+	bundle_processor: BundleProcessor<Block, PBlock, Client, PClient, TransactionPool, Backend>,
+	/// Headers of the bundles seen on the network, keyed by the bundle author and slot.
+	known_bundle_headers: Arc<Mutex<HashMap<(ExecutorId, u64), BundleHeader>>>,
+	bundle_pool: BundlePool,
 }
 
 impl<Block, PBlock, Client, PClient, TransactionPool, Backend, E> Clone
@@ -131,6 +151,8 @@ where
 			backend: self.backend.clone(),
 			code_executor: self.code_executor.clone(),
 			bundle_processor: self.bundle_processor.clone(),
+			known_bundle_headers: self.known_bundle_headers.clone(),
+			bundle_pool: self.bundle_pool.clone(),
 		}
 	}
 }
@@ -151,8 +173,12 @@ impl<Block, PBlock, Client, PClient, TransactionPool, Backend, E>
 where
 	Block: BlockT,
 	PBlock: BlockT,
-	Client:
-		HeaderBackend<Block> + BlockBackend<Block> + AuxStore + ProvideRuntimeApi<Block> + 'static,
+	Client: HeaderBackend<Block>
+		+ BlockBackend<Block>
+		+ AuxStore
+		+ ProvideRuntimeApi<Block>
+		+ Finalizer<Block, Backend>
+		+ 'static,
 	Client::Api: SecondaryApi<Block, AccountId>
 		+ sp_block_builder::BlockBuilder<Block>
 		+ sp_api::ApiExt<
@@ -196,11 +222,13 @@ where
 		code_executor: Arc<E>,
 		is_authority: bool,
 		keystore: SyncCryptoStorePtr,
+		archive_execution_receipts: bool,
+		bundle_pool: BundlePool,
 	) -> Result<Self, sp_consensus::Error>
 	where
 		SE: SpawnEssentialNamed,
 		SC: SelectChain<PBlock>,
-		IBNS: Stream<Item = NumberFor<PBlock>> + Send + 'static,
+		IBNS: Stream<Item = (PBlock::Hash, NumberFor<PBlock>)> + Send + 'static,
 		NSNS: Stream<Item = (Slot, Sha256Hash)> + Send + 'static,
 	{
 		let active_leaves = active_leaves(primary_chain_client.as_ref(), select_chain).await?;
@@ -218,10 +246,13 @@ where
 			primary_chain_client.clone(),
 			primary_network,
 			client.clone(),
+			transaction_pool.clone(),
 			execution_receipt_sender,
 			backend.clone(),
 			is_authority,
 			keystore,
+			archive_execution_receipts,
+			bundle_pool.clone(),
 		);
 
 		spawn_essential.spawn_essential_blocking(
@@ -229,8 +260,10 @@ where
 			None,
 			worker::start_worker(
 				primary_chain_client.clone(),
+				spawner.clone(),
 				bundle_producer,
 				bundle_processor.clone(),
+				bundle_pool.clone(),
 				imported_block_notification_stream,
 				new_slot_notification_stream,
 				active_leaves,
@@ -246,6 +279,8 @@ where
 			backend,
 			code_executor,
 			bundle_processor,
+			known_bundle_headers: Arc::new(Mutex::new(HashMap::new())),
+			bundle_pool,
 		})
 	}
 
@@ -342,6 +377,8 @@ where
 	fn submit_fraud_proof(&self, fraud_proof: FraudProof) {
 		let primary_chain_client = self.primary_chain_client.clone();
 		// TODO: No backpressure
+		// TODO: open and settle a dispute over the receipt first, the runtime only accepts the
+		// fraud proof for the diverging execution phase of a settled dispute.
 		self.spawner.spawn_blocking(
 			"cirrus-submit-fraud-proof",
 			None,
@@ -410,7 +447,7 @@ where
 		parent_header: &Block::Header,
 		current_hash: Block::Hash,
 		prover: &subspace_fraud_proof::ExecutionProver<Block, Backend, E>,
-	) -> Result<(StorageProof, ExecutionPhase), GossipMessageError> {
+	) -> Result<(CompactProof, ExecutionPhase), GossipMessageError> {
 		let extrinsics = self.block_body(current_hash)?;
 
 		let encoded_extrinsic = extrinsics
@@ -461,13 +498,14 @@ where
 				Ok(Some(local_receipt)) =>
 					return tx.send(Ok(local_receipt)).map_err(|_| GossipMessageError::SendError),
 				Ok(None) => {
-					// TODO: test how this works under the primary forks.
-					//       ref https://github.com/subspace/subspace/pull/250#discussion_r804247551
-					//
 					// Whether or not the best execution chain number on primary chain has been
 					// updated, the local client has proceeded to a higher block, that means the receipt
 					// of `block_hash` received from the network does not match the local one,
 					// we should just send back the local receipt at the same height.
+					//
+					// The receipt may also be derived from a primary fork, in which case the local
+					// receipt at the same height on the best secondary chain, i.e., the one derived
+					// from the best primary chain, is used.
 					if self.client.info().best_number >= secondary_block_number {
 						let local_block_hash = self
 							.client
@@ -551,6 +589,20 @@ impl From<sp_blockchain::Error> for GossipMessageError {
 	}
 }
 
+impl HandlerError for GossipMessageError {
+	fn misbehaviour(&self) -> Option<Misbehaviour> {
+		match self {
+			Self::BadBundleSignature | Self::BadExecutionReceiptSignature =>
+				Some(Misbehaviour::BadSignature),
+			Self::InvalidBundleAuthor { .. } | Self::InvalidExecutionReceiptAuthor { .. } =>
+				Some(Misbehaviour::InvalidAuthor),
+			// The equivocation is reported on chain against the executor, the peer that relayed
+			// the bundle is not at fault.
+			_ => None,
+		}
+	}
+}
+
 impl<Block, PBlock, Client, PClient, TransactionPool, Backend, E>
 	GossipMessageHandler<PBlock, Block>
 	for Executor<Block, PBlock, Client, PClient, TransactionPool, Backend, E>
@@ -589,71 +641,84 @@ where
 {
 	type Error = GossipMessageError;
 
+	fn best_primary_number(&self) -> NumberFor<PBlock> {
+		self.primary_chain_client.info().best_number
+	}
+
+	fn primary_number(&self, primary_hash: PBlock::Hash) -> Option<NumberFor<PBlock>> {
+		self.primary_chain_client.number(primary_hash).ok().flatten()
+	}
+
 	fn on_bundle(
 		&self,
-		SignedBundle { bundle, signature, signer }: &SignedBundle<Block::Extrinsic>,
+		signed_bundle: &SignedBundle<Block::Extrinsic>,
 	) -> Result<Action, Self::Error> {
-		let check_equivocation = |_bundle: &Bundle<Block::Extrinsic>| {
-			// TODO: check bundle equivocation
-			let bundle_is_an_equivocation = false;
-			if bundle_is_an_equivocation {
-				Some(BundleEquivocationProof::dummy_at(bundle.header.slot_number))
-			} else {
-				None
-			}
-		};
+		let SignedBundle { bundle, signature, signer } = signed_bundle;
 
-		// A bundle equivocation occurs.
-		if let Some(equivocation_proof) = check_equivocation(bundle) {
-			self.submit_bundle_equivocation_proof(equivocation_proof);
-			return Err(GossipMessageError::BundleEquivocation)
-		}
+		let primary_hash =
+			PBlock::Hash::decode(&mut bundle.header.primary_hash.encode().as_slice())
+				.expect("Hash type must be correct");
 
-		let bundle_exists = false;
+		if !signer.verify(&bundle.hash(), signature) {
+			return Err(Self::Error::BadBundleSignature)
+		}
 
-		if bundle_exists {
-			Ok(Action::Empty)
-		} else {
-			let primary_hash =
-				PBlock::Hash::decode(&mut bundle.header.primary_hash.encode().as_slice())
-					.expect("Hash type must be correct");
+		let expected_executor_id = self
+			.primary_chain_client
+			.runtime_api()
+			.executor_id(&BlockId::Hash(primary_hash))?;
+		if *signer != expected_executor_id {
+			return Err(Self::Error::InvalidBundleAuthor {
+				got: signer.clone(),
+				expected: expected_executor_id,
+			})
+		}
 
-			if !signer.verify(&bundle.hash(), signature) {
-				return Err(Self::Error::BadBundleSignature)
-			}
+		let slot_number = bundle.header.slot_number;
+		let known_header = {
+			let mut known_bundle_headers = self.known_bundle_headers.lock();
+			let known_header = known_bundle_headers
+				.entry((signer.clone(), slot_number))
+				.or_insert_with(|| bundle.header.clone())
+				.clone();
+			known_bundle_headers.retain(|(_, slot), _| {
+				slot.saturating_add(BUNDLE_EQUIVOCATION_SLOT_WINDOW) >= slot_number
+			});
+			known_header
+		};
 
-			let expected_executor_id = self
-				.primary_chain_client
-				.runtime_api()
-				.executor_id(&BlockId::Hash(primary_hash))?;
-			if *signer != expected_executor_id {
-				// TODO: handle the misbehavior.
-
-				return Err(Self::Error::InvalidBundleAuthor {
-					got: signer.clone(),
-					expected: expected_executor_id,
-				})
-			}
+		// A bundle equivocation occurs.
+		if known_header != bundle.header {
+			let offender = PAccountId::decode(&mut signer.encode().as_slice())
+				.expect("ExecutorId and AccountId are both 32 bytes; qed");
+			let bundle_equivocation_proof = BundleEquivocationProof {
+				offender,
+				slot: slot_number.into(),
+				first_header: known_header,
+				second_header: bundle.header.clone(),
+			};
+			self.submit_bundle_equivocation_proof(bundle_equivocation_proof);
+			return Err(GossipMessageError::BundleEquivocation)
+		}
 
-			for extrinsic in bundle.extrinsics.iter() {
-				let tx_hash = self.transaction_pool.hash_of(extrinsic);
+		for extrinsic in bundle.extrinsics.iter() {
+			let tx_hash = self.transaction_pool.hash_of(extrinsic);
 
-				if self.transaction_pool.ready_transaction(&tx_hash).is_some() {
-					// TODO: Set the status of each tx in the bundle to seen
-				} else {
-					// TODO: check the legality
-					//
-					// if illegal => illegal tx proof
-					let invalid_transaction_proof = InvalidTransactionProof;
+			if self.transaction_pool.ready_transaction(&tx_hash).is_some() {
+				// TODO: Set the status of each tx in the bundle to seen
+			} else {
+				// TODO: check the legality
+				//
+				// if illegal => illegal tx proof
+				let invalid_transaction_proof = InvalidTransactionProof;
 
-					self.submit_invalid_transaction_proof(invalid_transaction_proof);
-				}
+				self.submit_invalid_transaction_proof(invalid_transaction_proof);
 			}
+		}
 
-			// TODO: all checks pass, add to the bundle pool
+		self.bundle_pool.add(signed_bundle.clone().into());
 
-			Ok(Action::RebroadcastBundle)
-		}
+		Ok(Action::RebroadcastBundle)
 	}
 
 	/// Checks the execution receipt from the executor peers.
@@ -673,8 +738,6 @@ where
 			.runtime_api()
 			.executor_id(&BlockId::Hash(execution_receipt.primary_hash))?;
 		if *signer != expected_executor_id {
-			// TODO: handle the misbehavior.
-
 			return Err(Self::Error::InvalidExecutionReceiptAuthor {
 				got: signer.clone(),
 				expected: expected_executor_id,
@@ -841,7 +904,6 @@ where
 					&prover,
 				)?;
 
-				// TODO: proof should be a CompactProof.
 				FraudProof {
 					parent_number,
 					parent_hash: as_h256(&parent_header.hash())?,
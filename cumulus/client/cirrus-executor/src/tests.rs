@@ -1,21 +1,26 @@
 use cirrus_block_builder::{BlockBuilder, RecordProof};
+use cirrus_client_executor_gossip::{Action, GossipMessageHandler, HandlerError};
 use cirrus_primitives::{BlockNumber, Hash, SecondaryApi};
 use cirrus_test_service::{
 	run_primary_chain_validator_node,
 	runtime::Header,
-	Keyring::{Alice, Charlie, Dave},
+	Keyring::{Alice, Bob, Charlie, Dave},
 };
 use codec::Encode;
-use sc_client_api::{Backend, HeaderBackend, StateBackend, StorageProof};
+use sc_client_api::{Backend, HeaderBackend, StateBackend};
 use sc_service::Role;
 use sc_transaction_pool_api::TransactionSource;
 use sp_api::ProvideRuntimeApi;
 use sp_core::{traits::FetchRuntimeCode, Pair};
-use sp_executor::{ExecutionPhase, ExecutorPair, FraudProof, SignedExecutionReceipt};
+use sp_executor::{
+	Bundle, BundleHeader, ExecutionPhase, ExecutorPair, FraudProof, SignedBundle,
+	SignedExecutionReceipt,
+};
 use sp_runtime::{
 	generic::{BlockId, DigestItem},
 	traits::{BlakeTwo256, Hash as HashT, Header as HeaderT},
 };
+use sp_trie::CompactProof;
 use std::collections::HashSet;
 
 #[substrate_test_utils::test(flavor = "multi_thread")]
@@ -432,7 +437,7 @@ async fn invalid_execution_proof_should_not_work() {
 	let (proof0, post_delta_root0, execution_phase0) = create_extrinsic_proof(0);
 	let (proof1, post_delta_root1, execution_phase1) = create_extrinsic_proof(1);
 
-	let check_proof_executor = |post_delta_root: Hash, proof: StorageProof| {
+	let check_proof_executor = |post_delta_root: Hash, proof: CompactProof| {
 		let execution_phase =
 			ExecutionPhase::ApplyExtrinsic { call_data: transfer_to_charlie_again.encode() };
 		prover.check_execution_proof(
@@ -490,6 +495,151 @@ async fn invalid_execution_proof_should_not_work() {
 	assert!(proof_verifier.verify(&fraud_proof).is_ok());
 }
 
+#[substrate_test_utils::test(flavor = "multi_thread")]
+async fn compact_proof_should_be_smaller_than_storage_proof() {
+	let mut builder = sc_cli::LoggerBuilder::new("");
+	builder.with_colors(false);
+	let _ = builder.init();
+
+	let tokio_handle = tokio::runtime::Handle::current();
+
+	// start alice
+	let (alice, alice_network_starter) =
+		run_primary_chain_validator_node(tokio_handle.clone(), Alice, vec![]);
+
+	alice_network_starter.start_network();
+
+	// run cirrus charlie (a secondary chain authority node)
+	let charlie = cirrus_test_service::TestNodeBuilder::new(tokio_handle.clone(), Charlie)
+		.connect_to_relay_chain_node(&alice)
+		.build(Role::Authority)
+		.await;
+
+	charlie.wait_for_blocks(3).await;
+
+	// A block full of transfers between a handful of accounts, which touches the storage of the
+	// system, balances and transaction payment pallets like the real blocks do.
+	let senders = [Alice, Bob, Charlie, Dave];
+	let test_txs = (0..8u32)
+		.flat_map(|nonce| {
+			senders.iter().enumerate().map(move |(index, sender)| (nonce, index, *sender))
+		})
+		.map(|(nonce, index, sender)| {
+			let dest = senders[(index + 1) % senders.len()];
+			cirrus_test_service::construct_extrinsic(
+				&charlie.client,
+				pallet_balances::Call::transfer {
+					dest: cirrus_test_service::runtime::Address::Id(dest.public().into()),
+					value: 8,
+				},
+				sender,
+				false,
+				nonce,
+			)
+		})
+		.collect::<Vec<_>>();
+
+	let parent_header = charlie
+		.client
+		.header(&BlockId::Hash(charlie.client.info().best_hash))
+		.unwrap()
+		.unwrap();
+
+	let create_block_builder = || {
+		BlockBuilder::new(
+			&*charlie.client,
+			parent_header.hash(),
+			*parent_header.number(),
+			RecordProof::No,
+			Default::default(),
+			&*charlie.backend,
+			test_txs.clone().into_iter().map(Into::into).collect(),
+		)
+		.unwrap()
+	};
+
+	let prover = subspace_fraud_proof::ExecutionProver::new(
+		charlie.backend.clone(),
+		charlie.code_executor.clone(),
+		Box::new(charlie.task_manager.spawn_handle()),
+	);
+
+	let mut proofs = Vec::with_capacity(test_txs.len() + 2);
+
+	let new_header = Header::new(
+		*parent_header.number() + 1,
+		Default::default(),
+		Default::default(),
+		parent_header.hash(),
+		Default::default(),
+	);
+	let proof = prover
+		.prove_execution::<sp_trie::PrefixedMemoryDB<BlakeTwo256>>(
+			BlockId::Hash(parent_header.hash()),
+			&ExecutionPhase::InitializeBlock { call_data: new_header.encode() },
+			None,
+		)
+		.expect("Create `initialize_block` proof");
+	proofs.push(("initialize_block".to_string(), *parent_header.state_root(), proof));
+
+	for (extrinsic_index, xt) in test_txs.iter().enumerate() {
+		let storage_changes = create_block_builder()
+			.prepare_storage_changes_before(extrinsic_index)
+			.unwrap_or_else(|_| panic!("Get StorageChanges before extrinsic #{}", extrinsic_index));
+		let post_delta_root = storage_changes.transaction_storage_root;
+		let proof = prover
+			.prove_execution(
+				BlockId::Hash(parent_header.hash()),
+				&ExecutionPhase::ApplyExtrinsic { call_data: xt.encode() },
+				Some((storage_changes.transaction, post_delta_root)),
+			)
+			.expect("Create extrinsic execution proof");
+		proofs.push((format!("apply_extrinsic #{}", extrinsic_index), post_delta_root, proof));
+	}
+
+	let storage_changes = create_block_builder()
+		.prepare_storage_changes_before_finalize_block()
+		.expect("Get StorageChanges before `finalize_block`");
+	let post_delta_root = storage_changes.transaction_storage_root;
+	let proof = prover
+		.prove_execution(
+			BlockId::Hash(parent_header.hash()),
+			&ExecutionPhase::FinalizeBlock,
+			Some((storage_changes.transaction, post_delta_root)),
+		)
+		.expect("Create `finalize_block` proof");
+	proofs.push(("finalize_block".to_string(), post_delta_root, proof));
+
+	let (mut total_storage_proof_size, mut total_compact_proof_size) = (0, 0);
+	for (execution_phase, pre_state_root, compact_proof) in proofs {
+		let (storage_proof, root) = compact_proof
+			.to_storage_proof::<BlakeTwo256>(Some(&pre_state_root))
+			.expect("Compact proof must be valid against the pre state root");
+		assert_eq!(root, pre_state_root);
+
+		let storage_proof_size = storage_proof.encoded_size();
+		let compact_proof_size = compact_proof.encoded_size();
+		tracing::debug!(
+			%execution_phase,
+			storage_proof_size,
+			compact_proof_size,
+			"Proof sizes of the execution phase"
+		);
+		assert!(compact_proof_size <= storage_proof_size);
+
+		total_storage_proof_size += storage_proof_size;
+		total_compact_proof_size += compact_proof_size;
+	}
+
+	tracing::debug!(
+		transfers = test_txs.len(),
+		total_storage_proof_size,
+		total_compact_proof_size,
+		"Total proof sizes of the block"
+	);
+	assert!(total_compact_proof_size < total_storage_proof_size);
+}
+
 #[substrate_test_utils::test(flavor = "multi_thread")]
 async fn set_new_code_should_work() {
 	let mut builder = sc_cli::LoggerBuilder::new("");
@@ -514,11 +664,18 @@ async fn set_new_code_should_work() {
 
 	let new_runtime_wasm_blob = b"new_runtime_wasm_blob".to_vec();
 
+	let best_receipt = crate::aux_schema::load_execution_receipt::<_, Hash, BlockNumber, Hash>(
+		&*charlie.backend,
+		charlie.client.info().best_hash,
+	)
+	.unwrap()
+	.unwrap();
+
 	charlie
 		.executor
 		.clone()
 		.process_bundles(
-			Default::default(),
+			(best_receipt.primary_hash, best_receipt.primary_number),
 			Default::default(),
 			BlakeTwo256::hash_of(&[1u8; 64]).into(),
 			Some(new_runtime_wasm_blob.clone().into()),
@@ -667,3 +824,134 @@ async fn pallet_executor_unsigned_extrinsics_should_work() {
 	alice_executor.wait_for_blocks(5).await;
 	assert!(ready_txs().is_empty());
 }
+
+#[substrate_test_utils::test(flavor = "multi_thread")]
+async fn executor_should_follow_primary_chain_reorgs() {
+	let mut builder = sc_cli::LoggerBuilder::new("");
+	builder.with_colors(false);
+	let _ = builder.init();
+
+	let tokio_handle = tokio::runtime::Handle::current();
+
+	// Start alice and bob, the primary chain validator nodes are not connected to each other,
+	// hence they are building two different primary forks.
+	let (alice, alice_network_starter) =
+		run_primary_chain_validator_node(tokio_handle.clone(), Alice, vec![]);
+	alice_network_starter.start_network();
+
+	let (bob, bob_network_starter) =
+		run_primary_chain_validator_node(tokio_handle.clone(), Bob, vec![]);
+	bob_network_starter.start_network();
+
+	// run cirrus charlie (a secondary chain authority node) following alice's fork
+	let charlie = cirrus_test_service::TestNodeBuilder::new(tokio_handle, Charlie)
+		.connect_to_relay_chain_node(&alice)
+		.build(Role::Authority)
+		.await;
+
+	charlie.wait_for_blocks(3).await;
+
+	let alice_fork_secondary_hash = charlie.client.info().best_hash;
+	let alice_fork_secondary_number = charlie.client.info().best_number;
+
+	// Stop alice and let bob's fork become heavier, then reorg charlie's primary chain to it.
+	drop(alice);
+	bob.wait_for_blocks(alice_fork_secondary_number as usize + 5).await;
+
+	charlie
+		.primary_chain_network
+		.add_reserved_peer(bob.addr.to_string())
+		.expect("Bob's address is valid");
+
+	let primary_hash_of = |secondary_hash| {
+		crate::aux_schema::load_execution_receipt::<_, Hash, BlockNumber, Hash>(
+			&*charlie.backend,
+			secondary_hash,
+		)
+		.unwrap()
+		.map(|receipt| receipt.primary_hash)
+	};
+
+	while primary_hash_of(charlie.client.info().best_hash)
+		.and_then(|primary_hash| bob.client.number(primary_hash).unwrap())
+		.is_none()
+	{
+		charlie.wait_for_blocks(1).await;
+	}
+
+	// The secondary block derived from alice's fork is still known, but it's no longer on the
+	// best secondary chain.
+	assert!(charlie.client.number(alice_fork_secondary_hash).unwrap().is_some());
+	assert_ne!(
+		charlie.client.hash(alice_fork_secondary_number).unwrap(),
+		Some(alice_fork_secondary_hash)
+	);
+
+	// Each block on the best secondary chain is derived from the block at the same height on bob's
+	// fork.
+	for number in 1..=charlie.client.info().best_number {
+		let secondary_hash = charlie.client.hash(number).unwrap().unwrap();
+		assert_eq!(primary_hash_of(secondary_hash), bob.client.hash(number).unwrap());
+	}
+}
+
+#[substrate_test_utils::test(flavor = "multi_thread")]
+async fn bundle_equivocation_should_be_detected() {
+	let mut builder = sc_cli::LoggerBuilder::new("");
+	builder.with_colors(false);
+	let _ = builder.init();
+
+	let tokio_handle = tokio::runtime::Handle::current();
+
+	// start alice
+	let (alice, alice_network_starter) =
+		run_primary_chain_validator_node(tokio_handle.clone(), Alice, vec![]);
+
+	alice_network_starter.start_network();
+
+	// run cirrus alice (a secondary chain full node), the bundles are handled manually later.
+	let alice_executor = cirrus_test_service::TestNodeBuilder::new(tokio_handle, Alice)
+		.connect_to_relay_chain_node(&alice)
+		.build(Role::Full)
+		.await;
+
+	alice_executor.wait_for_blocks(3).await;
+
+	let pair = ExecutorPair::from_string("//Alice", None).unwrap();
+	let primary_hash = alice.client.info().best_hash;
+	let signed_bundle = |slot_number: u64, extrinsics_root: Hash| {
+		let bundle = Bundle {
+			header: BundleHeader { primary_hash, slot_number, extrinsics_root },
+			extrinsics: Vec::new(),
+		};
+		let signature = pair.sign(bundle.hash().as_ref());
+		SignedBundle { bundle, signature, signer: pair.public() }
+	};
+
+	let first_bundle = signed_bundle(1000, Hash::random());
+	assert!(matches!(
+		alice_executor.executor.on_bundle(&first_bundle),
+		Ok(Action::RebroadcastBundle)
+	));
+
+	// The same bundle seen again is not an equivocation.
+	assert!(matches!(
+		alice_executor.executor.on_bundle(&first_bundle),
+		Ok(Action::RebroadcastBundle)
+	));
+
+	// A different bundle at another slot is not an equivocation either.
+	assert!(matches!(
+		alice_executor.executor.on_bundle(&signed_bundle(1001, Hash::random())),
+		Ok(Action::RebroadcastBundle)
+	));
+
+	// A different bundle from the same executor at the same slot is an equivocation, which is
+	// not the fault of the peer that relayed it.
+	let err = alice_executor
+		.executor
+		.on_bundle(&signed_bundle(1000, Hash::random()))
+		.expect_err("Bundle equivocation must be detected");
+	assert!(matches!(err, crate::GossipMessageError::BundleEquivocation));
+	assert!(err.misbehaviour().is_none());
+}
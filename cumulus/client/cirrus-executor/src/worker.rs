@@ -14,16 +14,22 @@
 // You should have received a copy of the GNU General Public License
 // along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
 
-use crate::{BundleProcessor, BundleProducer};
+use crate::{BundlePool, BundleProcessor, BundleProducer};
 use cirrus_primitives::{AccountId, SecondaryApi};
 use codec::{Decode, Encode};
-use futures::{future, FutureExt, Stream, StreamExt, TryFutureExt};
-use sc_client_api::{AuxStore, BlockBackend};
+use futures::{
+	channel::oneshot,
+	future::{self, BoxFuture, Either, Shared},
+	stream::FuturesUnordered,
+	FutureExt, Stream, StreamExt, TryFutureExt,
+};
+use sc_client_api::{AuxStore, BlockBackend, Finalizer};
 use sc_consensus::BlockImport;
 use sp_api::{ApiError, BlockT, ProvideRuntimeApi, TransactionFor};
 use sp_block_builder::BlockBuilder;
 use sp_blockchain::HeaderBackend;
 use sp_consensus_slots::Slot;
+use sp_core::traits::SpawnNamed;
 use sp_executor::{ExecutorApi, OpaqueBundle, SignedOpaqueBundle};
 use sp_runtime::{
 	generic::{BlockId, DigestItem},
@@ -83,16 +89,22 @@ pub(super) async fn start_worker<
 	NSNS,
 >(
 	primary_chain_client: Arc<PClient>,
+	spawner: Box<dyn SpawnNamed + Send + Sync>,
 	bundle_producer: BundleProducer<Block, PBlock, Client, PClient, TransactionPool>,
-	bundle_processor: BundleProcessor<Block, PBlock, Client, PClient, Backend>,
+	bundle_processor: BundleProcessor<Block, PBlock, Client, PClient, TransactionPool, Backend>,
+	bundle_pool: BundlePool,
 	imported_block_notification_stream: IBNS,
 	new_slot_notification_stream: NSNS,
 	active_leaves: Vec<BlockInfo<PBlock>>,
 ) where
 	Block: BlockT,
 	PBlock: BlockT,
-	Client:
-		HeaderBackend<Block> + BlockBackend<Block> + AuxStore + ProvideRuntimeApi<Block> + 'static,
+	Client: HeaderBackend<Block>
+		+ BlockBackend<Block>
+		+ AuxStore
+		+ ProvideRuntimeApi<Block>
+		+ Finalizer<Block, Backend>
+		+ 'static,
 	Client::Api: SecondaryApi<Block, AccountId>
 		+ BlockBuilder<Block>
 		+ sp_api::ApiExt<
@@ -108,15 +120,17 @@ pub(super) async fn start_worker<
 	PClient::Api: ExecutorApi<PBlock, Block::Hash>,
 	TransactionPool: sc_transaction_pool_api::TransactionPool<Block = Block> + 'static,
 	Backend: sc_client_api::Backend<Block> + 'static,
-	IBNS: Stream<Item = NumberFor<PBlock>> + Send + 'static,
+	IBNS: Stream<Item = (PBlock::Hash, NumberFor<PBlock>)> + Send + 'static,
 	NSNS: Stream<Item = (Slot, Sha256Hash)> + Send + 'static,
 {
 	let span = tracing::Span::current();
 
 	let handle_block_import_notifications_fut = handle_block_import_notifications(
+		spawner,
 		primary_chain_client.as_ref(),
 		{
 			let span = span.clone();
+			let bundle_processor = bundle_processor.clone();
 
 			move |primary_hash, bundles, shuffling_seed, maybe_new_runtime| {
 				bundle_processor
@@ -134,6 +148,7 @@ pub(super) async fn start_worker<
 					.boxed()
 			}
 		},
+		move |primary_hash| bundle_processor.is_processed(primary_hash),
 		active_leaves
 			.into_iter()
 			.map(|BlockInfo { hash, parent_hash: _, number }| (hash, number))
@@ -158,10 +173,10 @@ pub(super) async fn start_worker<
 				})
 				.boxed()
 		},
-		Box::pin(
-			new_slot_notification_stream
-				.map(|(slot, global_challenge)| ExecutorSlotInfo { slot, global_challenge }),
-		),
+		Box::pin(new_slot_notification_stream.map(move |(slot, global_challenge)| {
+			bundle_pool.on_new_slot(slot.into());
+			ExecutorSlotInfo { slot, global_challenge }
+		})),
 	);
 
 	let _ = future::select(
@@ -199,11 +214,81 @@ async fn handle_slot_notifications<PBlock, PClient, BundlerFn, SecondaryHash>(
 	}
 }
 
-async fn handle_block_import_notifications<PBlock, PClient, ProcessorFn, SecondaryHash>(
+/// Processing of the primary blocks in flight.
+///
+/// The blocks derived from different primary forks are processed in parallel, while the ones on
+/// the same fork are processed one after another as each secondary block is built on top of the
+/// one derived from the primary parent.
+struct PrimaryBlockProcessing<PBlock: BlockT> {
+	spawner: Box<dyn SpawnNamed + Send + Sync>,
+	/// Futures resolving once the processing of the corresponding primary block is done.
+	in_flight: HashMap<PBlock::Hash, Shared<BoxFuture<'static, ()>>>,
+	/// Hashes of the primary blocks whose processing is done.
+	done: FuturesUnordered<BoxFuture<'static, PBlock::Hash>>,
+}
+
+impl<PBlock: BlockT> PrimaryBlockProcessing<PBlock> {
+	fn new(spawner: Box<dyn SpawnNamed + Send + Sync>) -> Self {
+		Self { spawner, in_flight: HashMap::new(), done: FuturesUnordered::new() }
+	}
+
+	fn is_in_flight(&self, primary_hash: &PBlock::Hash) -> bool {
+		self.in_flight.contains_key(primary_hash)
+	}
+
+	/// Spawns the processing of a primary block, which starts once the processing of its parent
+	/// is done if the parent is still in flight.
+	fn spawn(
+		&mut self,
+		(primary_hash, primary_parent_hash): (PBlock::Hash, PBlock::Hash),
+		processing: Pin<Box<dyn Future<Output = ()> + Send>>,
+	) {
+		let maybe_parent_done = self.in_flight.get(&primary_parent_hash).cloned();
+		let (done_sender, done_receiver) = oneshot::channel();
+
+		self.spawner.spawn_blocking(
+			"cirrus-process-bundles",
+			None,
+			async move {
+				if let Some(parent_done) = maybe_parent_done {
+					parent_done.await;
+				}
+				processing.await;
+				let _ = done_sender.send(());
+			}
+			.boxed(),
+		);
+
+		let done = done_receiver.map(|_| ()).boxed().shared();
+		self.done.push(done.clone().map(move |()| primary_hash).boxed());
+		self.in_flight.insert(primary_hash, done);
+	}
+
+	/// Waits for the processing of some primary block to be done, never resolves if there is
+	/// nothing in flight.
+	async fn next_done(&mut self) {
+		match self.done.next().await {
+			Some(primary_hash) => {
+				self.in_flight.remove(&primary_hash);
+			},
+			None => future::pending().await,
+		}
+	}
+}
+
+async fn handle_block_import_notifications<
+	PBlock,
+	PClient,
+	ProcessorFn,
+	IsProcessedFn,
+	SecondaryHash,
+>(
+	spawner: Box<dyn SpawnNamed + Send + Sync>,
 	primary_chain_client: &PClient,
 	processor: ProcessorFn,
+	is_processed: IsProcessedFn,
 	mut leaves: Vec<(PBlock::Hash, NumberFor<PBlock>)>,
-	mut block_imports: impl Stream<Item = NumberFor<PBlock>> + Unpin,
+	mut block_imports: impl Stream<Item = (PBlock::Hash, NumberFor<PBlock>)> + Unpin,
 ) where
 	PBlock: BlockT,
 	PClient: HeaderBackend<PBlock> + BlockBackend<PBlock> + ProvideRuntimeApi<PBlock>,
@@ -216,41 +301,58 @@ async fn handle_block_import_notifications<PBlock, PClient, ProcessorFn, Seconda
 		) -> Pin<Box<dyn Future<Output = ()> + Send>>
 		+ Send
 		+ Sync,
+	IsProcessedFn: Fn(PBlock::Hash) -> Result<bool, sp_blockchain::Error> + Send + Sync,
 	SecondaryHash: Encode + Decode,
 {
 	let mut active_leaves = HashMap::with_capacity(leaves.len());
+	let mut processing = PrimaryBlockProcessing::<PBlock>::new(spawner);
 
 	// Notify about active leaves on startup before starting the loop
 	for (hash, number) in std::mem::take(&mut leaves) {
 		let _ = active_leaves.insert(hash, number);
-		if let Err(error) =
-			process_primary_block(primary_chain_client, &processor, (hash, number)).await
-		{
+		if let Err(error) = process_primary_block(
+			primary_chain_client,
+			&processor,
+			&is_processed,
+			&mut processing,
+			hash,
+		) {
 			tracing::error!(target: LOG_TARGET, "Collation generation processing error: {error}");
 		}
 	}
 
-	while let Some(block_number) = block_imports.next().await {
+	// The blocks of all primary forks are processed, not only the ones on the best chain, so that
+	// there is a secondary block derived from each primary block.
+	loop {
+		let maybe_block_import =
+			match future::select(block_imports.next(), Box::pin(processing.next_done())).await {
+				Either::Left((maybe_block_import, _)) => maybe_block_import,
+				Either::Right(((), _)) => continue,
+			};
+
+		let (block_hash, block_number) = match maybe_block_import {
+			Some(block_import) => block_import,
+			None => break,
+		};
+
 		let header = primary_chain_client
-			.header(BlockId::Number(block_number))
+			.header(BlockId::Hash(block_hash))
 			.expect("Header of imported block must exist; qed")
 			.expect("Header of imported block must exist; qed");
 		let block_info = BlockInfo {
-			hash: header.hash(),
+			hash: block_hash,
 			parent_hash: *header.parent_hash(),
-			number: *header.number(),
+			number: block_number,
 		};
 
-		if let Err(error) =
-			block_imported(primary_chain_client, &processor, &mut active_leaves, block_info).await
-		{
-			tracing::error!(
-				target: LOG_TARGET,
-				error = ?error,
-				"Failed to process primary block"
-			);
-			break
-		}
+		block_imported(
+			primary_chain_client,
+			&processor,
+			&is_processed,
+			&mut processing,
+			&mut active_leaves,
+			block_info,
+		);
 	}
 }
 
@@ -291,15 +393,16 @@ where
 	Ok(())
 }
 
-async fn block_imported<PBlock, PClient, ProcessorFn, SecondaryHash>(
+fn block_imported<PBlock, PClient, ProcessorFn, IsProcessedFn, SecondaryHash>(
 	primary_chain_client: &PClient,
 	processor: &ProcessorFn,
+	is_processed: &IsProcessedFn,
+	processing: &mut PrimaryBlockProcessing<PBlock>,
 	active_leaves: &mut HashMap<PBlock::Hash, NumberFor<PBlock>>,
 	block_info: BlockInfo<PBlock>,
-) -> Result<(), ApiError>
-where
+) where
 	PBlock: BlockT,
-	PClient: HeaderBackend<PBlock> + BlockBackend<PBlock> + ProvideRuntimeApi<PBlock> + Send + Sync,
+	PClient: HeaderBackend<PBlock> + BlockBackend<PBlock> + ProvideRuntimeApi<PBlock>,
 	PClient::Api: ExecutorApi<PBlock, SecondaryHash>,
 	ProcessorFn: Fn(
 			(PBlock::Hash, NumberFor<PBlock>),
@@ -309,13 +412,14 @@ where
 		) -> Pin<Box<dyn Future<Output = ()> + Send>>
 		+ Send
 		+ Sync,
+	IsProcessedFn: Fn(PBlock::Hash) -> Result<bool, sp_blockchain::Error>,
 	SecondaryHash: Encode + Decode,
 {
 	match active_leaves.entry(block_info.hash) {
 		Entry::Vacant(entry) => entry.insert(block_info.number),
 		Entry::Occupied(entry) => {
 			debug_assert_eq!(*entry.get(), block_info.number);
-			return Ok(())
+			return
 		},
 	};
 
@@ -323,12 +427,80 @@ where
 		debug_assert_eq!(block_info.number.saturating_sub(One::one()), number);
 	}
 
-	if let Err(error) =
-		process_primary_block(primary_chain_client, processor, (block_info.hash, block_info.number))
-			.await
-	{
+	if let Err(error) = process_primary_block(
+		primary_chain_client,
+		processor,
+		is_processed,
+		processing,
+		block_info.hash,
+	) {
 		tracing::error!(target: LOG_TARGET, "Collation generation processing error: {error}");
 	}
+}
+
+/// Spawns the processing of the given primary block, preceded by its ancestors whose bundles
+/// were not processed yet, e.g., the ones imported while the executor was not running, as the
+/// secondary block must be built on top of the one derived from the primary parent.
+fn process_primary_block<PBlock, PClient, ProcessorFn, IsProcessedFn, SecondaryHash>(
+	primary_chain_client: &PClient,
+	processor: &ProcessorFn,
+	is_processed: &IsProcessedFn,
+	processing: &mut PrimaryBlockProcessing<PBlock>,
+	block_hash: PBlock::Hash,
+) -> Result<(), sp_blockchain::Error>
+where
+	PBlock: BlockT,
+	PClient: HeaderBackend<PBlock> + BlockBackend<PBlock> + ProvideRuntimeApi<PBlock>,
+	PClient::Api: ExecutorApi<PBlock, SecondaryHash>,
+	ProcessorFn: Fn(
+			(PBlock::Hash, NumberFor<PBlock>),
+			Vec<OpaqueBundle>,
+			Randomness,
+			Option<Cow<'static, [u8]>>,
+		) -> Pin<Box<dyn Future<Output = ()> + Send>>
+		+ Send
+		+ Sync,
+	IsProcessedFn: Fn(PBlock::Hash) -> Result<bool, sp_blockchain::Error>,
+	SecondaryHash: Encode + Decode,
+{
+	let mut to_process = Vec::new();
+	let mut next_hash = block_hash;
+	loop {
+		let header = primary_chain_client.header(BlockId::Hash(next_hash))?.ok_or_else(|| {
+			sp_blockchain::Error::Backend(format!(
+				"Header for primary block {next_hash:?} not found"
+			))
+		})?;
+		let parent_hash = *header.parent_hash();
+		// The secondary block derived from the primary block #1 is built on top of the genesis.
+		let is_parent_processed = *header.number() <= One::one() ||
+			processing.is_in_flight(&parent_hash) ||
+			is_processed(parent_hash)?;
+		to_process.push(header);
+
+		if is_parent_processed {
+			break
+		}
+		next_hash = parent_hash;
+	}
+
+	if to_process.len() > 1 {
+		tracing::debug!(
+			target: LOG_TARGET,
+			?block_hash,
+			missing = to_process.len() - 1,
+			"Catching up with the primary ancestors whose bundles were not processed",
+		);
+	}
+
+	for header in to_process.into_iter().rev() {
+		let primary_info = (header.hash(), *header.parent_hash());
+		if let Some(processing_fut) =
+			prepare_primary_block(primary_chain_client, processor, header)?
+		{
+			processing.spawn(primary_info, processing_fut);
+		}
+	}
 
 	Ok(())
 }
@@ -336,15 +508,15 @@ where
 /// Apply the transaction bundles for given primary block as follows:
 ///
 /// 1. Extract the transaction bundles from the block.
-/// 2. Pass the bundles to secondary node and do the computation there.
-async fn process_primary_block<PBlock, PClient, ProcessorFn, SecondaryHash>(
+/// 2. Pass the bundles to secondary node and return the future doing the computation there.
+fn prepare_primary_block<PBlock, PClient, ProcessorFn, SecondaryHash>(
 	primary_chain_client: &PClient,
 	processor: &ProcessorFn,
-	(block_hash, block_number): (PBlock::Hash, NumberFor<PBlock>),
-) -> Result<(), ApiError>
+	header: PBlock::Header,
+) -> Result<Option<Pin<Box<dyn Future<Output = ()> + Send>>>, ApiError>
 where
 	PBlock: BlockT,
-	PClient: HeaderBackend<PBlock> + BlockBackend<PBlock> + ProvideRuntimeApi<PBlock> + Send + Sync,
+	PClient: HeaderBackend<PBlock> + BlockBackend<PBlock> + ProvideRuntimeApi<PBlock>,
 	PClient::Api: ExecutorApi<PBlock, SecondaryHash>,
 	ProcessorFn: Fn(
 			(PBlock::Hash, NumberFor<PBlock>),
@@ -356,6 +528,8 @@ where
 		+ Sync,
 	SecondaryHash: Encode + Decode,
 {
+	let block_hash = header.hash();
+	let block_number = *header.number();
 	let block_id = BlockId::Hash(block_hash);
 	let extrinsics = match primary_chain_client.block_body(&block_id) {
 		Err(err) => {
@@ -364,11 +538,11 @@ where
 				?err,
 				"Failed to get block body from primary chain"
 			);
-			return Ok(())
+			return Ok(None)
 		},
 		Ok(None) => {
 			tracing::error!(target: LOG_TARGET, ?block_hash, "BlockBody unavailable");
-			return Ok(())
+			return Ok(None)
 		},
 		Ok(Some(body)) => body,
 	};
@@ -383,18 +557,6 @@ where
 			.collect(),
 	)?;
 
-	let header = match primary_chain_client.header(block_id) {
-		Err(err) => {
-			tracing::error!(target: LOG_TARGET, ?err, "Failed to get block from primary chain");
-			return Ok(())
-		},
-		Ok(None) => {
-			tracing::error!(target: LOG_TARGET, ?block_hash, "BlockHeader unavailable");
-			return Ok(())
-		},
-		Ok(Some(header)) => header,
-	};
-
 	let maybe_new_runtime = if header
 		.digest()
 		.logs
@@ -410,7 +572,5 @@ where
 		.runtime_api()
 		.extrinsics_shuffling_seed(&block_id, header)?;
 
-	processor((block_hash, block_number), bundles, shuffling_seed, maybe_new_runtime).await;
-
-	Ok(())
+	Ok(Some(processor((block_hash, block_number), bundles, shuffling_seed, maybe_new_runtime)))
 }
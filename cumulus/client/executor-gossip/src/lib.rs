@@ -1,17 +1,21 @@
+#[cfg(test)]
+mod tests;
 mod worker;
 
 use self::worker::GossipWorker;
 use parity_scale_codec::{Decode, Encode};
 use parking_lot::{Mutex, RwLock};
-use sc_network::{ObservedRole, PeerId};
+use sc_network::{ObservedRole, PeerId, ReputationChange};
 use sc_network_gossip::{
 	GossipEngine, MessageIntent, Network as GossipNetwork, ValidationResult, Validator,
 	ValidatorContext,
 };
-use sc_utils::mpsc::TracingUnboundedReceiver;
+use sc_utils::mpsc::{tracing_unbounded, TracingUnboundedReceiver, TracingUnboundedSender};
 use sp_core::hashing::twox_64;
 use sp_executor::{SignedBundle, SignedExecutionReceipt};
-use sp_runtime::traits::{Block as BlockT, Hash as HashT, Header as HeaderT, NumberFor};
+use sp_runtime::traits::{
+	Block as BlockT, Hash as HashT, Header as HeaderT, NumberFor, Saturating,
+};
 use std::{
 	collections::HashSet,
 	fmt::Debug,
@@ -24,13 +28,76 @@ const LOG_TARGET: &str = "gossip::executor";
 
 const EXECUTOR_PROTOCOL_NAME: &str = "/subspace/executor/1";
 
-// TODO: proper timeout
-/// Timeout for rebroadcasting messages.
-/// The default value used in network-gossip is 1100ms.
+/// Interval of rebroadcasting the messages kept in the gossip engine.
+///
+/// The default value used in network-gossip is 1100ms, the executor messages are produced at
+/// most once per primary slot/block, so there is no need to rebroadcast them that often.
 const REBROADCAST_AFTER: Duration = Duration::from_secs(6);
 
+/// Maximum number of the messages received from the network that can be rebroadcasted
+/// within [`REBROADCAST_AFTER`].
+const MAX_REBROADCASTS_PER_INTERVAL: usize = 512;
+
+/// Number of primary blocks after which a gossip message is considered as expired.
+///
+/// A message is expired once the primary block it was created at is more than
+/// `MESSAGE_EXPIRY_DEPTH` blocks behind the best primary block.
+const MESSAGE_EXPIRY_DEPTH: u32 = 32;
+
 type MessageHash = [u8; 8];
 
+mod cost {
+	use sc_network::ReputationChange as Rep;
+
+	/// A message can not be decoded.
+	pub(super) const UNDECODABLE_MESSAGE: Rep = Rep::new(-500, "Executor: Undecodable message");
+	/// A message was created at a primary block that is too old.
+	pub(super) const STALE_MESSAGE: Rep = Rep::new(-50, "Executor: Stale message");
+	/// A message is signed with an invalid signature.
+	pub(super) const BAD_SIGNATURE: Rep = Rep::new(-1000, "Executor: Bad signature");
+	/// A message is signed by an executor that is not the expected author.
+	pub(super) const INVALID_AUTHOR: Rep = Rep::new(-1000, "Executor: Invalid author");
+}
+
+mod benefit {
+	use sc_network::ReputationChange as Rep;
+
+	/// A valid message that is not known yet.
+	pub(super) const VALID_MESSAGE: Rep = Rep::new(10, "Executor: Valid message");
+}
+
+/// Misbehaviour of a peer detected by the [`GossipMessageHandler`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Misbehaviour {
+	/// The signature of a bundle or an execution receipt is invalid.
+	BadSignature,
+	/// A bundle or an execution receipt is not signed by the expected executor.
+	InvalidAuthor,
+}
+
+impl Misbehaviour {
+	fn cost(&self) -> ReputationChange {
+		match self {
+			Self::BadSignature => cost::BAD_SIGNATURE,
+			Self::InvalidAuthor => cost::INVALID_AUTHOR,
+		}
+	}
+}
+
+/// Error of the [`GossipMessageHandler`].
+pub trait HandlerError: Debug {
+	/// Returns the misbehaviour of the message sender, `None` if this error is not the
+	/// fault of the sender, e.g., some internal error of the local node.
+	fn misbehaviour(&self) -> Option<Misbehaviour>;
+}
+
+/// Reputation change of a peer that has to be reported to the network.
+#[derive(Debug)]
+pub(crate) struct PeerReport {
+	pub(crate) who: PeerId,
+	pub(crate) cost_benefit: ReputationChange,
+}
+
 /// Returns the configuration value to put in [`sc_network::config::NetworkConfiguration::extra_sets`].
 pub fn executor_gossip_peers_set_config() -> sc_network::config::NonDefaultSetConfig {
 	let mut cfg =
@@ -53,6 +120,26 @@ pub enum GossipMessage<PBlock: BlockT, Block: BlockT> {
 	ExecutionReceipt(SignedExecutionReceipt<NumberFor<PBlock>, PBlock::Hash, Block::Hash>),
 }
 
+impl<PBlock: BlockT, Block: BlockT> GossipMessage<PBlock, Block> {
+	/// Returns the number of primary block at which this message was created, `None` if the
+	/// primary block is unknown locally.
+	fn primary_number<Executor>(&self, executor: &Executor) -> Option<NumberFor<PBlock>>
+	where
+		Executor: GossipMessageHandler<PBlock, Block>,
+	{
+		match self {
+			Self::Bundle(SignedBundle { bundle, .. }) => {
+				let primary_hash =
+					PBlock::Hash::decode(&mut bundle.header.primary_hash.encode().as_slice())
+						.ok()?;
+				executor.primary_number(primary_hash)
+			},
+			Self::ExecutionReceipt(SignedExecutionReceipt { execution_receipt, .. }) =>
+				Some(execution_receipt.primary_number),
+		}
+	}
+}
+
 impl<PBlock: BlockT, Block: BlockT> From<SignedBundle<Block::Extrinsic>>
 	for GossipMessage<PBlock, Block>
 {
@@ -100,7 +187,13 @@ where
 	Block: BlockT,
 {
 	/// Error type.
-	type Error: Debug;
+	type Error: HandlerError;
+
+	/// Returns the number of the best block on the primary chain.
+	fn best_primary_number(&self) -> NumberFor<PBlock>;
+
+	/// Returns the number of given primary block, `None` if the block is unknown.
+	fn primary_number(&self, primary_hash: PBlock::Hash) -> Option<NumberFor<PBlock>>;
 
 	/// Validates and applies when a transaction bundle was received.
 	fn on_bundle(&self, bundle: &SignedBundle<Block::Extrinsic>) -> Result<Action, Self::Error>;
@@ -122,24 +215,60 @@ where
 	topic: Block::Hash,
 	executor: Executor,
 	next_rebroadcast: Mutex<Instant>,
+	rebroadcast_limiter: Mutex<RebroadcastLimiter>,
 	known_rebroadcasted: RwLock<HashSet<MessageHash>>,
+	report_sender: TracingUnboundedSender<PeerReport>,
 	_phantom_data: PhantomData<PBlock>,
 }
 
+/// Limits the number of received messages rebroadcasted within an interval.
+struct RebroadcastLimiter {
+	interval_start: Instant,
+	rebroadcasted: usize,
+}
+
+impl RebroadcastLimiter {
+	fn new() -> Self {
+		Self { interval_start: Instant::now(), rebroadcasted: 0 }
+	}
+
+	/// Returns `true` if one more message can be rebroadcasted in the current interval.
+	fn try_acquire(&mut self) -> bool {
+		let now = Instant::now();
+		if now.duration_since(self.interval_start) >= REBROADCAST_AFTER {
+			self.interval_start = now;
+			self.rebroadcasted = 0;
+		}
+		if self.rebroadcasted < MAX_REBROADCASTS_PER_INTERVAL {
+			self.rebroadcasted += 1;
+			true
+		} else {
+			false
+		}
+	}
+}
+
 impl<PBlock, Block, Executor> GossipValidator<PBlock, Block, Executor>
 where
 	PBlock: BlockT,
 	Block: BlockT,
 	Executor: GossipMessageHandler<PBlock, Block>,
 {
-	pub fn new(executor: Executor) -> Self {
-		Self {
+	/// Creates a new instance, returns the validator and the receiver of the peer reports
+	/// produced by the validator.
+	pub(crate) fn new(executor: Executor) -> (Self, TracingUnboundedReceiver<PeerReport>) {
+		let (report_sender, report_receiver) =
+			tracing_unbounded("mpsc_executor_gossip_peer_reports");
+		let validator = Self {
 			topic: topic::<Block>(),
 			executor,
 			next_rebroadcast: Mutex::new(Instant::now() + REBROADCAST_AFTER),
+			rebroadcast_limiter: Mutex::new(RebroadcastLimiter::new()),
 			known_rebroadcasted: RwLock::new(HashSet::new()),
+			report_sender,
 			_phantom_data: PhantomData::default(),
-		}
+		};
+		(validator, report_receiver)
 	}
 
 	pub(crate) fn note_rebroadcasted(&self, encoded_message: &[u8]) {
@@ -147,39 +276,69 @@ where
 		known_rebroadcasted.insert(twox_64(encoded_message));
 	}
 
-	fn validate_message(&self, msg: GossipMessage<PBlock, Block>) -> ValidationResult<Block::Hash> {
-		match msg {
+	/// Returns `true` if the message received from the network should be rebroadcasted.
+	///
+	/// The message is not rebroadcasted if it has already been rebroadcasted by us or the
+	/// rebroadcast quota of current interval has been exhausted.
+	pub(crate) fn should_rebroadcast(&self, encoded_message: &[u8]) -> bool {
+		if self.known_rebroadcasted.read().contains(&twox_64(encoded_message)) {
+			return false
+		}
+		self.rebroadcast_limiter.lock().try_acquire()
+	}
+
+	fn report(&self, who: &PeerId, cost_benefit: ReputationChange) {
+		let _ = self.report_sender.unbounded_send(PeerReport { who: *who, cost_benefit });
+	}
+
+	/// Returns `true` if the message was created at a primary block which is more than
+	/// [`MESSAGE_EXPIRY_DEPTH`] blocks behind the best primary block.
+	fn is_stale(&self, msg: &GossipMessage<PBlock, Block>) -> bool {
+		match msg.primary_number(&self.executor) {
+			Some(primary_number) =>
+				primary_number.saturating_add(MESSAGE_EXPIRY_DEPTH.into()) <
+					self.executor.best_primary_number(),
+			None => false,
+		}
+	}
+
+	fn validate_message(
+		&self,
+		sender: &PeerId,
+		msg: GossipMessage<PBlock, Block>,
+	) -> ValidationResult<Block::Hash> {
+		if self.is_stale(&msg) {
+			tracing::debug!(target: LOG_TARGET, ?msg, "Stale message discarded");
+			self.report(sender, cost::STALE_MESSAGE);
+			return ValidationResult::Discard
+		}
+
+		let (outcome, should_keep) = match msg {
 			GossipMessage::Bundle(bundle) => {
 				let outcome = self.executor.on_bundle(&bundle);
-				match outcome {
-					Ok(action) if action.rebroadcast_bundle() =>
-						ValidationResult::ProcessAndKeep(self.topic),
-					Err(err) => {
-						tracing::debug!(
-							target: LOG_TARGET,
-							?err,
-							"Invalid GossipMessage::Bundle discarded"
-						);
-						ValidationResult::Discard
-					},
-					_ => ValidationResult::ProcessAndDiscard(self.topic),
-				}
+				let should_keep = matches!(outcome, Ok(ref action) if action.rebroadcast_bundle());
+				(outcome, should_keep)
 			},
 			GossipMessage::ExecutionReceipt(execution_receipt) => {
 				let outcome = self.executor.on_execution_receipt(&execution_receipt);
-				match outcome {
-					Ok(action) if action.rebroadcast_execution_receipt() =>
-						ValidationResult::ProcessAndKeep(self.topic),
-					Err(err) => {
-						tracing::debug!(
-							target: LOG_TARGET,
-							?err,
-							"Invalid GossipMessage::ExecutionReceipt discarded"
-						);
-						ValidationResult::Discard
-					},
-					_ => ValidationResult::ProcessAndDiscard(self.topic),
+				let should_keep =
+					matches!(outcome, Ok(ref action) if action.rebroadcast_execution_receipt());
+				(outcome, should_keep)
+			},
+		};
+
+		match outcome {
+			Ok(_) if should_keep => {
+				self.report(sender, benefit::VALID_MESSAGE);
+				ValidationResult::ProcessAndKeep(self.topic)
+			},
+			Ok(_) => ValidationResult::ProcessAndDiscard(self.topic),
+			Err(err) => {
+				tracing::debug!(target: LOG_TARGET, ?err, "Invalid gossip message discarded");
+				if let Some(misbehaviour) = err.misbehaviour() {
+					self.report(sender, misbehaviour.cost());
 				}
+				ValidationResult::Discard
 			},
 		}
 	}
@@ -201,16 +360,18 @@ where
 
 	fn peer_disconnected(&self, _context: &mut dyn ValidatorContext<Block>, _who: &PeerId) {}
 
+	// NOTE: `ValidatorContext` provides no way to report peers, the reputation changes are
+	// sent to the gossip worker which reports them via the gossip engine instead.
 	fn validate(
 		&self,
 		_context: &mut dyn ValidatorContext<Block>,
-		_sender: &PeerId,
+		sender: &PeerId,
 		mut data: &[u8],
 	) -> ValidationResult<Block::Hash> {
 		match GossipMessage::<PBlock, Block>::decode(&mut data) {
 			Ok(msg) => {
 				tracing::debug!(target: LOG_TARGET, ?msg, "Validating incoming message");
-				self.validate_message(msg)
+				self.validate_message(sender, msg)
 			},
 			Err(err) => {
 				tracing::debug!(
@@ -219,6 +380,7 @@ where
 					?data,
 					"Message discarded due to the decoding error"
 				);
+				self.report(sender, cost::UNDECODABLE_MESSAGE);
 				ValidationResult::Discard
 			},
 		}
@@ -231,12 +393,11 @@ where
 	fn message_expired<'a>(&'a self) -> Box<dyn FnMut(Block::Hash, &[u8]) -> bool + 'a> {
 		Box::new(move |_topic, mut data| {
 			let msg_hash = twox_64(data);
-			// TODO: can be expired due to the message itself might be too old?
-			let _msg = match GossipMessage::<PBlock, Block>::decode(&mut data) {
+			let msg = match GossipMessage::<PBlock, Block>::decode(&mut data) {
 				Ok(msg) => msg,
 				Err(_) => return true,
 			};
-			let expired = {
+			let expired = self.is_stale(&msg) || {
 				let known_rebroadcasted = self.known_rebroadcasted.read();
 				known_rebroadcasted.contains(&msg_hash)
 			};
@@ -301,7 +462,8 @@ pub async fn start_gossip_worker<PBlock, Block, Network, Executor>(
 	let ExecutorGossipParams { network, executor, bundle_receiver, execution_receipt_receiver } =
 		gossip_params;
 
-	let gossip_validator = Arc::new(GossipValidator::new(executor));
+	let (gossip_validator, report_receiver) = GossipValidator::new(executor);
+	let gossip_validator = Arc::new(gossip_validator);
 	let gossip_engine =
 		GossipEngine::new(network, EXECUTOR_PROTOCOL_NAME, gossip_validator.clone(), None);
 
@@ -310,6 +472,7 @@ pub async fn start_gossip_worker<PBlock, Block, Network, Executor>(
 		Arc::new(Mutex::new(gossip_engine)),
 		bundle_receiver,
 		execution_receipt_receiver,
+		report_receiver,
 	);
 
 	gossip_worker.run().await
@@ -0,0 +1,349 @@
+use crate::{
+	benefit, cost, topic, Action, GossipMessage, GossipMessageHandler, GossipValidator,
+	HandlerError, Misbehaviour, PeerReport, EXECUTOR_PROTOCOL_NAME, MAX_REBROADCASTS_PER_INTERVAL,
+	MESSAGE_EXPIRY_DEPTH,
+};
+use futures::{FutureExt, StreamExt};
+use parity_scale_codec::Encode;
+use sc_network::PeerId;
+use sc_network_gossip::{GossipEngine, ValidationResult, Validator, ValidatorContext};
+use sc_network_test::{Block, Extrinsic, FullPeerConfig, Hash, TestNet, TestNetFactory};
+use sc_utils::mpsc::TracingUnboundedReceiver;
+use sp_core::{Pair, H256};
+use sp_executor::{
+	Bundle, BundleHeader, ExecutionReceipt, ExecutorPair, SignedBundle, SignedExecutionReceipt,
+};
+use sp_runtime::RuntimeAppPublic;
+use std::{collections::HashSet, sync::Arc, task::Poll};
+
+type TestGossipMessage = GossipMessage<Block, Block>;
+
+#[derive(Debug)]
+enum TestError {
+	BadSignature,
+	Equivocation,
+}
+
+impl HandlerError for TestError {
+	fn misbehaviour(&self) -> Option<Misbehaviour> {
+		match self {
+			Self::BadSignature => Some(Misbehaviour::BadSignature),
+			// The relaying peer is not the offender of an equivocation.
+			Self::Equivocation => None,
+		}
+	}
+}
+
+#[derive(Default)]
+struct TestExecutor {
+	best_primary_number: u64,
+	equivocation_slots: HashSet<u64>,
+}
+
+impl GossipMessageHandler<Block, Block> for TestExecutor {
+	type Error = TestError;
+
+	fn best_primary_number(&self) -> u64 {
+		self.best_primary_number
+	}
+
+	fn primary_number(&self, _primary_hash: Hash) -> Option<u64> {
+		None
+	}
+
+	fn on_bundle(
+		&self,
+		SignedBundle { bundle, signature, signer }: &SignedBundle<Extrinsic>,
+	) -> Result<Action, Self::Error> {
+		if !signer.verify(&bundle.hash(), signature) {
+			return Err(TestError::BadSignature)
+		}
+		if self.equivocation_slots.contains(&bundle.header.slot_number) {
+			return Err(TestError::Equivocation)
+		}
+		Ok(Action::RebroadcastBundle)
+	}
+
+	fn on_execution_receipt(
+		&self,
+		SignedExecutionReceipt { execution_receipt, signature, signer }: &SignedExecutionReceipt<
+			u64,
+			Hash,
+			Hash,
+		>,
+	) -> Result<Action, Self::Error> {
+		if !signer.verify(&execution_receipt.hash(), signature) {
+			return Err(TestError::BadSignature)
+		}
+		Ok(Action::RebroadcastExecutionReceipt)
+	}
+}
+
+struct NoopContext;
+
+impl ValidatorContext<Block> for NoopContext {
+	fn broadcast_topic(&mut self, _topic: Hash, _force: bool) {}
+
+	fn broadcast_message(&mut self, _topic: Hash, _message: Vec<u8>, _force: bool) {}
+
+	fn send_message(&mut self, _who: &PeerId, _message: Vec<u8>) {}
+
+	fn send_topic(&mut self, _who: &PeerId, _topic: Hash, _force: bool) {}
+}
+
+fn executor_pair() -> ExecutorPair {
+	ExecutorPair::from_seed(&[1u8; 32])
+}
+
+fn signed_bundle(slot_number: u64) -> SignedBundle<Extrinsic> {
+	let pair = executor_pair();
+	let bundle = Bundle {
+		header: BundleHeader {
+			primary_hash: H256::random(),
+			slot_number,
+			extrinsics_root: H256::random(),
+		},
+		extrinsics: Vec::new(),
+	};
+	let signature = pair.sign(bundle.hash().as_ref());
+	SignedBundle { bundle, signature, signer: pair.public() }
+}
+
+fn signed_execution_receipt(primary_number: u64) -> SignedExecutionReceipt<u64, Hash, Hash> {
+	let pair = executor_pair();
+	let execution_receipt = ExecutionReceipt {
+		primary_number,
+		primary_hash: H256::random(),
+		secondary_hash: H256::random(),
+		trace: vec![H256::random(), H256::random()],
+		trace_root: Default::default(),
+		invalid_extrinsics_root: Default::default(),
+	};
+	let signature = pair.sign(execution_receipt.hash().as_ref());
+	SignedExecutionReceipt { execution_receipt, signature, signer: pair.public() }
+}
+
+fn next_report(report_receiver: &mut TracingUnboundedReceiver<PeerReport>) -> Option<PeerReport> {
+	report_receiver.next().now_or_never().flatten()
+}
+
+#[test]
+fn undecodable_message_is_reported() {
+	let (validator, mut report_receiver) = GossipValidator::new(TestExecutor::default());
+	let who = PeerId::random();
+
+	let result = validator.validate(&mut NoopContext, &who, &[1, 2, 3]);
+	assert!(matches!(result, ValidationResult::Discard));
+
+	let report = next_report(&mut report_receiver).unwrap();
+	assert_eq!(report.who, who);
+	assert_eq!(report.cost_benefit, cost::UNDECODABLE_MESSAGE);
+}
+
+#[test]
+fn valid_message_is_kept_and_rewarded() {
+	let (validator, mut report_receiver) = GossipValidator::new(TestExecutor::default());
+	let who = PeerId::random();
+
+	let message: TestGossipMessage = signed_bundle(1).into();
+	let result = validator.validate(&mut NoopContext, &who, &message.encode());
+	assert!(matches!(result, ValidationResult::ProcessAndKeep(t) if t == topic::<Block>()));
+
+	let report = next_report(&mut report_receiver).unwrap();
+	assert_eq!(report.cost_benefit, benefit::VALID_MESSAGE);
+}
+
+#[test]
+fn misbehaviours_are_reported() {
+	let (validator, mut report_receiver) = GossipValidator::new(TestExecutor::default());
+	let who = PeerId::random();
+
+	let mut bad_signature_bundle = signed_bundle(1);
+	bad_signature_bundle.bundle.header.extrinsics_root = H256::random();
+	let message: TestGossipMessage = bad_signature_bundle.into();
+	let result = validator.validate(&mut NoopContext, &who, &message.encode());
+	assert!(matches!(result, ValidationResult::Discard));
+	assert_eq!(next_report(&mut report_receiver).unwrap().cost_benefit, cost::BAD_SIGNATURE);
+
+	let mut bad_signature_receipt = signed_execution_receipt(1);
+	bad_signature_receipt.execution_receipt.trace.push(H256::random());
+	let message: TestGossipMessage = bad_signature_receipt.into();
+	let result = validator.validate(&mut NoopContext, &who, &message.encode());
+	assert!(matches!(result, ValidationResult::Discard));
+	assert_eq!(next_report(&mut report_receiver).unwrap().cost_benefit, cost::BAD_SIGNATURE);
+}
+
+#[test]
+fn relaying_equivocation_is_not_penalized() {
+	let (validator, mut report_receiver) = GossipValidator::new(TestExecutor {
+		equivocation_slots: [2].into_iter().collect(),
+		..Default::default()
+	});
+	let who = PeerId::random();
+
+	let message: TestGossipMessage = signed_bundle(2).into();
+	let result = validator.validate(&mut NoopContext, &who, &message.encode());
+	assert!(matches!(result, ValidationResult::Discard));
+	assert!(next_report(&mut report_receiver).is_none());
+}
+
+#[test]
+fn stale_message_is_discarded_and_expired() {
+	let best_primary_number = 100;
+	let (validator, mut report_receiver) =
+		GossipValidator::new(TestExecutor { best_primary_number, ..Default::default() });
+	let who = PeerId::random();
+
+	let stale_primary_number = best_primary_number - u64::from(MESSAGE_EXPIRY_DEPTH) - 1;
+	let stale_message: TestGossipMessage = signed_execution_receipt(stale_primary_number).into();
+	let result = validator.validate(&mut NoopContext, &who, &stale_message.encode());
+	assert!(matches!(result, ValidationResult::Discard));
+	assert_eq!(next_report(&mut report_receiver).unwrap().cost_benefit, cost::STALE_MESSAGE);
+
+	let recent_message: TestGossipMessage =
+		signed_execution_receipt(best_primary_number - u64::from(MESSAGE_EXPIRY_DEPTH)).into();
+	let result = validator.validate(&mut NoopContext, &who, &recent_message.encode());
+	assert!(matches!(result, ValidationResult::ProcessAndKeep(_)));
+
+	let mut message_expired = validator.message_expired();
+	assert!(message_expired(topic::<Block>(), &stale_message.encode()));
+	assert!(!message_expired(topic::<Block>(), &recent_message.encode()));
+}
+
+#[test]
+fn rebroadcast_is_rate_limited() {
+	let (validator, _report_receiver) = GossipValidator::new(TestExecutor::default());
+
+	let message: TestGossipMessage = signed_bundle(1).into();
+	let encoded_message = message.encode();
+	assert!(validator.should_rebroadcast(&encoded_message));
+	validator.note_rebroadcasted(&encoded_message);
+	assert!(!validator.should_rebroadcast(&encoded_message));
+
+	// One message has been rebroadcasted in the current interval.
+	for slot_number in 2..=MAX_REBROADCASTS_PER_INTERVAL as u64 {
+		let message: TestGossipMessage = signed_bundle(slot_number).into();
+		assert!(validator.should_rebroadcast(&message.encode()));
+	}
+
+	let message: TestGossipMessage = signed_bundle(u64::MAX).into();
+	assert!(!validator.should_rebroadcast(&message.encode()));
+}
+
+fn executor_test_net(peers: usize) -> TestNet {
+	let mut net = TestNet::new(0);
+	for _ in 0..peers {
+		net.add_full_peer_with_config(FullPeerConfig {
+			notifications_protocols: vec![EXECUTOR_PROTOCOL_NAME.into()],
+			is_authority: true,
+			..Default::default()
+		});
+	}
+	net
+}
+
+type TestValidator = GossipValidator<Block, Block, TestExecutor>;
+
+fn start_gossip_engines(
+	net: &mut TestNet,
+) -> Vec<(GossipEngine<Block>, TracingUnboundedReceiver<PeerReport>)> {
+	(0..net.peers().len())
+		.map(|i| {
+			let (validator, report_receiver) = TestValidator::new(TestExecutor::default());
+			let gossip_engine = GossipEngine::new(
+				net.peer(i).network_service().clone(),
+				EXECUTOR_PROTOCOL_NAME,
+				Arc::new(validator),
+				None,
+			);
+			(gossip_engine, report_receiver)
+		})
+		.collect()
+}
+
+#[test]
+fn messages_are_gossiped_to_all_peers() {
+	sp_tracing::try_init_simple();
+
+	let mut net = executor_test_net(3);
+	net.block_until_connected();
+
+	let mut engines = start_gossip_engines(&mut net);
+	let mut incoming = engines
+		.iter_mut()
+		.skip(1)
+		.map(|(gossip_engine, _)| gossip_engine.messages_for(topic::<Block>()))
+		.collect::<Vec<_>>();
+
+	let message: TestGossipMessage = signed_bundle(1).into();
+	let encoded_message = message.encode();
+	let mut received = vec![false; incoming.len()];
+
+	futures::executor::block_on(futures::future::poll_fn(|cx| {
+		net.poll(cx);
+
+		// The notification substreams may not be open yet, keep gossiping until all the peers
+		// have received the message.
+		engines[0].0.gossip_message(topic::<Block>(), encoded_message.clone(), true);
+
+		for (gossip_engine, _) in engines.iter_mut() {
+			if gossip_engine.poll_unpin(cx).is_ready() {
+				panic!("Gossip engine has terminated unexpectedly");
+			}
+		}
+
+		for (incoming, received) in incoming.iter_mut().zip(received.iter_mut()) {
+			while let Poll::Ready(Some(notification)) = incoming.poll_next_unpin(cx) {
+				assert_eq!(notification.message, encoded_message);
+				*received = true;
+			}
+		}
+
+		if received.iter().all(|received| *received) {
+			Poll::Ready(())
+		} else {
+			Poll::Pending
+		}
+	}));
+
+	// The message may be relayed by any other peer.
+	for (_, report_receiver) in engines.iter_mut().skip(1) {
+		let report = next_report(report_receiver).unwrap();
+		assert_eq!(report.cost_benefit, benefit::VALID_MESSAGE);
+	}
+}
+
+#[test]
+fn bad_signature_sender_is_reported() {
+	sp_tracing::try_init_simple();
+
+	let mut net = executor_test_net(2);
+	net.block_until_connected();
+
+	let mut engines = start_gossip_engines(&mut net);
+
+	let mut bad_signature_bundle = signed_bundle(1);
+	bad_signature_bundle.bundle.header.extrinsics_root = H256::random();
+	let message: TestGossipMessage = bad_signature_bundle.into();
+	let encoded_message = message.encode();
+
+	let report = futures::executor::block_on(futures::future::poll_fn(|cx| {
+		net.poll(cx);
+
+		engines[0].0.gossip_message(topic::<Block>(), encoded_message.clone(), true);
+
+		for (gossip_engine, _) in engines.iter_mut() {
+			if gossip_engine.poll_unpin(cx).is_ready() {
+				panic!("Gossip engine has terminated unexpectedly");
+			}
+		}
+
+		engines[1]
+			.1
+			.poll_next_unpin(cx)
+			.map(|report| report.expect("Validator is alive; qed"))
+	}));
+
+	assert_eq!(report.who, net.peer(0).id());
+	assert_eq!(report.cost_benefit, cost::BAD_SIGNATURE);
+}
@@ -1,4 +1,4 @@
-use crate::{topic, GossipMessage, GossipMessageHandler, GossipValidator, LOG_TARGET};
+use crate::{topic, GossipMessage, GossipMessageHandler, GossipValidator, PeerReport, LOG_TARGET};
 use futures::{future, FutureExt, StreamExt};
 use parity_scale_codec::{Decode, Encode};
 use parking_lot::Mutex;
@@ -21,6 +21,7 @@ where
 	execution_receipt_receiver: TracingUnboundedReceiver<
 		SignedExecutionReceipt<NumberFor<PBlock>, PBlock::Hash, Block::Hash>,
 	>,
+	report_receiver: TracingUnboundedReceiver<PeerReport>,
 }
 
 impl<PBlock, Block, Executor> GossipWorker<PBlock, Block, Executor>
@@ -36,8 +37,29 @@ where
 		execution_receipt_receiver: TracingUnboundedReceiver<
 			SignedExecutionReceipt<NumberFor<PBlock>, PBlock::Hash, Block::Hash>,
 		>,
+		report_receiver: TracingUnboundedReceiver<PeerReport>,
 	) -> Self {
-		Self { gossip_validator, gossip_engine, bundle_receiver, execution_receipt_receiver }
+		Self {
+			gossip_validator,
+			gossip_engine,
+			bundle_receiver,
+			execution_receipt_receiver,
+			report_receiver,
+		}
+	}
+
+	/// Rebroadcasts a message received from the network unless it's rate limited.
+	fn rebroadcast_message(&self, message: GossipMessage<PBlock, Block>) {
+		let encoded_message = message.encode();
+		if !self.gossip_validator.should_rebroadcast(&encoded_message) {
+			tracing::trace!(target: LOG_TARGET, ?message, "Skipped rebroadcasting the message");
+			return
+		}
+		tracing::debug!(target: LOG_TARGET, ?message, "Rebroadcasting an executor gossip message");
+		self.gossip_validator.note_rebroadcasted(&encoded_message);
+		self.gossip_engine
+			.lock()
+			.gossip_message(topic::<Block>(), encoded_message, false);
 	}
 
 	fn gossip_bundle(&self, bundle: SignedBundle<Block::Extrinsic>) {
@@ -76,15 +98,16 @@ where
 			futures::select! {
 				gossip_message = incoming.next().fuse() => {
 					if let Some(message) = gossip_message {
-						tracing::debug!(target: LOG_TARGET, ?message, "Rebroadcasting an executor gossip message");
-						match message {
-							GossipMessage::Bundle(bundle) => self.gossip_bundle(bundle),
-							GossipMessage::ExecutionReceipt(execution_receipt) => self.gossip_execution_receipt(execution_receipt)
-						}
+						self.rebroadcast_message(message);
 					} else {
 						return
 					}
 				}
+				report = self.report_receiver.next().fuse() => {
+					if let Some(PeerReport { who, cost_benefit }) = report {
+						self.gossip_engine.lock().report(who, cost_benefit);
+					}
+				}
 				bundle = self.bundle_receiver.next().fuse() => {
 					if let Some(bundle) = bundle {
 						self.gossip_bundle(bundle);
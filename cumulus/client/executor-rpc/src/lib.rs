@@ -0,0 +1,499 @@
+//! RPC methods for querying the execution receipts and other executor specific state of the
+//! secondary node.
+//!
+//! The receipts are read from the local aux storage of the executor, which only keeps the recent
+//! ones unless the node runs with `--archive-execution-receipts`.
+//! `executor_compareExecutionReceipt` additionally fetches the receipt verified by
+//! `pallet_executor` on the primary chain, so that divergences can be spotted directly.
+//!
+//! The executor keys, the bundles produced locally and received from the gossip network, and the
+//! mapping between the primary and secondary blocks are exposed as well to help with debugging.
+
+#![warn(missing_docs)]
+
+use cirrus_client_executor::{
+	load_execution_receipt, load_execution_receipt_by_primary_hash, load_produced_bundle,
+	BundlePool,
+};
+use jsonrpsee::{
+	core::{Error as JsonRpseeError, RpcResult},
+	proc_macros::rpc,
+};
+use sc_client_api::AuxStore;
+use sc_rpc_api::DenyUnsafe;
+use serde::{Deserialize, Serialize};
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::H256;
+use sp_executor::{ExecutionReceipt, ExecutorApi, ExecutorId, SignedOpaqueBundle};
+use sp_keystore::{SyncCryptoStore, SyncCryptoStorePtr};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, NumberFor},
+	RuntimeAppPublic, SaturatedConversion,
+};
+use std::{marker::PhantomData, sync::Arc};
+
+/// Maximum number of receipts returned by a single `executor_executionReceipts` call.
+const MAX_RECEIPTS_PER_REQUEST: u64 = 256;
+
+/// Outcome of comparing the local execution receipt with the one stored on the primary chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReceiptStatus {
+	/// Both receipts exist and are identical.
+	Match,
+	/// Both receipts exist but differ.
+	Mismatch,
+	/// The receipt is missing locally, e.g. it has been pruned or not produced yet.
+	LocalMissing,
+	/// The receipt is not stored on the primary chain, e.g. it has been pruned or not
+	/// submitted yet.
+	OnChainMissing,
+}
+
+/// Result of `executor_compareExecutionReceipt`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReceiptComparison<Number, PHash, Hash> {
+	/// Receipt produced by this node.
+	pub local: Option<ExecutionReceipt<Number, PHash, Hash>>,
+	/// Receipt stored in `pallet_executor::Receipts` at the best primary block.
+	pub on_chain: Option<ExecutionReceipt<Number, PHash, Hash>>,
+	/// Comparison outcome.
+	pub status: ReceiptStatus,
+	/// Index of the first diverging storage root in the trace, if any.
+	pub first_mismatch_trace_index: Option<u32>,
+}
+
+impl<Number, PHash, Hash> ReceiptComparison<Number, PHash, Hash>
+where
+	Number: PartialEq,
+	PHash: PartialEq,
+	Hash: PartialEq,
+{
+	fn new(
+		local: Option<ExecutionReceipt<Number, PHash, Hash>>,
+		on_chain: Option<ExecutionReceipt<Number, PHash, Hash>>,
+	) -> Self {
+		let (status, first_mismatch_trace_index) = match (&local, &on_chain) {
+			(None, _) => (ReceiptStatus::LocalMissing, None),
+			(Some(_), None) => (ReceiptStatus::OnChainMissing, None),
+			(Some(local), Some(on_chain)) if local == on_chain => (ReceiptStatus::Match, None),
+			(Some(local), Some(on_chain)) => {
+				let common_len = local.trace.len().min(on_chain.trace.len());
+				let first_mismatch = local
+					.trace
+					.iter()
+					.zip(on_chain.trace.iter())
+					.position(|(a, b)| a != b)
+					.or_else(|| (local.trace.len() != on_chain.trace.len()).then(|| common_len));
+				(ReceiptStatus::Mismatch, first_mismatch.map(|index| index as u32))
+			},
+		};
+		Self { local, on_chain, status, first_mismatch_trace_index }
+	}
+}
+
+/// Executor authority and the executor keys of this node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutorInfo {
+	/// Executor authority at the best primary block.
+	pub executor_id: ExecutorId,
+	/// Executor keys in the local keystore.
+	pub local_keys: Vec<ExecutorId>,
+	/// Whether this node has the key of the executor authority, i.e., produces bundles and
+	/// execution receipts.
+	pub is_executor: bool,
+}
+
+/// Summary of a transaction bundle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleInfo {
+	/// Hash of the bundle.
+	pub hash: H256,
+	/// Hash of the primary block at which the bundle was created.
+	pub primary_hash: H256,
+	/// Slot at which the bundle was created.
+	pub slot_number: u64,
+	/// Merkle root of the extrinsics.
+	pub extrinsics_root: H256,
+	/// Number of the extrinsics.
+	pub extrinsics_count: u32,
+	/// Author of the bundle.
+	pub signer: ExecutorId,
+}
+
+impl From<SignedOpaqueBundle> for BundleInfo {
+	fn from(bundle: SignedOpaqueBundle) -> Self {
+		Self {
+			hash: bundle.hash(),
+			primary_hash: bundle.opaque_bundle.header.primary_hash,
+			slot_number: bundle.opaque_bundle.header.slot_number,
+			extrinsics_root: bundle.opaque_bundle.header.extrinsics_root,
+			extrinsics_count: bundle.opaque_bundle.opaque_extrinsics.len() as u32,
+			signer: bundle.signer,
+		}
+	}
+}
+
+/// Primary block and the secondary block derived from it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockMapping<Number, PHash, Hash> {
+	/// Primary block number.
+	pub primary_number: Number,
+	/// Primary block hash.
+	pub primary_hash: PHash,
+	/// Secondary block number.
+	pub secondary_number: Number,
+	/// Secondary block hash.
+	pub secondary_hash: Hash,
+}
+
+/// Provides rpc methods for querying the execution receipts and executor specific state.
+#[rpc(client, server)]
+pub trait ExecutorRpcApi<Number, PHash, Hash> {
+	/// Returns the local execution receipt of the canonical secondary block at given number.
+	#[method(name = "executor_executionReceiptByPrimaryNumber")]
+	fn execution_receipt_by_primary_number(
+		&self,
+		primary_number: Number,
+	) -> RpcResult<Option<ExecutionReceipt<Number, PHash, Hash>>>;
+
+	/// Returns the local execution receipt of given primary block hash.
+	#[method(name = "executor_executionReceiptByPrimaryHash")]
+	fn execution_receipt_by_primary_hash(
+		&self,
+		primary_hash: PHash,
+	) -> RpcResult<Option<ExecutionReceipt<Number, PHash, Hash>>>;
+
+	/// Returns the local execution receipts of the canonical chain in range `[from, to]`.
+	///
+	/// Missing receipts are skipped.
+	#[method(name = "executor_executionReceipts")]
+	fn execution_receipts(
+		&self,
+		from: Number,
+		to: Number,
+	) -> RpcResult<Vec<ExecutionReceipt<Number, PHash, Hash>>>;
+
+	/// Compares the local execution receipt at given number with the one on the primary chain.
+	#[method(name = "executor_compareExecutionReceipt")]
+	fn compare_execution_receipt(
+		&self,
+		primary_number: Number,
+	) -> RpcResult<ReceiptComparison<Number, PHash, Hash>>;
+
+	/// Returns the executor authority and the executor keys of this node.
+	///
+	/// This is an unsafe RPC as it exposes the keys in the local keystore.
+	#[method(name = "executor_executorInfo")]
+	fn executor_info(&self) -> RpcResult<ExecutorInfo>;
+
+	/// Returns the bundle produced by this node at given slot.
+	#[method(name = "executor_producedBundle")]
+	fn produced_bundle(&self, slot_number: u64) -> RpcResult<Option<BundleInfo>>;
+
+	/// Returns the bundles received from the executor gossip network that haven't been included
+	/// in the primary chain yet.
+	#[method(name = "executor_pendingBundles")]
+	fn pending_bundles(&self) -> RpcResult<Vec<BundleInfo>>;
+
+	/// Returns the secondary block derived from given primary block.
+	#[method(name = "executor_blockMappingByPrimaryHash")]
+	fn block_mapping_by_primary_hash(
+		&self,
+		primary_hash: PHash,
+	) -> RpcResult<Option<BlockMapping<Number, PHash, Hash>>>;
+
+	/// Returns the primary block given secondary block is derived from.
+	#[method(name = "executor_blockMappingBySecondaryHash")]
+	fn block_mapping_by_secondary_hash(
+		&self,
+		secondary_hash: Hash,
+	) -> RpcResult<Option<BlockMapping<Number, PHash, Hash>>>;
+}
+
+/// Implements the [`ExecutorRpcApiServer`] trait for querying the execution receipts.
+pub struct ExecutorRpc<Block, PBlock, Client, PClient> {
+	client: Arc<Client>,
+	primary_chain_client: Arc<PClient>,
+	keystore: SyncCryptoStorePtr,
+	bundle_pool: BundlePool,
+	deny_unsafe: DenyUnsafe,
+	_phantom: PhantomData<(Block, PBlock)>,
+}
+
+impl<Block, PBlock, Client, PClient> ExecutorRpc<Block, PBlock, Client, PClient> {
+	/// Creates a new instance of the `ExecutorRpc` handler.
+	pub fn new(
+		client: Arc<Client>,
+		primary_chain_client: Arc<PClient>,
+		keystore: SyncCryptoStorePtr,
+		bundle_pool: BundlePool,
+		deny_unsafe: DenyUnsafe,
+	) -> Self {
+		Self {
+			client,
+			primary_chain_client,
+			keystore,
+			bundle_pool,
+			deny_unsafe,
+			_phantom: PhantomData::default(),
+		}
+	}
+}
+
+impl<Block, PBlock, Client, PClient> ExecutorRpc<Block, PBlock, Client, PClient>
+where
+	Block: BlockT,
+	PBlock: BlockT,
+	Client: HeaderBackend<Block> + AuxStore,
+{
+	fn receipt_at(
+		&self,
+		primary_number: NumberFor<PBlock>,
+	) -> RpcResult<Option<ExecutionReceipt<NumberFor<PBlock>, PBlock::Hash, Block::Hash>>> {
+		// The secondary block number is always the same as the primary block number.
+		let number: NumberFor<Block> = primary_number.saturated_into::<u64>().saturated_into();
+
+		let block_hash = match self
+			.client
+			.hash(number)
+			.map_err(|error| JsonRpseeError::Custom(error.to_string()))?
+		{
+			Some(block_hash) => block_hash,
+			None => return Ok(None),
+		};
+
+		load_execution_receipt(&*self.client, block_hash)
+			.map_err(|error| JsonRpseeError::Custom(error.to_string()))
+	}
+
+	fn block_mapping(
+		&self,
+		receipt: Option<ExecutionReceipt<NumberFor<PBlock>, PBlock::Hash, Block::Hash>>,
+	) -> RpcResult<Option<BlockMapping<NumberFor<PBlock>, PBlock::Hash, Block::Hash>>> {
+		let receipt = match receipt {
+			Some(receipt) => receipt,
+			None => return Ok(None),
+		};
+
+		let secondary_number = match self
+			.client
+			.number(receipt.secondary_hash)
+			.map_err(|error| JsonRpseeError::Custom(error.to_string()))?
+		{
+			Some(secondary_number) => secondary_number,
+			None => return Ok(None),
+		};
+
+		Ok(Some(BlockMapping {
+			primary_number: receipt.primary_number,
+			primary_hash: receipt.primary_hash,
+			secondary_number: secondary_number.saturated_into::<u64>().saturated_into(),
+			secondary_hash: receipt.secondary_hash,
+		}))
+	}
+}
+
+impl<Block, PBlock, Client, PClient>
+	ExecutorRpcApiServer<NumberFor<PBlock>, PBlock::Hash, Block::Hash>
+	for ExecutorRpc<Block, PBlock, Client, PClient>
+where
+	Block: BlockT,
+	PBlock: BlockT,
+	Client: HeaderBackend<Block> + AuxStore + Send + Sync + 'static,
+	PClient: HeaderBackend<PBlock> + ProvideRuntimeApi<PBlock> + Send + Sync + 'static,
+	PClient::Api: ExecutorApi<PBlock, Block::Hash>,
+{
+	fn execution_receipt_by_primary_number(
+		&self,
+		primary_number: NumberFor<PBlock>,
+	) -> RpcResult<Option<ExecutionReceipt<NumberFor<PBlock>, PBlock::Hash, Block::Hash>>> {
+		self.receipt_at(primary_number)
+	}
+
+	fn execution_receipt_by_primary_hash(
+		&self,
+		primary_hash: PBlock::Hash,
+	) -> RpcResult<Option<ExecutionReceipt<NumberFor<PBlock>, PBlock::Hash, Block::Hash>>> {
+		load_execution_receipt_by_primary_hash::<_, Block::Hash, _, _>(&*self.client, primary_hash)
+			.map_err(|error| JsonRpseeError::Custom(error.to_string()))
+	}
+
+	fn execution_receipts(
+		&self,
+		from: NumberFor<PBlock>,
+		to: NumberFor<PBlock>,
+	) -> RpcResult<Vec<ExecutionReceipt<NumberFor<PBlock>, PBlock::Hash, Block::Hash>>> {
+		let from = from.saturated_into::<u64>();
+		let to = to.saturated_into::<u64>();
+
+		if from > to {
+			return Err(JsonRpseeError::Custom(format!("Invalid range: {from} > {to}")))
+		}
+		if to - from >= MAX_RECEIPTS_PER_REQUEST {
+			return Err(JsonRpseeError::Custom(format!(
+				"Range too large, at most {MAX_RECEIPTS_PER_REQUEST} receipts can be requested"
+			)))
+		}
+
+		let mut receipts = Vec::new();
+		for number in from..=to {
+			if let Some(receipt) = self.receipt_at(number.saturated_into())? {
+				receipts.push(receipt);
+			}
+		}
+		Ok(receipts)
+	}
+
+	fn compare_execution_receipt(
+		&self,
+		primary_number: NumberFor<PBlock>,
+	) -> RpcResult<ReceiptComparison<NumberFor<PBlock>, PBlock::Hash, Block::Hash>> {
+		let local = self.receipt_at(primary_number)?;
+
+		let at = BlockId::Hash(self.primary_chain_client.info().best_hash);
+		let runtime_api = self.primary_chain_client.runtime_api();
+
+		// `ExecutorApi::execution_receipt` is only available since version 2.
+		let has_execution_receipt_api = runtime_api
+			.has_api_with::<dyn ExecutorApi<PBlock, Block::Hash>, _>(&at, |version| version >= 2)
+			.map_err(|error| JsonRpseeError::Custom(error.to_string()))?;
+		if !has_execution_receipt_api {
+			return Err(JsonRpseeError::Custom(format!(
+				"The runtime at {at} does not support querying the execution receipts"
+			)))
+		}
+
+		let on_chain = runtime_api
+			.execution_receipt(&at, primary_number)
+			.map_err(|error| JsonRpseeError::Custom(error.to_string()))?;
+
+		Ok(ReceiptComparison::new(local, on_chain))
+	}
+
+	fn executor_info(&self) -> RpcResult<ExecutorInfo> {
+		self.deny_unsafe.check_if_safe()?;
+
+		let best_hash = self.primary_chain_client.info().best_hash;
+		let executor_id = self
+			.primary_chain_client
+			.runtime_api()
+			.executor_id(&BlockId::Hash(best_hash))
+			.map_err(|error| JsonRpseeError::Custom(error.to_string()))?;
+
+		let local_keys = SyncCryptoStore::sr25519_public_keys(&*self.keystore, ExecutorId::ID)
+			.into_iter()
+			.map(ExecutorId::from)
+			.collect::<Vec<_>>();
+
+		Ok(ExecutorInfo { is_executor: local_keys.contains(&executor_id), executor_id, local_keys })
+	}
+
+	fn produced_bundle(&self, slot_number: u64) -> RpcResult<Option<BundleInfo>> {
+		load_produced_bundle(&*self.client, slot_number)
+			.map(|maybe_bundle| maybe_bundle.map(Into::into))
+			.map_err(|error| JsonRpseeError::Custom(error.to_string()))
+	}
+
+	fn pending_bundles(&self) -> RpcResult<Vec<BundleInfo>> {
+		Ok(self.bundle_pool.pending_bundles().into_iter().map(Into::into).collect())
+	}
+
+	fn block_mapping_by_primary_hash(
+		&self,
+		primary_hash: PBlock::Hash,
+	) -> RpcResult<Option<BlockMapping<NumberFor<PBlock>, PBlock::Hash, Block::Hash>>> {
+		let receipt = load_execution_receipt_by_primary_hash::<_, Block::Hash, _, _>(
+			&*self.client,
+			primary_hash,
+		)
+		.map_err(|error| JsonRpseeError::Custom(error.to_string()))?;
+		self.block_mapping(receipt)
+	}
+
+	fn block_mapping_by_secondary_hash(
+		&self,
+		secondary_hash: Block::Hash,
+	) -> RpcResult<Option<BlockMapping<NumberFor<PBlock>, PBlock::Hash, Block::Hash>>> {
+		let receipt = load_execution_receipt(&*self.client, secondary_hash)
+			.map_err(|error| JsonRpseeError::Custom(error.to_string()))?;
+		self.block_mapping(receipt)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{BundleInfo, ReceiptComparison, ReceiptStatus};
+	use sp_core::{Pair, H256};
+	use sp_executor::{
+		BundleHeader, ExecutionReceipt, ExecutorPair, OpaqueBundle, SignedOpaqueBundle,
+	};
+
+	fn create_receipt(trace: Vec<H256>) -> ExecutionReceipt<u32, H256, H256> {
+		ExecutionReceipt {
+			primary_number: 1,
+			primary_hash: H256::repeat_byte(1),
+			secondary_hash: H256::repeat_byte(2),
+			trace,
+			trace_root: Default::default(),
+			invalid_extrinsics_root: Default::default(),
+		}
+	}
+
+	#[test]
+	fn compare_receipts() {
+		let receipt = create_receipt(vec![H256::repeat_byte(3), H256::repeat_byte(4)]);
+
+		let comparison = ReceiptComparison::new(None, Some(receipt.clone()));
+		assert_eq!(comparison.status, ReceiptStatus::LocalMissing);
+
+		let comparison = ReceiptComparison::new(Some(receipt.clone()), None);
+		assert_eq!(comparison.status, ReceiptStatus::OnChainMissing);
+
+		let comparison = ReceiptComparison::new(Some(receipt.clone()), Some(receipt.clone()));
+		assert_eq!(comparison.status, ReceiptStatus::Match);
+		assert_eq!(comparison.first_mismatch_trace_index, None);
+
+		let diverged = create_receipt(vec![H256::repeat_byte(3), H256::repeat_byte(5)]);
+		let comparison = ReceiptComparison::new(Some(receipt.clone()), Some(diverged));
+		assert_eq!(comparison.status, ReceiptStatus::Mismatch);
+		assert_eq!(comparison.first_mismatch_trace_index, Some(1));
+
+		let truncated = create_receipt(vec![H256::repeat_byte(3)]);
+		let comparison = ReceiptComparison::new(Some(receipt), Some(truncated));
+		assert_eq!(comparison.status, ReceiptStatus::Mismatch);
+		assert_eq!(comparison.first_mismatch_trace_index, Some(1));
+	}
+
+	#[test]
+	fn bundle_info_from_signed_bundle() {
+		let pair = ExecutorPair::from_string("//Alice", None).unwrap();
+		let opaque_bundle = OpaqueBundle {
+			header: BundleHeader {
+				primary_hash: H256::repeat_byte(1),
+				slot_number: 10,
+				extrinsics_root: H256::repeat_byte(2),
+			},
+			opaque_extrinsics: vec![Default::default(), Default::default()],
+		};
+		let signed_bundle = SignedOpaqueBundle {
+			signature: pair.sign(opaque_bundle.hash().as_ref()),
+			signer: pair.public(),
+			opaque_bundle,
+		};
+
+		let info: BundleInfo = signed_bundle.clone().into();
+		assert_eq!(info.hash, signed_bundle.hash());
+		assert_eq!(info.primary_hash, H256::repeat_byte(1));
+		assert_eq!(info.slot_number, 10);
+		assert_eq!(info.extrinsics_root, H256::repeat_byte(2));
+		assert_eq!(info.extrinsics_count, 2);
+		assert_eq!(info.signer, pair.public());
+	}
+}
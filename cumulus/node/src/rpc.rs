@@ -5,7 +5,9 @@
 
 #![warn(missing_docs)]
 
-use cirrus_runtime::{opaque::Block, AccountId, Balance, Index as Nonce};
+use cirrus_client_executor::BundlePool;
+use cirrus_client_executor_rpc::{ExecutorRpc, ExecutorRpcApiServer};
+use cirrus_runtime::{opaque::Block, AccountId, Balance, Hash, Index as Nonce};
 use jsonrpsee::RpcModule;
 use pallet_transaction_payment_rpc::{TransactionPaymentApiServer, TransactionPaymentRpc};
 use sc_client_api::AuxStore;
@@ -14,22 +16,31 @@ use sc_transaction_pool_api::TransactionPool;
 use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder;
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
+use sp_executor::ExecutorApi;
+use sp_keystore::SyncCryptoStorePtr;
+use sp_runtime::traits::Block as BlockT;
 use std::sync::Arc;
 use substrate_frame_rpc_system::{SystemApiServer, SystemRpc};
 
 /// Full client dependencies
-pub struct FullDeps<C, P> {
+pub struct FullDeps<C, PC, P> {
 	/// The client instance to use.
 	pub client: Arc<C>,
+	/// The primary chain client instance to use.
+	pub primary_chain_client: Arc<PC>,
 	/// Transaction pool instance.
 	pub pool: Arc<P>,
+	/// Keystore with the executor keys.
+	pub keystore: SyncCryptoStorePtr,
+	/// Bundles received from the executor gossip network.
+	pub bundle_pool: BundlePool,
 	/// Whether to deny unsafe calls
 	pub deny_unsafe: DenyUnsafe,
 }
 
 /// Instantiate all RPC extensions.
-pub fn create_full<C, P>(
-	deps: FullDeps<C, P>,
+pub fn create_full<C, PBlock, PC, P>(
+	deps: FullDeps<C, PC, P>,
 ) -> Result<RpcModule<()>, Box<dyn std::error::Error + Send + Sync>>
 where
 	C: ProvideRuntimeApi<Block>
@@ -42,13 +53,26 @@ where
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
 	C::Api: BlockBuilder<Block>,
+	PBlock: BlockT,
+	PC: ProvideRuntimeApi<PBlock> + HeaderBackend<PBlock> + Send + Sync + 'static,
+	PC::Api: ExecutorApi<PBlock, Hash>,
 	P: TransactionPool + Sync + Send + 'static,
 {
 	let mut module = RpcModule::new(());
-	let FullDeps { client, pool, deny_unsafe } = deps;
+	let FullDeps { client, primary_chain_client, pool, keystore, bundle_pool, deny_unsafe } = deps;
 
 	module.merge(SystemRpc::new(client.clone(), pool, deny_unsafe).into_rpc())?;
-	module.merge(TransactionPaymentRpc::new(client).into_rpc())?;
+	module.merge(TransactionPaymentRpc::new(client.clone()).into_rpc())?;
+	module.merge(
+		ExecutorRpc::<Block, PBlock, _, _>::new(
+			client,
+			primary_chain_client,
+			keystore,
+			bundle_pool,
+			deny_unsafe,
+		)
+		.into_rpc(),
+	)?;
 
 	Ok(module)
 }
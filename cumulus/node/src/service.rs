@@ -1,6 +1,6 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
-use cirrus_client_executor::Executor;
+use cirrus_client_executor::{BundlePool, Executor};
 use cirrus_client_executor_gossip::ExecutorGossipParams;
 use cirrus_runtime::{opaque::Block, Hash, RuntimeApi};
 use futures::Stream;
@@ -147,6 +147,7 @@ pub async fn new_full<PBlock, PClient, SC, IBNS, NSNS>(
 	select_chain: &SC,
 	imported_block_notification_stream: IBNS,
 	new_slot_notification_stream: NSNS,
+	archive_execution_receipts: bool,
 ) -> sc_service::error::Result<NewFull<Arc<FullClient<RuntimeApi, CirrusRuntimeExecutor>>>>
 where
 	PBlock: BlockT,
@@ -158,7 +159,7 @@ where
 		+ Sync,
 	PClient::Api: ExecutorApi<PBlock, Hash>,
 	SC: SelectChain<PBlock>,
-	IBNS: Stream<Item = NumberFor<PBlock>> + Send + 'static,
+	IBNS: Stream<Item = (PBlock::Hash, NumberFor<PBlock>)> + Send + 'static,
 	NSNS: Stream<Item = (Slot, Sha256Hash)> + Send + 'static,
 {
 	if matches!(parachain_config.role, Role::Light) {
@@ -195,18 +196,26 @@ where
 			warp_sync: None,
 		})?;
 
+	let bundle_pool = BundlePool::default();
+
 	let rpc_builder = {
 		let client = client.clone();
+		let primary_chain_client = primary_chain_client.clone();
 		let transaction_pool = transaction_pool.clone();
+		let keystore = params.keystore_container.sync_keystore();
+		let bundle_pool = bundle_pool.clone();
 
 		Box::new(move |deny_unsafe, _| {
 			let deps = crate::rpc::FullDeps {
 				client: client.clone(),
+				primary_chain_client: primary_chain_client.clone(),
 				pool: transaction_pool.clone(),
+				keystore: keystore.clone(),
+				bundle_pool: bundle_pool.clone(),
 				deny_unsafe,
 			};
 
-			crate::rpc::create_full(deps).map_err(Into::into)
+			crate::rpc::create_full::<_, PBlock, _, _>(deps).map_err(Into::into)
 		})
 	};
 
@@ -245,6 +254,8 @@ where
 			Arc::new(code_executor),
 			validator,
 			params.keystore_container.sync_keystore(),
+			archive_execution_receipts,
+			bundle_pool,
 		)
 		.await?;
 
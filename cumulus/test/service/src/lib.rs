@@ -163,6 +163,8 @@ async fn start_node_impl(
 	Arc<NetworkService<Block, H256>>,
 	RpcHandlers,
 	Executor,
+	Arc<subspace_test_client::Client>,
+	Arc<NetworkService<PBlock, H256>>,
 )> {
 	if matches!(parachain_config.role, Role::Light) {
 		return Err("Light client not supported!".into())
@@ -234,10 +236,14 @@ async fn start_node_impl(
 			primary_chain_full_node.network.clone(),
 			&spawn_essential,
 			&primary_chain_full_node.select_chain,
-			primary_chain_full_node
-				.imported_block_notification_stream
-				.subscribe()
-				.then(|(block_number, _)| async move { block_number }),
+			primary_chain_full_node.imported_block_notification_stream.subscribe().then(
+				|imported_block_notification| async move {
+					(
+						imported_block_notification.block_hash,
+						imported_block_notification.block_number,
+					)
+				},
+			),
 			primary_chain_full_node.new_slot_notification_stream.subscribe().then(
 				|slot_notification| async move {
 					let slot_info = slot_notification.new_slot_info;
@@ -254,6 +260,8 @@ async fn start_node_impl(
 			Arc::clone(&code_executor),
 			validator,
 			params.keystore_container.sync_keystore(),
+			false,
+			Default::default(),
 		)
 		.await?;
 
@@ -269,13 +277,26 @@ async fn start_node_impl(
 		executor
 	};
 
+	let primary_chain_client = primary_chain_full_node.client.clone();
+	let primary_chain_network = primary_chain_full_node.network.clone();
+
 	task_manager.add_child(primary_chain_full_node.task_manager);
 
 	start_network.start_network();
 
 	primary_chain_full_node.network_starter.start_network();
 
-	Ok((task_manager, client, backend, code_executor, network, rpc_handlers, executor))
+	Ok((
+		task_manager,
+		client,
+		backend,
+		code_executor,
+		network,
+		rpc_handlers,
+		executor,
+		primary_chain_client,
+		primary_chain_network,
+	))
 }
 
 /// A Cumulus test node instance used for testing.
@@ -297,6 +318,10 @@ pub struct TestNode {
 	pub rpc_handlers: RpcHandlers,
 	/// Secondary executor.
 	pub executor: Executor,
+	/// Client of the embedded primary chain node.
+	pub primary_chain_client: Arc<subspace_test_client::Client>,
+	/// Network of the embedded primary chain node.
+	pub primary_chain_network: Arc<NetworkService<PBlock, H256>>,
 }
 
 /// A builder to create a [`TestNode`].
@@ -416,10 +441,19 @@ impl TestNodeBuilder {
 			format!("{} (primary chain)", primary_chain_config.network.node_name);
 
 		let multiaddr = parachain_config.network.listen_addresses[0].clone();
-		let (task_manager, client, backend, code_executor, network, rpc_handlers, executor) =
-			start_node_impl(parachain_config, primary_chain_config)
-				.await
-				.expect("could not create Cumulus test service");
+		let (
+			task_manager,
+			client,
+			backend,
+			code_executor,
+			network,
+			rpc_handlers,
+			executor,
+			primary_chain_client,
+			primary_chain_network,
+		) = start_node_impl(parachain_config, primary_chain_config)
+			.await
+			.expect("could not create Cumulus test service");
 
 		let peer_id = *network.local_peer_id();
 		let addr = MultiaddrWithPeerId { multiaddr, peer_id };
@@ -433,6 +467,8 @@ impl TestNodeBuilder {
 			addr,
 			rpc_handlers,
 			executor,
+			primary_chain_client,
+			primary_chain_network,
 		}
 	}
 }
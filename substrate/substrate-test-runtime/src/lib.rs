@@ -669,6 +669,8 @@ impl pallet_subspace::Config for Runtime {
     type RecordedHistorySegmentSize = ConstU32<{ 3840 * 256 / 2 }>;
     type ExpectedVotesPerBlock = ConstU32<9>;
     type ShouldAdjustSolutionRange = ShouldAdjustSolutionRange;
+    type EraHistoryLength = ConstU32<4>;
+    type BlockListDuration = ConstU64<4>;
     type GlobalRandomnessIntervalTrigger = pallet_subspace::NormalGlobalRandomnessInterval;
     type EraChangeTrigger = pallet_subspace::NormalEraChange;
     type EonChangeTrigger = pallet_subspace::NormalEonChange;
@@ -940,7 +942,7 @@ cfg_if! {
 
             impl sp_consensus_subspace::SubspaceApi<Block, FarmerPublicKey> for Runtime {
                 fn confirmation_depth_k() -> <<Block as BlockT>::Header as HeaderT>::Number {
-                    <Self as pallet_subspace::Config>::ConfirmationDepthK::get()
+                    <pallet_subspace::Pallet<Runtime>>::confirmation_depth_k()
                 }
 
                 fn max_plot_size() -> u64 {
@@ -955,6 +957,14 @@ cfg_if! {
                     <pallet_subspace::Pallet<Runtime>>::total_pieces()
                 }
 
+                fn era_history() -> Vec<sp_consensus_subspace::EraSummary> {
+                    <pallet_subspace::Pallet<Runtime>>::era_history()
+                }
+
+                fn total_space_pledged() -> u128 {
+                    <pallet_subspace::Pallet<Runtime>>::total_space_pledged()
+                }
+
                 fn recorded_history_segment_size() -> u32 {
                     <Self as pallet_subspace::Config>::RecordedHistorySegmentSize::get()
                 }
@@ -1001,6 +1011,10 @@ cfg_if! {
                     <pallet_subspace::Pallet<Runtime>>::is_in_block_list(farmer_public_key)
                 }
 
+                fn remaining_block_list_eons(farmer_public_key: &sp_consensus_subspace::FarmerPublicKey) -> Option<u64> {
+                    <pallet_subspace::Pallet<Runtime>>::remaining_block_list_eons(farmer_public_key)
+                }
+
                 fn records_root(segment_index: u64) -> Option<subspace_core_primitives::Sha256Hash> {
                     <pallet_subspace::Pallet<Runtime>>::records_root(segment_index)
                 }
@@ -1278,7 +1292,7 @@ cfg_if! {
 
             impl sp_consensus_subspace::SubspaceApi<Block, FarmerPublicKey> for Runtime {
                 fn confirmation_depth_k() -> <<Block as BlockT>::Header as HeaderT>::Number {
-                    <Self as pallet_subspace::Config>::ConfirmationDepthK::get()
+                    <pallet_subspace::Pallet<Runtime>>::confirmation_depth_k()
                 }
 
                 fn record_size() -> u32 {
@@ -1293,6 +1307,14 @@ cfg_if! {
                     <pallet_subspace::Pallet<Runtime>>::total_pieces()
                 }
 
+                fn era_history() -> Vec<sp_consensus_subspace::EraSummary> {
+                    <pallet_subspace::Pallet<Runtime>>::era_history()
+                }
+
+                fn total_space_pledged() -> u128 {
+                    <pallet_subspace::Pallet<Runtime>>::total_space_pledged()
+                }
+
                 fn recorded_history_segment_size() -> u32 {
                     <Self as pallet_subspace::Config>::RecordedHistorySegmentSize::get()
                 }
@@ -1339,6 +1361,10 @@ cfg_if! {
                     <pallet_subspace::Pallet<Runtime>>::is_in_block_list(farmer_public_key)
                 }
 
+                fn remaining_block_list_eons(farmer_public_key: &sp_consensus_subspace::FarmerPublicKey) -> Option<u64> {
+                    <pallet_subspace::Pallet<Runtime>>::remaining_block_list_eons(farmer_public_key)
+                }
+
                 fn records_root(segment_index: u64) -> Option<subspace_core_primitives::Sha256Hash> {
                     <pallet_subspace::Pallet<Runtime>>::records_root(segment_index)
                 }
@@ -0,0 +1,288 @@
+// Copyright (C) 2022 Subspace Labs, Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! End-to-end test harness that runs Subspace test node together with an in-process farmer.
+//!
+//! Farmer is a real [`MultiFarming`] instance over a temporary plot that talks to the node through
+//! [`InMemoryRpcClient`], so plotting, farming, voting and rewards all go through the same code
+//! paths as in production.
+
+#![warn(missing_docs, unused_crate_dependencies)]
+
+mod rpc_client;
+
+pub use rpc_client::InMemoryRpcClient;
+
+use futures::Future;
+use sc_client_api::BlockBackend;
+use sc_consensus_subspace_rpc::{SubspaceRpc, SubspaceRpcApiServer};
+use sc_service::TaskManager;
+use sp_blockchain::HeaderBackend;
+use sp_keyring::Sr25519Keyring;
+use sp_runtime::codec::Encode;
+use sp_runtime::generic::BlockId;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use subspace_archiving::archiver::Archiver;
+use subspace_core_primitives::objects::BlockObjectMapping;
+use subspace_core_primitives::PublicKey;
+use subspace_farmer::multi_farming::{MultiFarming, Options as MultiFarmingOptions};
+use subspace_farmer::{plot_pieces, ObjectMappings, PiecesToPlot, Plot, RpcClient};
+use subspace_rpc_primitives::FarmerMetadata;
+use subspace_runtime_primitives::BlockNumber;
+use subspace_service::NewFull;
+use subspace_solving::SubspaceCodec;
+use subspace_test_client::Client;
+use substrate_test_client::BlockchainEventsExt;
+use tempfile::TempDir;
+use tokio::task::JoinHandle;
+
+/// How often plot is checked in [`TestHarness::wait_for_plotted_segments`]
+const PLOTTING_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Defines which slots farmer tries to solve.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum SlotMode {
+    /// Farmer tries to solve every slot, so blocks are produced as fast as slots of the test
+    /// runtime go (every second).
+    Auto,
+    /// Farmer only tries to solve slots until blocks requested with
+    /// [`TestHarness::produce_blocks`] are produced, all other slots are skipped.
+    Manual,
+}
+
+/// Options for [`TestHarness`] creation.
+#[derive(Debug, Clone)]
+pub struct TestHarnessOptions {
+    /// Key used for the node, public key of it is also used as farmer's reward address.
+    pub key: Sr25519Keyring,
+    /// Total plot size in bytes.
+    pub plot_size: u64,
+    /// Which slots farmer tries to solve.
+    pub slot_mode: SlotMode,
+}
+
+impl Default for TestHarnessOptions {
+    fn default() -> Self {
+        Self {
+            key: Sr25519Keyring::Alice,
+            plot_size: 32 * 1024 * 1024,
+            slot_mode: SlotMode::Manual,
+        }
+    }
+}
+
+/// Test node with an in-process farmer connected to it.
+pub struct TestHarness {
+    /// `TaskManager` of the node.
+    pub task_manager: TaskManager,
+    /// Client of the node.
+    pub client: Arc<Client>,
+    /// RPC client farmer uses to talk to the node.
+    pub rpc_client: InMemoryRpcClient,
+    /// Plots of the farmer.
+    pub plots: Arc<Vec<Plot>>,
+    /// Farmer metadata the node reported.
+    pub farmer_metadata: FarmerMetadata,
+    /// Farmer only tries to solve slots while best block number is below this one when
+    /// [`SlotMode::Manual`] is used.
+    target_block_number: Arc<AtomicU32>,
+    farmer_handle: JoinHandle<anyhow::Result<()>>,
+    _base_directory: TempDir,
+}
+
+impl Drop for TestHarness {
+    fn drop(&mut self) {
+        self.farmer_handle.abort();
+    }
+}
+
+impl TestHarness {
+    /// Start test node and farmer with the genesis segment already plotted.
+    pub async fn start(
+        tokio_handle: tokio::runtime::Handle,
+        options: TestHarnessOptions,
+    ) -> anyhow::Result<Self> {
+        let TestHarnessOptions {
+            key,
+            plot_size,
+            slot_mode,
+        } = options;
+
+        let config = subspace_test_service::node_config(tokio_handle, key, vec![], true);
+        let (
+            NewFull {
+                task_manager,
+                client,
+                network_starter,
+                new_slot_notification_stream,
+                reward_signing_notification_stream,
+                archived_segment_notification_stream,
+                ..
+            },
+            _executor,
+        ) = subspace_test_service::new_full(config, false, false);
+
+        let target_block_number = Arc::new(AtomicU32::new(0));
+        let slot_gate = match slot_mode {
+            SlotMode::Auto => None,
+            SlotMode::Manual => {
+                let client = Arc::clone(&client);
+                let target_block_number = Arc::clone(&target_block_number);
+
+                Some(Arc::new(move || {
+                    client.info().best_number < target_block_number.load(Ordering::Acquire)
+                }) as rpc_client::SlotGate)
+            }
+        };
+        let rpc_client = InMemoryRpcClient::new(
+            SubspaceRpc::new(
+                Arc::clone(&client),
+                Arc::new(task_manager.spawn_handle()),
+                new_slot_notification_stream,
+                reward_signing_notification_stream,
+                archived_segment_notification_stream,
+            )
+            .into_rpc(),
+            slot_gate,
+        );
+
+        let farmer_metadata = rpc_client
+            .farmer_metadata()
+            .await
+            .map_err(|error| anyhow::anyhow!(error))?;
+
+        let base_directory = TempDir::new()?;
+        let object_mappings = ObjectMappings::open_or_create(base_directory.path())?;
+        let base_path = base_directory.path().to_owned();
+        let multi_farming = MultiFarming::new(
+            MultiFarmingOptions {
+                base_directory: base_directory.path().to_owned(),
+                client: rpc_client.clone(),
+                object_mappings,
+                reward_address: PublicKey::from(key.public().0),
+                bootstrap_nodes: vec![],
+                listen_on: vec![],
+            },
+            plot_size,
+            farmer_metadata.max_plot_size,
+            move |plot_index, public_key, max_piece_count| {
+                Plot::open_or_create(
+                    base_path.join(format!("plot{plot_index}")),
+                    public_key,
+                    max_piece_count,
+                )
+            },
+            true,
+        )
+        .await?;
+
+        // Genesis segment is archived as soon as node starts and might be sent before farmer
+        // subscribes to archived segments, plot it upfront such that farmer has something to farm
+        let genesis_block = client
+            .block(&BlockId::Number(0))?
+            .ok_or_else(|| anyhow::anyhow!("Genesis block must exist"))?;
+        let mut archiver = Archiver::new(
+            farmer_metadata.record_size as usize,
+            farmer_metadata.recorded_history_segment_size as usize,
+        )?;
+        let pieces_per_segment = pieces_per_segment(&farmer_metadata);
+        for archived_segment in
+            archiver.add_block(genesis_block.encode(), BlockObjectMapping::default())
+        {
+            let piece_index_offset =
+                archived_segment.root_block.segment_index() * pieces_per_segment;
+            for (plot, commitments) in multi_farming.plots.iter().zip(&multi_farming.commitments) {
+                let mut plot_genesis_pieces = plot_pieces(
+                    SubspaceCodec::new(&*plot.public_key()),
+                    plot,
+                    commitments.clone(),
+                );
+                plot_genesis_pieces(PiecesToPlot {
+                    piece_index_offset,
+                    pieces: archived_segment.pieces.clone(),
+                });
+            }
+        }
+
+        let plots = Arc::clone(&multi_farming.plots);
+        let farmer_handle = tokio::spawn(multi_farming.wait());
+
+        network_starter.start_network();
+
+        Ok(Self {
+            task_manager,
+            client,
+            rpc_client,
+            plots,
+            farmer_metadata,
+            target_block_number,
+            farmer_handle,
+            _base_directory: base_directory,
+        })
+    }
+
+    /// Wait for `count` more blocks to be produced.
+    ///
+    /// With [`SlotMode::Manual`] this is what allows farmer to solve slots.
+    pub fn produce_blocks(&self, count: BlockNumber) -> impl Future<Output = ()> {
+        let blocks_imported = self.client.wait_for_blocks(count as usize);
+        self.target_block_number
+            .store(self.client.info().best_number + count, Ordering::Release);
+        blocks_imported
+    }
+
+    /// Wait until farmer plots all pieces of `segment_count` segments (genesis segment included).
+    ///
+    /// Plot must be big enough to store all of them or else this will never return.
+    pub async fn wait_for_plotted_segments(&self, segment_count: u64) {
+        let piece_count = segment_count * pieces_per_segment(&self.farmer_metadata);
+
+        while self.plots.iter().map(Plot::piece_count).sum::<u64>() < piece_count {
+            tokio::time::sleep(PLOTTING_CHECK_INTERVAL).await;
+        }
+    }
+}
+
+fn pieces_per_segment(farmer_metadata: &FarmerMetadata) -> u64 {
+    // Each segment is erasure coded, hence twice as many pieces as records
+    u64::from(farmer_metadata.recorded_history_segment_size / farmer_metadata.record_size * 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SlotMode, TestHarness, TestHarnessOptions};
+    use sp_blockchain::HeaderBackend;
+
+    #[substrate_test_utils::test]
+    async fn farmer_produces_requested_blocks() {
+        let harness = TestHarness::start(
+            tokio::runtime::Handle::current(),
+            TestHarnessOptions {
+                slot_mode: SlotMode::Manual,
+                ..TestHarnessOptions::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        harness.wait_for_plotted_segments(1).await;
+        harness.produce_blocks(3).await;
+
+        assert!(harness.client.info().best_number >= 3);
+    }
+}
@@ -0,0 +1,178 @@
+// Copyright (C) 2022 Subspace Labs, Inc.
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! In-memory [`RpcClient`] implementation for the farmer.
+
+use async_trait::async_trait;
+use futures::{stream, Stream, StreamExt};
+use jsonrpsee::core::DeserializeOwned;
+use jsonrpsee::types::EmptyParams;
+use jsonrpsee::RpcModule;
+use sc_consensus_subspace_rpc::SubspaceRpc;
+use std::pin::Pin;
+use std::sync::Arc;
+use subspace_archiving::archiver::ArchivedSegment;
+use subspace_farmer::{RpcClient, RpcClientError};
+use subspace_rpc_primitives::{
+    FarmerMetadata, RewardSignatureResponse, RewardSigningInfo, SlotInfo, SolutionResponse,
+};
+use subspace_runtime_primitives::opaque::Block;
+use subspace_test_client::Client;
+
+/// Decides whether farmer should try to solve the slot that has just arrived.
+pub(crate) type SlotGate = Arc<dyn Fn() -> bool + Send + Sync>;
+
+struct Inner {
+    module: RpcModule<SubspaceRpc<Block, Client>>,
+    slot_gate: Option<SlotGate>,
+}
+
+/// [`RpcClient`] that calls Subspace RPC module of the test node directly, without any network in
+/// between.
+#[derive(Clone)]
+pub struct InMemoryRpcClient {
+    inner: Arc<Inner>,
+}
+
+impl InMemoryRpcClient {
+    /// Create a new instance of [`InMemoryRpcClient`].
+    ///
+    /// If `slot_gate` is specified, slots for which it returns `false` are not sent to the farmer
+    /// and are responded to with no solution instead.
+    pub(crate) fn new(
+        module: RpcModule<SubspaceRpc<Block, Client>>,
+        slot_gate: Option<SlotGate>,
+    ) -> Self {
+        Self {
+            inner: Arc::new(Inner { module, slot_gate }),
+        }
+    }
+
+    async fn subscribe<T>(
+        &self,
+        method: &str,
+    ) -> Result<Pin<Box<dyn Stream<Item = T> + Send + 'static>>, RpcClientError>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let subscription = self
+            .inner
+            .module
+            .subscribe(method, EmptyParams::new())
+            .await?;
+
+        Ok(Box::pin(stream::unfold(
+            subscription,
+            |mut subscription| async move {
+                let (item, _subscription_id) = subscription.next::<T>().await?.ok()?;
+                Some((item, subscription))
+            },
+        )))
+    }
+}
+
+#[async_trait]
+impl RpcClient for InMemoryRpcClient {
+    async fn farmer_metadata(&self) -> Result<FarmerMetadata, RpcClientError> {
+        Ok(self
+            .inner
+            .module
+            .call("subspace_getFarmerMetadata", EmptyParams::new())
+            .await?)
+    }
+
+    async fn subscribe_slot_info(
+        &self,
+    ) -> Result<Pin<Box<dyn Stream<Item = SlotInfo> + Send + 'static>>, RpcClientError> {
+        let slot_infos = self.subscribe("subspace_subscribeSlotInfo").await?;
+
+        let slot_gate = match &self.inner.slot_gate {
+            Some(slot_gate) => Arc::clone(slot_gate),
+            None => {
+                return Ok(slot_infos);
+            }
+        };
+        let client = self.clone();
+
+        Ok(Box::pin(slot_infos.filter_map(
+            move |slot_info: SlotInfo| {
+                let slot_gate = Arc::clone(&slot_gate);
+                let client = client.clone();
+
+                async move {
+                    if slot_gate() {
+                        return Some(slot_info);
+                    }
+
+                    // Node waits for a response from every subscriber, respond right away such
+                    // that it doesn't need to wait for timeout. If this fails, node will just time
+                    // out.
+                    let _ = client
+                        .submit_solution_response(SolutionResponse {
+                            slot_number: slot_info.slot_number,
+                            maybe_solution: None,
+                        })
+                        .await;
+
+                    None
+                }
+            },
+        )))
+    }
+
+    async fn submit_solution_response(
+        &self,
+        solution_response: SolutionResponse,
+    ) -> Result<(), RpcClientError> {
+        Ok(self
+            .inner
+            .module
+            .call("subspace_submitSolutionResponse", [solution_response])
+            .await?)
+    }
+
+    async fn subscribe_reward_signing(
+        &self,
+    ) -> Result<Pin<Box<dyn Stream<Item = RewardSigningInfo> + Send + 'static>>, RpcClientError>
+    {
+        self.subscribe("subspace_subscribeRewardSigning").await
+    }
+
+    async fn submit_reward_signature(
+        &self,
+        reward_signature: RewardSignatureResponse,
+    ) -> Result<(), RpcClientError> {
+        Ok(self
+            .inner
+            .module
+            .call("subspace_submitRewardSignature", [reward_signature])
+            .await?)
+    }
+
+    async fn subscribe_archived_segments(
+        &self,
+    ) -> Result<Pin<Box<dyn Stream<Item = ArchivedSegment> + Send + 'static>>, RpcClientError> {
+        self.subscribe("subspace_subscribeArchivedSegment").await
+    }
+
+    async fn acknowledge_archived_segment(&self, segment_index: u64) -> Result<(), RpcClientError> {
+        Ok(self
+            .inner
+            .module
+            .call("subspace_acknowledgeArchivedSegment", [segment_index])
+            .await?)
+    }
+}
@@ -43,8 +43,8 @@ use pallet_grandpa_finality_verifier::chain::Chain;
 use sp_api::{impl_runtime_apis, BlockT, HashT, HeaderT};
 use sp_consensus_subspace::digests::CompatibleDigestItem;
 use sp_consensus_subspace::{
-    derive_randomness, EquivocationProof, FarmerPublicKey, GlobalRandomnesses, Salts, SignedVote,
-    SolutionRanges, Vote,
+    derive_randomness, EquivocationProof, EraSummary, FarmerPublicKey, GlobalRandomnesses, Salts,
+    SignedVote, SolutionRanges, Vote,
 };
 use sp_core::crypto::{ByteArray, KeyTypeId};
 use sp_core::{Hasher, OpaqueMetadata};
@@ -63,15 +63,17 @@ use sp_std::borrow::Cow;
 use sp_std::iter::Peekable;
 use sp_std::marker::PhantomData;
 use sp_std::prelude::*;
+use sp_transaction_fees::FeeBreakdown;
 #[cfg(feature = "std")]
 use sp_version::NativeVersion;
 use sp_version::RuntimeVersion;
 use subspace_core_primitives::objects::{BlockObject, BlockObjectMapping};
-use subspace_core_primitives::{Randomness, RootBlock, Sha256Hash, PIECE_SIZE};
+use subspace_core_primitives::{Randomness, RootBlock, Sha256Hash};
 use subspace_runtime_primitives::{
     opaque, AccountId, Balance, BlockNumber, Hash, Index, Moment, Signature, CONFIRMATION_DEPTH_K,
-    MAX_PLOT_SIZE, MIN_REPLICATION_FACTOR, RECORDED_HISTORY_SEGMENT_SIZE, RECORD_SIZE,
-    STORAGE_FEES_ESCROW_BLOCK_REWARD, STORAGE_FEES_ESCROW_BLOCK_TAX,
+    EXECUTOR_COMPUTE_FEES_SHARE, MAX_PLOT_SIZE, MIN_REPLICATION_FACTOR,
+    RECORDED_HISTORY_SEGMENT_SIZE, RECORD_SIZE, STORAGE_FEES_ESCROW_BLOCK_REWARD,
+    STORAGE_FEES_ESCROW_BLOCK_TAX,
 };
 
 sp_runtime::impl_opaque_keys! {
@@ -145,6 +147,8 @@ const GLOBAL_RANDOMNESS_UPDATE_INTERVAL: BlockNumber = 100;
 
 /// Era duration in blocks.
 const ERA_DURATION_IN_BLOCKS: BlockNumber = 2016;
+/// Number of recent eras for which summaries are kept on chain.
+const ERA_HISTORY_LENGTH: u32 = 32;
 
 const EQUIVOCATION_REPORT_LONGEVITY: BlockNumber = 256;
 
@@ -155,6 +159,9 @@ const EON_NEXT_SALT_REVEAL: u64 = EON_DURATION_IN_SLOTS
     .checked_sub(3600 * 24)
     .expect("Offset is smaller than eon duration; qed");
 
+/// Farmers stay in block list for 4 eons (4 weeks) after committing an offence
+const BLOCK_LIST_DURATION_IN_EONS: u64 = 4;
+
 /// Any solution range is valid in the test environment.
 const INITIAL_SOLUTION_RANGE: u64 = u64::MAX;
 
@@ -253,6 +260,8 @@ impl pallet_subspace::Config for Runtime {
     type RecordedHistorySegmentSize = ConstU32<RECORDED_HISTORY_SEGMENT_SIZE>;
     type ExpectedVotesPerBlock = ExpectedVotesPerBlock;
     type ShouldAdjustSolutionRange = ShouldAdjustSolutionRange;
+    type EraHistoryLength = ConstU32<ERA_HISTORY_LENGTH>;
+    type BlockListDuration = ConstU64<BLOCK_LIST_DURATION_IN_EONS>;
     type GlobalRandomnessIntervalTrigger = pallet_subspace::NormalGlobalRandomnessInterval;
     type EraChangeTrigger = pallet_subspace::NormalEraChange;
     type EonChangeTrigger = pallet_subspace::NormalEonChange;
@@ -291,6 +300,10 @@ impl pallet_balances::Config for Runtime {
 parameter_types! {
     pub const StorageFeesEscrowBlockReward: (u64, u64) = STORAGE_FEES_ESCROW_BLOCK_REWARD;
     pub const StorageFeesEscrowBlockTax: (u64, u64) = STORAGE_FEES_ESCROW_BLOCK_TAX;
+    pub const ExecutorComputeFeesShare: (u64, u64) = EXECUTOR_COMPUTE_FEES_SHARE;
+    // Receipts are confirmed once pruned, which takes at least the pruning depth, twice that
+    // leaves room for the execution chain to lag behind
+    pub ExecutorFeesTimeout: BlockNumber = 2 * ReceiptsPruningDepth::get();
 }
 
 pub struct CreditSupply;
@@ -305,15 +318,7 @@ pub struct TotalSpacePledged;
 
 impl Get<u128> for TotalSpacePledged {
     fn get() -> u128 {
-        let piece_size = u128::try_from(PIECE_SIZE)
-            .expect("Piece size is definitely small enough to fit into u128; qed");
-        // Operations reordered to avoid data loss, but essentially are:
-        // u64::MAX * SlotProbability / (solution_range / PIECE_SIZE)
-        u128::from(u64::MAX)
-            .saturating_mul(piece_size)
-            .saturating_mul(u128::from(SlotProbability::get().0))
-            / u128::from(Subspace::solution_ranges().current)
-            / u128::from(SlotProbability::get().1)
+        Subspace::total_space_pledged()
     }
 }
 
@@ -330,6 +335,9 @@ impl pallet_transaction_fees::Config for Runtime {
     type MinReplicationFactor = ConstU16<MIN_REPLICATION_FACTOR>;
     type StorageFeesEscrowBlockReward = StorageFeesEscrowBlockReward;
     type StorageFeesEscrowBlockTax = StorageFeesEscrowBlockTax;
+    type ExecutorComputeFeesShare = ExecutorComputeFeesShare;
+    type ExecutorFeesTimeout = ExecutorFeesTimeout;
+    type MaxExecutorFeesBlocks = ConstU32<16>;
     type CreditSupply = CreditSupply;
     type TotalSpacePledged = TotalSpacePledged;
     type BlockchainHistorySize = BlockchainHistorySize;
@@ -439,6 +447,39 @@ impl pallet_transaction_payment::OnChargeTransaction<Runtime> for OnChargeTransa
     }
 }
 
+/// Breakdown of fees of storing `size` bytes in the history of the blockchain.
+fn storage_fee_quote(size: u32) -> FeeBreakdown<Balance> {
+    let transaction_byte_fee = TransactionByteFee::get();
+
+    TransactionFees::fee_breakdown(
+        transaction_byte_fee,
+        transaction_byte_fee * Balance::from(size),
+        Zero::zero(),
+    )
+}
+
+/// Breakdown of fees of the extrinsic, split into storage and compute fees the same way as
+/// [`OnChargeTransaction`] does it.
+fn query_fee_breakdown(uxt: UncheckedExtrinsic, len: u32) -> FeeBreakdown<Balance> {
+    let transaction_byte_fee = TransactionByteFee::get();
+    let storage_fee = transaction_byte_fee
+        * Balance::try_from(uxt.function.encoded_size())
+            .expect("Size of the call never exceeds balance units; qed");
+
+    let (storage_fee, compute_fee) =
+        match TransactionPayment::query_fee_details(uxt, len).inclusion_fee {
+            Some(inclusion_fee) => {
+                let fee = inclusion_fee.inclusion_fee();
+                let storage_fee = storage_fee.min(fee);
+                (storage_fee, fee - storage_fee)
+            }
+            // Unsigned extrinsics don't pay any fees
+            None => (Zero::zero(), Zero::zero()),
+        };
+
+    TransactionFees::fee_breakdown(transaction_byte_fee, storage_fee, compute_fee)
+}
+
 impl pallet_transaction_payment::Config for Runtime {
     type OnChargeTransaction = OnChargeTransaction;
     type OperationalFeeMultiplier = ConstU8<5>;
@@ -475,6 +516,9 @@ impl pallet_offences_subspace::Config for Runtime {
 parameter_types! {
     pub const ReceiptsPruningDepth: BlockNumber = 256;
     pub const MaximumReceiptDrift: BlockNumber = 4;
+    pub const DisputeTimeout: BlockNumber = 32;
+    pub const DisputeBond: Balance = 100 * SSC;
+    pub const MaxDisputesPerReceipt: u32 = 16;
 }
 
 impl pallet_executor::Config for Runtime {
@@ -482,11 +526,17 @@ impl pallet_executor::Config for Runtime {
     type SecondaryHash = cirrus_primitives::Hash;
     type ReceiptsPruningDepth = ReceiptsPruningDepth;
     type MaximumReceiptDrift = MaximumReceiptDrift;
+    type DisputeTimeout = DisputeTimeout;
+    type Currency = Balances;
+    type DisputeBond = DisputeBond;
+    type MaxDisputesPerReceipt = MaxDisputesPerReceipt;
+    type OnExecutionReceipt = TransactionFees;
 }
 
 parameter_types! {
     pub const BlockReward: Balance = SSC / (ExpectedVotesPerBlock::get() as Balance + 1);
     pub const VoteReward: Balance = SSC / (ExpectedVotesPerBlock::get() as Balance + 1);
+    pub const RewardScheduleEnactmentDelay: BlockNumber = 10;
 }
 
 impl pallet_rewards::Config for Runtime {
@@ -494,6 +544,7 @@ impl pallet_rewards::Config for Runtime {
     type Currency = Balances;
     type BlockReward = BlockReward;
     type VoteReward = VoteReward;
+    type RewardScheduleEnactmentDelay = RewardScheduleEnactmentDelay;
     type FindBlockRewardAddress = Subspace;
     type FindVotingRewardAddresses = Subspace;
     type WeightInfo = ();
@@ -561,6 +612,7 @@ impl pallet_feeds::Config for Runtime {
 
 impl pallet_grandpa_finality_verifier::Config for Runtime {
     type ChainId = FeedId;
+    type HeadersToKeep = ConstU32<256>;
 }
 
 impl pallet_object_store::Config for Runtime {
@@ -961,13 +1013,21 @@ impl_runtime_apis! {
 
     impl sp_consensus_subspace::SubspaceApi<Block, FarmerPublicKey> for Runtime {
         fn confirmation_depth_k() -> <<Block as BlockT>::Header as HeaderT>::Number {
-            <Self as pallet_subspace::Config>::ConfirmationDepthK::get()
+            Subspace::confirmation_depth_k()
         }
 
         fn total_pieces() -> u64 {
             <pallet_subspace::Pallet<Runtime>>::total_pieces()
         }
 
+        fn era_history() -> Vec<EraSummary> {
+            Subspace::era_history()
+        }
+
+        fn total_space_pledged() -> u128 {
+            Subspace::total_space_pledged()
+        }
+
         fn record_size() -> u32 {
             <Self as pallet_subspace::Config>::RecordSize::get()
         }
@@ -1030,6 +1090,10 @@ impl_runtime_apis! {
             Subspace::is_in_block_list(farmer_public_key)
         }
 
+        fn remaining_block_list_eons(farmer_public_key: &FarmerPublicKey) -> Option<u64> {
+            Subspace::remaining_block_list_eons(farmer_public_key)
+        }
+
         fn records_root(segment_index: u64) -> Option<Sha256Hash> {
             Subspace::records_root(segment_index)
         }
@@ -1091,6 +1155,12 @@ impl_runtime_apis! {
         fn maximum_receipt_drift() -> NumberFor<Block> {
             MaximumReceiptDrift::get()
         }
+
+        fn execution_receipt(
+            primary_number: NumberFor<Block>,
+        ) -> Option<sp_executor::ExecutionReceipt<NumberFor<Block>, <Block as BlockT>::Hash, cirrus_primitives::Hash>> {
+            Executor::receipts(primary_number)
+        }
     }
 
     impl sp_session::SessionKeys<Block> for Runtime {
@@ -1125,4 +1195,14 @@ impl_runtime_apis! {
             TransactionPayment::query_fee_details(uxt, len)
         }
     }
+
+    impl sp_transaction_fees::TransactionFeesApi<Block, Balance> for Runtime {
+        fn storage_fee_quote(size: u32) -> FeeBreakdown<Balance> {
+            storage_fee_quote(size)
+        }
+
+        fn query_fee_breakdown(uxt: <Block as BlockT>::Extrinsic, len: u32) -> FeeBreakdown<Balance> {
+            query_fee_breakdown(uxt, len)
+        }
+    }
 }